@@ -3,16 +3,18 @@
 //! Enhanced configuration system for Xylux IDE supporting multiple formats
 //! and hierarchical configuration loading.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 
 use crate::core::error::{Result, XyluxError};
+use crate::core::ini;
+use crate::core::util::line_of;
 
 /// Main configuration structure for Xylux IDE.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub struct Config {
     /// Editor configuration.
     pub editor: EditorConfig,
@@ -35,7 +37,8 @@ pub struct Config {
 }
 
 /// Editor-specific configuration.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[expect(clippy::struct_excessive_bools, reason = "each flag is a separate setting")]
 pub struct EditorConfig {
     /// Tab size in spaces.
     pub tab_size: usize,
@@ -63,6 +66,24 @@ pub struct EditorConfig {
     pub bracket_matching: bool,
     /// Whether to auto-close brackets.
     pub auto_close_brackets: bool,
+    /// Whether keys in the editor use Vim-style modal editing.
+    #[serde(default)]
+    pub vim_mode: bool,
+    /// Undo history settings.
+    #[serde(default)]
+    pub undo: UndoConfig,
+}
+
+/// Undo history configuration.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UndoConfig {
+    /// Maximum number of undoable commands kept per file.
+    pub max_commands: usize,
+    /// Whether to keep undo history across sessions.
+    pub persist: bool,
+    /// Directory for persisted history; defaults to `undo` in the data directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub directory: Option<PathBuf>,
 }
 
 /// UI configuration.
@@ -89,7 +110,8 @@ pub struct UiConfig {
 }
 
 /// Status bar configuration.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[expect(clippy::struct_excessive_bools, reason = "each flag is a separate setting")]
 pub struct StatusBarConfig {
     /// Whether to show cursor position.
     pub show_cursor_position: bool,
@@ -106,7 +128,8 @@ pub struct StatusBarConfig {
 }
 
 /// Language Server Protocol configuration.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[expect(clippy::struct_excessive_bools, reason = "each flag is a separate setting")]
 pub struct LspConfig {
     /// Whether LSP is enabled.
     pub enabled: bool,
@@ -122,10 +145,29 @@ pub struct LspConfig {
     pub show_hover: bool,
     /// Whether to enable code completion.
     pub enable_completion: bool,
+    /// Additional language servers, e.g. for WGSL shaders.
+    #[serde(default)]
+    pub servers: Vec<LanguageServerConfig>,
+    /// How crashed language servers are restarted.
+    #[serde(default)]
+    pub restart: LspRestartConfig,
+}
+
+/// Restart policy for language servers that exit unexpectedly.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LspRestartConfig {
+    /// Restarts attempted before giving up on a server.
+    pub max_restarts: u32,
+    /// Delay before the first restart (in milliseconds); doubled on each further attempt.
+    pub initial_backoff_ms: u64,
+    /// Upper bound for the restart delay (in milliseconds).
+    pub max_backoff_ms: u64,
+    /// Uptime after which a server counts as healthy again (in milliseconds).
+    pub reset_after_ms: u64,
 }
 
 /// Rust analyzer specific configuration.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct RustAnalyzerConfig {
     /// Path to rust-analyzer binary (None for auto-detect).
     pub binary_path: Option<PathBuf>,
@@ -140,7 +182,7 @@ pub struct RustAnalyzerConfig {
 }
 
 /// Alux LSP configuration.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct AluxLspConfig {
     /// Path to alux-lsp binary (None for auto-detect).
     pub binary_path: Option<PathBuf>,
@@ -150,8 +192,26 @@ pub struct AluxLspConfig {
     pub inline_hints: bool,
 }
 
+/// A language server launched over stdio for an additional language.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LanguageServerConfig {
+    /// Language id the server handles, e.g. `wgsl`.
+    pub language: String,
+    /// Server binary.
+    pub command: PathBuf,
+    /// Additional command line arguments.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// File extensions routed to the server, without the leading dot.
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    /// Server-specific `initializationOptions`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initialization_options: Option<serde_json::Value>,
+}
+
 /// Project management configuration.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct ProjectConfig {
     /// Whether to auto-detect project type.
     pub auto_detect_type: bool,
@@ -168,7 +228,7 @@ pub struct ProjectConfig {
 }
 
 /// Git integration configuration.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct GitConfig {
     /// Whether Git integration is enabled.
     pub enabled: bool,
@@ -179,7 +239,7 @@ pub struct GitConfig {
 }
 
 /// Build system configuration.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct BuildConfig {
     /// Default build command for Rust projects.
     pub rust_build_command: String,
@@ -198,7 +258,7 @@ pub struct BuildConfig {
 }
 
 /// Alux language configuration.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct AluxConfig {
     /// Path to Alux compiler (None for auto-detect).
     pub compiler_path: Option<PathBuf>,
@@ -215,7 +275,7 @@ pub struct AluxConfig {
 }
 
 /// Xylux engine configuration.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct XyluxConfig {
     /// Path to Xylux engine (None for auto-detect).
     pub engine_path: Option<PathBuf>,
@@ -230,7 +290,7 @@ pub struct XyluxConfig {
 }
 
 /// WebAssembly compilation configuration.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct WasmConfig {
     /// Whether WASM compilation is enabled.
     pub enabled: bool,
@@ -241,7 +301,7 @@ pub struct WasmConfig {
 }
 
 /// Shader development configuration.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct ShaderConfig {
     /// Whether to enable shader hot reload.
     pub hot_reload: bool,
@@ -252,7 +312,7 @@ pub struct ShaderConfig {
 }
 
 /// Plugin system configuration.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct PluginConfig {
     /// Whether plugins are enabled.
     pub enabled: bool,
@@ -265,7 +325,7 @@ pub struct PluginConfig {
 }
 
 /// Advanced configuration options.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct AdvancedConfig {
     /// Number of quit confirmations needed.
     pub quit_times: usize,
@@ -280,7 +340,7 @@ pub struct AdvancedConfig {
 }
 
 /// Performance monitoring configuration.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct PerformanceConfig {
     /// Whether to enable performance monitoring.
     pub enabled: bool,
@@ -291,7 +351,7 @@ pub struct PerformanceConfig {
 }
 
 /// Memory management configuration.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct MemoryConfig {
     /// Maximum memory usage for text buffers (in MB).
     pub max_buffer_memory_mb: usize,
@@ -301,19 +361,61 @@ pub struct MemoryConfig {
     pub syntax_cache_size_mb: usize,
 }
 
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            editor: EditorConfig::default(),
-            ui: UiConfig::default(),
-            lsp: LspConfig::default(),
-            project: ProjectConfig::default(),
-            build: BuildConfig::default(),
-            alux: AluxConfig::default(),
-            xylux: XyluxConfig::default(),
-            plugins: PluginConfig::default(),
-            advanced: AdvancedConfig::default(),
-        }
+impl Config {
+    /// Get the dotted keys, such as `editor.tab_size`, of the values that
+    /// differ from `other`.
+    #[must_use]
+    pub fn diff(&self, other: &Self) -> Vec<String> {
+        let values = |config: &Self| -> BTreeMap<String, serde_json::Value> {
+            serde_json::to_value(config)
+                .map(|value| leaves(&value).into_iter().collect())
+                .unwrap_or_default()
+        };
+        let (old, new) = (values(self), values(other));
+        let mut keys: Vec<String> = old
+            .iter()
+            .filter(|(key, value)| new.get(*key) != Some(value))
+            .map(|(key, _)| key.clone())
+            .chain(new.keys().filter(|key| !old.contains_key(*key)).cloned())
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    /// Get the key of the first value that cannot be used, with the reason.
+    fn invalid_setting(&self) -> Option<(&'static str, String)> {
+        const LOG_LEVELS: [&str; 5] = ["trace", "debug", "info", "warn", "error"];
+
+        let checks = [
+            ("editor.tab_size", self.editor.tab_size == 0, "must be at least 1".to_owned()),
+            ("ui.font_size", self.ui.font_size == 0, "must be at least 1".to_owned()),
+            (
+                "ui.transparency",
+                !(0.0..=1.0).contains(&self.ui.transparency),
+                "must be between 0.0 and 1.0".to_owned(),
+            ),
+            (
+                "lsp.request_timeout_ms",
+                self.lsp.request_timeout_ms == 0,
+                "must be at least 1".to_owned(),
+            ),
+            (
+                "lsp.restart.max_backoff_ms",
+                self.lsp.restart.max_backoff_ms < self.lsp.restart.initial_backoff_ms,
+                "must not be less than lsp.restart.initial_backoff_ms".to_owned(),
+            ),
+            (
+                "advanced.log_level",
+                !LOG_LEVELS.contains(&self.advanced.log_level.as_str()),
+                format!("must be one of {}", LOG_LEVELS.join(", ")),
+            ),
+            (
+                "alux.vm_log_level",
+                !LOG_LEVELS.contains(&self.alux.vm_log_level.as_str()),
+                format!("must be one of {}", LOG_LEVELS.join(", ")),
+            ),
+        ];
+        checks.into_iter().find(|(_, invalid, _)| *invalid).map(|(key, _, message)| (key, message))
     }
 }
 
@@ -333,15 +435,23 @@ impl Default for EditorConfig {
             scroll_offset: 3,
             bracket_matching: true,
             auto_close_brackets: true,
+            vim_mode: false,
+            undo: UndoConfig::default(),
         }
     }
 }
 
+impl Default for UndoConfig {
+    fn default() -> Self {
+        Self { max_commands: 1000, persist: true, directory: None }
+    }
+}
+
 impl Default for UiConfig {
     fn default() -> Self {
         Self {
-            theme: "dark".to_string(),
-            font_family: "JetBrains Mono".to_string(),
+            theme: "dark".to_owned(),
+            font_family: "JetBrains Mono".to_owned(),
             font_size: 14,
             show_file_explorer: true,
             show_terminal: true,
@@ -376,6 +486,19 @@ impl Default for LspConfig {
             show_diagnostics_inline: true,
             show_hover: true,
             enable_completion: true,
+            servers: Vec::new(),
+            restart: LspRestartConfig::default(),
+        }
+    }
+}
+
+impl Default for LspRestartConfig {
+    fn default() -> Self {
+        Self {
+            max_restarts: 5,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 30_000,
+            reset_after_ms: 60_000,
         }
     }
 }
@@ -405,11 +528,11 @@ impl Default for ProjectConfig {
             default_templates: HashMap::new(),
             file_watching: true,
             ignore_patterns: vec![
-                "target/".to_string(),
-                "node_modules/".to_string(),
-                ".git/".to_string(),
-                "*.tmp".to_string(),
-                "*.log".to_string(),
+                "target/".to_owned(),
+                "node_modules/".to_owned(),
+                ".git/".to_owned(),
+                "*.tmp".to_owned(),
+                "*.log".to_owned(),
             ],
             auto_reload_files: true,
             git: GitConfig::default(),
@@ -426,9 +549,9 @@ impl Default for GitConfig {
 impl Default for BuildConfig {
     fn default() -> Self {
         Self {
-            rust_build_command: "cargo build".to_string(),
-            rust_run_command: "cargo run".to_string(),
-            rust_test_command: "cargo test".to_string(),
+            rust_build_command: "cargo build".to_owned(),
+            rust_run_command: "cargo run".to_owned(),
+            rust_test_command: "cargo test".to_owned(),
             xylux_cli_path: None,
             show_build_output: true,
             auto_build_on_save: false,
@@ -443,7 +566,7 @@ impl Default for AluxConfig {
             compiler_path: None,
             vm_path: None,
             hot_reload: true,
-            vm_log_level: "warn".to_string(),
+            vm_log_level: "warn".to_owned(),
             auto_compile_bytecode: true,
             optimization_level: 1,
         }
@@ -454,7 +577,7 @@ impl Default for XyluxConfig {
     fn default() -> Self {
         Self {
             engine_path: None,
-            default_target: "native".to_string(),
+            default_target: "native".to_owned(),
             headless_testing: false,
             wasm: WasmConfig::default(),
             shaders: ShaderConfig::default(),
@@ -490,7 +613,7 @@ impl Default for AdvancedConfig {
         Self {
             quit_times: 2,
             debug_logging: false,
-            log_level: "info".to_string(),
+            log_level: "info".to_owned(),
             performance: PerformanceConfig::default(),
             memory: MemoryConfig::default(),
         }
@@ -509,131 +632,258 @@ impl Default for MemoryConfig {
     }
 }
 
+/// A source of configuration, from lowest to highest precedence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ConfigLayer {
+    /// Built-in defaults.
+    Default,
+    /// System-wide configuration, such as `/etc/xylux-ide`.
+    System,
+    /// The user's configuration directory.
+    User,
+    /// The `.xylux-ide` directory of the current project.
+    Project,
+}
+
+impl std::fmt::Display for ConfigLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::System => write!(f, "system"),
+            Self::User => write!(f, "user"),
+            Self::Project => write!(f, "project"),
+        }
+    }
+}
+
+/// The file a configuration value was read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigOrigin {
+    /// Layer the file belongs to.
+    pub layer: ConfigLayer,
+    /// Path of the file.
+    pub path: PathBuf,
+}
+
+/// Configuration merged from every layer, with the origin of each value.
+#[derive(Debug, Clone)]
+pub struct LayeredConfig {
+    /// The merged configuration.
+    pub config: Config,
+    /// Files loaded, from lowest to highest precedence.
+    files: Vec<ConfigOrigin>,
+    /// Origin of each value set by a file, by dotted key such as `editor.tab_size`.
+    origins: BTreeMap<String, ConfigOrigin>,
+}
+
+impl LayeredConfig {
+    /// Get the files that were loaded, from lowest to highest precedence.
+    #[must_use]
+    pub fn files(&self) -> &[ConfigOrigin] {
+        &self.files
+    }
+
+    /// Get the file that set a value, by dotted key such as `editor.tab_size`.
+    ///
+    /// For a section such as `editor`, this is the highest-precedence file
+    /// that set any value in it. Returns `None` for built-in defaults.
+    #[must_use]
+    pub fn origin(&self, key: &str) -> Option<&ConfigOrigin> {
+        if let Some(origin) = self.origins.get(key) {
+            return Some(origin);
+        }
+        let prefix = format!("{key}.");
+        self.origins
+            .range(prefix.clone()..)
+            .take_while(|(child, _)| child.starts_with(&prefix))
+            .map(|(_, origin)| origin)
+            .max_by_key(|origin| origin.layer)
+    }
+
+    /// Get the layer that set a value, by dotted key such as `editor.tab_size`.
+    #[must_use]
+    pub fn layer(&self, key: &str) -> ConfigLayer {
+        self.origin(key).map_or(ConfigLayer::Default, |origin| origin.layer)
+    }
+
+    /// Check that the values are usable, naming the file that set a bad one.
+    ///
+    /// # Errors
+    ///
+    /// Fails with the offending key if a value is out of range.
+    pub fn validate(&self) -> Result<()> {
+        match self.config.invalid_setting() {
+            None => Ok(()),
+            Some((key, message)) => {
+                let path = self.origin(key).map(|origin| origin.path.clone()).unwrap_or_default();
+                Err(XyluxError::config_error(path, 0, format!("{key}: {message}")))
+            }
+        }
+    }
+}
+
+/// Names of configuration files in a layer directory, in order of preference.
+pub(crate) const CONFIG_FILES: [&str; 5] =
+    ["config.toml", "config.json", "config.ini", "xylux-ide.toml", "xylux-ide.json"];
+
 /// Configuration loader that supports multiple formats and sources.
+///
+/// Layers are applied from system to user to project. Each file only
+/// overrides the values it sets, so a project file setting one key keeps the
+/// rest of the user's settings.
 pub struct ConfigLoader {
     config_dirs: Vec<PathBuf>,
+    /// Base directory of the user's configuration; defaults to the platform one.
+    user_dir: Option<PathBuf>,
+    /// Project whose `.xylux-ide` directory is loaded; defaults to the current directory.
+    project_root: Option<PathBuf>,
 }
 
 impl ConfigLoader {
     /// Create a new configuration loader.
+    ///
+    /// # Errors
+    ///
+    /// Currently never fails.
     pub fn new() -> Result<Self> {
-        let config_dirs = Self::get_config_directories()?;
-        Ok(Self { config_dirs })
+        Ok(Self::default())
     }
 
-    /// Load configuration from all available sources.
-    pub fn load(&self) -> Result<Config> {
-        let mut config = Config::default();
+    /// Use `dir` as the base of the user's configuration.
+    #[must_use]
+    pub fn with_user_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.user_dir = Some(dir.into());
+        self
+    }
 
-        // Load from system-wide configuration
-        for dir in &self.config_dirs {
-            let config_path = dir.join("xylux-ide");
-            if config_path.exists() {
-                self.load_from_directory(&config_path, &mut config)?;
-            }
-        }
+    /// Load the project configuration of `root`.
+    #[must_use]
+    pub fn with_project_root<P: Into<PathBuf>>(mut self, root: P) -> Self {
+        self.project_root = Some(root.into());
+        self
+    }
 
-        // Load from user-specific configuration
-        if let Some(user_config_dir) = dirs::config_dir() {
-            let user_config_path = user_config_dir.join("xylux-ide");
-            if user_config_path.exists() {
-                self.load_from_directory(&user_config_path, &mut config)?;
-            }
-        }
+    /// Use `dirs` as the bases of the system-wide configuration.
+    #[must_use]
+    pub fn with_system_dirs(mut self, dirs: Vec<PathBuf>) -> Self {
+        self.config_dirs = dirs;
+        self
+    }
 
-        // Load from current project configuration
-        if let Ok(current_dir) = std::env::current_dir() {
-            let project_config = current_dir.join(".xylux-ide");
-            if project_config.exists() {
-                self.load_from_directory(&project_config, &mut config)?;
-            }
-        }
+    /// Get the directory of each layer, from lowest to highest precedence.
+    #[must_use]
+    pub fn layer_directories(&self) -> Vec<(ConfigLayer, PathBuf)> {
+        let system =
+            self.config_dirs.iter().map(|dir| (ConfigLayer::System, dir.join("xylux-ide")));
+        let user = self.user_dir.iter().map(|dir| (ConfigLayer::User, dir.join("xylux-ide")));
+        let project =
+            self.project_root.iter().map(|root| (ConfigLayer::Project, root.join(".xylux-ide")));
+        system.chain(user).chain(project).collect()
+    }
 
-        Ok(config)
+    /// Load configuration from all available sources.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a configuration file cannot be read, does not parse or holds
+    /// an invalid value.
+    pub fn load(&self) -> Result<Config> {
+        Ok(self.load_layered()?.config)
     }
 
-    /// Load configuration from a specific directory.
-    fn load_from_directory(&self, dir: &Path, config: &mut Config) -> Result<()> {
-        // Try different configuration file formats
-        let config_files =
-            ["config.toml", "config.json", "config.ini", "xylux-ide.toml", "xylux-ide.json"];
+    /// Load configuration from all available sources, recording which layer
+    /// set each value.
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`load`](Self::load).
+    pub fn load_layered(&self) -> Result<LayeredConfig> {
+        let mut merged = serde_json::to_value(Config::default())
+            .map_err(|e| XyluxError::config_error("", 0, e.to_string()))?;
+        let mut files = Vec::new();
+        let mut origins = BTreeMap::new();
+
+        for (layer, dir) in self.layer_directories() {
+            let Some(path) = Self::find_config_file(&dir) else {
+                continue;
+            };
+            let values = Self::load_from_file(&path)?;
+            Self::merge_configs(&mut merged, &values, &path)?;
 
-        for file_name in &config_files {
-            let config_path = dir.join(file_name);
-            if config_path.exists() {
-                self.load_from_file(&config_path, config)?;
-                break; // Use the first available format
+            let origin = ConfigOrigin { layer, path };
+            for (key, _) in leaves(&values) {
+                origins.insert(key, origin.clone());
             }
+            files.push(origin);
         }
 
-        Ok(())
+        let config = serde_json::from_value(merged)
+            .map_err(|e| XyluxError::config_error("", 0, e.to_string()))?;
+        let layered = LayeredConfig { config, files, origins };
+        layered.validate()?;
+        Ok(layered)
     }
 
-    /// Load configuration from a specific file.
-    fn load_from_file(&self, path: &Path, config: &mut Config) -> Result<()> {
+    /// Get the configuration file in a layer directory, if there is one.
+    fn find_config_file(dir: &Path) -> Option<PathBuf> {
+        // Try different configuration file formats, using the first available
+        CONFIG_FILES.iter().map(|file_name| dir.join(file_name)).find(|path| path.exists())
+    }
+
+    /// Read the values set in a configuration file.
+    fn load_from_file(path: &Path) -> Result<serde_json::Value> {
         let content = std::fs::read_to_string(path)
             .map_err(|e| XyluxError::config_error(path, 0, e.to_string()))?;
 
         let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
 
-        let loaded_config: Config = match extension {
-            "toml" => toml::from_str(&content)
-                .map_err(|e| XyluxError::config_error(path, 0, e.to_string()))?,
+        let values = match extension {
+            "toml" => toml::from_str(&content).map_err(|e| {
+                let line = e.span().map_or(0, |span| line_of(&content, span.start));
+                XyluxError::config_error(path, line, e.message())
+            })?,
             "json" => serde_json::from_str(&content)
-                .map_err(|e| XyluxError::config_error(path, 0, e.to_string()))?,
-            "ini" => self.load_ini_config(path, &content)?,
+                .map_err(|e| XyluxError::config_error(path, e.line(), e.to_string()))?,
+            "ini" => ini::parse(path, &content)?,
             _ => return Err(XyluxError::config_error(path, 0, "Unsupported config format")),
         };
-
-        self.merge_configs(config, loaded_config);
-        Ok(())
-    }
-
-    /// Load configuration from INI format (legacy support).
-    fn load_ini_config(&self, path: &Path, content: &str) -> Result<Config> {
-        // For now, just return default config with basic INI support
-        // This can be expanded to parse specific INI sections
-        let mut config = Config::default();
-
-        for (line_num, line) in content.lines().enumerate() {
-            let line = line.trim();
-            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
-                continue;
-            }
-
-            if let Some((key, value)) = line.split_once('=') {
-                let key = key.trim();
-                let value = value.trim();
-
-                match key {
-                    "tab_size" => {
-                        config.editor.tab_size = value.parse().map_err(|_| {
-                            XyluxError::config_error(path, line_num + 1, "Invalid tab_size")
-                        })?;
-                    }
-                    "theme" => config.ui.theme = value.to_string(),
-                    "font_size" => {
-                        config.ui.font_size = value.parse().map_err(|_| {
-                            XyluxError::config_error(path, line_num + 1, "Invalid font_size")
-                        })?;
-                    }
-                    _ => {} // Ignore unknown keys for compatibility
-                }
-            }
+        if values.is_object() {
+            Ok(values)
+        } else {
+            Err(XyluxError::config_error(path, 0, "Expected a table of settings"))
         }
-
-        Ok(config)
     }
 
-    /// Merge two configurations, with the second one taking precedence.
-    fn merge_configs(&self, base: &mut Config, override_config: Config) {
-        // For now, completely replace sections
-        // TODO: Implement field-by-field merging
-        *base = override_config;
+    /// Merge the values set in the file at `path` into `base`.
+    ///
+    /// Tables are merged key by key and any other value replaces the one in
+    /// `base`. Values of the wrong type are reported with their key, and
+    /// leave `base` unchanged.
+    fn merge_configs(
+        base: &mut serde_json::Value,
+        values: &serde_json::Value,
+        path: &Path,
+    ) -> Result<()> {
+        let mut merged = base.clone();
+        merge_values(&mut merged, values);
+        if let Err(e) = Config::deserialize(&merged) {
+            // Find the setting that does not fit, to name it in the error
+            let message = leaves(values)
+                .into_iter()
+                .find_map(|(key, value)| {
+                    let mut single = base.clone();
+                    merge_values(&mut single, &value);
+                    Config::deserialize(&single).err().map(|e| format!("{key}: {e}"))
+                })
+                .unwrap_or_else(|| e.to_string());
+            return Err(XyluxError::config_error(path, 0, message));
+        }
+        *base = merged;
+        Ok(())
     }
 
     /// Get system configuration directories.
-    fn get_config_directories() -> Result<Vec<PathBuf>> {
+    fn get_config_directories() -> Vec<PathBuf> {
         let mut dirs = Vec::new();
 
         #[cfg(unix)]
@@ -653,10 +903,14 @@ impl ConfigLoader {
             }
         }
 
-        Ok(dirs)
+        dirs
     }
 
     /// Save configuration to the user's config directory.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the user's config directory is unknown or cannot be written.
     pub fn save(&self, config: &Config) -> Result<()> {
         let user_config_dir = dirs::config_dir().ok_or_else(|| {
             XyluxError::config_error("", 0, "Cannot determine user config directory")
@@ -676,10 +930,54 @@ impl ConfigLoader {
 
 impl Default for ConfigLoader {
     fn default() -> Self {
-        Self::new().expect("Failed to create ConfigLoader")
+        Self {
+            config_dirs: Self::get_config_directories(),
+            user_dir: dirs::config_dir(),
+            project_root: std::env::current_dir().ok(),
+        }
     }
 }
 
+/// Merge `overlay` into `base`: tables merge key by key, other values replace.
+pub(crate) fn merge_values(base: &mut serde_json::Value, overlay: &serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
+/// Get each value set in a table, by dotted key, with a table setting only
+/// that value.
+fn leaves(value: &serde_json::Value) -> Vec<(String, serde_json::Value)> {
+    let serde_json::Value::Object(table) = value else {
+        return Vec::new();
+    };
+    let mut found = Vec::new();
+    for (key, child) in table {
+        let nested = if child.as_object().is_some_and(|inner| !inner.is_empty()) {
+            leaves(child)
+        } else {
+            vec![(String::new(), child.clone())]
+        };
+        for (path, leaf) in nested {
+            let path = if path.is_empty() { key.clone() } else { format!("{key}.{path}") };
+            let mut wrapped = serde_json::Map::new();
+            wrapped.insert(key.clone(), leaf);
+            found.push((path, serde_json::Value::Object(wrapped)));
+        }
+    }
+    found
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -711,18 +1009,49 @@ mod tests {
     #[test]
     fn test_config_loader() {
         let temp_dir = TempDir::new().unwrap();
-        let config_dir = temp_dir.path().join("xylux-ide");
-        std::fs::create_dir_all(&config_dir).unwrap();
-
-        let mut config = Config::default();
-        config.editor.tab_size = 8;
-        config.ui.theme = "light".to_string();
-
-        let config_content = toml::to_string(&config).unwrap();
-        std::fs::write(config_dir.join("config.toml"), config_content).unwrap();
+        let write = |dir: &str, file: &str, content: &str| {
+            let dir = temp_dir.path().join(dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join(file), content).unwrap();
+        };
 
-        // Test would require mocking the config directories
-        // For now, just test that the ConfigLoader can be created
-        assert!(ConfigLoader::new().is_ok());
+        write(
+            "system/xylux-ide",
+            "config.toml",
+            "[editor]\ntab_size = 8\n\n[ui]\ntheme = \"light\"\n",
+        );
+        write("user/xylux-ide", "config.json", r#"{"ui": {"font_size": 16}}"#);
+        write("project/.xylux-ide", "config.toml", "[editor]\ntab_size = 2\n");
+
+        let loader = ConfigLoader::new()
+            .unwrap()
+            .with_system_dirs(vec![temp_dir.path().join("system")])
+            .with_user_dir(temp_dir.path().join("user"))
+            .with_project_root(temp_dir.path().join("project"));
+        let layered = loader.load_layered().unwrap();
+
+        assert_eq!(layered.config.editor.tab_size, 2);
+        assert_eq!(layered.config.ui.theme, "light");
+        assert_eq!(layered.config.ui.font_size, 16);
+        assert!(layered.config.editor.use_spaces);
+        assert_eq!(layered.files().len(), 3);
+        assert_eq!(layered.layer("editor.tab_size"), ConfigLayer::Project);
+        assert_eq!(layered.layer("ui.theme"), ConfigLayer::System);
+        assert_eq!(layered.layer("ui"), ConfigLayer::User);
+        assert_eq!(layered.layer("editor"), ConfigLayer::Project);
+        assert_eq!(layered.layer("editor.vim_mode"), ConfigLayer::Default);
+        let origin = layered.origin("ui.font_size").unwrap();
+        assert_eq!(origin.path, temp_dir.path().join("user/xylux-ide/config.json"));
+
+        write("project/.xylux-ide", "config.toml", "[editor]\ntab_size = \"wide\"\n");
+        let error = loader.load().unwrap_err().to_string();
+        assert!(error.contains("editor.tab_size"), "{}", error);
+        assert!(error.contains("project"), "{}", error);
+
+        write("project/.xylux-ide", "config.toml", "[editor]\ntab_size = 2\n[ui\n");
+        match loader.load() {
+            Err(XyluxError::Config { line, .. }) => assert_eq!(line, 3),
+            other => panic!("expected a config error, got {:?}", other.map(|_| ())),
+        }
     }
 }
@@ -0,0 +1,297 @@
+//! # Configuration Watcher
+//!
+//! Reloads the configuration when a file in one of its layers changes.
+//!
+//! A reload re-runs the [`ConfigLoader`], validates the result and compares
+//! it with the current configuration. If anything changed, the shared
+//! [`Config`] is replaced and the change is published: a
+//! [`SystemEvent::ConfigReloaded`] listing the changed keys, plus
+//! [`UiEvent::ThemeChanged`] and [`UiEvent::FontChanged`] for those settings.
+//! A configuration that does not load or validate is rejected with a
+//! [`UiEvent::StatusMessage`], and the current one is kept.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::{RwLock, mpsc};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use super::config::{CONFIG_FILES, Config, ConfigLoader};
+use super::error::Result;
+use super::events::{Event, EventBus, EventMessage, EventPriority, SystemEvent, UiEvent};
+
+/// Time to wait for more changes before reloading, as editors often save a
+/// file in several steps.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Watches the configuration layers and reloads the configuration on change.
+///
+/// Watching stops when the watcher is dropped.
+pub struct ConfigWatcher {
+    /// Loader that finds and merges the configuration layers.
+    loader: Arc<ConfigLoader>,
+    /// Configuration shared with the rest of the IDE.
+    config: Arc<RwLock<Config>>,
+    /// Bus on which reloads are announced.
+    event_bus: Arc<EventBus>,
+    /// Task that owns the file system watcher and reloads the configuration.
+    task: JoinHandle<()>,
+}
+
+impl ConfigWatcher {
+    /// Start watching the layers of `loader`, reloading `config` on change.
+    ///
+    /// Layer directories that do not exist yet are watched through their
+    /// parent. Must be called within a Tokio runtime.
+    pub fn start(
+        loader: ConfigLoader,
+        config: Arc<RwLock<Config>>,
+        event_bus: Arc<EventBus>,
+    ) -> Result<Self> {
+        let layers: Vec<PathBuf> =
+            loader.layer_directories().into_iter().map(|(_, dir)| dir).collect();
+        let (sender, mut changes) = mpsc::unbounded_channel();
+        let filter = layers.clone();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+                Ok(event) if is_config_change(&event, &filter) => {
+                    let _unused = sender.send(());
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Config watcher error: {}", e),
+            })?;
+        let mut watched = HashSet::new();
+        watch_layers(&mut watcher, &layers, &mut watched);
+
+        let loader = Arc::new(loader);
+        let task = tokio::spawn({
+            let (loader, config, event_bus) = (loader.clone(), config.clone(), event_bus.clone());
+            async move {
+                while changes.recv().await.is_some() {
+                    tokio::time::sleep(DEBOUNCE).await;
+                    while changes.try_recv().is_ok() {}
+
+                    // A layer directory may have just been created
+                    watch_layers(&mut watcher, &layers, &mut watched);
+                    if let Err(e) = Self::reload(&loader, &config, &event_bus).await {
+                        warn!("Configuration not reloaded: {}", e);
+                    }
+                }
+            }
+        });
+        Ok(Self { loader, config, event_bus, task })
+    }
+
+    /// Reload the configuration now, without waiting for a file change.
+    pub async fn reload_now(&self) -> Result<Vec<String>> {
+        Self::reload(&self.loader, &self.config, &self.event_bus).await
+    }
+
+    /// Load the configuration again and publish what changed.
+    ///
+    /// Returns the dotted keys of the changed values. An invalid
+    /// configuration is reported on the event bus and returned as an error,
+    /// and `config` is left as it was.
+    pub async fn reload(
+        loader: &ConfigLoader,
+        config: &RwLock<Config>,
+        event_bus: &EventBus,
+    ) -> Result<Vec<String>> {
+        let new_config = match loader.load() {
+            Ok(new_config) => new_config,
+            Err(e) => {
+                let event = UiEvent::StatusMessage {
+                    message: format!("Configuration not reloaded: {}", e),
+                    level: "error".to_string(),
+                };
+                publish(event_bus, Event::Ui(event), EventPriority::High).await;
+                return Err(e);
+            }
+        };
+
+        let changed = {
+            let mut config = config.write().await;
+            let changed = config.diff(&new_config);
+            *config = new_config.clone();
+            changed
+        };
+        if changed.is_empty() {
+            debug!("Configuration files changed without changing any setting");
+            return Ok(changed);
+        }
+        info!("Configuration reloaded: {}", changed.join(", "));
+
+        let ui = &new_config.ui;
+        if changed.iter().any(|key| key == "ui.theme") {
+            let event = UiEvent::ThemeChanged { theme: ui.theme.clone() };
+            publish(event_bus, Event::Ui(event), EventPriority::Normal).await;
+        }
+        if changed.iter().any(|key| key == "ui.font_family" || key == "ui.font_size") {
+            let event = UiEvent::FontChanged { family: ui.font_family.clone(), size: ui.font_size };
+            publish(event_bus, Event::Ui(event), EventPriority::Normal).await;
+        }
+        let event = SystemEvent::ConfigReloaded { changed: changed.clone() };
+        publish(event_bus, Event::System(event), EventPriority::High).await;
+        Ok(changed)
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Publish an event from the watcher, logging failures.
+async fn publish(event_bus: &EventBus, event: Event, priority: EventPriority) {
+    let message =
+        EventMessage::from_event(event).with_priority(priority).with_source("config_watcher");
+    if let Err(e) = event_bus.publish(message).await {
+        warn!("Failed to publish configuration event: {}", e);
+    }
+}
+
+/// Watch each layer directory, or the parent of one that does not exist,
+/// unless it is watched already.
+fn watch_layers(
+    watcher: &mut RecommendedWatcher,
+    layers: &[PathBuf],
+    watched: &mut HashSet<PathBuf>,
+) {
+    for dir in layers {
+        let target = if dir.is_dir() { dir.as_path() } else { dir.parent().unwrap_or(dir) };
+        if watched.contains(target) || !target.is_dir() {
+            continue;
+        }
+        match watcher.watch(target, RecursiveMode::NonRecursive) {
+            Ok(()) => {
+                debug!("Watching {} for configuration changes", target.display());
+                watched.insert(target.to_path_buf());
+            }
+            Err(e) => warn!("Cannot watch {}: {}", target.display(), e),
+        }
+    }
+}
+
+/// Check whether a file system event touches a configuration file or a
+/// layer directory.
+fn is_config_change(event: &notify::Event, layers: &[PathBuf]) -> bool {
+    if matches!(event.kind, EventKind::Access(_)) {
+        return false;
+    }
+    event.paths.iter().any(|path| {
+        let in_layer = path.parent().is_some_and(|parent| layers.iter().any(|dir| dir == parent));
+        let config_file = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| CONFIG_FILES.contains(&name));
+        (in_layer && config_file) || layers.iter().any(|dir| dir == Path::new(path))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::events::EventSubscription;
+    use tempfile::TempDir;
+
+    fn loader(root: &Path) -> ConfigLoader {
+        ConfigLoader::new()
+            .unwrap()
+            .with_system_dirs(Vec::new())
+            .with_user_dir(root.join("user"))
+            .with_project_root(root.join("project"))
+    }
+
+    #[tokio::test]
+    async fn test_reload_publishes_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let loader = loader(temp_dir.path());
+        let config = RwLock::new(loader.load().unwrap());
+        let event_bus = EventBus::new();
+        let mut events = event_bus.subscribe(EventSubscription::all());
+
+        let user = temp_dir.path().join("user/xylux-ide");
+        std::fs::create_dir_all(&user).unwrap();
+        std::fs::write(
+            user.join("config.toml"),
+            "[ui]\ntheme = \"light\"\n[editor]\ntab_size = 2\n",
+        )
+        .unwrap();
+
+        let changed = ConfigWatcher::reload(&loader, &config, &event_bus).await.unwrap();
+        assert_eq!(changed, vec!["editor.tab_size", "ui.theme"]);
+        assert_eq!(config.read().await.editor.tab_size, 2);
+
+        let mut received = Vec::new();
+        while let Ok(message) = events.try_recv() {
+            received.push(serde_json::from_value::<Event>(message.data).unwrap());
+        }
+        assert!(
+            matches!(&received[0], Event::Ui(UiEvent::ThemeChanged { theme }) if theme == "light")
+        );
+        assert!(matches!(
+            &received[1],
+            Event::System(SystemEvent::ConfigReloaded { changed: keys }) if *keys == changed
+        ));
+
+        assert!(ConfigWatcher::reload(&loader, &config, &event_bus).await.unwrap().is_empty());
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reload_rejects_invalid_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let loader = loader(temp_dir.path());
+        let config = RwLock::new(loader.load().unwrap());
+        let event_bus = EventBus::new();
+        let mut events = event_bus.subscribe(EventSubscription::all());
+
+        let project = temp_dir.path().join("project/.xylux-ide");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::write(project.join("config.toml"), "[editor]\ntab_size = 0\n").unwrap();
+
+        let error = ConfigWatcher::reload(&loader, &config, &event_bus).await.unwrap_err();
+        assert!(error.to_string().contains("editor.tab_size"), "{}", error);
+        assert_eq!(config.read().await.editor.tab_size, 4);
+
+        let message = events.try_recv().unwrap();
+        match serde_json::from_value::<Event>(message.data).unwrap() {
+            Event::Ui(UiEvent::StatusMessage { message, level }) => {
+                assert_eq!(level, "error");
+                assert!(message.contains("config.toml"), "{}", message);
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_config_change_filter() {
+        let layers = vec![PathBuf::from("/home/me/.config/xylux-ide")];
+        let event = |kind, path: &str| notify::Event::new(kind).add_path(PathBuf::from(path));
+        let modify = EventKind::Modify(notify::event::ModifyKind::Any);
+
+        assert!(is_config_change(&event(modify, "/home/me/.config/xylux-ide/config.ini"), &layers));
+        assert!(is_config_change(
+            &event(
+                EventKind::Create(notify::event::CreateKind::Folder),
+                "/home/me/.config/xylux-ide"
+            ),
+            &layers
+        ));
+        assert!(!is_config_change(
+            &event(modify, "/home/me/.config/xylux-ide/keymap.toml"),
+            &layers
+        ));
+        assert!(!is_config_change(&event(modify, "/home/me/.config/other/config.toml"), &layers));
+        let access = EventKind::Access(notify::event::AccessKind::Any);
+        assert!(!is_config_change(
+            &event(access, "/home/me/.config/xylux-ide/config.ini"),
+            &layers
+        ));
+    }
+}
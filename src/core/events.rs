@@ -61,6 +61,8 @@ pub enum Event {
 pub enum EditorEvent {
     /// Text was inserted at a specific position.
     TextInserted {
+        /// Path of the edited file.
+        path: PathBuf,
         /// Line number where text was inserted.
         line: usize,
         /// Column position where text was inserted.
@@ -70,6 +72,8 @@ pub enum EditorEvent {
     },
     /// Text was deleted from a range.
     TextDeleted {
+        /// Path of the edited file.
+        path: PathBuf,
         /// Starting line of deleted text.
         start_line: usize,
         /// Starting column of deleted text.
@@ -118,6 +122,14 @@ pub enum EditorEvent {
         query: String,
         /// Optional replacement string.
         replacement: Option<String>,
+        /// Whether the query is a regular expression.
+        regex: bool,
+        /// Whether matching is case sensitive.
+        case_sensitive: bool,
+        /// Whether only whole words match.
+        whole_word: bool,
+        /// Number of matches found or replaced.
+        count: usize,
     },
     /// Undo operation performed.
     Undo,
@@ -438,6 +450,50 @@ pub enum ProjectEvent {
         /// Path to the project whose dependencies were updated.
         path: PathBuf,
     },
+    /// Matches found in one file by a workspace search.
+    SearchResults {
+        /// Search query string.
+        query: String,
+        /// File the matches are in.
+        path: PathBuf,
+        /// The matches, in file order.
+        hits: Vec<SearchHit>,
+    },
+    /// Workspace search finished.
+    SearchCompleted {
+        /// Search query string.
+        query: String,
+        /// Number of files with matches.
+        files: usize,
+        /// Total number of matches.
+        matches: usize,
+    },
+    /// Workspace replacements were applied.
+    ReplaceCompleted {
+        /// Search query string.
+        query: String,
+        /// Number of files changed.
+        files: usize,
+        /// Total number of replacements.
+        replacements: usize,
+    },
+}
+
+/// A match found by a workspace search.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchHit {
+    /// Line where the match starts (0-indexed).
+    pub line: usize,
+    /// Byte column where the match starts.
+    pub column: usize,
+    /// Line where the match ends.
+    pub end_line: usize,
+    /// Byte column just past the match.
+    pub end_column: usize,
+    /// Text of the line the match starts on.
+    pub line_text: String,
+    /// What the match would be replaced with, when previewing a replace.
+    pub replacement: Option<String>,
 }
 
 /// Build system events.
@@ -587,7 +643,10 @@ pub enum SystemEvent {
     /// Shutdown requested.
     ShutdownRequested,
     /// Configuration reloaded.
-    ConfigReloaded,
+    ConfigReloaded {
+        /// Dotted keys of the values that changed, such as `editor.tab_size`.
+        changed: Vec<String>,
+    },
     /// Memory usage warning.
     MemoryWarning {
         /// Memory used in megabytes.
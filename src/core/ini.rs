@@ -0,0 +1,360 @@
+//! # INI Configuration
+//!
+//! Reader for configuration in the legacy `config.ini` format.
+//!
+//! A section names a part of [`Config`], such as `[editor]` or
+//! `[lsp.rust_analyzer]`, and its keys name the fields in it. Values take the
+//! type of their field: `true`/`false` (or `yes`/`no`, `on`/`off`), numbers,
+//! text with or without quotes, comma-separated lists, paths, and seconds for
+//! durations. Keys before the first section, and the names documented for
+//! earlier versions, are read through [`ALIASES`]. Table fields such as
+//! `[build.env_vars]` take keys of any name.
+
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use tracing::warn;
+
+use super::config::Config;
+use super::error::{Result, XyluxError};
+
+/// Other names for settings, as `(section, key, setting)`. An empty section
+/// is a key before the first section header.
+const ALIASES: &[(&str, &str, &str)] = &[
+    ("", "tab_stop", "editor.tab_size"),
+    ("", "tab_size", "editor.tab_size"),
+    ("", "show_line_numbers", "editor.show_line_numbers"),
+    ("", "quit_times", "advanced.quit_times"),
+    ("", "message_duration", "ui.message_duration"),
+    ("", "theme", "ui.theme"),
+    ("", "font_size", "ui.font_size"),
+    ("editor", "tab_stop", "editor.tab_size"),
+    ("editor", "tab_width", "editor.tab_size"),
+    ("editor", "theme", "ui.theme"),
+    ("editor", "font", "ui.font_family"),
+    ("editor", "font_size", "ui.font_size"),
+    ("alux", "lsp_enabled", "lsp.enabled"),
+    ("build", "target", "xylux.default_target"),
+    ("build", "wasm_opt", "xylux.wasm.optimize"),
+];
+
+/// Type of value a setting takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Bool,
+    Integer,
+    Float,
+    Text,
+    List,
+    Seconds,
+    /// An optional path; empty for none.
+    Path,
+    /// Any value, in JSON syntax or as text.
+    Any,
+}
+
+/// Optional settings, which have no value by default, and their types.
+/// Some are left out of the defaults altogether.
+const OPTIONAL: &[(&str, Kind)] = &[
+    ("editor.undo.directory", Kind::Path),
+    ("lsp.rust_analyzer.binary_path", Kind::Path),
+    ("lsp.alux_lsp.binary_path", Kind::Path),
+    ("build.xylux_cli_path", Kind::Path),
+    ("alux.compiler_path", Kind::Path),
+    ("alux.vm_path", Kind::Path),
+    ("xylux.engine_path", Kind::Path),
+    ("xylux.wasm.wasm_pack_path", Kind::Path),
+];
+
+impl Kind {
+    /// Get the type of `setting`, from [`OPTIONAL`] or its default value, or
+    /// `None` for a section or an unknown setting.
+    fn of(defaults: &Value, setting: &str) -> Option<Self> {
+        if let Some(&(_, kind)) = OPTIONAL.iter().find(|&&(name, _)| name == setting) {
+            return Some(kind);
+        }
+        let default = lookup(defaults, setting)?;
+        Some(match default {
+            Value::Bool(_) => Self::Bool,
+            Value::Number(number) if number.is_f64() => Self::Float,
+            Value::Number(_) => Self::Integer,
+            Value::String(_) => Self::Text,
+            Value::Array(_) => Self::List,
+            Value::Object(fields) if is_duration(fields) => Self::Seconds,
+            Value::Object(_) | Value::Null => return None,
+        })
+    }
+}
+
+/// Table fields, whose keys are chosen by the user.
+const TABLES: &[&str] = &["build.env_vars", "project.default_templates", "plugins.plugin_configs"];
+
+/// Read the settings in an INI file, as a table of the values it sets.
+///
+/// Unknown sections and keys are skipped with a warning. Lines that are not
+/// a section, a setting or a comment, and values that do not fit their
+/// setting, are errors naming their line.
+pub fn parse(path: &Path, content: &str) -> Result<Value> {
+    let defaults = serde_json::to_value(Config::default())
+        .map_err(|e| XyluxError::config_error(path, 0, e.to_string()))?;
+    let mut values = Value::Object(Map::new());
+    // Section of the keys being read, or `None` after an unknown section
+    let mut section = Some(String::new());
+
+    for (index, line) in content.lines().enumerate() {
+        let line_num = index + 1;
+        let error = |message: String| XyluxError::config_error(path, line_num, message);
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('[') {
+            let name = header
+                .strip_suffix(']')
+                .ok_or_else(|| error(format!("Expected `]` to close section `{line}`")))?
+                .trim();
+            section = match lookup(&defaults, name) {
+                Some(Value::Object(fields)) if !is_duration(fields) => Some(name.to_owned()),
+                _ => {
+                    warn!("{}:{}: unknown section [{}]", path.display(), line_num, name);
+                    None
+                }
+            };
+            continue;
+        }
+
+        let (key, raw) = line
+            .split_once('=')
+            .ok_or_else(|| error(format!("Expected `key = value`, found `{line}`")))?;
+        let (key, raw) = (key.trim(), raw.trim());
+        if key.is_empty() {
+            return Err(error("Expected a key before `=`".to_owned()));
+        }
+        let Some(section) = &section else {
+            continue;
+        };
+
+        let Some((setting, kind)) = resolve(&defaults, section, key) else {
+            let name = if section.is_empty() { key.to_owned() } else { format!("{section}.{key}") };
+            warn!("{}:{}: unknown setting `{}`", path.display(), line_num, name);
+            continue;
+        };
+        let value = parse_value(kind, raw).map_err(&error)?;
+
+        // Check the value against the setting's type, such as the range of a `u8`
+        let mut single = defaults.clone();
+        set(&mut single, &setting, value.clone());
+        if let Err(e) = Config::deserialize(&single) {
+            return Err(error(format!("Invalid value for `{setting}`: {e}")));
+        }
+        set(&mut values, &setting, value);
+    }
+
+    Ok(values)
+}
+
+/// Get the setting a key in a section sets and its type, or `None` if there
+/// is no such setting.
+fn resolve(defaults: &Value, section: &str, key: &str) -> Option<(String, Kind)> {
+    if let Some(&(_, _, setting)) =
+        ALIASES.iter().find(|&&(alias_section, alias, _)| alias_section == section && alias == key)
+    {
+        return Some((setting.to_owned(), Kind::of(defaults, setting)?));
+    }
+
+    let setting = if section.is_empty() { key.to_owned() } else { format!("{section}.{key}") };
+    if TABLES.contains(&section) {
+        // Plugin settings keep the type they are written in; other tables hold text
+        let kind = if section == "plugins.plugin_configs" { Kind::Any } else { Kind::Text };
+        return Some((setting, kind));
+    }
+    let kind = Kind::of(defaults, &setting)?;
+    Some((setting, kind))
+}
+
+/// Parse a value for a setting of type `kind`.
+fn parse_value(kind: Kind, raw: &str) -> std::result::Result<Value, String> {
+    let text = unquote(raw);
+    match kind {
+        Kind::Bool => match text.to_ascii_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Ok(Value::Bool(true)),
+            "false" | "no" | "off" | "0" => Ok(Value::Bool(false)),
+            _ => Err(format!("Expected true or false, found `{raw}`")),
+        },
+        Kind::Float => text
+            .parse::<f64>()
+            .map(Value::from)
+            .map_err(|_| format!("Expected a number, found `{raw}`")),
+        Kind::Integer => text
+            .parse::<u64>()
+            .map(Value::from)
+            .or_else(|_| text.parse::<i64>().map(Value::from))
+            .map_err(|_| format!("Expected a whole number, found `{raw}`")),
+        Kind::List => Ok(Value::Array(
+            text.split(',')
+                .map(|item| unquote(item.trim()))
+                .filter(|item| !item.is_empty())
+                .map(Value::from)
+                .collect(),
+        )),
+        Kind::Seconds => {
+            let seconds = text
+                .parse::<f64>()
+                .ok()
+                .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+                .ok_or_else(|| format!("Expected a number of seconds, found `{raw}`"))?;
+            let duration = Duration::from_secs_f64(seconds);
+            Ok(serde_json::json!({ "secs": duration.as_secs(), "nanos": duration.subsec_nanos() }))
+        }
+        Kind::Path if text.is_empty() => Ok(Value::Null),
+        Kind::Path | Kind::Text => Ok(Value::from(text)),
+        Kind::Any if raw.starts_with(['"', '\'']) => Ok(Value::from(text)),
+        Kind::Any => Ok(serde_json::from_str(raw).unwrap_or_else(|_| Value::from(text))),
+    }
+}
+
+/// Remove one pair of matching quotes around a value.
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value.strip_prefix(quote).and_then(|rest| rest.strip_suffix(quote)) {
+            return inner;
+        }
+    }
+    value
+}
+
+/// Check whether the fields are those of a serialized [`Duration`].
+fn is_duration(fields: &Map<String, Value>) -> bool {
+    fields.len() == 2 && fields.contains_key("secs") && fields.contains_key("nanos")
+}
+
+/// Get the value at a dotted path such as `lsp.rust_analyzer`.
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |value, key| value.get(key))
+}
+
+/// Set the value at a dotted path, adding tables on the way as needed.
+fn set(value: &mut Value, path: &str, new_value: Value) {
+    let mut keys = path.split('.').peekable();
+    let mut current = value;
+    while let Some(key) = keys.next() {
+        if !current.is_object() {
+            *current = Value::Object(Map::new());
+        }
+        let Value::Object(table) = current else {
+            return;
+        };
+        if keys.peek().is_none() {
+            table.insert(key.to_owned(), new_value);
+            return;
+        }
+        current = table.entry(key).or_insert_with(|| Value::Object(Map::new()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::merge_values;
+
+    fn config(content: &str) -> Config {
+        let mut values = serde_json::to_value(Config::default()).unwrap();
+        merge_values(&mut values, &parse(Path::new("config.ini"), content).unwrap());
+        Config::deserialize(&values).unwrap()
+    }
+
+    fn error_line(content: &str) -> usize {
+        match parse(Path::new("config.ini"), content) {
+            Err(XyluxError::Config { line, .. }) => line,
+            other => panic!("expected a config error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_sections_and_aliases() {
+        let config = config(
+            "[editor]\ntheme = \"light\"\nfont = JetBrains Mono\n\
+             tab_width = 2\nvim_mode = yes\n\n[alux]\nlsp_enabled = false\nhot_reload = true\n\
+             vm_log_level = \"error\"\n\n[build]\ntarget = native\nwasm_opt = true\n\
+             xylux_cli_path = /opt/xylux\n\n[build.env_vars]\nRUST_LOG = debug\n\n\
+             [lsp.rust_analyzer]\ncargo_features = serde, \"tokio\"\n\n[ui]\ntransparency = 0.5\n\n\
+             [plugins.plugin_configs]\nlint = {\"strict\": true}\n",
+        );
+        assert_eq!(config.ui.theme, "light");
+        assert_eq!(config.ui.font_family, "JetBrains Mono");
+        assert_eq!(config.editor.tab_size, 2);
+        assert!(config.editor.vim_mode);
+        assert!(!config.lsp.enabled);
+        assert!(config.alux.hot_reload);
+        assert_eq!(config.alux.vm_log_level, "error");
+        assert_eq!(config.xylux.default_target, "native");
+        assert!(config.xylux.wasm.optimize);
+        assert_eq!(config.build.xylux_cli_path, Some("/opt/xylux".into()));
+        assert_eq!(config.build.env_vars.get("RUST_LOG").map(String::as_str), Some("debug"));
+        assert_eq!(config.lsp.rust_analyzer.cargo_features, vec!["serde", "tokio"]);
+        assert_eq!(config.ui.transparency, 0.5);
+        assert_eq!(config.plugins.plugin_configs["lint"], serde_json::json!({ "strict": true }));
+    }
+
+    #[test]
+    fn test_example_config() {
+        let content = include_str!("../../config_example.ini");
+        let config = config(content);
+        assert_eq!(config.editor.tab_size, 4);
+        assert_eq!(config.advanced.quit_times, 2);
+        assert_eq!(config.ui.message_duration, Duration::from_secs(3));
+        assert!(config.editor.show_line_numbers);
+    }
+
+    #[test]
+    fn test_optional_settings() {
+        fn nulls(value: &Value, path: &str, found: &mut Vec<String>) {
+            match value {
+                Value::Null => found.push(path.to_owned()),
+                Value::Object(fields) => {
+                    for (key, field) in fields {
+                        let path =
+                            if path.is_empty() { key.clone() } else { format!("{path}.{key}") };
+                        nulls(field, &path, found);
+                    }
+                }
+                Value::Bool(_) | Value::Number(_) | Value::String(_) | Value::Array(_) => {}
+            }
+        }
+
+        let defaults = serde_json::to_value(Config::default()).unwrap();
+        let mut found = Vec::new();
+        nulls(&defaults, "", &mut found);
+        for setting in found {
+            assert!(OPTIONAL.iter().any(|&(name, _)| name == setting), "{}", setting);
+        }
+
+        let config =
+            config("[alux]\ncompiler_path = /opt/aluxc\n[editor.undo]\ndirectory = /tmp/undo\n");
+        assert_eq!(config.alux.compiler_path, Some("/opt/aluxc".into()));
+        assert_eq!(config.editor.undo.directory, Some("/tmp/undo".into()));
+    }
+
+    #[test]
+    fn test_errors_and_unknown_keys() {
+        assert_eq!(error_line("[editor]\ntab_size = four\n"), 2);
+        assert_eq!(error_line("# comment\n[editor\n"), 2);
+        assert_eq!(error_line("[editor]\n\nshow_whitespace\n"), 3);
+        assert_eq!(error_line("[alux]\noptimization_level = 300\n"), 2);
+        assert_eq!(error_line("[ui]\nmessage_duration = soon\n"), 2);
+
+        let parsed = parse(
+            Path::new("config.ini"),
+            "[editor]\nsparkles = true\n[status_bar]\nshow_encoding = no\n[ui.status_bar]\n\
+             show_encoding = no\n",
+        )
+        .unwrap();
+        assert_eq!(
+            parsed,
+            serde_json::json!({ "ui": { "status_bar": { "show_encoding": false } } })
+        );
+    }
+}
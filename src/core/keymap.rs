@@ -0,0 +1,709 @@
+//! # Keymap
+//!
+//! Keyboard shortcuts for IDE commands, loaded from `keymap.toml` files.
+//!
+//! The built-in bindings are layered with the user's
+//! `<config dir>/xylux-ide/keymap.toml` and then the project's
+//! `.xylux-ide/keymap.toml`. A file lists bindings as
+//!
+//! ```toml
+//! [[bindings]]
+//! key = "Ctrl+K Ctrl+C"
+//! command = "edit.comment"
+//! when = "editorFocus"
+//!
+//! [[bindings]]
+//! key = "Ctrl+B"
+//! command = "-view.file_explorer"
+//! ```
+//!
+//! `key` is a chord of one or more space-separated keystrokes. `when`
+//! restricts a binding to `editorFocus` or `fileTreeFocus`; without it the
+//! binding is global. A command prefixed with `-` removes that binding
+//! instead of adding one. A binding replaces the bindings of earlier layers
+//! for the same chord and context.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::util::line_of;
+use crate::core::{Result, XyluxError};
+
+/// Name of keymap files in configuration directories.
+pub const KEYMAP_FILE: &str = "keymap.toml";
+
+/// Built-in bindings: chord, command and context.
+const DEFAULT_BINDINGS: &[(&str, &str, KeyContext)] = &[
+    ("Ctrl+N", "file.new", KeyContext::Global),
+    ("Ctrl+O", "file.open", KeyContext::Global),
+    ("Ctrl+P", "file.go_to", KeyContext::Global),
+    ("Ctrl+S", "file.save", KeyContext::Global),
+    ("Ctrl+W", "file.close", KeyContext::Global),
+    ("Ctrl+Q", "app.quit", KeyContext::Global),
+    ("Ctrl+Z", "edit.undo", KeyContext::EditorFocus),
+    ("Ctrl+Shift+Z", "edit.redo", KeyContext::EditorFocus),
+    ("Ctrl+Y", "edit.redo", KeyContext::EditorFocus),
+    ("Ctrl+B", "view.file_explorer", KeyContext::Global),
+    ("Ctrl+Shift+P", "view.command_palette", KeyContext::Global),
+    ("Shift+Alt+F", "code.format", KeyContext::EditorFocus),
+    ("F12", "code.definition", KeyContext::EditorFocus),
+    ("Shift+F12", "code.references", KeyContext::EditorFocus),
+    ("F2", "code.rename", KeyContext::EditorFocus),
+    ("Ctrl+K Ctrl+S", "help.shortcuts", KeyContext::Global),
+];
+
+/// Named keys, as reported by the GUI toolkit.
+const NAMED_KEYS: &[&str] = &[
+    "Down",
+    "Left",
+    "Right",
+    "Up",
+    "Escape",
+    "Tab",
+    "Backspace",
+    "Enter",
+    "Insert",
+    "Delete",
+    "Home",
+    "End",
+    "PageUp",
+    "PageDown",
+    "Space",
+    "Colon",
+    "Comma",
+    "Minus",
+    "Period",
+    "Plus",
+    "Equals",
+    "Semicolon",
+    "Backslash",
+    "Slash",
+    "Pipe",
+    "Questionmark",
+    "OpenBracket",
+    "CloseBracket",
+    "Backtick",
+    "Quote",
+];
+
+/// Alternative spellings of named keys.
+const KEY_ALIASES: &[(&str, &str)] = &[
+    ("arrowdown", "Down"),
+    ("arrowleft", "Left"),
+    ("arrowright", "Right"),
+    ("arrowup", "Up"),
+    ("esc", "Escape"),
+    ("return", "Enter"),
+    ("del", "Delete"),
+    ("ins", "Insert"),
+    ("pgup", "PageUp"),
+    ("pgdn", "PageDown"),
+    ("pgdown", "PageDown"),
+    (":", "Colon"),
+    (",", "Comma"),
+    ("-", "Minus"),
+    (".", "Period"),
+    ("+", "Plus"),
+    ("=", "Equals"),
+    (";", "Semicolon"),
+    ("\\", "Backslash"),
+    ("/", "Slash"),
+    ("|", "Pipe"),
+    ("?", "Questionmark"),
+    ("[", "OpenBracket"),
+    ("]", "CloseBracket"),
+    ("`", "Backtick"),
+    ("'", "Quote"),
+];
+
+/// Where keyboard focus is, which decides the bindings that apply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum KeyContext {
+    /// Anywhere in the IDE.
+    #[default]
+    #[serde(rename = "global")]
+    Global,
+    /// The text editor has focus.
+    #[serde(rename = "editorFocus")]
+    EditorFocus,
+    /// The file tree has focus.
+    #[serde(rename = "fileTreeFocus")]
+    FileTreeFocus,
+}
+
+impl fmt::Display for KeyContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Global => write!(f, "global"),
+            Self::EditorFocus => write!(f, "editorFocus"),
+            Self::FileTreeFocus => write!(f, "fileTreeFocus"),
+        }
+    }
+}
+
+/// A single key press with modifiers, such as `Ctrl+Shift+P`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyStroke {
+    /// Control, or Command on macOS.
+    pub ctrl: bool,
+    /// Alt, or Option on macOS.
+    pub alt: bool,
+    /// Shift.
+    pub shift: bool,
+    /// Key name: a letter, digit, `F1` to `F35` or a named key such as `Enter`.
+    pub key: String,
+}
+
+impl KeyStroke {
+    /// Parse a keystroke such as `Ctrl+Shift+P`, `Alt+Enter` or `F12`.
+    ///
+    /// # Errors
+    ///
+    /// Fails with a message for an unknown key or modifier.
+    pub fn parse(text: &str) -> std::result::Result<Self, String> {
+        let text = text.trim();
+        // A trailing `++` is the plus key after a modifier separator.
+        let (modifiers, key) = match text.strip_suffix("++") {
+            Some(modifiers) => (modifiers, "+"),
+            None if text == "+" => ("", text),
+            None => text.rsplit_once('+').unwrap_or(("", text)),
+        };
+
+        let mut stroke =
+            Self { ctrl: false, alt: false, shift: false, key: canonical_key(key.trim())? };
+        for modifier in modifiers.split('+').filter(|modifier| !modifier.is_empty()) {
+            match modifier.trim().to_ascii_lowercase().as_str() {
+                "ctrl" | "control" | "cmd" | "command" => stroke.ctrl = true,
+                "alt" | "option" | "opt" => stroke.alt = true,
+                "shift" => stroke.shift = true,
+                other => return Err(format!("Unknown modifier '{other}' in '{text}'")),
+            }
+        }
+        Ok(stroke)
+    }
+}
+
+impl fmt::Display for KeyStroke {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+/// Normalize a key name to the spelling the GUI toolkit reports.
+fn canonical_key(key: &str) -> std::result::Result<String, String> {
+    if key.is_empty() {
+        return Err("Missing key".to_owned());
+    }
+    if key.len() == 1 && key.as_bytes()[0].is_ascii_alphanumeric() {
+        return Ok(key.to_ascii_uppercase());
+    }
+
+    let lower = key.to_ascii_lowercase();
+    if let Some(number) = lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+        if (1..=35).contains(&number) {
+            return Ok(format!("F{number}"));
+        }
+    }
+    if let Some(&(_, name)) = KEY_ALIASES.iter().find(|(alias, _)| *alias == lower) {
+        return Ok(name.to_owned());
+    }
+    NAMED_KEYS
+        .iter()
+        .find(|name| name.eq_ignore_ascii_case(key))
+        .map(|name| (*name).to_owned())
+        .ok_or_else(|| format!("Unknown key '{key}'"))
+}
+
+/// A sequence of keystrokes, such as `Ctrl+K Ctrl+C`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyChord(pub Vec<KeyStroke>);
+
+impl KeyChord {
+    /// Parse a chord of space-separated keystrokes.
+    ///
+    /// # Errors
+    ///
+    /// Fails with a message for an empty chord or an invalid keystroke.
+    pub fn parse(text: &str) -> std::result::Result<Self, String> {
+        let strokes =
+            text.split_whitespace().map(KeyStroke::parse).collect::<std::result::Result<_, _>>()?;
+        let chord = Self(strokes);
+        if chord.0.is_empty() {
+            return Err("Empty key chord".to_owned());
+        }
+        Ok(chord)
+    }
+
+    /// Check whether this chord begins with all of `strokes`.
+    fn starts_with(&self, strokes: &[KeyStroke]) -> bool {
+        self.0.starts_with(strokes)
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, stroke) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{stroke}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A chord bound to a command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyBinding {
+    /// Keys that run the command.
+    pub chord: KeyChord,
+    /// Identifier of the command.
+    pub command: String,
+    /// Where the binding applies.
+    pub when: KeyContext,
+}
+
+/// Two bindings that get in each other's way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeymapConflict {
+    /// One chord is bound to several commands in the same context.
+    Duplicate {
+        /// The chord.
+        chord: KeyChord,
+        /// The context of the bindings.
+        when: KeyContext,
+        /// The commands bound to the chord.
+        commands: Vec<String>,
+    },
+    /// A chord runs a command before a longer chord starting with it can be typed.
+    Shadowed {
+        /// The shorter chord.
+        prefix: KeyChord,
+        /// The command the shorter chord runs.
+        command: String,
+        /// The unreachable chord.
+        chord: KeyChord,
+        /// The command of the unreachable chord.
+        shadowed: String,
+        /// The context of the bindings.
+        when: KeyContext,
+    },
+}
+
+impl fmt::Display for KeymapConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Duplicate { chord, when, commands } => {
+                write!(f, "{} ({}) is bound to {}", chord, when, commands.join(", "))
+            }
+            Self::Shadowed { prefix, command, chord, shadowed, when } => {
+                write!(f, "{prefix} ({when}) runs {command} before {chord} can reach {shadowed}")
+            }
+        }
+    }
+}
+
+/// What a keystroke did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyResolution {
+    /// A chord was completed; run the command.
+    Command(String),
+    /// The keys so far start a chord; wait for more.
+    Pending(KeyChord),
+    /// The keys so far started a chord that the last key did not continue.
+    Cancelled(KeyChord),
+    /// The key is not bound and should reach the focused widget.
+    Unbound,
+}
+
+/// A binding as written in a keymap file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BindingEntry {
+    /// The chord, with its location in the file for error messages.
+    key: toml::Spanned<String>,
+    /// Command identifier, or `-` and an identifier to unbind.
+    command: String,
+    /// Where the binding applies; global when omitted.
+    #[serde(default)]
+    when: KeyContext,
+}
+
+/// Contents of a keymap file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeymapFile {
+    #[serde(default)]
+    bindings: Vec<BindingEntry>,
+}
+
+/// Key bindings for IDE commands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keymap {
+    /// Bindings in the order they were added.
+    bindings: Vec<KeyBinding>,
+}
+
+impl Keymap {
+    /// Create a keymap without bindings.
+    #[must_use]
+    pub const fn empty() -> Self {
+        Self { bindings: Vec::new() }
+    }
+
+    /// Create a keymap with the built-in bindings.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a built-in binding does not parse, which the tests rule out.
+    #[must_use]
+    #[expect(clippy::expect_used, reason = "the built-in bindings are checked by the tests")]
+    pub fn with_defaults() -> Self {
+        let bindings = DEFAULT_BINDINGS
+            .iter()
+            .map(|&(chord, command, when)| KeyBinding {
+                chord: KeyChord::parse(chord).expect("built-in key chords are valid"),
+                command: command.to_owned(),
+                when,
+            })
+            .collect();
+        Self { bindings }
+    }
+
+    /// Load the built-in bindings layered with the user keymap and, when a
+    /// project root is given, the project keymap.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a keymap file cannot be read or is invalid.
+    pub fn load(project_root: Option<&Path>) -> Result<Self> {
+        let mut keymap = Self::with_defaults();
+        for path in Self::layer_paths(project_root) {
+            if path.exists() {
+                keymap.apply_file(&path)?;
+            }
+        }
+        Ok(keymap)
+    }
+
+    /// Keymap files layered over the built-in bindings, in order.
+    #[must_use]
+    pub fn layer_paths(project_root: Option<&Path>) -> Vec<PathBuf> {
+        let user = dirs::config_dir().map(|dir| dir.join("xylux-ide").join(KEYMAP_FILE));
+        let project = project_root.map(|root| root.join(".xylux-ide").join(KEYMAP_FILE));
+        user.into_iter().chain(project).collect()
+    }
+
+    /// Layer the bindings of a keymap file over this keymap.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file cannot be read or is invalid.
+    pub fn apply_file(&mut self, path: &Path) -> Result<()> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| XyluxError::config_error(path, 0, e.to_string()))?;
+        self.apply_toml(path, &content)
+    }
+
+    /// Layer the bindings of keymap file `content`, read from `path`.
+    ///
+    /// # Errors
+    ///
+    /// Fails with the offending line if the content is not a valid keymap.
+    pub fn apply_toml(&mut self, path: &Path, content: &str) -> Result<()> {
+        let file: KeymapFile = toml::from_str(content).map_err(|e| {
+            let line = e.span().map_or(0, |span| line_of(content, span.start));
+            XyluxError::config_error(path, line, e.message())
+        })?;
+
+        let mut layer = Vec::new();
+        let mut unbind = Vec::new();
+        for entry in file.bindings {
+            let chord = KeyChord::parse(entry.key.get_ref()).map_err(|message| {
+                XyluxError::config_error(path, line_of(content, entry.key.span().start), message)
+            })?;
+            match entry.command.strip_prefix('-') {
+                Some(command) => unbind.push((chord, command.to_owned(), entry.when)),
+                None => layer.push(KeyBinding { chord, command: entry.command, when: entry.when }),
+            }
+        }
+
+        self.bindings.retain(|binding| {
+            let replaced =
+                layer.iter().any(|new| new.chord == binding.chord && new.when == binding.when);
+            let removed = unbind.iter().any(|(chord, command, when)| {
+                *chord == binding.chord && *command == binding.command && *when == binding.when
+            });
+            !replaced && !removed
+        });
+        self.bindings.extend(layer);
+        Ok(())
+    }
+
+    /// Get all bindings.
+    #[must_use]
+    pub fn bindings(&self) -> &[KeyBinding] {
+        &self.bindings
+    }
+
+    /// Get the bindings that run `command`.
+    #[must_use]
+    pub fn bindings_for(&self, command: &str) -> Vec<&KeyBinding> {
+        self.bindings.iter().filter(|binding| binding.command == command).collect()
+    }
+
+    /// Find bindings that cannot all work as written.
+    pub fn conflicts(&self) -> Vec<KeymapConflict> {
+        let mut conflicts = Vec::new();
+
+        let mut by_chord: HashMap<(&KeyChord, KeyContext), Vec<&str>> = HashMap::new();
+        for binding in &self.bindings {
+            let commands = by_chord.entry((&binding.chord, binding.when)).or_default();
+            if !commands.contains(&binding.command.as_str()) {
+                commands.push(&binding.command);
+            }
+        }
+        for binding in &self.bindings {
+            let Some(commands) = by_chord.remove(&(&binding.chord, binding.when)) else {
+                continue;
+            };
+            if commands.len() > 1 {
+                conflicts.push(KeymapConflict::Duplicate {
+                    chord: binding.chord.clone(),
+                    when: binding.when,
+                    commands: commands.into_iter().map(str::to_owned).collect(),
+                });
+            }
+        }
+
+        for prefix in &self.bindings {
+            for binding in &self.bindings {
+                if binding.when == prefix.when
+                    && binding.chord.0.len() > prefix.chord.0.len()
+                    && binding.chord.starts_with(&prefix.chord.0)
+                {
+                    conflicts.push(KeymapConflict::Shadowed {
+                        prefix: prefix.chord.clone(),
+                        command: prefix.command.clone(),
+                        chord: binding.chord.clone(),
+                        shadowed: binding.command.clone(),
+                        when: binding.when,
+                    });
+                }
+            }
+        }
+
+        conflicts
+    }
+
+    /// Resolve a keystroke typed in `context`, following on from the
+    /// keystrokes in `pending`, which is updated for the next call.
+    ///
+    /// Bindings for the focused context take precedence over global ones.
+    pub fn resolve(
+        &self,
+        pending: &mut Vec<KeyStroke>,
+        stroke: KeyStroke,
+        context: KeyContext,
+    ) -> KeyResolution {
+        pending.push(stroke);
+
+        let mut tiers = vec![context];
+        if context != KeyContext::Global {
+            tiers.push(KeyContext::Global);
+        }
+        for when in tiers {
+            let active = || self.bindings.iter().filter(move |binding| binding.when == when);
+            if let Some(binding) = active().find(|binding| binding.chord.0 == *pending) {
+                pending.clear();
+                return KeyResolution::Command(binding.command.clone());
+            }
+            if active().any(|binding| binding.chord.starts_with(pending)) {
+                return KeyResolution::Pending(KeyChord(pending.clone()));
+            }
+        }
+
+        let typed = KeyChord(std::mem::take(pending));
+        if typed.0.len() > 1 { KeyResolution::Cancelled(typed) } else { KeyResolution::Unbound }
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::with_defaults()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stroke(text: &str) -> KeyStroke {
+        KeyStroke::parse(text).unwrap()
+    }
+
+    #[test]
+    fn test_parse_keys() {
+        assert_eq!(stroke("ctrl+shift+p").to_string(), "Ctrl+Shift+P");
+        assert_eq!(stroke("Cmd+Alt+/").to_string(), "Ctrl+Alt+Slash");
+        assert_eq!(stroke("Ctrl++").to_string(), "Ctrl+Plus");
+        assert_eq!(stroke("+").to_string(), "Plus");
+        assert_eq!(stroke("esc").to_string(), "Escape");
+        assert_eq!(stroke("f12").key, "F12");
+        assert_eq!(KeyChord::parse("Ctrl+K  Ctrl+C").unwrap().to_string(), "Ctrl+K Ctrl+C");
+        assert!(KeyStroke::parse("Hyper+K").is_err());
+        assert!(KeyStroke::parse("Ctrl+F99").is_err());
+        assert!(KeyChord::parse(" ").is_err());
+    }
+
+    #[test]
+    fn test_layering_and_errors() {
+        let path = Path::new("keymap.toml");
+        let mut keymap = Keymap::with_defaults();
+        keymap
+            .apply_toml(
+                path,
+                r#"
+[[bindings]]
+key = "Ctrl+P"
+command = "view.command_palette"
+
+[[bindings]]
+key = "Ctrl+B"
+command = "-view.file_explorer"
+"#,
+            )
+            .unwrap();
+
+        let palette: Vec<String> = keymap
+            .bindings_for("view.command_palette")
+            .iter()
+            .map(|binding| binding.chord.to_string())
+            .collect();
+        assert_eq!(palette, vec!["Ctrl+Shift+P", "Ctrl+P"]);
+        assert!(keymap.bindings_for("file.go_to").is_empty());
+        assert!(keymap.bindings_for("view.file_explorer").is_empty());
+        assert!(keymap.conflicts().is_empty());
+
+        let error = keymap
+            .apply_toml(path, "[[bindings]]\ncommand = \"x\"\n\n[[bindings]]\nkey = \"Ctrl+Nope\"\ncommand = \"y\"\n")
+            .unwrap_err();
+        assert!(matches!(error, XyluxError::Config { line: 1, .. }), "{:?}", error);
+        let error = keymap
+            .apply_toml(path, "[[bindings]]\ncommand = \"y\"\nkey = \"Ctrl+Nope\"\n")
+            .unwrap_err();
+        assert!(matches!(error, XyluxError::Config { line: 3, .. }), "{:?}", error);
+    }
+
+    #[test]
+    fn test_chords_and_contexts() {
+        let mut keymap = Keymap::with_defaults();
+        keymap
+            .apply_toml(
+                Path::new("keymap.toml"),
+                r#"
+[[bindings]]
+key = "Ctrl+K Ctrl+C"
+command = "edit.comment"
+when = "editorFocus"
+
+[[bindings]]
+key = "Delete"
+command = "file_tree.delete"
+when = "fileTreeFocus"
+"#,
+            )
+            .unwrap();
+        let mut pending = Vec::new();
+        let mut press = |text: &str, context| keymap.resolve(&mut pending, stroke(text), context);
+
+        assert_eq!(
+            press("Ctrl+K", KeyContext::EditorFocus),
+            KeyResolution::Pending(KeyChord(vec![stroke("Ctrl+K")]))
+        );
+        assert_eq!(
+            press("Ctrl+C", KeyContext::EditorFocus),
+            KeyResolution::Command("edit.comment".into())
+        );
+        assert_eq!(
+            press("Ctrl+K", KeyContext::EditorFocus),
+            KeyResolution::Pending(KeyChord(vec![stroke("Ctrl+K")]))
+        );
+        assert_eq!(
+            press("S", KeyContext::EditorFocus),
+            KeyResolution::Cancelled(KeyChord(vec![stroke("Ctrl+K"), stroke("S")]))
+        );
+        assert_eq!(
+            press("Ctrl+K", KeyContext::Global),
+            KeyResolution::Pending(KeyChord(vec![stroke("Ctrl+K")]))
+        );
+        assert_eq!(
+            press("Ctrl+S", KeyContext::Global),
+            KeyResolution::Command("help.shortcuts".into())
+        );
+
+        assert_eq!(
+            press("Delete", KeyContext::FileTreeFocus),
+            KeyResolution::Command("file_tree.delete".into())
+        );
+        assert_eq!(press("Delete", KeyContext::EditorFocus), KeyResolution::Unbound);
+        assert_eq!(press("Ctrl+Z", KeyContext::Global), KeyResolution::Unbound);
+        assert_eq!(
+            press("Ctrl+Z", KeyContext::EditorFocus),
+            KeyResolution::Command("edit.undo".into())
+        );
+        assert_eq!(
+            press("Ctrl+S", KeyContext::FileTreeFocus),
+            KeyResolution::Command("file.save".into())
+        );
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let mut keymap = Keymap::empty();
+        keymap
+            .apply_toml(
+                Path::new("keymap.toml"),
+                r#"
+[[bindings]]
+key = "Ctrl+K"
+command = "a"
+
+[[bindings]]
+key = "Ctrl+K"
+command = "b"
+
+[[bindings]]
+key = "Ctrl+K Ctrl+C"
+command = "c"
+
+[[bindings]]
+key = "Ctrl+K Ctrl+D"
+command = "d"
+when = "editorFocus"
+"#,
+            )
+            .unwrap();
+
+        let conflicts = keymap.conflicts();
+        assert_eq!(conflicts.len(), 3, "{:?}", conflicts);
+        assert_eq!(conflicts[0].to_string(), "Ctrl+K (global) is bound to a, b");
+        assert_eq!(
+            conflicts[1].to_string(),
+            "Ctrl+K (global) runs a before Ctrl+K Ctrl+C can reach c"
+        );
+        assert!(Keymap::with_defaults().conflicts().is_empty());
+    }
+}
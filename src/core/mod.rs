@@ -3,16 +3,22 @@
 //! Core functionality and shared components for Xylux IDE.
 
 pub mod config;
+pub mod config_watcher;
 pub mod error;
 pub mod events;
+mod ini;
+pub mod keymap;
+mod util;
 
-pub use config::{Config, ConfigLoader};
+pub use config::{Config, ConfigLayer, ConfigLoader, ConfigOrigin, LayeredConfig};
+pub use config_watcher::ConfigWatcher;
 pub use error::{Result, ResultExt, XyluxError};
 pub use events::{
     AluxEvent, BuildEvent, EditorEvent, Event, EventBus, EventHandler, EventMessage, EventPriority,
-    EventSubscription, FileSystemEvent, LspEvent, PluginEvent, ProjectEvent, SystemEvent, UiEvent,
-    XyluxEvent,
+    EventSubscription, FileSystemEvent, LspEvent, PluginEvent, ProjectEvent, SearchHit,
+    SystemEvent, UiEvent, XyluxEvent,
 };
+pub use keymap::{KeyBinding, KeyChord, KeyContext, KeyResolution, KeyStroke, Keymap};
 
 /// Version information for Xylux IDE.
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -0,0 +1,24 @@
+//! # Utilities
+//!
+//! Small helpers shared by the core modules.
+
+/// The 1-based line containing byte `offset` of `content`.
+///
+/// Offsets past the end count as being on the last line.
+pub fn line_of(content: &str, offset: usize) -> usize {
+    content[..offset.min(content.len())].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_of() {
+        let content = "one\ntwo\n";
+        assert_eq!(line_of(content, 0), 1);
+        assert_eq!(line_of(content, 3), 1);
+        assert_eq!(line_of(content, 4), 2);
+        assert_eq!(line_of(content, 100), 3);
+    }
+}
@@ -1,25 +1,37 @@
 //! # Buffer Module
 //!
 //! Text buffer management for the editor.
-
+//!
+//! Buffers store their text in a [`Rope`], so inserts and deletes cost
+//! O(log n) regardless of file size and snapshots share structure with the
+//! live text. Line endings are normalized to `\n` internally and restored
+//! when the content is read back. Line and column positions are byte offsets,
+//! columns relative to the start of their line.
+
+use std::borrow::Cow;
+use std::ops::{Range, RangeInclusive};
 use std::path::PathBuf;
 use std::time::SystemTime;
 
+use ropey::{Rope, RopeSlice};
+use unicode_width::UnicodeWidthChar;
+
+use super::search::{SearchMatch, SearchOptions, SearchQuery};
 use crate::core::{Result, XyluxError};
 
 /// A text buffer that holds the content of a file.
 #[derive(Debug, Clone)]
 pub struct Buffer {
-    /// The text content of the buffer.
-    lines: Vec<String>,
+    /// The text content of the buffer, with `\n` line endings.
+    text: Rope,
     /// The file path associated with this buffer (if any).
     file_path: Option<PathBuf>,
     /// Whether the buffer has been modified since last save.
     modified: bool,
     /// Timestamp of last modification.
     last_modified: SystemTime,
-    /// Original content for comparison.
-    original_content: String,
+    /// Snapshot of the content as last loaded or saved.
+    saved: Rope,
     /// File encoding.
     encoding: String,
     /// Line ending style.
@@ -27,7 +39,7 @@ pub struct Buffer {
 }
 
 /// Line ending styles.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum LineEnding {
     /// Unix-style line endings (\n).
     Unix,
@@ -39,128 +51,292 @@ pub enum LineEnding {
 
 impl Buffer {
     /// Create a new buffer with the given content.
-    pub fn new(content: String, file_path: Option<PathBuf>) -> Self {
-        let line_ending = Self::detect_line_ending(&content);
-        let lines = Self::split_lines(&content, &line_ending);
+    #[must_use]
+    pub fn new(content: &str, file_path: Option<PathBuf>) -> Self {
+        let line_ending = Self::detect_line_ending(content);
+        let text = Rope::from_str(&Self::normalize_line_endings(content, &line_ending));
 
         Self {
-            lines,
+            saved: text.clone(),
+            text,
             file_path,
             modified: false,
             last_modified: SystemTime::now(),
-            original_content: content,
-            encoding: "UTF-8".to_string(),
+            encoding: "UTF-8".to_owned(),
             line_ending,
         }
     }
 
     /// Create an empty buffer.
+    #[must_use]
     pub fn empty() -> Self {
-        Self::new(String::new(), None)
+        Self::new("", None)
     }
 
     /// Get the complete content of the buffer.
+    #[must_use]
     pub fn get_content(&self) -> String {
-        self.lines.join(&self.line_ending.to_string())
+        match self.line_ending {
+            LineEnding::Unix => self.text.to_string(),
+            LineEnding::Windows | LineEnding::Mac => {
+                self.text.to_string().replace('\n', self.line_ending.as_str())
+            }
+        }
     }
 
-    /// Get a specific line from the buffer.
-    pub fn get_line(&self, line_index: usize) -> Option<&String> {
-        self.lines.get(line_index)
+    /// Get a specific line from the buffer, without its line ending.
+    pub fn get_line(&self, line_index: usize) -> Option<String> {
+        self.line_slice(line_index).map(String::from)
+    }
+
+    /// Borrow a line from the buffer without copying it.
+    #[must_use]
+    pub fn line_slice(&self, line_index: usize) -> Option<RopeSlice<'_>> {
+        if line_index >= self.line_count() {
+            return None;
+        }
+        let (start, end) = self.line_bounds(line_index);
+        Some(self.text.byte_slice(start..end))
     }
 
     /// Get the number of lines in the buffer.
+    #[must_use]
     pub fn line_count(&self) -> usize {
-        self.lines.len()
+        self.text.len_lines()
     }
 
     /// Get the length of a specific line.
+    #[must_use]
     pub fn line_length(&self, line_index: usize) -> usize {
-        self.lines.get(line_index).map_or(0, |line| line.len())
+        if line_index >= self.line_count() {
+            return 0;
+        }
+        let (start, end) = self.line_bounds(line_index);
+        end - start
+    }
+
+    /// Get the length of the buffer in bytes.
+    #[must_use]
+    pub fn len_bytes(&self) -> usize {
+        self.text.len_bytes()
+    }
+
+    /// Get the length of the buffer in characters.
+    #[must_use]
+    pub fn len_chars(&self) -> usize {
+        self.text.len_chars()
+    }
+
+    /// Convert a line index to the byte offset of its start.
+    #[must_use]
+    pub fn line_to_byte(&self, line: usize) -> usize {
+        self.text.line_to_byte(line.min(self.line_count()))
+    }
+
+    /// Convert a line index to the char offset of its start.
+    #[must_use]
+    pub fn line_to_char(&self, line: usize) -> usize {
+        self.text.line_to_char(line.min(self.line_count()))
+    }
+
+    /// Convert a byte offset to the index of the line containing it.
+    #[must_use]
+    pub fn byte_to_line(&self, byte: usize) -> usize {
+        self.text.byte_to_line(byte.min(self.len_bytes()))
+    }
+
+    /// Convert a char offset to the index of the line containing it.
+    #[must_use]
+    pub fn char_to_line(&self, char_index: usize) -> usize {
+        self.text.char_to_line(char_index.min(self.len_chars()))
+    }
+
+    /// Convert a byte offset to a char offset. Offsets inside a multi-byte
+    /// character map to that character.
+    #[must_use]
+    pub fn byte_to_char(&self, byte: usize) -> usize {
+        self.text.byte_to_char(byte.min(self.len_bytes()))
+    }
+
+    /// Convert a char offset to a byte offset.
+    #[must_use]
+    pub fn char_to_byte(&self, char_index: usize) -> usize {
+        self.text.char_to_byte(char_index.min(self.len_chars()))
+    }
+
+    /// Convert a line and column to a byte offset, clamping the column to the line.
+    ///
+    /// Offsets count `\n` line endings, whatever the buffer's [`LineEnding`].
+    #[must_use]
+    pub fn position_to_byte(&self, line: usize, column: usize) -> usize {
+        if line >= self.line_count() {
+            return self.len_bytes();
+        }
+        let (start, end) = self.line_bounds(line);
+        start + column.min(end - start)
+    }
+
+    /// Clamp a byte column to a line's length, rounding down to a character boundary.
+    #[must_use]
+    pub fn clamp_column(&self, line: usize, column: usize) -> usize {
+        let byte = self.position_to_byte(line, column);
+        self.char_to_byte(self.byte_to_char(byte)) - self.line_to_byte(line)
+    }
+
+    /// Display width of a line up to a byte column.
+    ///
+    /// Widths come from `unicode-width`; tabs advance to the next multiple of
+    /// `tab_size` and other control characters take no space.
+    #[must_use]
+    pub fn display_column(&self, line: usize, column: usize, tab_size: usize) -> usize {
+        let Some(slice) = self.line_slice(line) else {
+            return 0;
+        };
+
+        let mut width = 0;
+        let mut offset = 0;
+        for ch in slice.chars() {
+            if offset >= column {
+                break;
+            }
+            width = advance_display_column(width, ch, tab_size);
+            offset += ch.len_utf8();
+        }
+        width
+    }
+
+    /// Byte column of the first character of a line starting at or after a
+    /// display column, or the line length when the line is narrower.
+    #[must_use]
+    pub fn column_at_display(&self, line: usize, display_column: usize, tab_size: usize) -> usize {
+        let Some(slice) = self.line_slice(line) else {
+            return 0;
+        };
+
+        let mut width = 0;
+        let mut offset = 0;
+        for ch in slice.chars() {
+            if width >= display_column {
+                break;
+            }
+            width = advance_display_column(width, ch, tab_size);
+            offset += ch.len_utf8();
+        }
+        offset
+    }
+
+    /// Display width of a whole line.
+    #[must_use]
+    pub fn display_width(&self, line: usize, tab_size: usize) -> usize {
+        self.display_column(line, usize::MAX, tab_size)
+    }
+
+    /// Get the text of a rectangle, one string per line.
+    ///
+    /// A character belongs to the rectangle when it starts inside `columns`,
+    /// which are display columns. Lines ending before the rectangle give
+    /// empty strings.
+    #[must_use]
+    pub fn block_text(
+        &self,
+        lines: RangeInclusive<usize>,
+        columns: Range<usize>,
+        tab_size: usize,
+    ) -> Vec<String> {
+        lines
+            .take_while(|&line| line < self.line_count())
+            .map(|line| {
+                let start = self.column_at_display(line, columns.start, tab_size);
+                let end = self.column_at_display(line, columns.end, tab_size);
+                self.get_text_range(line, start, line, end).unwrap_or_default()
+            })
+            .collect()
+    }
+
+    /// Convert a byte offset to a line and column.
+    #[must_use]
+    pub fn byte_to_position(&self, byte: usize) -> (usize, usize) {
+        let byte = byte.min(self.len_bytes());
+        let line = self.text.byte_to_line(byte);
+        (line, byte - self.text.line_to_byte(line))
+    }
+
+    /// Take a snapshot of the current text.
+    ///
+    /// Snapshots share structure with the buffer, so this is cheap regardless
+    /// of size and later edits do not affect the snapshot.
+    #[must_use]
+    pub fn snapshot(&self) -> Rope {
+        self.text.clone()
+    }
+
+    /// Get the text as it was when the buffer was loaded or last saved.
+    #[must_use]
+    pub const fn saved_snapshot(&self) -> &Rope {
+        &self.saved
     }
 
     /// Insert text at the specified position.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the line does not exist, or the column is past the end of the
+    /// line or inside a character.
     pub fn insert_text(&mut self, line: usize, column: usize, text: &str) -> Result<()> {
-        if line >= self.lines.len() {
+        if line >= self.line_count() {
             return Err(XyluxError::syntax_error(format!(
                 "Line index {} out of bounds (max: {})",
                 line,
-                self.lines.len()
+                self.line_count()
             )));
         }
 
-        let current_line = &mut self.lines[line];
-        if column > current_line.len() {
+        let line_length = self.line_length(line);
+        if column > line_length {
             return Err(XyluxError::syntax_error(format!(
-                "Column index {} out of bounds for line {} (max: {})",
-                column,
-                line,
-                current_line.len()
+                "Column index {column} out of bounds for line {line} (max: {line_length})"
             )));
         }
 
-        // Handle multi-line text insertion
-        if text.contains('\n') {
-            self.insert_multiline_text(line, column, text)?;
-        } else {
-            // Simple single-line insertion
-            current_line.insert_str(column, text);
+        let char_index = self.char_at(line, column);
+        if self.text.char_to_byte(char_index) != self.line_to_byte(line) + column {
+            return Err(XyluxError::syntax_error(format!(
+                "Column index {column} is not on a character boundary in line {line}"
+            )));
         }
 
+        self.text.insert(char_index, text);
         self.mark_modified();
         Ok(())
     }
 
-    /// Insert multi-line text.
-    fn insert_multiline_text(&mut self, line: usize, column: usize, text: &str) -> Result<()> {
-        let lines_to_insert = text.split('\n').collect::<Vec<_>>();
-        let current_line = &mut self.lines[line];
-
-        if lines_to_insert.len() == 1 {
-            // Single line, just insert
-            current_line.insert_str(column, text);
-            return Ok(());
-        }
-
-        // Split the current line at the insertion point
-        let after_cursor = current_line.split_off(column);
-
-        // Insert the first part of the new text
-        current_line.push_str(lines_to_insert[0]);
-
-        // Insert the middle lines
-        for (i, &line_text) in lines_to_insert.iter().enumerate().skip(1) {
-            if i == lines_to_insert.len() - 1 {
-                // Last line: add the text and the remaining part of the original line
-                self.lines.insert(line + i, format!("{}{}", line_text, after_cursor));
-            } else {
-                // Middle lines: just add the text
-                self.lines.insert(line + i, line_text.to_string());
-            }
-        }
-
-        Ok(())
-    }
-
     /// Delete text from the buffer.
+    ///
+    /// # Errors
+    ///
+    /// Never fails; positions outside the buffer are ignored.
     pub fn delete_text(&mut self, line: usize, column: usize, count: usize) -> Result<()> {
-        if line >= self.lines.len() {
+        if line >= self.line_count() {
             return Ok(()); // Nothing to delete
         }
 
-        let current_line = &mut self.lines[line];
-        if column >= current_line.len() {
+        if column >= self.line_length(line) {
             return Ok(()); // Nothing to delete
         }
 
-        let end_pos = (column + count).min(current_line.len());
-        current_line.drain(column..end_pos);
+        let start = self.char_at(line, column);
+        let end = self.char_at(line, column + count);
+        self.text.remove(start..end);
 
         self.mark_modified();
         Ok(())
     }
 
     /// Delete a range of text.
+    ///
+    /// # Errors
+    ///
+    /// Fails if either line does not exist.
     pub fn delete_range(
         &mut self,
         start_line: usize,
@@ -168,30 +344,14 @@ impl Buffer {
         end_line: usize,
         end_col: usize,
     ) -> Result<()> {
-        if start_line >= self.lines.len() || end_line >= self.lines.len() {
-            return Err(XyluxError::syntax_error("Line range out of bounds".to_string()));
+        if start_line >= self.line_count() || end_line >= self.line_count() {
+            return Err(XyluxError::syntax_error("Line range out of bounds".to_owned()));
         }
 
-        if start_line == end_line {
-            // Single line deletion
-            let line = &mut self.lines[start_line];
-            let start = start_col.min(line.len());
-            let end = end_col.min(line.len());
-            if start < end {
-                line.drain(start..end);
-            }
-        } else {
-            // Multi-line deletion
-            let start_line_content =
-                &self.lines[start_line][..start_col.min(self.lines[start_line].len())];
-            let end_line_content = &self.lines[end_line][end_col.min(self.lines[end_line].len())..];
-
-            // Create the merged line
-            let merged_line = format!("{}{}", start_line_content, end_line_content);
-
-            // Remove the lines in between and replace the start line
-            self.lines.drain(start_line + 1..=end_line);
-            self.lines[start_line] = merged_line;
+        let start = self.char_at(start_line, start_col);
+        let end = self.char_at(end_line, end_col);
+        if start < end {
+            self.text.remove(start..end);
         }
 
         self.mark_modified();
@@ -199,55 +359,83 @@ impl Buffer {
     }
 
     /// Insert a new line at the specified position.
-    pub fn insert_line(&mut self, line: usize, content: String) -> Result<()> {
-        if line > self.lines.len() {
+    ///
+    /// # Errors
+    ///
+    /// Fails if the line is past the end of the buffer.
+    pub fn insert_line(&mut self, line: usize, content: &str) -> Result<()> {
+        let line_count = self.line_count();
+        if line > line_count {
             return Err(XyluxError::syntax_error(format!(
-                "Line index {} out of bounds (max: {})",
-                line,
-                self.lines.len()
+                "Line index {line} out of bounds (max: {line_count})"
             )));
         }
 
-        self.lines.insert(line, content);
+        if line == line_count {
+            self.text.insert(self.len_chars(), &format!("\n{content}"));
+        } else {
+            self.text.insert(self.text.line_to_char(line), &format!("{content}\n"));
+        }
         self.mark_modified();
         Ok(())
     }
 
     /// Delete a line from the buffer.
+    ///
+    /// # Errors
+    ///
+    /// Never fails; positions outside the buffer are ignored.
     pub fn delete_line(&mut self, line: usize) -> Result<()> {
-        if line >= self.lines.len() {
+        let line_count = self.line_count();
+        if line >= line_count {
             return Ok(()); // Nothing to delete
         }
 
-        self.lines.remove(line);
+        let mut start = self.text.line_to_char(line);
+        let end = self.text.line_to_char(line + 1);
+        if line + 1 == line_count && line > 0 {
+            // The last line has no line ending of its own; take the previous one.
+            start -= 1;
+        }
+        self.text.remove(start..end);
+
         self.mark_modified();
         Ok(())
     }
 
     /// Replace the content of a line.
-    pub fn replace_line(&mut self, line: usize, content: String) -> Result<()> {
-        if line >= self.lines.len() {
+    ///
+    /// # Errors
+    ///
+    /// Fails if the line does not exist.
+    pub fn replace_line(&mut self, line: usize, content: &str) -> Result<()> {
+        if line >= self.line_count() {
             return Err(XyluxError::syntax_error(format!(
                 "Line index {} out of bounds (max: {})",
                 line,
-                self.lines.len()
+                self.line_count()
             )));
         }
 
-        self.lines[line] = content;
+        let start = self.text.line_to_char(line);
+        let end = self.char_at(line, usize::MAX);
+        self.text.remove(start..end);
+        self.text.insert(start, content);
+
         self.mark_modified();
         Ok(())
     }
 
     /// Check if the buffer has been modified.
-    pub fn is_modified(&self) -> bool {
+    #[must_use]
+    pub const fn is_modified(&self) -> bool {
         self.modified
     }
 
     /// Mark the buffer as saved (not modified).
     pub fn mark_saved(&mut self) {
         self.modified = false;
-        self.original_content = self.get_content();
+        self.saved = self.text.clone();
     }
 
     /// Mark the buffer as modified.
@@ -257,7 +445,8 @@ impl Buffer {
     }
 
     /// Get the file path associated with this buffer.
-    pub fn file_path(&self) -> Option<&PathBuf> {
+    #[must_use]
+    pub const fn file_path(&self) -> Option<&PathBuf> {
         self.file_path.as_ref()
     }
 
@@ -267,12 +456,14 @@ impl Buffer {
     }
 
     /// Get the encoding of the buffer.
+    #[must_use]
     pub fn encoding(&self) -> &str {
         &self.encoding
     }
 
     /// Get the line ending style.
-    pub fn line_ending(&self) -> &LineEnding {
+    #[must_use]
+    pub const fn line_ending(&self) -> &LineEnding {
         &self.line_ending
     }
 
@@ -283,64 +474,73 @@ impl Buffer {
     }
 
     /// Get the last modification time.
-    pub fn last_modified(&self) -> SystemTime {
+    #[must_use]
+    pub const fn last_modified(&self) -> SystemTime {
         self.last_modified
     }
 
     /// Delete a single character at the specified position.
+    ///
+    /// # Errors
+    ///
+    /// Never fails; positions outside the buffer are ignored.
     pub fn delete_char(&mut self, line: usize, column: usize) -> Result<()> {
-        if line >= self.lines.len() {
+        if line >= self.line_count() {
             return Ok(()); // Nothing to delete
         }
 
-        if column >= self.lines[line].len() {
+        if column >= self.line_length(line) {
             return Ok(()); // Nothing to delete
         }
 
-        self.lines[line].remove(column);
+        let start = self.char_at(line, column);
+        self.text.remove(start..=start);
         self.mark_modified();
         Ok(())
     }
 
     /// Join the current line with the next line.
+    ///
+    /// # Errors
+    ///
+    /// Never fails; positions outside the buffer are ignored.
     pub fn join_lines(&mut self, line: usize) -> Result<()> {
-        if line >= self.lines.len() || line + 1 >= self.lines.len() {
+        if line + 1 >= self.line_count() {
             return Ok(()); // Nothing to join
         }
 
-        let next_line = self.lines.remove(line + 1);
-        self.lines[line].push_str(&next_line);
+        let line_end = self.char_at(line, usize::MAX);
+        self.text.remove(line_end..=line_end);
         self.mark_modified();
         Ok(())
     }
 
     /// Insert a newline at the specified position, splitting the line.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the line does not exist.
     pub fn insert_newline(&mut self, line: usize, column: usize) -> Result<()> {
-        if line >= self.lines.len() {
+        if line >= self.line_count() {
             return Err(XyluxError::syntax_error(format!(
                 "Line index {} out of bounds (max: {})",
                 line,
-                self.lines.len() - 1
+                self.line_count() - 1
             )));
         }
 
-        let current_line_content = self.lines[line].clone();
-        let (left, right) = if column <= current_line_content.len() {
-            current_line_content.split_at(column)
-        } else {
-            (current_line_content.as_str(), "")
-        };
-
-        // Replace current line with left part
-        self.lines[line] = left.to_string();
-        // Insert new line with right part
-        self.lines.insert(line + 1, right.to_string());
+        let char_index = self.char_at(line, column);
+        self.text.insert_char(char_index, '\n');
 
         self.mark_modified();
         Ok(())
     }
 
     /// Get text in a specific range.
+    ///
+    /// # Errors
+    ///
+    /// Fails if either line does not exist.
     pub fn get_text_range(
         &self,
         start_line: usize,
@@ -348,126 +548,119 @@ impl Buffer {
         end_line: usize,
         end_col: usize,
     ) -> Result<String> {
-        if start_line >= self.lines.len() || end_line >= self.lines.len() {
-            return Err(XyluxError::syntax_error("Line range out of bounds".to_string()));
-        }
-
-        if start_line == end_line {
-            // Same line
-            let line = &self.lines[start_line];
-            let start = start_col.min(line.len());
-            let end = end_col.min(line.len());
-            return Ok(line[start..end].to_string());
+        if start_line >= self.line_count() || end_line >= self.line_count() {
+            return Err(XyluxError::syntax_error("Line range out of bounds".to_owned()));
         }
 
-        let mut result = String::new();
-
-        // First line (from start_col to end)
-        let first_line = &self.lines[start_line];
-        let start = start_col.min(first_line.len());
-        result.push_str(&first_line[start..]);
-        result.push('\n');
-
-        // Middle lines (complete lines)
-        for line_idx in start_line + 1..end_line {
-            result.push_str(&self.lines[line_idx]);
-            result.push('\n');
-        }
-
-        // Last line (from start to end_col)
-        let last_line = &self.lines[end_line];
-        let end = end_col.min(last_line.len());
-        result.push_str(&last_line[..end]);
-
-        Ok(result)
+        let start = self.char_at(start_line, start_col);
+        let end = self.char_at(end_line, end_col).max(start);
+        Ok(self.text.slice(start..end).to_string())
     }
 
     /// Find all occurrences of a pattern in the buffer.
+    #[must_use]
     pub fn find_all(&self, pattern: &str) -> Vec<(usize, usize)> {
-        let mut matches = Vec::new();
-
-        for (line_idx, line) in self.lines.iter().enumerate() {
-            let mut start = 0;
-            while let Some(pos) = line[start..].find(pattern) {
-                matches.push((line_idx, start + pos));
-                start = start + pos + 1;
-            }
-        }
-
-        matches
+        self.find(pattern, true)
     }
 
     /// Find text in the buffer.
+    #[must_use]
     pub fn find(&self, query: &str, case_sensitive: bool) -> Vec<(usize, usize)> {
-        let mut matches = Vec::new();
-
-        for (line_idx, line) in self.lines.iter().enumerate() {
-            let search_line = if case_sensitive { line.clone() } else { line.to_lowercase() };
-
-            let search_query =
-                if case_sensitive { query.to_string() } else { query.to_lowercase() };
-
-            let mut start = 0;
-            while let Some(pos) = search_line[start..].find(&search_query) {
-                matches.push((line_idx, start + pos));
-                start += pos + 1;
-            }
-        }
-
-        matches
+        literal_query(query, case_sensitive).map_or_else(
+            |_| Vec::new(),
+            |query| self.search(&query).into_iter().map(|found| found.start).collect(),
+        )
     }
 
     /// Replace text in the buffer.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the query cannot be compiled.
     pub fn replace(
         &mut self,
         query: &str,
         replacement: &str,
         case_sensitive: bool,
     ) -> Result<usize> {
-        let matches = self.find(query, case_sensitive);
-        let mut replaced_count = 0;
+        self.replace_all(&literal_query(query, case_sensitive)?, replacement)
+    }
+
+    /// Find all matches of a search query.
+    #[must_use]
+    pub fn search(&self, query: &SearchQuery) -> Vec<SearchMatch> {
+        let text: Cow<'_, str> = self.text.slice(..).into();
+        query.find_in(&text).into_iter().map(|range| self.search_match(range)).collect()
+    }
+
+    /// Find all matches of a search query along with their replacement,
+    /// with capture groups expanded in regex mode.
+    #[must_use]
+    pub fn search_replacements(
+        &self,
+        query: &SearchQuery,
+        replacement: &str,
+    ) -> Vec<(SearchMatch, String)> {
+        let text: Cow<'_, str> = self.text.slice(..).into();
+        query
+            .replacements_in(&text, replacement)
+            .into_iter()
+            .map(|(range, text)| (self.search_match(range), text))
+            .collect()
+    }
+
+    /// Replace every match of a search query, returning how many were replaced.
+    ///
+    /// # Errors
+    ///
+    /// Never fails; positions outside the buffer are ignored.
+    pub fn replace_all(&mut self, query: &SearchQuery, replacement: &str) -> Result<usize> {
+        let replacements = self.search_replacements(query, replacement);
 
         // Process matches in reverse order to maintain correct positions
-        for (line_idx, col_idx) in matches.into_iter().rev() {
-            if let Some(line) = self.lines.get_mut(line_idx) {
-                let search_query = if case_sensitive {
-                    query
-                } else {
-                    // For case-insensitive replacement, we need to find the actual match
-                    let start = col_idx;
-                    let end = start + query.len();
-                    if end <= line.len() {
-                        let actual_match = &line[start..end];
-                        if actual_match.to_lowercase() == query.to_lowercase() {
-                            line.replace_range(start..end, replacement);
-                            replaced_count += 1;
-                        }
-                    }
-                    continue;
-                };
-
-                if line[col_idx..].starts_with(search_query) {
-                    let end = col_idx + query.len();
-                    line.replace_range(col_idx..end, replacement);
-                    replaced_count += 1;
-                }
-            }
+        for (found, text) in replacements.iter().rev() {
+            let start = self.text.byte_to_char(found.range.start);
+            let end = self.text.byte_to_char(found.range.end);
+            self.text.remove(start..end);
+            self.text.insert(start, text);
         }
 
-        if replaced_count > 0 {
+        if !replacements.is_empty() {
             self.mark_modified();
         }
 
-        Ok(replaced_count)
+        Ok(replacements.len())
+    }
+
+    /// Locate a byte range found by a search.
+    fn search_match(&self, range: Range<usize>) -> SearchMatch {
+        SearchMatch {
+            start: self.byte_to_position(range.start),
+            end: self.byte_to_position(range.end),
+            range,
+        }
     }
 
     /// Get a range of lines.
-    pub fn get_lines(&self, start: usize, end: usize) -> Vec<&String> {
-        let end = end.min(self.lines.len());
-        if start >= end {
-            return Vec::new();
+    #[must_use]
+    pub fn get_lines(&self, start: usize, end: usize) -> Vec<String> {
+        let end = end.min(self.line_count());
+        (start..end).filter_map(|line| self.get_line(line)).collect()
+    }
+
+    /// Byte range of a line's content, excluding its line ending.
+    fn line_bounds(&self, line: usize) -> (usize, usize) {
+        let start = self.text.line_to_byte(line);
+        let mut end = self.text.line_to_byte(line + 1);
+        if end > start && self.text.byte(end - 1) == b'\n' {
+            end -= 1;
         }
-        self.lines[start..end].iter().collect()
+        (start, end)
+    }
+
+    /// Char offset of a line and byte column, clamping the column to the line.
+    fn char_at(&self, line: usize, column: usize) -> usize {
+        self.text.byte_to_char(self.position_to_byte(line, column))
     }
 
     /// Detect line ending style from content.
@@ -481,32 +674,56 @@ impl Buffer {
         }
     }
 
-    /// Split content into lines based on line ending style.
-    fn split_lines(content: &str, line_ending: &LineEnding) -> Vec<String> {
+    /// Convert content to `\n` line endings based on its line ending style.
+    fn normalize_line_endings<'a>(content: &'a str, line_ending: &LineEnding) -> Cow<'a, str> {
         match line_ending {
-            LineEnding::Windows => content.split("\r\n").map(String::from).collect(),
-            LineEnding::Mac => content.split('\r').map(String::from).collect(),
-            LineEnding::Unix => content.split('\n').map(String::from).collect(),
+            LineEnding::Windows => Cow::Owned(content.replace("\r\n", "\n")),
+            LineEnding::Mac => Cow::Owned(content.replace('\r', "\n")),
+            LineEnding::Unix => Cow::Borrowed(content),
+        }
+    }
+}
+
+/// Compile a literal search for the plain find and replace methods.
+fn literal_query(query: &str, case_sensitive: bool) -> Result<SearchQuery> {
+    SearchQuery::new(query, SearchOptions { case_sensitive, ..SearchOptions::default() })
+}
+
+/// Display column reached after `text` when it starts at display column `start`.
+#[must_use]
+pub fn text_display_end(start: usize, text: &str, tab_size: usize) -> usize {
+    text.chars().fold(start, |width, ch| advance_display_column(width, ch, tab_size))
+}
+
+/// Display column after `ch` when it starts at display column `width`.
+fn advance_display_column(width: usize, ch: char, tab_size: usize) -> usize {
+    match ch {
+        '\t' => {
+            let tab_size = tab_size.max(1);
+            (width / tab_size + 1) * tab_size
         }
+        _ => width + ch.width().unwrap_or(0),
     }
 }
 
 impl LineEnding {
-    /// Convert line ending to string representation.
-    pub fn to_string(&self) -> String {
+    /// Get the characters that end a line in this style.
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
         match self {
-            LineEnding::Unix => "\n".to_string(),
-            LineEnding::Windows => "\r\n".to_string(),
-            LineEnding::Mac => "\r".to_string(),
+            Self::Unix => "\n",
+            Self::Windows => "\r\n",
+            Self::Mac => "\r",
         }
     }
 
     /// Get the display name of the line ending.
-    pub fn display_name(&self) -> &'static str {
+    #[must_use]
+    pub const fn display_name(&self) -> &'static str {
         match self {
-            LineEnding::Unix => "LF",
-            LineEnding::Windows => "CRLF",
-            LineEnding::Mac => "CR",
+            Self::Unix => "LF",
+            Self::Windows => "CRLF",
+            Self::Mac => "CR",
         }
     }
 }
@@ -518,17 +735,17 @@ mod tests {
     #[test]
     fn test_buffer_creation() {
         let content = "Hello\nWorld\n";
-        let buffer = Buffer::new(content.to_string(), None);
+        let buffer = Buffer::new(&content, None);
 
         assert_eq!(buffer.line_count(), 3); // Empty line at end
-        assert_eq!(buffer.get_line(0), Some(&"Hello".to_string()));
-        assert_eq!(buffer.get_line(1), Some(&"World".to_string()));
+        assert_eq!(buffer.get_line(0), Some("Hello".to_string()));
+        assert_eq!(buffer.get_line(1), Some("World".to_string()));
         assert_eq!(buffer.line_ending(), &LineEnding::Unix);
     }
 
     #[test]
     fn test_text_insertion() {
-        let mut buffer = Buffer::new("Hello World".to_string(), None);
+        let mut buffer = Buffer::new("Hello World", None);
 
         buffer.insert_text(0, 5, ", Beautiful").unwrap();
         assert_eq!(buffer.get_content(), "Hello, Beautiful World");
@@ -537,7 +754,7 @@ mod tests {
 
     #[test]
     fn test_text_deletion() {
-        let mut buffer = Buffer::new("Hello World".to_string(), None);
+        let mut buffer = Buffer::new("Hello World", None);
 
         buffer.delete_text(0, 5, 6).unwrap(); // Delete " World"
         assert_eq!(buffer.get_content(), "Hello");
@@ -546,20 +763,20 @@ mod tests {
 
     #[test]
     fn test_line_operations() {
-        let mut buffer = Buffer::new("Line 1\nLine 2".to_string(), None);
+        let mut buffer = Buffer::new("Line 1\nLine 2", None);
 
-        buffer.insert_line(1, "New Line".to_string()).unwrap();
+        buffer.insert_line(1, "New Line").unwrap();
         assert_eq!(buffer.line_count(), 3);
-        assert_eq!(buffer.get_line(1), Some(&"New Line".to_string()));
+        assert_eq!(buffer.get_line(1), Some("New Line".to_string()));
 
         buffer.delete_line(1).unwrap();
         assert_eq!(buffer.line_count(), 2);
-        assert_eq!(buffer.get_line(1), Some(&"Line 2".to_string()));
+        assert_eq!(buffer.get_line(1), Some("Line 2".to_string()));
     }
 
     #[test]
     fn test_find_and_replace() {
-        let mut buffer = Buffer::new("Hello world, hello universe".to_string(), None);
+        let mut buffer = Buffer::new("Hello world, hello universe", None);
 
         let matches = buffer.find("hello", false);
         assert_eq!(matches.len(), 2);
@@ -571,6 +788,21 @@ mod tests {
         assert_eq!(buffer.get_content(), "hi world, hi universe");
     }
 
+    #[test]
+    fn test_regex_search() {
+        let mut buffer = Buffer::new("fn one() {}\r\nfn two() {}\r\n", None);
+        let options = SearchOptions { regex: true, ..SearchOptions::default() };
+        let query = SearchQuery::new(r"^FN (\w+)\(\) \{\}\nfn", options).unwrap();
+
+        let matches = buffer.search(&query);
+        assert_eq!(matches.len(), 1);
+        assert_eq!((matches[0].start, matches[0].end), ((0, 0), (1, 2)));
+
+        let query = SearchQuery::new(r"fn (\w+)", options).unwrap();
+        assert_eq!(buffer.replace_all(&query, "fn ${1}_renamed").unwrap(), 2);
+        assert_eq!(buffer.get_content(), "fn one_renamed() {}\r\nfn two_renamed() {}\r\n");
+    }
+
     #[test]
     fn test_line_ending_detection() {
         let unix_content = "line1\nline2\n";
@@ -584,12 +816,91 @@ mod tests {
 
     #[test]
     fn test_multiline_insertion() {
-        let mut buffer = Buffer::new("Line 1\nLine 3".to_string(), None);
+        let mut buffer = Buffer::new("Line 1\nLine 3", None);
 
         buffer.insert_text(0, 6, "\nLine 2").unwrap();
         assert_eq!(buffer.line_count(), 3);
-        assert_eq!(buffer.get_line(0), Some(&"Line 1".to_string()));
-        assert_eq!(buffer.get_line(1), Some(&"Line 2".to_string()));
-        assert_eq!(buffer.get_line(2), Some(&"Line 3".to_string()));
+        assert_eq!(buffer.get_line(0), Some("Line 1".to_string()));
+        assert_eq!(buffer.get_line(1), Some("Line 2".to_string()));
+        assert_eq!(buffer.get_line(2), Some("Line 3".to_string()));
+    }
+
+    #[test]
+    fn test_index_conversions() {
+        let buffer = Buffer::new("héllo\nwörld\n", None);
+
+        assert_eq!(buffer.len_bytes(), 14);
+        assert_eq!(buffer.len_chars(), 12);
+        assert_eq!(buffer.line_to_byte(1), 7);
+        assert_eq!(buffer.line_to_char(1), 6);
+        assert_eq!(buffer.byte_to_line(8), 1);
+        assert_eq!(buffer.char_to_line(5), 0);
+        assert_eq!(buffer.byte_to_char(3), 2);
+        assert_eq!(buffer.char_to_byte(2), 3);
+        assert_eq!(buffer.position_to_byte(1, 3), 10);
+        assert_eq!(buffer.byte_to_position(10), (1, 3));
+        assert_eq!(buffer.line_length(1), 6);
+
+        let mut buffer = buffer;
+        assert!(buffer.insert_text(0, 2, "x").is_err()); // Inside "é"
+        buffer.insert_text(0, 3, "x").unwrap();
+        assert_eq!(buffer.get_line(0), Some("héxllo".to_string()));
+    }
+
+    #[test]
+    fn test_snapshots() {
+        let mut buffer = Buffer::new("one\ntwo", None);
+        let snapshot = buffer.snapshot();
+
+        buffer.replace_line(1, "three").unwrap();
+        assert_eq!(snapshot.to_string(), "one\ntwo");
+        assert_eq!(buffer.saved_snapshot().to_string(), "one\ntwo");
+
+        buffer.mark_saved();
+        assert_eq!(buffer.saved_snapshot().to_string(), "one\nthree");
+        assert!(!buffer.is_modified());
+    }
+
+    #[test]
+    fn test_windows_line_endings_round_trip() {
+        let mut buffer = Buffer::new("a\r\nb\r\n", None);
+
+        assert_eq!(buffer.line_count(), 3);
+        assert_eq!(buffer.line_length(0), 1);
+        buffer.join_lines(0).unwrap();
+        buffer.insert_newline(0, 1).unwrap();
+        buffer.delete_line(2).unwrap();
+        assert_eq!(buffer.get_content(), "a\r\nb");
+    }
+
+    #[test]
+    fn test_large_buffer_edits() {
+        let content = "0123456789\n".repeat(100_000);
+        let mut buffer = Buffer::new(&content, None);
+
+        buffer.insert_text(50_000, 5, "inserted").unwrap();
+        buffer.delete_range(10, 0, 99_990, 0).unwrap();
+        assert_eq!(buffer.line_count(), 21);
+        assert_eq!(buffer.get_line(10), Some("0123456789".to_string()));
+        assert_eq!(buffer.get_text_range(9, 8, 10, 2).unwrap(), "89\n01");
+    }
+
+    #[test]
+    fn test_display_columns() {
+        let buffer = Buffer::new("a漢字b\n\tx\nshort", None);
+
+        assert_eq!(buffer.display_column(0, 4, 4), 3); // After "a漢"
+        assert_eq!(buffer.display_width(0, 4), 6);
+        assert_eq!(buffer.column_at_display(0, 3, 4), 4);
+        assert_eq!(buffer.column_at_display(0, 2, 4), 4); // Inside "漢"
+        assert_eq!(buffer.column_at_display(0, 10, 4), 8);
+        assert_eq!(buffer.display_column(1, 1, 4), 4);
+        assert_eq!(buffer.column_at_display(1, 4, 4), 1);
+        assert_eq!(text_display_end(2, "\t漢", 4), 6);
+
+        assert_eq!(
+            buffer.block_text(0..=3, 1..5, 4),
+            vec!["漢字".to_string(), "x".to_string(), "hort".to_string()]
+        );
     }
 }
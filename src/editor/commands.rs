@@ -4,11 +4,13 @@
 
 use std::path::PathBuf;
 
+use serde::{Deserialize, Serialize};
+
 use super::{Buffer, Cursor, Selection};
 use crate::core::{Result, XyluxError};
 
 /// Represents an editor command that can be undone/redone.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Command {
     /// The type of command.
     pub command_type: CommandType,
@@ -16,12 +18,12 @@ pub struct Command {
     pub buffer_path: Option<PathBuf>,
     /// Whether this command has been executed.
     pub executed: bool,
-    /// Timestamp when the command was created.
+    /// Timestamp of the command's latest change, including merged ones.
     pub timestamp: std::time::SystemTime,
 }
 
 /// Different types of editor commands.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CommandType {
     /// Insert text at a position.
     InsertText { line: usize, column: usize, text: String },
@@ -147,7 +149,7 @@ impl Command {
             return Ok(()); // Already executed
         }
 
-        match &self.command_type {
+        match &mut self.command_type {
             CommandType::InsertText { line, column, text } => {
                 buffer.insert_text(*line, *column, text)?;
             }
@@ -166,7 +168,7 @@ impl Command {
                 buffer.insert_text(*start_line, *start_column, new_text)?;
             }
             CommandType::InsertLine { line, content } => {
-                buffer.insert_line(*line, content.clone())?;
+                buffer.insert_line(*line, content)?;
             }
             CommandType::DeleteLine { line, .. } => {
                 buffer.delete_line(*line)?;
@@ -178,9 +180,8 @@ impl Command {
                 // Selection change doesn't affect buffer
             }
             CommandType::Composite { commands, .. } => {
-                for cmd in commands {
-                    let mut cmd_clone = cmd.clone();
-                    cmd_clone.execute(buffer)?;
+                for cmd in commands.iter_mut() {
+                    cmd.execute(buffer)?;
                 }
             }
         }
@@ -195,7 +196,7 @@ impl Command {
             return Ok(()); // Not executed, nothing to undo
         }
 
-        match &self.command_type {
+        match &mut self.command_type {
             CommandType::InsertText { line, column, text } => {
                 buffer.delete_text(*line, *column, text.len())?;
             }
@@ -214,7 +215,7 @@ impl Command {
                     *start_line,
                     *start_column,
                     *start_line,
-                    start_column + new_text.len(),
+                    *start_column + new_text.len(),
                 )?;
                 buffer.insert_text(*start_line, *start_column, old_text)?;
             }
@@ -222,7 +223,7 @@ impl Command {
                 buffer.delete_line(*line)?;
             }
             CommandType::DeleteLine { line, content } => {
-                buffer.insert_line(*line, content.clone())?;
+                buffer.insert_line(*line, content)?;
             }
             CommandType::MoveCursor { .. } => {
                 // Cursor movement undo is handled differently
@@ -232,9 +233,8 @@ impl Command {
             }
             CommandType::Composite { commands, .. } => {
                 // Undo composite commands in reverse order
-                for cmd in commands.iter().rev() {
-                    let mut cmd_clone = cmd.clone();
-                    cmd_clone.undo(buffer)?;
+                for cmd in commands.iter_mut().rev() {
+                    cmd.undo(buffer)?;
                 }
             }
         }
@@ -255,8 +255,8 @@ impl Command {
                 CommandType::InsertText { line: line1, column: column1, text: text1 },
                 CommandType::InsertText { line: line2, column: column2, text: _text2 },
             ) => {
-                // Merge if inserting at consecutive positions
-                *line1 == *line2 && *column1 + text1.len() == *column2
+                // Merge if inserting at consecutive positions on one line
+                !text1.contains('\n') && *line1 == *line2 && *column1 + text1.len() == *column2
             }
             (
                 CommandType::DeleteText { line: line1, column: column1, .. },
@@ -275,6 +275,7 @@ impl Command {
             return Err(XyluxError::syntax_error("Cannot merge incompatible commands".to_string()));
         }
 
+        self.timestamp = self.timestamp.max(other.timestamp);
         match (&mut self.command_type, other.command_type) {
             (
                 CommandType::InsertText { text: text1, .. },
@@ -348,31 +349,90 @@ impl Command {
     }
 }
 
+/// A change to make to a buffer when moving through the history.
+#[derive(Debug, Clone)]
+pub enum HistoryStep {
+    /// Revert a command.
+    Undo(Command),
+    /// Apply a command again.
+    Redo(Command),
+}
+
+/// The tip of one branch of the undo tree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryBranch {
+    /// Node of the branch's last command, for [`CommandHistory::goto`].
+    /// Valid until the history changes.
+    pub id: usize,
+    /// Description of the branch's last command.
+    pub description: String,
+    /// Time of the branch's last change.
+    pub timestamp: std::time::SystemTime,
+    /// Number of commands from the original state to the tip.
+    pub length: usize,
+    /// Whether redo from the current state leads to this tip.
+    pub active: bool,
+}
+
+/// A command in the undo tree.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct HistoryNode {
+    /// The command.
+    command: Command,
+    /// The node this command was applied after, `None` for the original state.
+    parent: Option<usize>,
+    /// The child redo moves to: the one most recently added or visited.
+    redo: Option<usize>,
+}
+
 /// Command history manager for undo/redo functionality.
-#[derive(Debug)]
+///
+/// The history is a tree: adding a command after undoing starts a new branch
+/// instead of discarding the undone commands, and [`goto`](Self::goto),
+/// [`earlier`](Self::earlier) and [`later`](Self::later) move between
+/// branches. Histories serialize with their commands, so they can be kept
+/// across sessions (see [`UndoStore`](super::undo_store::UndoStore)).
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CommandHistory {
-    /// List of commands in order.
-    commands: Vec<Command>,
-    /// Current position in the command history.
-    position: usize,
+    /// Commands in the order they were added; parents come before children.
+    nodes: Vec<HistoryNode>,
+    /// The node of the last applied command, `None` at the original state.
+    current: Option<usize>,
+    /// The child of the original state that redo moves to.
+    root_redo: Option<usize>,
     /// Maximum number of commands to keep.
     max_size: usize,
     /// Whether to merge similar commands.
     merge_similar: bool,
+    /// Whether the next command is kept apart from the last one.
+    #[serde(skip)]
+    sealed: bool,
 }
 
 impl CommandHistory {
     /// Create a new command history.
     pub fn new(max_size: usize) -> Self {
-        Self { commands: Vec::new(), position: 0, max_size, merge_similar: true }
+        Self {
+            nodes: Vec::new(),
+            current: None,
+            root_redo: None,
+            max_size,
+            merge_similar: true,
+            sealed: false,
+        }
     }
 
     /// Add a command to the history.
+    ///
+    /// After an undo the command starts a new branch; the undone commands
+    /// stay reachable through [`branches`](Self::branches).
     pub fn add_command(&mut self, command: Command) {
-        // Try to merge with the last command if enabled
-        if self.merge_similar && !self.commands.is_empty() && self.position > 0 {
-            let last_index = self.position - 1;
-            if let Some(last_command) = self.commands.get_mut(last_index) {
+        // Try to merge with the last command if enabled, unless other states
+        // were reached from it or it was sealed
+        let sealed = std::mem::take(&mut self.sealed);
+        if self.merge_similar && !sealed {
+            if let Some(index) = self.current.filter(|&index| !self.has_children(Some(index))) {
+                let last_command = &mut self.nodes[index].command;
                 if last_command.can_merge_with(&command) {
                     if last_command.merge_with(command.clone()).is_ok() {
                         return; // Successfully merged
@@ -381,97 +441,237 @@ impl CommandHistory {
             }
         }
 
-        // Remove any commands after the current position (if we're not at the end)
-        self.commands.truncate(self.position);
+        let index = self.nodes.len();
+        self.nodes.push(HistoryNode { command, parent: self.current, redo: None });
+        self.set_redo(self.current, Some(index));
+        self.current = Some(index);
 
-        // Add the new command
-        self.commands.push(command);
-        self.position = self.commands.len();
-
-        // Ensure we don't exceed the maximum size
-        while self.commands.len() > self.max_size {
-            self.commands.remove(0);
-            self.position = self.position.saturating_sub(1);
-        }
+        self.prune();
     }
 
     /// Undo the last command.
     pub fn undo(&mut self, buffer: &mut Buffer) -> Result<Option<Command>> {
-        if self.position == 0 {
-            return Ok(None); // Nothing to undo
-        }
-
-        self.position -= 1;
-        if let Some(command) = self.commands.get_mut(self.position) {
-            command.undo(buffer)?;
-            Ok(Some(command.clone()))
-        } else {
-            Ok(None)
+        match self.step_back() {
+            Some(command) => {
+                command.undo(buffer)?;
+                Ok(Some(command.clone()))
+            }
+            None => Ok(None), // Nothing to undo
         }
     }
 
     /// Redo the next command.
     pub fn redo(&mut self, buffer: &mut Buffer) -> Result<Option<Command>> {
-        if self.position >= self.commands.len() {
-            return Ok(None); // Nothing to redo
+        match self.step_forward() {
+            Some(command) => {
+                command.execute(buffer)?;
+                Ok(Some(command.clone()))
+            }
+            None => Ok(None), // Nothing to redo
         }
+    }
 
-        if let Some(command) = self.commands.get_mut(self.position) {
-            command.execute(buffer)?;
-            self.position += 1;
-            Ok(Some(command.clone()))
-        } else {
-            Ok(None)
+    /// Move back over the last command without applying it, for callers
+    /// that revert its changes themselves.
+    pub fn step_back(&mut self) -> Option<&mut Command> {
+        let index = self.current?;
+        self.current = self.nodes[index].parent;
+        // Redo returns along the branch just left
+        self.set_redo(self.current, Some(index));
+        Some(&mut self.nodes[index].command)
+    }
+
+    /// Move forward over the next command without applying it.
+    pub fn step_forward(&mut self) -> Option<&mut Command> {
+        let index = self.redo_child(self.current)?;
+        self.current = Some(index);
+        Some(&mut self.nodes[index].command)
+    }
+
+    /// Get the last undoable command.
+    pub fn last_undoable(&self) -> Option<&Command> {
+        self.current.map(|index| &self.nodes[index].command)
+    }
+
+    /// Get the next redoable command.
+    pub fn next_redoable(&self) -> Option<&Command> {
+        self.redo_child(self.current).map(|index| &self.nodes[index].command)
+    }
+
+    /// Move to the state after node `target`, or to the original state for
+    /// `None`, returning the steps that lead there.
+    ///
+    /// Returns `None` when `target` is not a node of the history.
+    pub fn goto(&mut self, target: Option<usize>) -> Option<Vec<HistoryStep>> {
+        if target.is_some_and(|index| index >= self.nodes.len()) {
+            return None;
+        }
+
+        let from = self.ancestors(self.current);
+        let to = self.ancestors(target);
+        let common = from.iter().rev().zip(to.iter().rev()).take_while(|(a, b)| a == b).count();
+
+        let mut steps = Vec::new();
+        for &index in &from[..from.len() - common] {
+            self.set_redo(self.nodes[index].parent, Some(index));
+            steps.push(HistoryStep::Undo(self.nodes[index].command.clone()));
+        }
+        for &index in to[..to.len() - common].iter().rev() {
+            self.set_redo(self.nodes[index].parent, Some(index));
+            steps.push(HistoryStep::Redo(self.nodes[index].command.clone()));
+        }
+
+        self.current = target;
+        Some(steps)
+    }
+
+    /// Move to the latest state from at least `duration` before the current
+    /// one, on any branch.
+    pub fn earlier(&mut self, duration: std::time::Duration) -> Vec<HistoryStep> {
+        let target = self.state_time(self.current).checked_sub(duration);
+        let target = target.and_then(|time| self.state_at(time));
+        self.goto(target).unwrap_or_default()
+    }
+
+    /// Move to the latest state from at most `duration` after the current
+    /// one, on any branch.
+    pub fn later(&mut self, duration: std::time::Duration) -> Vec<HistoryStep> {
+        let target = self.state_time(self.current).checked_add(duration);
+        let target = match target {
+            Some(time) => self.state_at(time),
+            None => self.nodes.len().checked_sub(1),
+        };
+        self.goto(target).unwrap_or_default()
+    }
+
+    /// List the tips of all branches, oldest first.
+    pub fn branches(&self) -> Vec<HistoryBranch> {
+        let mut active = self.current;
+        while let Some(index) = self.redo_child(active) {
+            active = Some(index);
         }
+
+        (0..self.nodes.len())
+            .filter(|&index| !self.has_children(Some(index)))
+            .map(|index| {
+                let command = &self.nodes[index].command;
+                HistoryBranch {
+                    id: index,
+                    description: command.description(),
+                    timestamp: command.timestamp(),
+                    length: self.ancestors(Some(index)).len(),
+                    active: active == Some(index),
+                }
+            })
+            .collect()
     }
 
     /// Check if there are commands to undo.
     pub fn can_undo(&self) -> bool {
-        self.position > 0
+        self.current.is_some()
     }
 
     /// Check if there are commands to redo.
     pub fn can_redo(&self) -> bool {
-        self.position < self.commands.len()
+        self.redo_child(self.current).is_some()
     }
 
-    /// Get the current position in the history.
+    /// Get the number of commands that can be undone.
     pub fn position(&self) -> usize {
-        self.position
+        self.ancestors(self.current).len()
+    }
+
+    /// Get the number of commands that can be redone along the active branch.
+    pub fn redo_count(&self) -> usize {
+        let mut count = 0;
+        let mut state = self.current;
+        while let Some(index) = self.redo_child(state) {
+            count += 1;
+            state = Some(index);
+        }
+        count
+    }
+
+    /// Get the current node, `None` at the original state.
+    pub fn current(&self) -> Option<usize> {
+        self.current
     }
 
-    /// Get the total number of commands.
+    /// Get the total number of commands on all branches.
     pub fn len(&self) -> usize {
-        self.commands.len()
+        self.nodes.len()
     }
 
     /// Check if the history is empty.
     pub fn is_empty(&self) -> bool {
-        self.commands.is_empty()
+        self.nodes.is_empty()
     }
 
     /// Clear the command history.
     pub fn clear(&mut self) {
-        self.commands.clear();
-        self.position = 0;
+        self.nodes.clear();
+        self.current = None;
+        self.root_redo = None;
     }
 
     /// Get a description of the last undoable command.
     pub fn last_undoable_description(&self) -> Option<String> {
-        if self.position > 0 {
-            self.commands.get(self.position - 1).map(|cmd| cmd.description())
-        } else {
-            None
-        }
+        self.last_undoable().map(|cmd| cmd.description())
     }
 
     /// Get a description of the next redoable command.
     pub fn next_redoable_description(&self) -> Option<String> {
-        if self.position < self.commands.len() {
-            self.commands.get(self.position).map(|cmd| cmd.description())
-        } else {
-            None
+        self.next_redoable().map(|cmd| cmd.description())
+    }
+
+    /// Change the maximum number of commands, dropping the oldest ones
+    /// beyond it.
+    pub fn set_max_size(&mut self, max_size: usize) {
+        self.max_size = max_size;
+        self.prune();
+    }
+
+    /// Keep the next command from merging into the last one.
+    pub fn seal(&mut self) {
+        self.sealed = true;
+    }
+
+    /// Combine the last `count` commands leading to the current state into
+    /// one composite command, so they undo and redo together.
+    ///
+    /// Returns `false`, leaving the history alone, unless `count` is at least
+    /// two and those commands are the most recently added ones.
+    pub fn squash(&mut self, count: usize, description: &str) -> bool {
+        let len = self.nodes.len();
+        if count < 2 || count > len {
+            return false;
+        }
+        let chain = self.ancestors(self.current);
+        if chain.len() < count || (0..count).any(|i| chain[i] != len - 1 - i) {
+            return false;
+        }
+
+        let first = len - count;
+        let tail = self.nodes.split_off(first + 1);
+        let node = &mut self.nodes[first];
+        let executed = node.command.executed;
+        let timestamp = tail.last().map_or(node.command.timestamp, |last| last.command.timestamp);
+        let buffer_path = node.command.buffer_path.clone();
+
+        let mut commands = vec![node.command.clone()];
+        commands.extend(tail.into_iter().map(|node| node.command));
+        // The parts undo and redo with the composite, so share its state
+        for command in &mut commands {
+            command.executed = executed;
         }
+        let mut composite = Command::composite(commands, description.to_string(), buffer_path);
+        composite.executed = executed;
+        composite.timestamp = timestamp;
+
+        node.command = composite;
+        node.redo = None;
+        self.current = Some(first);
+        true
     }
 
     /// Enable or disable command merging.
@@ -479,9 +679,108 @@ impl CommandHistory {
         self.merge_similar = merge;
     }
 
-    /// Get the command history as a slice.
-    pub fn commands(&self) -> &[Command] {
-        &self.commands
+    /// Get the commands on all branches in the order they were added.
+    pub fn commands(&self) -> impl Iterator<Item = &Command> {
+        self.nodes.iter().map(|node| &node.command)
+    }
+
+    /// Get the child redo moves to from a state.
+    fn redo_child(&self, state: Option<usize>) -> Option<usize> {
+        match state {
+            Some(index) => self.nodes[index].redo,
+            None => self.root_redo,
+        }
+    }
+
+    /// Set the child redo moves to from a state.
+    fn set_redo(&mut self, state: Option<usize>, child: Option<usize>) {
+        match state {
+            Some(index) => self.nodes[index].redo = child,
+            None => self.root_redo = child,
+        }
+    }
+
+    /// Check whether any command was applied after a state.
+    fn has_children(&self, state: Option<usize>) -> bool {
+        self.nodes.iter().any(|node| node.parent == state)
+    }
+
+    /// Get the nodes from a state back to the original state, nearest first.
+    fn ancestors(&self, state: Option<usize>) -> Vec<usize> {
+        let mut ancestors = Vec::new();
+        let mut state = state;
+        while let Some(index) = state {
+            ancestors.push(index);
+            state = self.nodes[index].parent;
+        }
+        ancestors
+    }
+
+    /// Get the time a state was reached. The original state counts as
+    /// reached just as the first command was added.
+    fn state_time(&self, state: Option<usize>) -> std::time::SystemTime {
+        match state {
+            Some(index) => self.nodes[index].command.timestamp(),
+            None => self
+                .nodes
+                .iter()
+                .map(|node| node.command.timestamp())
+                .min()
+                .unwrap_or(std::time::UNIX_EPOCH),
+        }
+    }
+
+    /// Get the latest state reached at or before `time`.
+    fn state_at(&self, time: std::time::SystemTime) -> Option<usize> {
+        (0..self.nodes.len())
+            .filter(|&index| self.nodes[index].command.timestamp() <= time)
+            .max_by_key(|&index| (self.nodes[index].command.timestamp(), index))
+    }
+
+    /// Drop the oldest commands beyond the maximum size.
+    ///
+    /// The oldest command always follows the original state. If the current
+    /// state builds on it, it becomes part of the original state and branches
+    /// that bypass it are dropped; otherwise its branch is dropped.
+    fn prune(&mut self) {
+        while self.nodes.len() > self.max_size {
+            // The first command after the original state that each node builds on
+            let mut base = Vec::with_capacity(self.nodes.len());
+            for index in 0..self.nodes.len() {
+                let node_base = self.nodes[index].parent.map_or(index, |parent| base[parent]);
+                base.push(node_base);
+            }
+
+            let keep_oldest = self.ancestors(self.current).contains(&0);
+            let keep: Vec<bool> = base
+                .iter()
+                .enumerate()
+                .map(|(index, &node_base)| (node_base == 0) == keep_oldest && index != 0)
+                .collect();
+            let root_redo = if keep_oldest { self.nodes[0].redo } else { self.root_redo };
+
+            let mut new_index = Vec::with_capacity(keep.len());
+            let mut next = 0;
+            for &kept in &keep {
+                new_index.push(kept.then_some(next));
+                next += usize::from(kept);
+            }
+            let remap = |index: Option<usize>| index.and_then(|index| new_index[index]);
+
+            let nodes = std::mem::take(&mut self.nodes);
+            self.nodes = nodes
+                .into_iter()
+                .zip(&keep)
+                .filter(|(_, kept)| **kept)
+                .map(|(node, _)| HistoryNode {
+                    command: node.command,
+                    parent: remap(node.parent),
+                    redo: remap(node.redo),
+                })
+                .collect();
+            self.current = remap(self.current);
+            self.root_redo = remap(root_redo);
+        }
     }
 }
 
@@ -505,7 +804,7 @@ mod tests {
 
     #[test]
     fn test_command_execution() {
-        let mut buffer = Buffer::new("".to_string(), None);
+        let mut buffer = Buffer::new("", None);
         let mut cmd = Command::insert_text(0, 0, "Hello".to_string(), None);
 
         cmd.execute(&mut buffer).unwrap();
@@ -515,7 +814,7 @@ mod tests {
 
     #[test]
     fn test_command_undo() {
-        let mut buffer = Buffer::new("".to_string(), None);
+        let mut buffer = Buffer::new("", None);
         let mut cmd = Command::insert_text(0, 0, "Hello".to_string(), None);
 
         cmd.execute(&mut buffer).unwrap();
@@ -544,32 +843,121 @@ mod tests {
     #[test]
     fn test_command_history() {
         let mut history = CommandHistory::new(10);
-        let mut buffer = Buffer::new("".to_string(), None);
+        let mut buffer = Buffer::new("", None);
 
-        let cmd1 = Command::insert_text(0, 0, "Hello".to_string(), None);
-        let cmd2 = Command::insert_text(0, 5, " World".to_string(), None);
+        let mut cmd1 = Command::insert_text(0, 0, "Hello".to_string(), None);
+        let mut cmd2 = Command::insert_text(0, 5, " World".to_string(), None);
+        cmd1.execute(&mut buffer).unwrap();
+        cmd2.execute(&mut buffer).unwrap();
 
+        // Adjacent insertions merge into one command
         history.add_command(cmd1);
         history.add_command(cmd2);
 
-        assert_eq!(history.len(), 2);
+        assert_eq!(history.len(), 1);
         assert!(history.can_undo());
         assert!(!history.can_redo());
 
         // Undo
         let undone = history.undo(&mut buffer).unwrap();
         assert!(undone.is_some());
+        assert_eq!(buffer.get_content(), "");
         assert!(history.can_redo());
 
         // Redo
         let redone = history.redo(&mut buffer).unwrap();
         assert!(redone.is_some());
+        assert_eq!(buffer.get_content(), "Hello World");
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn test_add_after_undo_starts_branch() {
+        let mut history = CommandHistory::new(10);
+        let mut buffer = Buffer::new("", None);
+
+        history.add_command(Command::insert_text(0, 0, "ab".to_string(), None));
+        history.add_command(Command::insert_line(1, "cd".to_string(), None));
+        history.undo(&mut buffer).unwrap();
+
+        // Typing after an undo keeps the undone command on its own branch.
+        history.add_command(Command::insert_text(0, 2, "x".to_string(), None));
+        assert_eq!(history.len(), 3);
         assert!(!history.can_redo());
+        assert_eq!(history.last_undoable_description().as_deref(), Some("Insert 'x'"));
+
+        let branches = history.branches();
+        assert_eq!(branches.len(), 2);
+        assert_eq!((branches[0].description.as_str(), branches[0].active), ("Insert line", false));
+        assert_eq!((branches[1].description.as_str(), branches[1].active), ("Insert 'x'", true));
+        assert_eq!(branches[0].length, 2);
+
+        let steps = history.goto(Some(branches[0].id)).unwrap();
+        assert!(matches!(steps.as_slice(), [HistoryStep::Undo(_), HistoryStep::Redo(_)]));
+        history.step_back();
+        assert_eq!(history.next_redoable_description().as_deref(), Some("Insert line"));
+        assert!(history.goto(Some(3)).is_none());
+
+        // Pruning folds the oldest command into the original state.
+        history.set_max_size(2);
+        assert_eq!(history.len(), 2);
+        assert_eq!(history.position(), 0);
+        assert_eq!(history.branches().len(), 2);
+
+        history.set_max_size(0);
+        assert!(history.is_empty());
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn test_seal_and_squash() {
+        let mut history = CommandHistory::new(10);
+
+        history.add_command(Command::insert_text(0, 0, "a".to_string(), None));
+        history.seal();
+        history.add_command(Command::insert_text(0, 1, "b".to_string(), None));
+        history.add_command(Command::insert_text(0, 2, "c".to_string(), None));
+        assert_eq!(history.position(), 2);
+
+        history.add_command(Command::insert_line(1, "d".to_string(), None));
+        assert!(history.squash(2, "Change"));
+        assert_eq!(history.len(), 2);
+        assert_eq!(history.last_undoable_description().as_deref(), Some("Change"));
+        assert!(!history.squash(3, "Too many"));
+        assert!(!history.squash(1, "Too few"));
+
+        // Commands on another branch cannot be squashed.
+        history.step_back();
+        history.add_command(Command::insert_line(1, "e".to_string(), None));
+        assert!(!history.squash(2, "Across branches"));
+        assert_eq!(history.len(), 3);
+    }
+
+    #[test]
+    fn test_time_navigation() {
+        let start = std::time::SystemTime::now();
+        let minute = std::time::Duration::from_secs(60);
+        let mut history = CommandHistory::new(10);
+        for index in 0..3u32 {
+            let mut command = Command::insert_line(index as usize, "line".to_string(), None);
+            command.timestamp = start + minute * index;
+            history.add_command(command);
+        }
+
+        assert_eq!(history.earlier(minute * 3 / 2).len(), 2);
+        assert_eq!(history.current(), Some(0));
+        assert!(matches!(history.later(minute).as_slice(), [HistoryStep::Redo(_)]));
+        assert_eq!(history.current(), Some(1));
+        assert_eq!(history.earlier(minute * 60).len(), 2);
+        assert_eq!(history.current(), None);
+        assert_eq!(history.later(minute * 60 * 24).len(), 3);
+        assert_eq!(history.current(), Some(2));
+        assert!(history.later(minute).is_empty());
     }
 
     #[test]
     fn test_composite_command() {
-        let mut buffer = Buffer::new("Line 1\nLine 2".to_string(), None);
+        let mut buffer = Buffer::new("Line 1\nLine 2", None);
 
         let cmd1 = Command::insert_text(0, 6, " Modified".to_string(), None);
         let cmd2 = Command::insert_text(1, 6, " Modified".to_string(), None);
@@ -586,7 +974,7 @@ mod tests {
 
     #[test]
     fn test_delete_command() {
-        let mut buffer = Buffer::new("Hello World".to_string(), None);
+        let mut buffer = Buffer::new("Hello World", None);
         let mut cmd = Command::delete_text(0, 5, " World".to_string(), None);
 
         cmd.execute(&mut buffer).unwrap();
@@ -598,7 +986,7 @@ mod tests {
 
     #[test]
     fn test_replace_command() {
-        let mut buffer = Buffer::new("Hello World".to_string(), None);
+        let mut buffer = Buffer::new("Hello World", None);
         let mut cmd =
             Command::replace_text(0, 6, 0, 11, "World".to_string(), "Universe".to_string(), None);
 
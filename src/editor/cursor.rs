@@ -4,8 +4,10 @@
 
 use std::cmp;
 
+use serde::{Deserialize, Serialize};
+
 /// Represents a cursor position in the text editor.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Cursor {
     /// Current line (0-indexed).
     pub line: usize,
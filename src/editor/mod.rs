@@ -5,23 +5,40 @@
 pub mod buffer;
 pub mod commands;
 pub mod cursor;
+pub mod multi_cursor;
+pub mod search;
 pub mod selection;
+pub mod undo_store;
+pub mod vim;
 
 pub use buffer::Buffer;
 pub use commands::Command;
 pub use cursor::Cursor;
+pub use multi_cursor::MultiCursor;
+pub use search::{SearchMatch, SearchOptions, SearchQuery};
 pub use selection::Selection;
+pub use undo_store::UndoStore;
+pub use vim::{Register, RegisterKind, Registers, Vim, VimKey, VimMode, VimOutcome};
 
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::ops::{Range, RangeInclusive};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
+use ropey::Rope;
 use tokio::sync::RwLock;
 use tracing::{debug, error, info, warn};
 
 use crate::core::{Config, Event, EventBus, EventMessage, EventPriority, Result, XyluxError};
+use crate::syntax::DocumentSource;
+use crate::syntax::lsp_client::{
+    Position, TextEdit, WorkspaceEdit, uri_to_path, utf16_to_byte_offset,
+};
 
-use commands::CommandType;
+use buffer::text_display_end;
+use commands::{CommandHistory, CommandType, HistoryBranch, HistoryStep};
+use selection::SelectionMode;
 
 /// Main editor component that manages text buffers and editing operations.
 pub struct Editor {
@@ -33,14 +50,12 @@ pub struct Editor {
     buffers: Arc<RwLock<HashMap<PathBuf, Buffer>>>,
     /// Current active buffer.
     active_buffer: Arc<RwLock<Option<PathBuf>>>,
-    /// Cursor state.
-    cursor: Arc<RwLock<Cursor>>,
-    /// Current selection.
-    selection: Arc<RwLock<Option<Selection>>>,
-    /// Undo/redo history.
-    command_history: Arc<RwLock<Vec<Command>>>,
-    /// Current position in command history.
-    history_position: Arc<RwLock<usize>>,
+    /// Cursors and their selections.
+    cursors: Arc<RwLock<MultiCursor>>,
+    /// Undo/redo history of each open buffer.
+    histories: Arc<RwLock<HashMap<PathBuf, CommandHistory>>>,
+    /// Where undo history is kept across sessions, if anywhere.
+    undo_store: Option<UndoStore>,
 }
 
 impl Editor {
@@ -48,15 +63,21 @@ impl Editor {
     pub async fn new(config: Arc<RwLock<Config>>, event_bus: Arc<EventBus>) -> Result<Self> {
         debug!("Initializing editor");
 
+        let undo_store = match &config.read().await.editor.undo {
+            undo if undo.persist => {
+                undo.directory.clone().or_else(UndoStore::default_directory).map(UndoStore::new)
+            }
+            _ => None,
+        };
+
         Ok(Self {
             config,
             event_bus,
             buffers: Arc::new(RwLock::new(HashMap::new())),
             active_buffer: Arc::new(RwLock::new(None)),
-            cursor: Arc::new(RwLock::new(Cursor::new(0, 0))),
-            selection: Arc::new(RwLock::new(None)),
-            command_history: Arc::new(RwLock::new(Vec::new())),
-            history_position: Arc::new(RwLock::new(0)),
+            cursors: Arc::new(RwLock::new(MultiCursor::default())),
+            histories: Arc::new(RwLock::new(HashMap::new())),
+            undo_store,
         })
     }
 
@@ -72,7 +93,8 @@ impl Editor {
             }
         };
 
-        let buffer = Buffer::new(content, Some(path.clone()));
+        self.restore_history(path, &content).await;
+        let buffer = Buffer::new(&content, Some(path.clone()));
 
         {
             let mut buffers = self.buffers.write().await;
@@ -84,16 +106,10 @@ impl Editor {
             *active = Some(path.clone());
         }
 
-        // Reset cursor to beginning of file
-        {
-            let mut cursor = self.cursor.write().await;
-            *cursor = Cursor::new(0, 0);
-        }
-
-        // Clear selection
+        // Reset to a single cursor at the beginning of the file
         {
-            let mut selection = self.selection.write().await;
-            *selection = None;
+            let mut cursors = self.cursors.write().await;
+            *cursors = MultiCursor::default();
         }
 
         // Publish file opened event
@@ -108,6 +124,31 @@ impl Editor {
         Ok(())
     }
 
+    /// Create an empty buffer and make it active.
+    ///
+    /// The buffer has no file yet, so it is keyed by an `untitled-N` id
+    /// instead of a path and cannot be saved.
+    pub async fn new_file(&self) -> PathBuf {
+        let id = {
+            let mut buffers = self.buffers.write().await;
+            let mut number = 1;
+            while buffers.contains_key(&untitled_id(number)) {
+                number += 1;
+            }
+            let id = untitled_id(number);
+            buffers.insert(id.clone(), Buffer::empty());
+            id
+        };
+
+        let max_commands = self.config.read().await.editor.undo.max_commands;
+        self.histories.write().await.insert(id.clone(), CommandHistory::new(max_commands));
+        *self.active_buffer.write().await = Some(id.clone());
+        *self.cursors.write().await = MultiCursor::default();
+
+        info!("Created {}", id.display());
+        id
+    }
+
     /// Save the current buffer.
     pub async fn save_current(&self) -> Result<()> {
         let active_path = {
@@ -127,7 +168,11 @@ impl Editor {
     pub async fn save_all(&self) -> Result<()> {
         let paths: Vec<PathBuf> = {
             let buffers = self.buffers.read().await;
-            buffers.keys().cloned().collect()
+            buffers
+                .iter()
+                .filter(|(_, buffer)| buffer.file_path().is_some())
+                .map(|(path, _)| path.clone())
+                .collect()
         };
 
         for path in paths {
@@ -143,15 +188,22 @@ impl Editor {
     async fn save_buffer(&self, path: &PathBuf) -> Result<()> {
         let content = {
             let buffers = self.buffers.read().await;
-            if let Some(buffer) = buffers.get(path) {
-                buffer.get_content()
-            } else {
-                warn!("Buffer not found for path: {}", path.display());
-                return Ok(());
+            match buffers.get(path) {
+                Some(buffer) if buffer.file_path().is_none() => {
+                    return Err(XyluxError::invalid_input(format!(
+                        "{} has no file to save to",
+                        path.display()
+                    )));
+                }
+                Some(buffer) => buffer.get_content(),
+                None => {
+                    warn!("Buffer not found for path: {}", path.display());
+                    return Ok(());
+                }
             }
         };
 
-        std::fs::write(path, content)?;
+        std::fs::write(path, &content)?;
 
         // Mark buffer as saved
         {
@@ -161,6 +213,8 @@ impl Editor {
             }
         }
 
+        self.persist_history(path, &content).await;
+
         // Publish file saved event
         let event = EventMessage::from_event(Event::Editor(crate::core::EditorEvent::FileSaved {
             path: path.clone(),
@@ -185,7 +239,7 @@ impl Editor {
             let buffers = self.buffers.read().await;
             buffers
                 .iter()
-                .filter(|(_, buffer)| buffer.is_modified())
+                .filter(|(_, buffer)| buffer.is_modified() && buffer.file_path().is_some())
                 .map(|(path, _)| path.clone())
                 .collect()
         };
@@ -199,359 +253,1272 @@ impl Editor {
         Ok(())
     }
 
-    /// Insert text at the current cursor position.
+    /// Insert text at every cursor, replacing selected text.
+    ///
+    /// Edits at several cursors are recorded as one command. With a block
+    /// selection the text is typed on every line of the block instead.
     pub async fn insert_text(&self, text: &str) -> Result<()> {
-        let active_path = {
-            let active = self.active_buffer.read().await;
-            active.clone()
-        };
+        if self.has_block_selection().await {
+            return self.insert_block_text(text).await;
+        }
 
-        if let Some(path) = active_path {
-            let cursor_pos = {
-                let cursor = self.cursor.read().await;
-                (cursor.line, cursor.column)
-            };
+        self.edit_at_cursors("Insert text", |_, selection| {
+            let selection = selection.normalized();
+            Some((selection.start, selection.end, text.to_string()))
+        })
+        .await
+    }
 
-            // Create command for undo/redo
-            let command = Command::new(
-                CommandType::InsertText {
-                    line: cursor_pos.0,
-                    column: cursor_pos.1,
-                    text: text.to_string(),
-                },
-                Some(path.clone()),
-            );
-
-            // Execute the command
-            self.execute_command(command).await?;
+    /// Delete selected text at every cursor, or `count` bytes after the
+    /// cursors without a selection.
+    ///
+    /// With a block selection the rectangle is deleted, or `count` display
+    /// columns after it on every line when it is empty.
+    pub async fn delete_text(&self, count: usize) -> Result<()> {
+        if self.has_block_selection().await {
+            return self.delete_block(count).await;
         }
 
-        Ok(())
+        self.edit_at_cursors("Delete text", |buffer, selection| {
+            let selection = selection.normalized();
+            if !selection.is_empty() {
+                return Some((selection.start, selection.end, String::new()));
+            }
+
+            let cursor = selection.end;
+            let column = buffer.clamp_column(cursor.line, cursor.column + count);
+            Some((cursor, Cursor::new(cursor.line, column), String::new()))
+        })
+        .await
     }
 
-    /// Delete text at the current cursor position.
-    pub async fn delete_text(&self, count: usize) -> Result<()> {
-        let active_path = {
-            let active = self.active_buffer.read().await;
-            active.clone()
+    /// Check whether the only selection is a block selection.
+    async fn has_block_selection(&self) -> bool {
+        let cursors = self.cursors.read().await;
+        cursors.is_single() && cursors.primary().mode == SelectionMode::Block
+    }
+
+    /// Get the text of the block selection, one string per line.
+    ///
+    /// Returns `None` when there is no block selection.
+    pub async fn copy_block(&self) -> Option<Vec<String>> {
+        if !self.has_block_selection().await {
+            return None;
+        }
+        let path = self.active_path().await?;
+        let tab_size = self.tab_size().await;
+
+        let buffers = self.buffers.read().await;
+        let buffer = buffers.get(&path)?;
+        let (lines, columns) = block_of(buffer, self.cursors.read().await.primary(), tab_size);
+        Some(buffer.block_text(lines, columns, tab_size))
+    }
+
+    /// Paste lines as a block at the primary cursor, one per line.
+    ///
+    /// Each line goes in at the cursor's display column, replacing the block
+    /// selection if there is one. Short lines are padded with spaces and lines
+    /// are added at the end of the buffer as needed.
+    pub async fn paste_block(&self, lines: &[String]) -> Result<()> {
+        let Some(path) = self.active_path().await else {
+            return Ok(());
         };
+        let tab_size = self.tab_size().await;
 
-        if let Some(path) = active_path {
-            let cursor_pos = {
-                let cursor = self.cursor.read().await;
-                (cursor.line, cursor.column)
+        let (command, caret_line, caret_column) = {
+            let buffers = self.buffers.read().await;
+            let Some(buffer) = buffers.get(&path) else {
+                return Ok(());
+            };
+            let cursors = self.cursors.read().await;
+            let primary = cursors.primary();
+            let (rows, columns) = if primary.mode == SelectionMode::Block {
+                block_of(buffer, primary, tab_size)
+            } else {
+                let caret = primary.end;
+                let column = buffer.display_column(caret.line, caret.column, tab_size);
+                (caret.line..=caret.line, column..column)
             };
 
-            // Get the text that will be deleted for undo
-            let deleted_text = {
-                let buffers = self.buffers.read().await;
-                if let Some(buffer) = buffers.get(&path) {
-                    buffer
-                        .get_text_range(
-                            cursor_pos.0,
-                            cursor_pos.1,
-                            cursor_pos.0,
-                            cursor_pos.1 + count,
-                        )
-                        .unwrap_or_default()
+            let first_line = *rows.start();
+            let mut edits = Vec::new();
+            let mut appended = String::new();
+            for (index, text) in lines.iter().enumerate() {
+                let line = first_line + index;
+                if line >= buffer.line_count() {
+                    appended.push('\n');
+                    appended.push_str(&" ".repeat(columns.start));
+                    appended.push_str(text);
+                    continue;
+                }
+
+                let width = buffer.display_width(line, tab_size);
+                let length = buffer.line_length(line);
+                if width < columns.start {
+                    let padded = format!("{}{}", " ".repeat(columns.start - width), text);
+                    edits.push((line, length, length, padded));
                 } else {
-                    String::new()
+                    let start = buffer.column_at_display(line, columns.start, tab_size);
+                    let end = if rows.contains(&line) {
+                        buffer.column_at_display(line, columns.end, tab_size)
+                    } else {
+                        start
+                    };
+                    edits.push((line, start, end, text.clone()));
                 }
-            };
+            }
+
+            // Selected lines past the pasted ones are cleared.
+            for line in rows.clone().skip(lines.len()) {
+                let start = buffer.column_at_display(line, columns.start, tab_size);
+                let end = buffer.column_at_display(line, columns.end, tab_size);
+                edits.push((line, start, end, String::new()));
+            }
+
+            if !appended.is_empty() {
+                let last_line = buffer.line_count() - 1;
+                let length = buffer.line_length(last_line);
+                // Ahead of any edit at the same position, so it is applied first
+                // and ends up after that edit's text.
+                edits.insert(0, (last_line, length, length, appended));
+            }
 
-            // Create command for undo/redo
-            let command = Command::new(
-                CommandType::DeleteText {
-                    line: cursor_pos.0,
-                    column: cursor_pos.1,
-                    text: deleted_text,
-                },
-                Some(path.clone()),
-            );
-
-            // Execute the command
+            let caret_line = first_line + lines.len().saturating_sub(1);
+            let caret_column =
+                text_display_end(columns.start, lines.last().map_or("", String::as_str), tab_size);
+            (line_edits_command(buffer, &path, "Paste block", edits)?, caret_line, caret_column)
+        };
+
+        if let Some(command) = command {
             self.execute_command(command).await?;
         }
 
-        Ok(())
+        let column = {
+            let buffers = self.buffers.read().await;
+            buffers
+                .get(&path)
+                .map_or(0, |buffer| buffer.column_at_display(caret_line, caret_column, tab_size))
+        };
+        self.move_cursor(caret_line, column).await
     }
 
-    /// Move cursor to a specific position.
-    pub async fn move_cursor(&self, line: usize, column: usize) -> Result<()> {
-        {
-            let mut cursor = self.cursor.write().await;
-            cursor.line = line;
-            cursor.column = column;
+    /// Type text on every line of the block selection, replacing its contents.
+    ///
+    /// Lines that end before the block are left alone. Text spanning several
+    /// lines is pasted as a block instead.
+    async fn insert_block_text(&self, text: &str) -> Result<()> {
+        if text.contains('\n') {
+            let lines: Vec<String> = text.split('\n').map(String::from).collect();
+            return self.paste_block(&lines).await;
         }
 
-        // Publish cursor moved event
-        let event =
-            EventMessage::from_event(Event::Editor(crate::core::EditorEvent::CursorMoved {
-                line,
-                column,
-            }))
-            .with_priority(EventPriority::Low)
-            .with_source("editor");
-
-        self.event_bus.publish(event).await?;
-
-        Ok(())
+        self.edit_block("Type in block", |columns| columns, text).await
     }
 
-    /// Get the current cursor position.
-    pub async fn get_cursor_position(&self) -> (usize, usize) {
-        let cursor = self.cursor.read().await;
-        (cursor.line, cursor.column)
+    /// Delete the block selection, or `count` display columns after it when
+    /// it is empty.
+    async fn delete_block(&self, count: usize) -> Result<()> {
+        let columns = |columns: Range<usize>| {
+            if columns.is_empty() { columns.start..columns.start + count } else { columns }
+        };
+        self.edit_block("Delete block", columns, "").await
     }
 
-    /// Get the content of the active buffer.
-    pub async fn get_active_content(&self) -> Option<String> {
-        let active_path = {
-            let active = self.active_buffer.read().await;
-            active.clone()
+    /// Replace the same display columns with `text` on every line of the
+    /// block selection, leaving an empty block selection after the text.
+    ///
+    /// `columns` picks the columns to replace from those of the block.
+    async fn edit_block(
+        &self,
+        description: &str,
+        columns: impl FnOnce(Range<usize>) -> Range<usize>,
+        text: &str,
+    ) -> Result<()> {
+        let Some(path) = self.active_path().await else {
+            return Ok(());
         };
+        let tab_size = self.tab_size().await;
 
-        if let Some(path) = active_path {
+        let (command, rows, caret_column) = {
             let buffers = self.buffers.read().await;
-            buffers.get(&path).map(|buffer| buffer.get_content())
-        } else {
-            None
-        }
-    }
-
-    /// Get a list of all open files.
-    pub async fn get_open_files(&self) -> Vec<PathBuf> {
-        let buffers = self.buffers.read().await;
-        buffers.keys().cloned().collect()
-    }
-
-    /// Check if there are any modified buffers.
-    pub async fn has_unsaved_changes(&self) -> bool {
-        let buffers = self.buffers.read().await;
-        buffers.values().any(|buffer| buffer.is_modified())
-    }
+            let Some(buffer) = buffers.get(&path) else {
+                return Ok(());
+            };
+            let (rows, block_columns) =
+                block_of(buffer, self.cursors.read().await.primary(), tab_size);
+            let columns = columns(block_columns);
+
+            let edits = rows
+                .clone()
+                .filter(|&line| buffer.display_width(line, tab_size) >= columns.start)
+                .map(|line| {
+                    let start = buffer.column_at_display(line, columns.start, tab_size);
+                    let end = buffer.column_at_display(line, columns.end, tab_size);
+                    (line, start, end, text.to_string())
+                })
+                .collect();
+
+            let caret_column = text_display_end(columns.start, text, tab_size);
+            (line_edits_command(buffer, &path, description, edits)?, rows, caret_column)
+        };
 
-    /// Close a file.
-    pub async fn close_file(&self, path: &PathBuf) -> Result<()> {
-        {
-            let mut buffers = self.buffers.write().await;
-            buffers.remove(path);
+        if let Some(command) = command {
+            self.execute_command(command).await?;
         }
 
-        // If this was the active buffer, clear it
         {
-            let mut active = self.active_buffer.write().await;
-            if active.as_ref() == Some(path) {
-                *active = None;
+            let buffers = self.buffers.read().await;
+            if let Some(buffer) = buffers.get(&path) {
+                let corner = |line: usize| {
+                    Cursor::new(line, buffer.column_at_display(line, caret_column, tab_size))
+                };
+                let block = Selection::with_mode(
+                    corner(*rows.start()),
+                    corner(*rows.end()),
+                    SelectionMode::Block,
+                );
+                *self.cursors.write().await = MultiCursor::from_selection(block);
             }
         }
 
-        // Publish file closed event
-        let event = EventMessage::from_event(Event::Editor(crate::core::EditorEvent::FileClosed {
-            path: path.clone(),
-        }))
-        .with_priority(EventPriority::Normal)
-        .with_source("editor");
+        self.publish_cursor_moved().await
+    }
 
-        self.event_bus.publish(event).await?;
+    /// Get the configured tab size.
+    async fn tab_size(&self) -> usize {
+        self.config.read().await.editor.tab_size.max(1)
+    }
 
-        info!("Closed: {}", path.display());
-        Ok(())
+    /// Get the text of one level of indentation.
+    pub async fn indent_unit(&self) -> String {
+        let config = self.config.read().await;
+        if config.editor.use_spaces {
+            " ".repeat(config.editor.tab_size.max(1))
+        } else {
+            "\t".to_string()
+        }
     }
 
-    /// Execute a command and add it to history.
-    async fn execute_command(&self, command: Command) -> Result<()> {
-        // Execute the command
-        match &command.command_type {
-            CommandType::InsertText { line, column, text } => {
-                if let Some(path) = &command.buffer_path {
-                    {
-                        let mut buffers = self.buffers.write().await;
-                        if let Some(buffer) = buffers.get_mut(path) {
-                            buffer.insert_text(*line, *column, text)?;
-                        }
-                    }
-
-                    // Update cursor position
-                    {
-                        let mut cursor = self.cursor.write().await;
-                        cursor.column += text.len();
-                    }
-
-                    // Publish event
-                    let event = EventMessage::from_event(Event::Editor(
-                        crate::core::EditorEvent::TextInserted {
-                            line: *line,
-                            column: *column,
-                            text: text.clone(),
-                        },
-                    ))
-                    .with_priority(EventPriority::Normal)
-                    .with_source("editor");
-
-                    self.event_bus.publish(event).await?;
+    /// Replace a range at every cursor of the active buffer.
+    ///
+    /// `edit` picks the range and replacement for each selection. The edits
+    /// are applied from the bottom up and recorded as one command, and each
+    /// cursor ends up after its replacement.
+    async fn edit_at_cursors(
+        &self,
+        description: &str,
+        edit: impl Fn(&Buffer, &Selection) -> Option<(Cursor, Cursor, String)>,
+    ) -> Result<()> {
+        let Some(path) = self.active_path().await else {
+            return Ok(());
+        };
+
+        let (mut commands, carets) = {
+            let buffers = self.buffers.read().await;
+            let Some(buffer) = buffers.get(&path) else {
+                return Ok(());
+            };
+            let cursors = self.cursors.read().await;
+
+            // Byte ranges in document order, so each caret can be placed after
+            // the edits before it.
+            let mut edits = Vec::new();
+            for selection in cursors.selections() {
+                if let Some((start, end, text)) = edit(buffer, selection) {
+                    let start = buffer.position_to_byte(start.line, start.column);
+                    let end = buffer.position_to_byte(end.line, end.column).max(start);
+                    edits.push((start, end, text));
                 }
             }
-            CommandType::DeleteText { line, column, text } => {
-                if let Some(path) = &command.buffer_path {
-                    {
-                        let mut buffers = self.buffers.write().await;
-                        if let Some(buffer) = buffers.get_mut(path) {
-                            buffer.delete_text(*line, *column, text.len())?;
-                        }
-                    }
-
-                    // Publish event
-                    let event = EventMessage::from_event(Event::Editor(
-                        crate::core::EditorEvent::TextDeleted {
-                            start_line: *line,
-                            start_column: *column,
-                            end_line: *line,
-                            end_column: *column + text.len(),
-                        },
-                    ))
-                    .with_priority(EventPriority::Normal)
-                    .with_source("editor");
-
-                    self.event_bus.publish(event).await?;
+
+            let mut carets = Vec::with_capacity(edits.len());
+            let mut shift = 0isize;
+            for (start, end, text) in &edits {
+                carets.push(start.saturating_add_signed(shift) + text.len());
+                shift += text.len() as isize - (end - start) as isize;
+            }
+
+            let mut commands = Vec::with_capacity(edits.len());
+            for (start, end, text) in edits.into_iter().rev() {
+                let (start_line, start_column) = buffer.byte_to_position(start);
+                let (end_line, end_column) = buffer.byte_to_position(end);
+                if let Some(command_type) = replacement_command(
+                    buffer,
+                    (start_line, start_column),
+                    (end_line, end_column),
+                    &text,
+                )? {
+                    commands.push(Command::new(command_type, Some(path.clone())));
                 }
             }
-            _ => {} // Handle other command types as needed
+            (commands, carets)
+        };
+
+        let command = match commands.len() {
+            0 => return Ok(()),
+            1 => commands.remove(0),
+            _ => Command::composite(commands, description.to_string(), Some(path.clone())),
+        };
+        self.execute_command(command).await?;
+
+        let selections = {
+            let buffers = self.buffers.read().await;
+            let Some(buffer) = buffers.get(&path) else {
+                return Ok(());
+            };
+            carets
+                .into_iter()
+                .map(|caret| {
+                    let (line, column) = buffer.byte_to_position(caret);
+                    Selection::from_cursor(Cursor::new(line, column))
+                })
+                .collect()
+        };
+        if let Some(cursors) = MultiCursor::from_selections(selections) {
+            *self.cursors.write().await = cursors;
         }
 
-        // Add to command history
+        self.publish_cursor_moved().await
+    }
+
+    /// Move cursor to a specific position, removing any other cursors.
+    pub async fn move_cursor(&self, line: usize, column: usize) -> Result<()> {
         {
-            let mut history = self.command_history.write().await;
-            let mut position = self.history_position.write().await;
+            let mut cursors = self.cursors.write().await;
+            *cursors = MultiCursor::new(Cursor::new(line, column));
+        }
+
+        self.publish_cursor_moved().await
+    }
+
+    /// Get the current cursor position.
+    pub async fn get_cursor_position(&self) -> (usize, usize) {
+        self.cursors.read().await.primary_cursor().position()
+    }
 
-            // Truncate history after current position (when executing new command after undo)
-            history.truncate(*position);
+    /// Get the cursors and their selections.
+    pub async fn cursors(&self) -> MultiCursor {
+        self.cursors.read().await.clone()
+    }
 
-            // Add new command
-            history.push(command);
-            *position = history.len();
+    /// Select a range, removing any other cursors.
+    pub async fn set_selection(&self, selection: Selection) -> Result<()> {
+        let (start, end) = (*selection.actual_start(), *selection.actual_end());
+        {
+            let mut cursors = self.cursors.write().await;
+            *cursors = MultiCursor::from_selection(selection);
         }
 
-        Ok(())
+        let event =
+            EventMessage::from_event(Event::Editor(crate::core::EditorEvent::SelectionChanged {
+                start_line: start.line,
+                start_column: start.column,
+                end_line: end.line,
+                end_column: end.column,
+            }))
+            .with_priority(EventPriority::Low)
+            .with_source("editor");
+
+        self.event_bus.publish(event).await
     }
 
-    /// Undo the last command.
-    pub async fn undo(&self) -> Result<bool> {
-        let command_to_undo = {
-            let mut position = self.history_position.write().await;
-            if *position == 0 {
-                return Ok(false); // Nothing to undo
-            }
+    /// Remove all cursors but the primary one.
+    pub async fn clear_secondary_cursors(&self) {
+        self.cursors.write().await.collapse_to_primary();
+    }
+
+    /// Add a cursor on the line above the topmost cursor.
+    ///
+    /// Returns `false` when the topmost cursor is on the first line.
+    pub async fn add_cursor_above(&self) -> Result<bool> {
+        self.add_cursor_vertically(false).await
+    }
+
+    /// Add a cursor on the line below the bottommost cursor.
+    ///
+    /// Returns `false` when the bottommost cursor is on the last line.
+    pub async fn add_cursor_below(&self) -> Result<bool> {
+        self.add_cursor_vertically(true).await
+    }
 
-            *position -= 1;
-            let history = self.command_history.read().await;
-            history[*position].clone()
+    /// Add a cursor next to the topmost or bottommost one, keeping its
+    /// desired column.
+    async fn add_cursor_vertically(&self, below: bool) -> Result<bool> {
+        let Some(path) = self.active_path().await else {
+            return Ok(false);
         };
 
-        // Execute the reverse command
-        match &command_to_undo.command_type {
-            CommandType::InsertText { line, column, text } => {
-                if let Some(path) = &command_to_undo.buffer_path {
-                    // Undo insert by deleting the inserted text
-                    let mut buffers = self.buffers.write().await;
-                    if let Some(buffer) = buffers.get_mut(path) {
-                        buffer.delete_text(*line, *column, text.len())?;
-                    }
-                }
-            }
-            CommandType::DeleteText { line, column, text } => {
-                if let Some(path) = &command_to_undo.buffer_path {
-                    // Undo delete by inserting the deleted text back
-                    let mut buffers = self.buffers.write().await;
-                    if let Some(buffer) = buffers.get_mut(path) {
-                        buffer.insert_text(*line, *column, text)?;
-                    }
-                }
-            }
-            _ => {} // Handle other command types as needed
-        }
+        {
+            let buffers = self.buffers.read().await;
+            let Some(buffer) = buffers.get(&path) else {
+                return Ok(false);
+            };
+            let mut cursors = self.cursors.write().await;
 
-        // Publish undo event
-        let event = EventMessage::from_event(Event::Editor(crate::core::EditorEvent::Undo))
-            .with_priority(EventPriority::Normal)
-            .with_source("editor");
+            let selections = cursors.selections();
+            let edge = if below { selections.last() } else { selections.first() };
+            let Some(edge) = edge.map(|selection| selection.end) else {
+                return Ok(false);
+            };
+            let line = if below {
+                if edge.line + 1 >= buffer.line_count() {
+                    return Ok(false);
+                }
+                edge.line + 1
+            } else {
+                let Some(line) = edge.line.checked_sub(1) else {
+                    return Ok(false);
+                };
+                line
+            };
 
-        self.event_bus.publish(event).await?;
+            let cursor = Cursor {
+                line,
+                column: buffer.clamp_column(line, edge.desired_column),
+                desired_column: edge.desired_column,
+            };
+            cursors.add(Selection::from_cursor(cursor));
+        }
 
+        self.publish_cursor_moved().await?;
         Ok(true)
     }
 
-    /// Redo the next command.
-    pub async fn redo(&self) -> Result<bool> {
-        let command_to_redo = {
-            let mut position = self.history_position.write().await;
-            let history = self.command_history.read().await;
+    /// Select the next occurrence of the primary selection, wrapping around
+    /// the end of the buffer.
+    ///
+    /// Without a selection, the word under the primary cursor is selected
+    /// instead. Returns `false` when there is nothing left to select.
+    pub async fn add_next_occurrence(&self) -> Result<bool> {
+        let Some(path) = self.active_path().await else {
+            return Ok(false);
+        };
 
-            if *position >= history.len() {
-                return Ok(false); // Nothing to redo
+        {
+            let buffers = self.buffers.read().await;
+            let Some(buffer) = buffers.get(&path) else {
+                return Ok(false);
+            };
+            let mut cursors = self.cursors.write().await;
+
+            let primary = cursors.primary().normalized();
+            if primary.is_empty() {
+                let Some(word) = word_at(buffer, primary.end) else {
+                    return Ok(false);
+                };
+                cursors.set_primary(word);
+            } else {
+                let from = buffer.position_to_byte(primary.end.line, primary.end.column);
+                let start = buffer.position_to_byte(primary.start.line, primary.start.column);
+                let text = buffer.snapshot().to_string();
+                let needle = &text[start..from];
+
+                let selected: Vec<(usize, usize)> = cursors
+                    .selections()
+                    .iter()
+                    .map(|selection| selection.actual_start().position())
+                    .collect();
+                let matches: Vec<usize> =
+                    text.match_indices(needle).map(|(offset, _)| offset).collect();
+                let next = matches
+                    .iter()
+                    .filter(|&&offset| offset >= from)
+                    .chain(matches.iter().filter(|&&offset| offset < from))
+                    .map(|&offset| {
+                        (
+                            buffer.byte_to_position(offset),
+                            buffer.byte_to_position(offset + needle.len()),
+                        )
+                    })
+                    .find(|(start, _)| !selected.contains(start));
+                let Some((start, end)) = next else {
+                    return Ok(false);
+                };
+
+                cursors
+                    .add(Selection::new(Cursor::new(start.0, start.1), Cursor::new(end.0, end.1)));
             }
+        }
 
-            let command = history[*position].clone();
-            *position += 1;
-            command
+        self.publish_cursor_moved().await?;
+        Ok(true)
+    }
+
+    /// Split every selection spanning several lines into one selection per line.
+    pub async fn split_selection_into_lines(&self) -> Result<()> {
+        let Some(path) = self.active_path().await else {
+            return Ok(());
         };
 
-        // Re-execute the command (without adding to history again)
-        match &command_to_redo.command_type {
-            CommandType::InsertText { line, column, text } => {
-                if let Some(path) = &command_to_redo.buffer_path {
-                    let mut buffers = self.buffers.write().await;
-                    if let Some(buffer) = buffers.get_mut(path) {
-                        buffer.insert_text(*line, *column, text)?;
-                    }
+        {
+            let buffers = self.buffers.read().await;
+            let Some(buffer) = buffers.get(&path) else {
+                return Ok(());
+            };
+            let mut cursors = self.cursors.write().await;
+
+            let mut selections = Vec::new();
+            for selection in cursors.selections() {
+                let norm = selection.normalized();
+                if norm.start.line == norm.end.line {
+                    selections.push(selection.clone());
+                    continue;
                 }
-            }
-            CommandType::DeleteText { line, column, text } => {
-                if let Some(path) = &command_to_redo.buffer_path {
-                    let mut buffers = self.buffers.write().await;
-                    if let Some(buffer) = buffers.get_mut(path) {
-                        buffer.delete_text(*line, *column, text.len())?;
-                    }
+
+                // A selection ending at the start of a line does not include that line.
+                let last_line =
+                    if norm.end.column == 0 { norm.end.line - 1 } else { norm.end.line };
+                for line in norm.start.line..=last_line {
+                    let start = if line == norm.start.line { norm.start.column } else { 0 };
+                    let end = if line == norm.end.line {
+                        norm.end.column
+                    } else {
+                        buffer.line_length(line)
+                    };
+                    selections
+                        .push(Selection::new(Cursor::new(line, start), Cursor::new(line, end)));
                 }
             }
-            _ => {} // Handle other command types as needed
-        }
-
-        // Publish redo event
-        let event = EventMessage::from_event(Event::Editor(crate::core::EditorEvent::Redo))
-            .with_priority(EventPriority::Normal)
-            .with_source("editor");
 
-        self.event_bus.publish(event).await?;
+            if let Some(split) = MultiCursor::from_selections(selections) {
+                *cursors = split;
+            }
+        }
 
-        Ok(true)
+        self.publish_cursor_moved().await
     }
 
-    /// Clear command history.
-    pub async fn clear_history(&self) {
-        let mut history = self.command_history.write().await;
-        let mut position = self.history_position.write().await;
+    /// Publish the position of the primary cursor.
+    async fn publish_cursor_moved(&self) -> Result<()> {
+        let (line, column) = self.get_cursor_position().await;
+        let event =
+            EventMessage::from_event(Event::Editor(crate::core::EditorEvent::CursorMoved {
+                line,
+                column,
+            }))
+            .with_priority(EventPriority::Low)
+            .with_source("editor");
 
-        history.clear();
-        *position = 0;
+        self.event_bus.publish(event).await
     }
 
-    /// Get the number of commands that can be undone.
-    pub async fn can_undo_count(&self) -> usize {
-        let position = self.history_position.read().await;
-        *position
+    /// Find all matches of a search query in the active buffer.
+    pub async fn find(&self, query: &SearchQuery) -> Vec<SearchMatch> {
+        let Some(path) = self.active_path().await else {
+            return Vec::new();
+        };
+        let buffers = self.buffers.read().await;
+        buffers.get(&path).map(|buffer| buffer.search(query)).unwrap_or_default()
     }
 
-    /// Get the number of commands that can be redone.
-    pub async fn can_redo_count(&self) -> usize {
-        let position = self.history_position.read().await;
-        let history = self.command_history.read().await;
-        history.len() - *position
-    }
+    /// Select the first match after the primary cursor, wrapping around to
+    /// the start of the buffer.
+    ///
+    /// Returns whether there was a match.
+    pub async fn find_next(&self, query: &SearchQuery) -> Result<bool> {
+        let Some(path) = self.active_path().await else {
+            return Ok(false);
+        };
 
-    /// Shutdown the editor.
-    pub async fn shutdown(&self) -> Result<()> {
-        debug!("Shutting down editor");
+        let (found, count) = {
+            let buffers = self.buffers.read().await;
+            let Some(buffer) = buffers.get(&path) else {
+                return Ok(false);
+            };
+            let (line, column) = self.cursors.read().await.primary_cursor().position();
+            let caret = buffer.position_to_byte(line, column);
+
+            let matches = buffer.search(query);
+            let next = matches
+                .iter()
+                .find(|found| {
+                    found.range.start > caret
+                        || (found.range.start == caret && !found.range.is_empty())
+                })
+                .or_else(|| matches.first())
+                .cloned();
+            (next, matches.len())
+        };
+
+        self.publish_find_replace(query, None, count).await?;
+        match found {
+            Some(found) => {
+                let start = Cursor::new(found.start.0, found.start.1);
+                let end = Cursor::new(found.end.0, found.end.1);
+                self.set_selection(Selection::new(start, end)).await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Replace every match of a search query in the active buffer, recorded
+    /// as one undoable command.
+    ///
+    /// In regex mode `$1`-style references in `replacement` are expanded from
+    /// each match's capture groups. Returns the number of replacements.
+    pub async fn replace_all(&self, query: &SearchQuery, replacement: &str) -> Result<usize> {
+        match self.active_path().await {
+            Some(path) => self.replace_all_in(&path, query, replacement).await,
+            None => Ok(0),
+        }
+    }
+
+    /// Replace every match of a search query in an open buffer, recorded as
+    /// one undoable command.
+    ///
+    /// Returns the number of replacements, or 0 when the file is not open.
+    pub async fn replace_all_in(
+        &self,
+        path: &PathBuf,
+        query: &SearchQuery,
+        replacement: &str,
+    ) -> Result<usize> {
+        let (commands, count) = {
+            let buffers = self.buffers.read().await;
+            let Some(buffer) = buffers.get(path) else {
+                return Ok(0);
+            };
+
+            let replacements = buffer.search_replacements(query, replacement);
+            let mut commands = Vec::with_capacity(replacements.len());
+            // Apply from the bottom up so earlier matches keep their positions
+            for (found, text) in replacements.iter().rev() {
+                if let Some(command_type) =
+                    replacement_command(buffer, found.start, found.end, text)?
+                {
+                    commands.push(Command::new(command_type, Some(path.clone())));
+                }
+            }
+            (commands, replacements.len())
+        };
+
+        if !commands.is_empty() {
+            let description = format!("Replace {} occurrences of '{}'", count, query.pattern());
+            self.execute_command(Command::composite(commands, description, Some(path.clone())))
+                .await?;
+        }
+
+        if count > 0 && self.active_path().await.as_ref() == Some(path) {
+            // Keep the primary cursor, clamped to the changed text
+            let caret = {
+                let buffers = self.buffers.read().await;
+                let (line, column) = self.cursors.read().await.primary_cursor().position();
+                buffers.get(path).map_or((0, 0), |buffer| {
+                    let line = line.min(buffer.line_count().saturating_sub(1));
+                    (line, buffer.clamp_column(line, column))
+                })
+            };
+            self.move_cursor(caret.0, caret.1).await?;
+        }
+
+        self.publish_find_replace(query, Some(replacement), count).await?;
+        Ok(count)
+    }
+
+    /// Publish a find or replace in the active buffer.
+    async fn publish_find_replace(
+        &self,
+        query: &SearchQuery,
+        replacement: Option<&str>,
+        count: usize,
+    ) -> Result<()> {
+        let options = query.options();
+        let event =
+            EventMessage::from_event(Event::Editor(crate::core::EditorEvent::FindReplace {
+                query: query.pattern().to_string(),
+                replacement: replacement.map(str::to_string),
+                regex: options.regex,
+                case_sensitive: options.case_sensitive,
+                whole_word: options.whole_word,
+                count,
+            }))
+            .with_priority(EventPriority::Low)
+            .with_source("editor");
+
+        self.event_bus.publish(event).await
+    }
+
+    /// Get the content of the active buffer.
+    pub async fn get_active_content(&self) -> Option<String> {
+        let active_path = {
+            let active = self.active_buffer.read().await;
+            active.clone()
+        };
+
+        if let Some(path) = active_path {
+            let buffers = self.buffers.read().await;
+            buffers.get(&path).map(|buffer| buffer.get_content())
+        } else {
+            None
+        }
+    }
+
+    /// Get the content of an open buffer.
+    pub async fn get_content(&self, path: &PathBuf) -> Option<String> {
+        let buffers = self.buffers.read().await;
+        buffers.get(path).map(|buffer| buffer.get_content())
+    }
+
+    /// Get a snapshot of an open buffer's text, with `\n` line endings.
+    pub async fn snapshot(&self, path: &PathBuf) -> Option<Rope> {
+        let buffers = self.buffers.read().await;
+        buffers.get(path).map(Buffer::snapshot)
+    }
+
+    /// Get the open buffers as a source of document text for language servers.
+    pub fn document_source(&self) -> Arc<dyn DocumentSource> {
+        self.buffers.clone()
+    }
+
+    /// Check whether an open buffer has unsaved changes.
+    pub async fn is_modified(&self, path: &PathBuf) -> bool {
+        let buffers = self.buffers.read().await;
+        buffers.get(path).is_some_and(Buffer::is_modified)
+    }
+
+    /// Get the path of the active buffer.
+    pub async fn active_path(&self) -> Option<PathBuf> {
+        self.active_buffer.read().await.clone()
+    }
+
+    /// Make an open buffer the active one, keeping its unsaved changes.
+    pub async fn set_active_buffer(&self, path: &PathBuf) -> Result<()> {
+        if !self.buffers.read().await.contains_key(path) {
+            return Err(XyluxError::syntax_error(format!(
+                "Buffer not found for path: {}",
+                path.display()
+            )));
+        }
+
+        *self.active_buffer.write().await = Some(path.clone());
+        *self.cursors.write().await = MultiCursor::default();
+        Ok(())
+    }
+
+    /// Replace a range of characters in an open buffer with `text`, recording
+    /// an undoable command.
+    ///
+    /// Character offsets count `\n` line endings, matching [`Editor::snapshot`].
+    pub async fn replace_chars(
+        &self,
+        path: &PathBuf,
+        range: Range<usize>,
+        text: &str,
+    ) -> Result<()> {
+        let command_type = {
+            let buffers = self.buffers.read().await;
+            let Some(buffer) = buffers.get(path) else {
+                return Err(XyluxError::syntax_error(format!(
+                    "Buffer not found for path: {}",
+                    path.display()
+                )));
+            };
+
+            let (start_line, start_column) =
+                buffer.byte_to_position(buffer.char_to_byte(range.start));
+            let (end_line, end_column) =
+                buffer.byte_to_position(buffer.char_to_byte(range.end.max(range.start)));
+            match replacement_command(
+                buffer,
+                (start_line, start_column),
+                (end_line, end_column),
+                text,
+            )? {
+                Some(command_type) => command_type,
+                None => return Ok(()),
+            }
+        };
+
+        self.execute_command(Command::new(command_type, Some(path.clone()))).await
+    }
+
+    /// Get a list of all open files.
+    pub async fn get_open_files(&self) -> Vec<PathBuf> {
+        let buffers = self.buffers.read().await;
+        buffers.keys().cloned().collect()
+    }
+
+    /// Check if there are any modified buffers.
+    pub async fn has_unsaved_changes(&self) -> bool {
+        let buffers = self.buffers.read().await;
+        buffers.values().any(|buffer| buffer.is_modified())
+    }
+
+    /// Close a file.
+    pub async fn close_file(&self, path: &PathBuf) -> Result<()> {
+        {
+            let mut buffers = self.buffers.write().await;
+            buffers.remove(path);
+        }
+
+        // Its history was persisted when the file was last saved
+        {
+            let mut histories = self.histories.write().await;
+            histories.remove(path);
+        }
+
+        // If this was the active buffer, clear it
+        {
+            let mut active = self.active_buffer.write().await;
+            if active.as_ref() == Some(path) {
+                *active = None;
+            }
+        }
+
+        // Publish file closed event
+        let event = EventMessage::from_event(Event::Editor(crate::core::EditorEvent::FileClosed {
+            path: path.clone(),
+        }))
+        .with_priority(EventPriority::Normal)
+        .with_source("editor");
+
+        self.event_bus.publish(event).await?;
+
+        info!("Closed: {}", path.display());
+        Ok(())
+    }
+
+    /// Apply LSP text edits to a file as a single undoable command.
+    ///
+    /// Files that are not open are loaded into a background buffer without
+    /// becoming active, so the change can be reviewed, saved or undone.
+    pub async fn apply_text_edits(
+        &self,
+        path: &PathBuf,
+        edits: &[TextEdit],
+        description: &str,
+    ) -> Result<()> {
+        if edits.is_empty() {
+            return Ok(());
+        }
+
+        self.load_buffer(path).await?;
+
+        let commands = {
+            let buffers = self.buffers.read().await;
+            let Some(buffer) = buffers.get(path) else {
+                return Err(XyluxError::syntax_error(format!(
+                    "Buffer not found for path: {}",
+                    path.display()
+                )));
+            };
+
+            let mut resolved = Vec::with_capacity(edits.len());
+            for (index, edit) in edits.iter().enumerate() {
+                let (start_line, start_column) = buffer_position(buffer, &edit.range.start);
+                let (end_line, end_column) = buffer_position(buffer, &edit.range.end);
+                let old_text =
+                    buffer.get_text_range(start_line, start_column, end_line, end_column)?;
+                resolved.push((
+                    (start_line, start_column, index),
+                    Command::replace_text(
+                        start_line,
+                        start_column,
+                        end_line,
+                        end_column,
+                        old_text,
+                        edit.new_text.clone(),
+                        Some(path.clone()),
+                    ),
+                ));
+            }
+
+            // Apply from the bottom up so earlier edits keep their positions;
+            // inserts at the same position keep the order the server sent them in.
+            resolved.sort_by(|(a, _), (b, _)| b.cmp(a));
+            resolved.into_iter().map(|(_, command)| command).collect()
+        };
+
+        self.execute_command(Command::composite(
+            commands,
+            description.to_string(),
+            Some(path.clone()),
+        ))
+        .await
+    }
+
+    /// Apply a workspace edit, recording one undoable command per file.
+    pub async fn apply_workspace_edit(
+        &self,
+        edit: &WorkspaceEdit,
+        description: &str,
+    ) -> Result<()> {
+        for (uri, edits) in &edit.changes {
+            let path = PathBuf::from(uri_to_path(uri));
+            self.apply_text_edits(&path, edits, description).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Load a file into a buffer without making it active.
+    async fn load_buffer(&self, path: &PathBuf) -> Result<()> {
+        if self.buffers.read().await.contains_key(path) {
+            return Ok(());
+        }
+
+        let content = std::fs::read_to_string(path)?;
+        self.restore_history(path, &content).await;
+        {
+            let mut buffers = self.buffers.write().await;
+            buffers.insert(path.clone(), Buffer::new(&content, Some(path.clone())));
+        }
+
+        let event = EventMessage::from_event(Event::Editor(crate::core::EditorEvent::FileOpened {
+            path: path.clone(),
+        }))
+        .with_priority(EventPriority::Normal)
+        .with_source("editor");
+
+        self.event_bus.publish(event).await
+    }
+
+    /// Start the history of a file that was just read, restoring the
+    /// persisted one if it was saved for `content`.
+    async fn restore_history(&self, path: &PathBuf, content: &str) {
+        let max_commands = self.config.read().await.editor.undo.max_commands;
+        let history = match self.undo_store.as_ref().and_then(|store| store.load(path, content)) {
+            Some(mut history) => {
+                debug!("Restored {} undo steps for {}", history.position(), path.display());
+                history.set_max_size(max_commands);
+                history
+            }
+            None => CommandHistory::new(max_commands),
+        };
+
+        let mut histories = self.histories.write().await;
+        histories.insert(path.clone(), history);
+    }
+
+    /// Persist the history of a file that was just saved with `content`.
+    async fn persist_history(&self, path: &PathBuf, content: &str) {
+        let Some(store) = &self.undo_store else {
+            return;
+        };
+
+        let histories = self.histories.read().await;
+        if let Some(history) = histories.get(path) {
+            if let Err(e) = store.save(path, content, history) {
+                warn!("Failed to save undo history for {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    /// Execute a command and add it to its buffer's history.
+    async fn execute_command(&self, command: Command) -> Result<()> {
+        self.apply_command(&command).await?;
+
+        if let Some(path) = command.buffer_path.clone() {
+            let max_commands = self.config.read().await.editor.undo.max_commands;
+            let mut histories = self.histories.write().await;
+            histories
+                .entry(path)
+                .or_insert_with(|| CommandHistory::new(max_commands))
+                .add_command(command);
+        }
+
+        Ok(())
+    }
+
+    /// Apply a command's changes to its buffer.
+    async fn apply_command(&self, command: &Command) -> Result<()> {
+        for (path, command_type) in leaf_commands(command) {
+            match command_type {
+                CommandType::InsertText { line, column, text } => {
+                    self.apply_insert(&path, line, column, &text).await?;
+                }
+                CommandType::DeleteText { line, column, text } => {
+                    self.apply_delete(&path, line, column, &text).await?;
+                }
+                CommandType::ReplaceText {
+                    start_line, start_column, old_text, new_text, ..
+                } => {
+                    self.apply_delete(&path, start_line, start_column, &old_text).await?;
+                    self.apply_insert(&path, start_line, start_column, &new_text).await?;
+                }
+                _ => {} // Handle other command types as needed
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reverse a command's changes to its buffer.
+    async fn revert_command(&self, command: &Command) -> Result<()> {
+        for (path, command_type) in leaf_commands(command).into_iter().rev() {
+            match command_type {
+                CommandType::InsertText { line, column, text } => {
+                    // Undo insert by deleting the inserted text
+                    self.apply_delete(&path, line, column, &text).await?;
+                }
+                CommandType::DeleteText { line, column, text } => {
+                    // Undo delete by inserting the deleted text back
+                    self.apply_insert(&path, line, column, &text).await?;
+                }
+                CommandType::ReplaceText {
+                    start_line, start_column, old_text, new_text, ..
+                } => {
+                    self.apply_delete(&path, start_line, start_column, &new_text).await?;
+                    self.apply_insert(&path, start_line, start_column, &old_text).await?;
+                }
+                _ => {} // Handle other command types as needed
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Insert text into a buffer and publish the change.
+    async fn apply_insert(
+        &self,
+        path: &PathBuf,
+        line: usize,
+        column: usize,
+        text: &str,
+    ) -> Result<()> {
+        {
+            let mut buffers = self.buffers.write().await;
+            if let Some(buffer) = buffers.get_mut(path) {
+                buffer.insert_text(line, column, text)?;
+            }
+        }
+
+        let event =
+            EventMessage::from_event(Event::Editor(crate::core::EditorEvent::TextInserted {
+                path: path.clone(),
+                line,
+                column,
+                text: text.to_string(),
+            }))
+            .with_priority(EventPriority::Normal)
+            .with_source("editor");
+
+        self.event_bus.publish(event).await
+    }
+
+    /// Delete previously inserted or read text from a buffer and publish the change.
+    async fn apply_delete(
+        &self,
+        path: &PathBuf,
+        line: usize,
+        column: usize,
+        text: &str,
+    ) -> Result<()> {
+        let (end_line, end_column) = text_end(line, column, text);
+
+        {
+            let mut buffers = self.buffers.write().await;
+            if let Some(buffer) = buffers.get_mut(path) {
+                buffer.delete_range(line, column, end_line, end_column)?;
+            }
+        }
+
+        let event =
+            EventMessage::from_event(Event::Editor(crate::core::EditorEvent::TextDeleted {
+                path: path.clone(),
+                start_line: line,
+                start_column: column,
+                end_line,
+                end_column,
+            }))
+            .with_priority(EventPriority::Normal)
+            .with_source("editor");
+
+        self.event_bus.publish(event).await
+    }
+
+    /// Undo the most recent command in the active buffer.
+    pub async fn undo(&self) -> Result<bool> {
+        match self.active_path().await {
+            Some(path) => self.undo_buffer(&path).await,
+            None => Ok(false),
+        }
+    }
+
+    /// Undo the most recent command in the buffer for `path`.
+    ///
+    /// Returns whether there was a command to undo.
+    pub async fn undo_buffer(&self, path: &Path) -> Result<bool> {
+        let command_to_undo = {
+            let mut histories = self.histories.write().await;
+            match histories.get_mut(path).and_then(|history| history.step_back().cloned()) {
+                Some(command) => command,
+                None => return Ok(false), // Nothing to undo
+            }
+        };
+
+        // Execute the reverse command
+        self.revert_command(&command_to_undo).await?;
+
+        // Publish undo event
+        let event = EventMessage::from_event(Event::Editor(crate::core::EditorEvent::Undo))
+            .with_priority(EventPriority::Normal)
+            .with_source("editor");
+
+        self.event_bus.publish(event).await?;
+
+        Ok(true)
+    }
+
+    /// Redo the most recently undone command in the active buffer.
+    pub async fn redo(&self) -> Result<bool> {
+        match self.active_path().await {
+            Some(path) => self.redo_buffer(&path).await,
+            None => Ok(false),
+        }
+    }
+
+    /// Redo the most recently undone command in the buffer for `path`.
+    ///
+    /// Returns whether there was a command to redo.
+    pub async fn redo_buffer(&self, path: &Path) -> Result<bool> {
+        let command_to_redo = {
+            let mut histories = self.histories.write().await;
+            match histories.get_mut(path).and_then(|history| history.step_forward().cloned()) {
+                Some(command) => command,
+                None => return Ok(false), // Nothing to redo
+            }
+        };
+
+        // Re-execute the command (without adding to history again)
+        self.apply_command(&command_to_redo).await?;
+
+        // Publish redo event
+        let event = EventMessage::from_event(Event::Editor(crate::core::EditorEvent::Redo))
+            .with_priority(EventPriority::Normal)
+            .with_source("editor");
+
+        self.event_bus.publish(event).await?;
+
+        Ok(true)
+    }
+
+    /// List the undo branches of the active buffer, oldest first.
+    pub async fn history_branches(&self) -> Vec<HistoryBranch> {
+        let Some(path) = self.active_path().await else {
+            return Vec::new();
+        };
+        let histories = self.histories.read().await;
+        histories.get(&path).map(CommandHistory::branches).unwrap_or_default()
+    }
+
+    /// Move the active buffer to the state after history node `id`, such as
+    /// a [`HistoryBranch::id`], or to its original state for `None`.
+    ///
+    /// Returns whether the buffer changed.
+    pub async fn goto_history_state(&self, id: Option<usize>) -> Result<bool> {
+        self.travel_history(|history| history.goto(id).unwrap_or_default()).await
+    }
+
+    /// Move the active buffer back to its latest state from at least
+    /// `duration` earlier, on any undo branch.
+    ///
+    /// Returns whether the buffer changed.
+    pub async fn earlier(&self, duration: Duration) -> Result<bool> {
+        self.travel_history(|history| history.earlier(duration)).await
+    }
+
+    /// Move the active buffer forward to its latest state from at most
+    /// `duration` later, on any undo branch.
+    ///
+    /// Returns whether the buffer changed.
+    pub async fn later(&self, duration: Duration) -> Result<bool> {
+        self.travel_history(|history| history.later(duration)).await
+    }
+
+    /// Move through the active buffer's history and apply the resulting steps.
+    async fn travel_history(
+        &self,
+        navigate: impl FnOnce(&mut CommandHistory) -> Vec<HistoryStep>,
+    ) -> Result<bool> {
+        let Some(path) = self.active_path().await else {
+            return Ok(false);
+        };
+        let steps = {
+            let mut histories = self.histories.write().await;
+            match histories.get_mut(&path) {
+                Some(history) => navigate(history),
+                None => return Ok(false),
+            }
+        };
+
+        for step in &steps {
+            let event = match step {
+                HistoryStep::Undo(command) => {
+                    self.revert_command(command).await?;
+                    crate::core::EditorEvent::Undo
+                }
+                HistoryStep::Redo(command) => {
+                    self.apply_command(command).await?;
+                    crate::core::EditorEvent::Redo
+                }
+            };
+            let event = EventMessage::from_event(Event::Editor(event))
+                .with_priority(EventPriority::Normal)
+                .with_source("editor");
+            self.event_bus.publish(event).await?;
+        }
+
+        Ok(!steps.is_empty())
+    }
+
+    /// Clear the command history of every buffer.
+    pub async fn clear_history(&self) {
+        let mut histories = self.histories.write().await;
+        histories.values_mut().for_each(CommandHistory::clear);
+    }
+
+    /// Start a group of edits to the active buffer that undo as one step.
+    ///
+    /// Returns a mark to pass to [`end_undo_group`](Self::end_undo_group).
+    /// The first edit of the group does not merge into earlier commands.
+    pub async fn begin_undo_group(&self) -> usize {
+        let Some(path) = self.active_path().await else {
+            return 0;
+        };
+        let mut histories = self.histories.write().await;
+        histories.get_mut(&path).map_or(0, |history| {
+            history.seal();
+            history.position()
+        })
+    }
+
+    /// Combine the edits to the active buffer made since `mark` into one
+    /// undo step.
+    pub async fn end_undo_group(&self, mark: usize, description: &str) {
+        let Some(path) = self.active_path().await else {
+            return;
+        };
+        let mut histories = self.histories.write().await;
+        if let Some(history) = histories.get_mut(&path) {
+            let count = history.position().saturating_sub(mark);
+            history.squash(count, description);
+            history.seal();
+        }
+    }
+
+    /// Get the number of commands that can be undone in the active buffer.
+    pub async fn can_undo_count(&self) -> usize {
+        let Some(path) = self.active_path().await else {
+            return 0;
+        };
+        let histories = self.histories.read().await;
+        histories.get(&path).map_or(0, CommandHistory::position)
+    }
+
+    /// Get the number of commands that can be redone in the active buffer.
+    pub async fn can_redo_count(&self) -> usize {
+        let Some(path) = self.active_path().await else {
+            return 0;
+        };
+        let histories = self.histories.read().await;
+        histories.get(&path).map_or(0, CommandHistory::redo_count)
+    }
+
+    /// Shutdown the editor.
+    pub async fn shutdown(&self) -> Result<()> {
+        debug!("Shutting down editor");
 
         // Save all modified files
         self.save_all().await?;
@@ -574,6 +1541,155 @@ impl Editor {
     }
 }
 
+/// Id of the `number`th untitled buffer.
+fn untitled_id(number: usize) -> PathBuf {
+    PathBuf::from(format!("untitled-{number}"))
+}
+
+/// Compute the position just past `text` when it is inserted at `line`/`column`.
+fn text_end(line: usize, column: usize, text: &str) -> (usize, usize) {
+    match text.rfind('\n') {
+        Some(last_newline) => (line + text.matches('\n').count(), text.len() - last_newline - 1),
+        None => (line, column + text.len()),
+    }
+}
+
+/// Build the command replacing `start..end` in `buffer` with `text`, or
+/// `None` when nothing would change.
+fn replacement_command(
+    buffer: &Buffer,
+    (start_line, start_column): (usize, usize),
+    (end_line, end_column): (usize, usize),
+    text: &str,
+) -> Result<Option<CommandType>> {
+    let old_text = buffer.get_text_range(start_line, start_column, end_line, end_column)?;
+
+    Ok(match (old_text.is_empty(), text.is_empty()) {
+        (true, true) => None,
+        (true, false) => Some(CommandType::InsertText {
+            line: start_line,
+            column: start_column,
+            text: text.to_string(),
+        }),
+        (false, true) => {
+            Some(CommandType::DeleteText { line: start_line, column: start_column, text: old_text })
+        }
+        (false, false) => Some(CommandType::ReplaceText {
+            start_line,
+            start_column,
+            end_line,
+            end_column,
+            old_text,
+            new_text: text.to_string(),
+        }),
+    })
+}
+
+/// Build one command replacing byte column ranges within lines, applied from
+/// the bottom up, or `None` when nothing would change.
+///
+/// Each edit is `(line, start_column, end_column, text)`. Edits at the same
+/// position are applied in the order given.
+fn line_edits_command(
+    buffer: &Buffer,
+    path: &PathBuf,
+    description: &str,
+    mut edits: Vec<(usize, usize, usize, String)>,
+) -> Result<Option<Command>> {
+    edits.sort_by(|a, b| (b.0, b.1).cmp(&(a.0, a.1)));
+
+    let mut commands = Vec::with_capacity(edits.len());
+    for (line, start, end, text) in edits {
+        if let Some(command_type) = replacement_command(buffer, (line, start), (line, end), &text)?
+        {
+            commands.push(Command::new(command_type, Some(path.clone())));
+        }
+    }
+
+    Ok(match commands.len() {
+        0 => None,
+        1 => commands.pop(),
+        _ => Some(Command::composite(commands, description.to_string(), Some(path.clone()))),
+    })
+}
+
+/// Lines and display columns covered by a block selection.
+fn block_of(
+    buffer: &Buffer,
+    selection: &Selection,
+    tab_size: usize,
+) -> (RangeInclusive<usize>, Range<usize>) {
+    let (start, end) = (selection.start, selection.end);
+    let start_column = buffer.display_column(start.line, start.column, tab_size);
+    let end_column = buffer.display_column(end.line, end.column, tab_size);
+
+    (
+        start.line.min(end.line)..=start.line.max(end.line),
+        start_column.min(end_column)..start_column.max(end_column),
+    )
+}
+
+/// Select the word containing or just before `cursor`.
+fn word_at(buffer: &Buffer, cursor: Cursor) -> Option<Selection> {
+    let line = buffer.get_line(cursor.line)?;
+    let column = buffer.clamp_column(cursor.line, cursor.column);
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+
+    let start = line[..column]
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| is_word(c))
+        .last()
+        .map_or(column, |(offset, _)| offset);
+    let end =
+        line[column..].find(|c: char| !is_word(c)).map_or(line.len(), |offset| column + offset);
+    if start == end {
+        return None;
+    }
+
+    Some(Selection::new(Cursor::new(cursor.line, start), Cursor::new(cursor.line, end)))
+}
+
+/// Flatten a command into the buffer changes it performs, in execution order.
+///
+/// Sub-commands of a composite without their own buffer apply to the composite's.
+fn leaf_commands(command: &Command) -> Vec<(PathBuf, CommandType)> {
+    fn collect(
+        command: &Command,
+        path: Option<&PathBuf>,
+        leaves: &mut Vec<(PathBuf, CommandType)>,
+    ) {
+        let path = command.buffer_path.as_ref().or(path);
+        match &command.command_type {
+            CommandType::Composite { commands, .. } => {
+                for sub_command in commands {
+                    collect(sub_command, path, leaves);
+                }
+            }
+            command_type => {
+                if let Some(path) = path {
+                    leaves.push((path.clone(), command_type.clone()));
+                }
+            }
+        }
+    }
+
+    let mut leaves = Vec::new();
+    collect(command, None, &mut leaves);
+    leaves
+}
+
+/// Convert an LSP position into a byte position in `buffer`, clamped to its end.
+fn buffer_position(buffer: &Buffer, position: &Position) -> (usize, usize) {
+    match buffer.get_line(position.line) {
+        Some(line) => (position.line, utf16_to_byte_offset(&line, position.character)),
+        None => {
+            let last_line = buffer.line_count().saturating_sub(1);
+            (last_line, buffer.line_length(last_line))
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -590,11 +1706,13 @@ mod tests {
 
     #[tokio::test]
     async fn test_file_operations() {
-        let config = Arc::new(RwLock::new(Config::default()));
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.editor.undo.directory = Some(temp_dir.path().join("undo"));
+        let config = Arc::new(RwLock::new(config));
         let event_bus = Arc::new(EventBus::new());
         let editor = Editor::new(config, event_bus).await.unwrap();
 
-        let temp_dir = TempDir::new().unwrap();
         let test_file = temp_dir.path().join("test.txt");
         std::fs::write(&test_file, "Hello, world!").unwrap();
 
@@ -612,4 +1730,380 @@ mod tests {
         // Test saving
         editor.save_current().await.unwrap();
     }
+
+    #[tokio::test]
+    async fn test_new_file() {
+        let config = Arc::new(RwLock::new(Config::default()));
+        let editor = Editor::new(config, Arc::new(EventBus::new())).await.unwrap();
+
+        let first = editor.new_file().await;
+        editor.insert_text("draft").await.unwrap();
+        assert_eq!(editor.get_content(&first).await, Some("draft".to_string()));
+        assert!(editor.undo().await.unwrap());
+
+        let second = editor.new_file().await;
+        assert_ne!(first, second);
+        assert_eq!(editor.active_path().await, Some(second));
+        assert!(editor.save_current().await.is_err());
+        editor.save_all().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_undo_multiline_insert() {
+        let config = Arc::new(RwLock::new(Config::default()));
+        let event_bus = Arc::new(EventBus::new());
+        let editor = Editor::new(config, Arc::clone(&event_bus)).await.unwrap();
+
+        let temp_dir = TempDir::new().unwrap();
+        let test_file = temp_dir.path().join("test.rs");
+        std::fs::write(&test_file, "fn main() {}").unwrap();
+        editor.open_file(&test_file).await.unwrap();
+
+        let mut events = event_bus.subscribe(crate::core::EventSubscription::all());
+        editor.move_cursor(0, 11).await.unwrap();
+        editor.insert_text("\n    run();\n").await.unwrap();
+        assert_eq!(editor.get_active_content().await.unwrap(), "fn main() {\n    run();\n}");
+
+        assert!(editor.undo().await.unwrap());
+        assert_eq!(editor.get_active_content().await.unwrap(), "fn main() {}");
+
+        // The undo is published as a deletion of the inserted lines.
+        let mut deleted = None;
+        while let Ok(event) = events.try_recv() {
+            if let Ok(Event::Editor(crate::core::EditorEvent::TextDeleted {
+                path,
+                start_line,
+                start_column,
+                end_line,
+                end_column,
+            })) = serde_json::from_value(event.data)
+            {
+                deleted = Some((path, start_line, start_column, end_line, end_column));
+            }
+        }
+        assert_eq!(deleted, Some((test_file, 0, 11, 2, 0)));
+    }
+
+    #[tokio::test]
+    async fn test_text_edits_undo_as_one_command() {
+        let config = Arc::new(RwLock::new(Config::default()));
+        let event_bus = Arc::new(EventBus::new());
+        let editor = Editor::new(config, event_bus).await.unwrap();
+
+        let temp_dir = TempDir::new().unwrap();
+        let test_file = temp_dir.path().join("test.rs");
+        std::fs::write(&test_file, "let é = 1;\nlet b = é;").unwrap();
+
+        let edit = |line, start, end, text: &str| {
+            TextEdit::new(
+                crate::syntax::lsp_client::Range::new(
+                    Position::new(line, start),
+                    Position::new(line, end),
+                ),
+                text.to_string(),
+            )
+        };
+        // Columns are UTF-16 offsets; the two inserts share a position and keep their order.
+        let edits =
+            [edit(0, 4, 5, "x"), edit(1, 8, 9, "x"), edit(1, 0, 0, "// "), edit(1, 0, 0, "a ")];
+
+        // The file is loaded on demand without becoming active.
+        editor.apply_text_edits(&test_file, &edits, "Rename to 'x'").await.unwrap();
+        assert_eq!(editor.get_active_content().await, None);
+        assert_eq!(
+            editor.get_content(&test_file).await.as_deref(),
+            Some("let x = 1;\n// a let b = x;")
+        );
+
+        // Its history is undone once it is the active buffer.
+        assert_eq!(editor.can_undo_count().await, 0);
+        editor.set_active_buffer(&test_file).await.unwrap();
+        assert_eq!(editor.can_undo_count().await, 1);
+        assert!(editor.undo().await.unwrap());
+        assert_eq!(editor.get_content(&test_file).await.as_deref(), Some("let é = 1;\nlet b = é;"));
+        assert!(editor.redo().await.unwrap());
+        assert_eq!(
+            editor.get_content(&test_file).await.as_deref(),
+            Some("let x = 1;\n// a let b = x;")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replace_chars() {
+        let config = Arc::new(RwLock::new(Config::default()));
+        let event_bus = Arc::new(EventBus::new());
+        let editor = Editor::new(config, event_bus).await.unwrap();
+
+        let temp_dir = TempDir::new().unwrap();
+        let test_file = temp_dir.path().join("test.txt");
+        std::fs::write(&test_file, "héllo\r\nworld").unwrap();
+        editor.open_file(&test_file).await.unwrap();
+
+        // Offsets are in characters of the `\n` text; typing merges into one command.
+        editor.replace_chars(&test_file, 5..5, "!").await.unwrap();
+        editor.replace_chars(&test_file, 6..6, "?").await.unwrap();
+        editor.replace_chars(&test_file, 9..13, "ant").await.unwrap();
+        assert_eq!(editor.snapshot(&test_file).await.unwrap().to_string(), "héllo!?\nwant");
+        assert_eq!(editor.get_content(&test_file).await.unwrap(), "héllo!?\r\nwant");
+        assert!(editor.is_modified(&test_file).await);
+        assert_eq!(editor.can_undo_count().await, 2);
+
+        assert!(editor.undo().await.unwrap());
+        assert!(editor.undo().await.unwrap());
+        assert_eq!(editor.get_content(&test_file).await.unwrap(), "héllo\r\nworld");
+    }
+
+    #[tokio::test]
+    async fn test_undo_history_persists() {
+        let temp_dir = TempDir::new().unwrap();
+        let test_file = temp_dir.path().join("test.txt");
+        std::fs::write(&test_file, "one").unwrap();
+        let mut config = Config::default();
+        config.editor.undo.directory = Some(temp_dir.path().join("undo"));
+        let config = Arc::new(RwLock::new(config));
+        let new_editor = || Editor::new(Arc::clone(&config), Arc::new(EventBus::new()));
+
+        let editor = new_editor().await.unwrap();
+        editor.open_file(&test_file).await.unwrap();
+        editor.move_cursor(0, 3).await.unwrap();
+        editor.insert_text(" two").await.unwrap();
+        editor.move_cursor(0, 0).await.unwrap();
+        editor.insert_text("zero ").await.unwrap();
+        editor.save_current().await.unwrap();
+        editor.close_file(&test_file).await.unwrap();
+
+        // A restarted editor can undo edits from the previous session.
+        let editor = new_editor().await.unwrap();
+        editor.open_file(&test_file).await.unwrap();
+        assert_eq!(editor.can_undo_count().await, 2);
+        assert!(editor.undo().await.unwrap());
+        assert!(editor.undo().await.unwrap());
+        assert_eq!(editor.get_active_content().await.as_deref(), Some("one"));
+        assert!(editor.redo().await.unwrap());
+        assert_eq!(editor.get_active_content().await.as_deref(), Some("one two"));
+
+        // History for content changed outside the editor is discarded.
+        std::fs::write(&test_file, "rewritten").unwrap();
+        let editor = new_editor().await.unwrap();
+        editor.open_file(&test_file).await.unwrap();
+        assert_eq!(editor.can_undo_count().await, 0);
+        assert!(!editor.undo().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_undo_is_per_buffer() {
+        let (editor, temp_dir, first) = open_editor("one").await;
+        editor.insert_text("1").await.unwrap();
+
+        let second = temp_dir.path().join("second.txt");
+        std::fs::write(&second, "two").unwrap();
+        editor.open_file(&second).await.unwrap();
+        assert_eq!(editor.can_undo_count().await, 0);
+        assert!(!editor.undo().await.unwrap());
+        assert_eq!(editor.get_content(&first).await.as_deref(), Some("1one"));
+
+        editor.insert_text("2").await.unwrap();
+        editor.set_active_buffer(&first).await.unwrap();
+        assert_eq!(editor.can_undo_count().await, 1);
+        assert!(editor.undo().await.unwrap());
+        assert_eq!(editor.get_content(&first).await.as_deref(), Some("one"));
+        assert_eq!(editor.get_content(&second).await.as_deref(), Some("2two"));
+        assert_eq!(editor.can_redo_count().await, 1);
+
+        editor.set_active_buffer(&second).await.unwrap();
+        assert_eq!(editor.can_redo_count().await, 0);
+        assert!(!editor.redo().await.unwrap());
+        assert!(editor.undo().await.unwrap());
+        assert_eq!(editor.get_content(&second).await.as_deref(), Some("two"));
+    }
+
+    #[tokio::test]
+    async fn test_undo_tree() {
+        let (editor, _temp_dir, _) = open_editor("x").await;
+        editor.insert_text("a").await.unwrap();
+        editor.undo().await.unwrap();
+        editor.insert_text("b").await.unwrap();
+        assert!(!editor.redo().await.unwrap());
+
+        let branches = editor.history_branches().await;
+        assert_eq!(branches.len(), 2);
+        assert_eq!(branches[0].description, "Insert 'a'");
+        assert!(branches[1].active);
+
+        assert!(editor.goto_history_state(Some(branches[0].id)).await.unwrap());
+        assert_eq!(editor.get_active_content().await.as_deref(), Some("ax"));
+        assert!(editor.earlier(Duration::from_secs(3600)).await.unwrap());
+        assert_eq!(editor.get_active_content().await.as_deref(), Some("x"));
+        assert!(editor.later(Duration::from_secs(3600)).await.unwrap());
+        assert_eq!(editor.get_active_content().await.as_deref(), Some("xb"));
+        assert!(!editor.later(Duration::from_secs(3600)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_regex_replace_all() {
+        let (editor, _temp_dir, _) = open_editor("let a = 1;\nlet bb = 22;\nlet\nc = 3;").await;
+        let options = SearchOptions { regex: true, case_sensitive: true, whole_word: false };
+        let query = SearchQuery::new(r"let\s+(\w+) = (\d+);", options).unwrap();
+
+        assert_eq!(editor.find(&query).await.len(), 3);
+        editor.move_cursor(0, 3).await.unwrap();
+        assert!(editor.find_next(&query).await.unwrap());
+        assert_eq!(editor.get_cursor_position().await, (1, 12));
+        assert!(editor.find_next(&query).await.unwrap());
+        assert_eq!(editor.get_cursor_position().await, (3, 6));
+        assert!(editor.find_next(&query).await.unwrap());
+        assert_eq!(editor.get_cursor_position().await, (0, 10));
+
+        assert_eq!(editor.replace_all(&query, "const $1: u32 = $2;").await.unwrap(), 3);
+        assert_eq!(
+            editor.get_active_content().await.as_deref(),
+            Some("const a: u32 = 1;\nconst bb: u32 = 22;\nconst c: u32 = 3;")
+        );
+
+        // All replacements are undone together.
+        assert!(editor.undo().await.unwrap());
+        assert_eq!(
+            editor.get_active_content().await.as_deref(),
+            Some("let a = 1;\nlet bb = 22;\nlet\nc = 3;")
+        );
+        assert!(!editor.undo().await.unwrap());
+    }
+
+    async fn open_editor(content: &str) -> (Editor, TempDir, PathBuf) {
+        let config = Arc::new(RwLock::new(Config::default()));
+        let event_bus = Arc::new(EventBus::new());
+        let editor = Editor::new(config, event_bus).await.unwrap();
+
+        let temp_dir = TempDir::new().unwrap();
+        let test_file = temp_dir.path().join("test.txt");
+        std::fs::write(&test_file, content).unwrap();
+        editor.open_file(&test_file).await.unwrap();
+        (editor, temp_dir, test_file)
+    }
+
+    #[tokio::test]
+    async fn test_multi_cursor_edits() {
+        let (editor, _temp_dir, _) = open_editor("let a = 1;\nlet b = 2;\nlet c = 3;").await;
+
+        editor.move_cursor(0, 4).await.unwrap();
+        assert!(editor.add_cursor_below().await.unwrap());
+        assert!(editor.add_cursor_below().await.unwrap());
+        assert!(!editor.add_cursor_below().await.unwrap());
+        assert!(!editor.add_cursor_above().await.unwrap());
+        assert_eq!(editor.cursors().await.count(), 3);
+
+        editor.insert_text("x_").await.unwrap();
+        editor.delete_text(1).await.unwrap();
+        assert_eq!(
+            editor.get_active_content().await.unwrap(),
+            "let x_ = 1;\nlet x_ = 2;\nlet x_ = 3;"
+        );
+        let carets: Vec<_> =
+            editor.cursors().await.selections().iter().map(|s| s.end.position()).collect();
+        assert_eq!(carets, vec![(0, 6), (1, 6), (2, 6)]);
+
+        // Each edit at all cursors is one command.
+        assert_eq!(editor.can_undo_count().await, 2);
+        assert!(editor.undo().await.unwrap());
+        assert!(editor.undo().await.unwrap());
+        assert_eq!(
+            editor.get_active_content().await.unwrap(),
+            "let a = 1;\nlet b = 2;\nlet c = 3;"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_add_next_occurrence() {
+        let (editor, _temp_dir, _) = open_editor("foo bar foo\nfoo_bar foo").await;
+
+        editor.move_cursor(0, 1).await.unwrap();
+        assert!(editor.add_next_occurrence().await.unwrap());
+        assert_eq!(
+            editor.cursors().await.primary(),
+            &Selection::new(Cursor::new(0, 0), Cursor::new(0, 3))
+        );
+        assert!(editor.add_next_occurrence().await.unwrap());
+        assert!(editor.add_next_occurrence().await.unwrap());
+        assert!(editor.add_next_occurrence().await.unwrap());
+        assert!(!editor.add_next_occurrence().await.unwrap());
+        assert_eq!(editor.cursors().await.count(), 4);
+
+        editor.insert_text("baz").await.unwrap();
+        assert_eq!(editor.get_active_content().await.unwrap(), "baz bar baz\nbaz_bar baz");
+        assert_eq!(editor.can_undo_count().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_split_selection_into_lines() {
+        let (editor, _temp_dir, _) = open_editor("ab\ncd\nef\ngh").await;
+
+        editor.set_selection(Selection::new(Cursor::new(0, 1), Cursor::new(3, 0))).await.unwrap();
+        editor.split_selection_into_lines().await.unwrap();
+        let ranges: Vec<_> = editor
+            .cursors()
+            .await
+            .selections()
+            .iter()
+            .map(|s| (s.start.position(), s.end.position()))
+            .collect();
+        assert_eq!(ranges, vec![((0, 1), (0, 2)), ((1, 0), (1, 2)), ((2, 0), (2, 2))]);
+
+        editor.insert_text("-").await.unwrap();
+        assert_eq!(editor.get_active_content().await.unwrap(), "a-\n-\n-\ngh");
+    }
+
+    #[tokio::test]
+    async fn test_block_editing() {
+        let original = "abcd\nx\nefgh\n漢字ij";
+        let (editor, _temp_dir, _) = open_editor(original).await;
+        let block = |start: (usize, usize), end: (usize, usize)| {
+            Selection::with_mode(
+                Cursor::new(start.0, start.1),
+                Cursor::new(end.0, end.1),
+                SelectionMode::Block,
+            )
+        };
+
+        editor.set_selection(block((0, 1), (2, 3))).await.unwrap();
+        assert_eq!(
+            editor.copy_block().await,
+            Some(vec!["bc".to_string(), String::new(), "fg".to_string()])
+        );
+
+        // Typing replaces the block on every line that reaches it.
+        editor.insert_text("Z").await.unwrap();
+        assert_eq!(editor.get_active_content().await.unwrap(), "aZd\nxZ\neZh\n漢字ij");
+        assert_eq!(editor.cursors().await.primary(), &block((0, 2), (2, 2)));
+
+        editor.delete_text(1).await.unwrap();
+        assert_eq!(editor.get_active_content().await.unwrap(), "aZ\nxZ\neZ\n漢字ij");
+
+        assert_eq!(editor.can_undo_count().await, 2);
+        assert!(editor.undo().await.unwrap());
+        assert!(editor.undo().await.unwrap());
+        assert_eq!(editor.get_active_content().await.unwrap(), original);
+
+        // Columns are display widths: "漢" is two columns wide.
+        editor.set_selection(block((1, 1), (3, 7))).await.unwrap();
+        assert_eq!(
+            editor.copy_block().await,
+            Some(vec![String::new(), "fgh".to_string(), "字i".to_string()])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_paste_block() {
+        let (editor, _temp_dir, _) = open_editor("abcd\nx\n漢字").await;
+
+        // Short lines are padded and missing lines appended.
+        editor.move_cursor(0, 4).await.unwrap();
+        editor.paste_block(&["-".to_string(), "-".to_string()]).await.unwrap();
+        assert_eq!(editor.get_active_content().await.unwrap(), "abcd-\nx   -\n漢字");
+        assert_eq!(editor.get_cursor_position().await, (1, 5));
+
+        editor.move_cursor(2, 3).await.unwrap();
+        editor.paste_block(&["1".to_string(), "2".to_string()]).await.unwrap();
+        assert_eq!(editor.get_active_content().await.unwrap(), "abcd-\nx   -\n漢1字\n  2");
+        assert_eq!(editor.can_undo_count().await, 2);
+    }
 }
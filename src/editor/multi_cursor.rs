@@ -0,0 +1,170 @@
+//! # Multi-Cursor Module
+//!
+//! A set of selections that are edited together.
+//!
+//! Each cursor is a [`Selection`] whose `start` is the anchor and `end` the
+//! caret; an empty selection is a plain cursor. Selections are kept in
+//! document order, and overlapping ones are merged.
+
+use super::cursor::Cursor;
+use super::selection::Selection;
+
+/// The cursors of an editor, one of which is primary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiCursor {
+    /// Selections in document order, never overlapping.
+    selections: Vec<Selection>,
+    /// Index of the primary selection.
+    primary: usize,
+}
+
+impl MultiCursor {
+    /// Create a single cursor at the given position.
+    pub fn new(cursor: Cursor) -> Self {
+        Self::from_selection(Selection::from_cursor(cursor))
+    }
+
+    /// Create a single cursor with a selection.
+    pub fn from_selection(selection: Selection) -> Self {
+        Self { selections: vec![selection], primary: 0 }
+    }
+
+    /// Create cursors from selections, the last of which becomes primary.
+    ///
+    /// Returns `None` when `selections` is empty.
+    pub fn from_selections(selections: Vec<Selection>) -> Option<Self> {
+        let primary = selections.len().checked_sub(1)?;
+        let mut cursors = Self { selections, primary };
+        cursors.normalize();
+        Some(cursors)
+    }
+
+    /// Get all selections in document order.
+    pub fn selections(&self) -> &[Selection] {
+        &self.selections
+    }
+
+    /// Get the primary selection.
+    pub fn primary(&self) -> &Selection {
+        &self.selections[self.primary]
+    }
+
+    /// Get the caret of the primary selection.
+    pub fn primary_cursor(&self) -> Cursor {
+        self.primary().end
+    }
+
+    /// Get the number of cursors.
+    pub fn count(&self) -> usize {
+        self.selections.len()
+    }
+
+    /// Check whether there is only one cursor.
+    pub fn is_single(&self) -> bool {
+        self.selections.len() == 1
+    }
+
+    /// Add a selection and make it primary, merging it with any it overlaps.
+    pub fn add(&mut self, selection: Selection) {
+        self.selections.push(selection);
+        self.primary = self.selections.len() - 1;
+        self.normalize();
+    }
+
+    /// Replace the primary selection.
+    pub fn set_primary(&mut self, selection: Selection) {
+        self.selections[self.primary] = selection;
+        self.normalize();
+    }
+
+    /// Keep only the primary selection.
+    pub fn collapse_to_primary(&mut self) {
+        let primary = self.primary().clone();
+        *self = Self::from_selection(primary);
+    }
+
+    /// Sort the selections and merge the ones that overlap.
+    ///
+    /// Selections that only touch stay separate, so adjacent occurrences of a
+    /// word can be selected individually; cursors at the same position merge.
+    fn normalize(&mut self) {
+        let primary = self.selections[self.primary].clone();
+        self.selections.sort_by_key(|selection| {
+            let start = selection.actual_start();
+            (start.line, start.column)
+        });
+
+        let mut merged: Vec<Selection> = Vec::with_capacity(self.selections.len());
+        for selection in self.selections.drain(..) {
+            if let Some(last) = merged.last_mut() {
+                let touching = last.actual_end().position() == selection.actual_start().position()
+                    && !last.is_empty()
+                    && !selection.is_empty();
+                if !touching {
+                    if let Some(union) = last.merge_with(&selection) {
+                        *last = union;
+                        continue;
+                    }
+                }
+            }
+            merged.push(selection);
+        }
+        self.selections = merged;
+
+        // The primary is whichever selection now holds the old primary's caret.
+        let caret = primary.end;
+        self.primary = self
+            .selections
+            .iter()
+            .position(|selection| *selection == primary)
+            .or_else(|| {
+                self.selections.iter().position(|selection| {
+                    !caret.is_before(selection.actual_start())
+                        && !caret.is_after(selection.actual_end())
+                })
+            })
+            .unwrap_or(self.selections.len() - 1);
+    }
+}
+
+impl Default for MultiCursor {
+    fn default() -> Self {
+        Self::new(Cursor::origin())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: (usize, usize), end: (usize, usize)) -> Selection {
+        Selection::new(Cursor::new(start.0, start.1), Cursor::new(end.0, end.1))
+    }
+
+    #[test]
+    fn test_overlapping_selections_merge() {
+        let mut cursors = MultiCursor::new(Cursor::new(2, 0));
+        cursors.add(range((0, 0), (0, 4)));
+        cursors.add(range((0, 2), (1, 1)));
+        cursors.add(Selection::from_cursor(Cursor::new(2, 0)));
+
+        assert_eq!(cursors.count(), 2);
+        assert_eq!(cursors.selections()[0], range((0, 0), (1, 1)));
+        assert_eq!(cursors.primary_cursor(), Cursor::new(2, 0));
+    }
+
+    #[test]
+    fn test_touching_selections_stay_separate() {
+        let cursors =
+            MultiCursor::from_selections(vec![range((0, 2), (0, 4)), range((0, 0), (0, 2))])
+                .unwrap();
+
+        assert_eq!(cursors.count(), 2);
+        assert_eq!(cursors.primary(), &range((0, 0), (0, 2)));
+        assert!(MultiCursor::from_selections(Vec::new()).is_none());
+
+        let mut cursors = cursors;
+        cursors.collapse_to_primary();
+        assert!(cursors.is_single());
+    }
+}
@@ -0,0 +1,159 @@
+//! # Search Module
+//!
+//! Literal and regular expression search over buffer text.
+//!
+//! Patterns are compiled once into a [`SearchQuery`] and matched against the
+//! whole text, so regular expressions may span lines; `^` and `$` match at
+//! line boundaries. Replacements in regex mode may refer to capture groups as
+//! `$1`, `${1}` or `${name}`.
+
+use std::ops::Range;
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::core::{Result, XyluxError};
+
+/// How a search pattern is interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct SearchOptions {
+    /// Treat the pattern as a regular expression rather than literal text.
+    pub regex: bool,
+    /// Match letter case exactly.
+    pub case_sensitive: bool,
+    /// Only match whole words.
+    pub whole_word: bool,
+}
+
+/// A compiled search pattern.
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    /// The pattern as entered.
+    pattern: String,
+    /// Options the pattern was compiled with.
+    options: SearchOptions,
+    /// The compiled expression.
+    regex: Regex,
+}
+
+impl SearchQuery {
+    /// Compile a pattern with the given options.
+    pub fn new(pattern: &str, options: SearchOptions) -> Result<Self> {
+        let mut expression =
+            if options.regex { pattern.to_string() } else { regex::escape(pattern) };
+        if options.whole_word {
+            expression = format!(r"\b(?:{})\b", expression);
+        }
+
+        let regex = RegexBuilder::new(&expression)
+            .case_insensitive(!options.case_sensitive)
+            .multi_line(true)
+            // Files searched on disk may keep `\r\n` line endings.
+            .crlf(true)
+            .build()
+            .map_err(|e| XyluxError::syntax_error(format!("Invalid search pattern: {}", e)))?;
+
+        Ok(Self { pattern: pattern.to_string(), options, regex })
+    }
+
+    /// Get the pattern as entered.
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Get the options the pattern was compiled with.
+    pub fn options(&self) -> SearchOptions {
+        self.options
+    }
+
+    /// Check whether the pattern is empty, which matches nothing.
+    pub fn is_empty(&self) -> bool {
+        self.pattern.is_empty()
+    }
+
+    /// Find the byte ranges of all matches in `text`.
+    pub fn find_in(&self, text: &str) -> Vec<Range<usize>> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        self.regex.find_iter(text).map(|found| found.range()).collect()
+    }
+
+    /// Find all matches in `text` along with their replacement.
+    ///
+    /// In regex mode `$` references in `replacement` are expanded from the
+    /// match's capture groups; write `$$` for a literal `$`. Otherwise the
+    /// replacement is used as is.
+    pub fn replacements_in(&self, text: &str, replacement: &str) -> Vec<(Range<usize>, String)> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+
+        self.regex
+            .captures_iter(text)
+            .filter_map(|captures| {
+                let range = captures.get(0)?.range();
+                let expanded = if self.options.regex {
+                    let mut expanded = String::new();
+                    captures.expand(replacement, &mut expanded);
+                    expanded
+                } else {
+                    replacement.to_string()
+                };
+                Some((range, expanded))
+            })
+            .collect()
+    }
+}
+
+/// A match of a search in a buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchMatch {
+    /// Byte range of the match in the buffer text.
+    pub range: Range<usize>,
+    /// Line and byte column where the match starts.
+    pub start: (usize, usize),
+    /// Line and byte column just past the match.
+    pub end: (usize, usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(pattern: &str, regex: bool, case_sensitive: bool, whole_word: bool) -> SearchQuery {
+        SearchQuery::new(pattern, SearchOptions { regex, case_sensitive, whole_word }).unwrap()
+    }
+
+    #[test]
+    fn test_literal_and_word_matching() {
+        let text = "cat concat Cat.*";
+
+        assert_eq!(query("cat", false, true, false).find_in(text), vec![0..3, 7..10]);
+        assert_eq!(query("cat", false, false, true).find_in(text), vec![0..3, 11..14]);
+        assert_eq!(query(".*", false, true, false).find_in(text), vec![14..16]);
+        assert!(query("", true, false, false).find_in(text).is_empty());
+        assert!(
+            SearchQuery::new("(", SearchOptions { regex: true, ..Default::default() }).is_err()
+        );
+    }
+
+    #[test]
+    fn test_capture_group_replacement() {
+        let text = "let a = 1;\nlet b = 2;\n";
+        let replacements = query(r"let (\w+) = (\d+);$", true, true, false)
+            .replacements_in(text, "const ${1}_VALUE: i32 = $2;");
+
+        assert_eq!(replacements.len(), 2);
+        assert_eq!(replacements[1], (11..21, "const b_VALUE: i32 = 2;".to_string()));
+
+        let literal = query("a", false, true, true).replacements_in(text, "$1");
+        assert_eq!(literal, vec![(4..5, "$1".to_string())]);
+
+        let multiline = query(r"1;\nlet", true, true, false).find_in(text);
+        assert_eq!(multiline, vec![8..14]);
+
+        let crlf = query(r"= (\d+);$", true, true, false).find_in("let a = 1;\r\nlet b = 2;\r\n");
+        assert_eq!(crlf, vec![6..10, 18..22]);
+    }
+}
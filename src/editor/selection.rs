@@ -4,10 +4,12 @@
 
 use std::cmp;
 
+use serde::{Deserialize, Serialize};
+
 use super::cursor::Cursor;
 
 /// Represents a text selection in the editor.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Selection {
     /// Start position of the selection.
     pub start: Cursor,
@@ -18,7 +20,7 @@ pub struct Selection {
 }
 
 /// Different modes of text selection.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SelectionMode {
     /// Character-based selection.
     Character,
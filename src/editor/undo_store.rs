@@ -0,0 +1,143 @@
+//! # Undo Store Module
+//!
+//! Persistent undo history, one file per edited document.
+//!
+//! Each history is saved together with a hash of the file content it leads
+//! up to. When the file is reopened the hash is compared with what is on
+//! disk, and history for content that has since changed is discarded.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use super::commands::CommandHistory;
+use crate::core::Result;
+
+/// A persisted history and the content it applies to.
+#[derive(Debug, Serialize, Deserialize)]
+struct UndoFile {
+    /// The document the history belongs to.
+    path: PathBuf,
+    /// Hash of the document content when the history was saved.
+    content_hash: String,
+    /// The undo history.
+    history: CommandHistory,
+}
+
+/// Saves and restores undo histories in a directory.
+#[derive(Debug, Clone)]
+pub struct UndoStore {
+    /// Directory holding the history files.
+    directory: PathBuf,
+}
+
+impl UndoStore {
+    /// Create a store keeping histories in `directory`.
+    pub fn new(directory: PathBuf) -> Self {
+        Self { directory }
+    }
+
+    /// Get the default history directory, `undo` in the user data directory.
+    pub fn default_directory() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("xylux-ide").join("undo"))
+    }
+
+    /// Get the directory holding the history files.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Save the history of `path`, whose content is now `content`.
+    pub fn save(&self, path: &Path, content: &str, history: &CommandHistory) -> Result<()> {
+        let file = UndoFile {
+            path: path.to_path_buf(),
+            content_hash: content_hash(content),
+            history: history.clone(),
+        };
+
+        std::fs::create_dir_all(&self.directory)?;
+        std::fs::write(self.history_path(path), serde_json::to_vec(&file)?)?;
+        Ok(())
+    }
+
+    /// Load the history of `path` if it was saved for `content`.
+    ///
+    /// Stale or unreadable history is removed and `None` returned.
+    pub fn load(&self, path: &Path, content: &str) -> Option<CommandHistory> {
+        let history_path = self.history_path(path);
+        let data = std::fs::read(&history_path).ok()?;
+
+        match serde_json::from_slice::<UndoFile>(&data) {
+            Ok(file) if file.path == path && file.content_hash == content_hash(content) => {
+                return Some(file.history);
+            }
+            Ok(_) => debug!("Discarding stale undo history for {}", path.display()),
+            Err(e) => debug!("Discarding unreadable undo history for {}: {}", path.display(), e),
+        }
+
+        if let Err(e) = std::fs::remove_file(&history_path) {
+            debug!("Failed to remove {}: {}", history_path.display(), e);
+        }
+        None
+    }
+
+    /// Location of the history file for `path`.
+    fn history_path(&self, path: &Path) -> PathBuf {
+        let name = format!("{:016x}.json", fnv1a(path.to_string_lossy().as_bytes()));
+        self.directory.join(name)
+    }
+}
+
+/// Hash file content for comparing against saved history.
+///
+/// The hash must be stable across builds, so the standard library's hashers
+/// are not used.
+pub fn content_hash(content: &str) -> String {
+    format!("{:016x}", fnv1a(content.as_bytes()))
+}
+
+/// 64-bit FNV-1a hash.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::Command;
+
+    #[test]
+    fn test_history_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = UndoStore::new(dir.path().join("undo"));
+        let path = dir.path().join("main.rs");
+
+        let mut history = CommandHistory::new(10);
+        history.add_command(Command::insert_text(0, 0, "fn".to_string(), Some(path.clone())));
+        history.add_command(Command::insert_line(1, "}".to_string(), Some(path.clone())));
+        store.save(&path, "fn\n}", &history).unwrap();
+
+        let restored = store.load(&path, "fn\n}").unwrap();
+        assert_eq!(restored.len(), 2);
+        assert_eq!(restored.position(), 2);
+        assert_eq!(restored.last_undoable_description().as_deref(), Some("Insert line"));
+        assert!(store.load(&dir.path().join("other.rs"), "fn\n}").is_none());
+    }
+
+    #[test]
+    fn test_stale_history_is_discarded() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = UndoStore::new(dir.path().to_path_buf());
+        let path = dir.path().join("notes.txt");
+
+        let mut history = CommandHistory::new(10);
+        history.add_command(Command::insert_text(0, 0, "a".to_string(), Some(path.clone())));
+        store.save(&path, "a", &history).unwrap();
+
+        assert!(store.load(&path, "changed elsewhere").is_none());
+        assert!(store.load(&path, "a").is_none());
+    }
+}
@@ -0,0 +1,2270 @@
+//! # Vim Module
+//!
+//! Optional modal input layer in the style of Vim, on top of [`Editor`].
+//!
+//! [`Vim`] turns keys into editor operations. It has normal, insert, visual,
+//! visual-line and visual-block modes; the visual modes select with the
+//! [`SelectionMode`] of the same shape. Normal mode takes counts, registers
+//! (`"a`), motions built on the [`Cursor`] movements, operators (`d`, `c`,
+//! `y`, `>` and `<`) over motions or text objects (`iw`, `a"`, `i(` and so
+//! on), puts, `.` to repeat the last change, and ex commands after `:`
+//! (`:w`, `:q`, `:q!`, `:wq`, `:x`, `:wa`, `:qa` and `:<line>`).
+//!
+//! Each change undoes as one step, including the text typed after it in
+//! insert mode.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+
+use super::selection::SelectionMode;
+use super::{Cursor, Editor, Selection};
+use crate::core::Result;
+
+/// Number of lines moved by `Ctrl+F` and `Ctrl+B`.
+const PAGE_LINES: usize = 20;
+
+/// Input mode of the Vim layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VimMode {
+    /// Keys run commands.
+    #[default]
+    Normal,
+    /// Keys type text.
+    Insert,
+    /// Keys extend a selection of characters.
+    Visual,
+    /// Keys extend a selection of whole lines.
+    VisualLine,
+    /// Keys extend a rectangular selection.
+    VisualBlock,
+}
+
+impl VimMode {
+    /// Get the kind of selection a visual mode makes.
+    #[must_use]
+    pub const fn selection_mode(self) -> Option<SelectionMode> {
+        match self {
+            Self::Visual => Some(SelectionMode::Character),
+            Self::VisualLine => Some(SelectionMode::Line),
+            Self::VisualBlock => Some(SelectionMode::Block),
+            Self::Normal | Self::Insert => None,
+        }
+    }
+
+    /// Check whether this is one of the visual modes.
+    #[must_use]
+    pub const fn is_visual(self) -> bool {
+        self.selection_mode().is_some()
+    }
+}
+
+impl fmt::Display for VimMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Normal => write!(f, "NORMAL"),
+            Self::Insert => write!(f, "INSERT"),
+            Self::Visual => write!(f, "VISUAL"),
+            Self::VisualLine => write!(f, "VISUAL LINE"),
+            Self::VisualBlock => write!(f, "VISUAL BLOCK"),
+        }
+    }
+}
+
+/// A key given to the Vim layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VimKey {
+    /// A typed character.
+    Char(char),
+    /// A letter pressed with Control, in lowercase.
+    Ctrl(char),
+    /// Escape.
+    Escape,
+    /// Enter.
+    Enter,
+    /// Backspace.
+    Backspace,
+    /// Tab.
+    Tab,
+    /// Left arrow.
+    Left,
+    /// Right arrow.
+    Right,
+    /// Up arrow.
+    Up,
+    /// Down arrow.
+    Down,
+}
+
+impl fmt::Display for VimKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Char(c) => write!(f, "{c}"),
+            Self::Ctrl(c) => write!(f, "^{}", c.to_ascii_uppercase()),
+            Self::Escape => write!(f, "<Esc>"),
+            Self::Enter => write!(f, "<CR>"),
+            Self::Backspace => write!(f, "<BS>"),
+            Self::Tab => write!(f, "<Tab>"),
+            Self::Left => write!(f, "<Left>"),
+            Self::Right => write!(f, "<Right>"),
+            Self::Up => write!(f, "<Up>"),
+            Self::Down => write!(f, "<Down>"),
+        }
+    }
+}
+
+/// What the caller should do after a key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VimOutcome {
+    /// Nothing; the key was handled.
+    Handled,
+    /// An ex command asked to close the editor.
+    Quit,
+    /// A message to show, such as the result of an ex command.
+    Message(String),
+}
+
+/// How register text goes back into a buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterKind {
+    /// Text within and across lines.
+    Characters,
+    /// Whole lines, each ending with a newline.
+    Lines,
+    /// A rectangle, with a line of text per row.
+    Block,
+}
+
+/// Text held in a register.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Register {
+    /// The text.
+    pub text: String,
+    /// How the text was taken.
+    pub kind: RegisterKind,
+}
+
+impl Register {
+    /// Create a register value.
+    pub fn new<T: Into<String>>(text: T, kind: RegisterKind) -> Self {
+        Self { text: text.into(), kind }
+    }
+
+    /// Add text to the end of this value, as lines if either part is.
+    fn append(&mut self, other: &Self) {
+        if self.kind == RegisterKind::Lines || other.kind == RegisterKind::Lines {
+            if !self.text.is_empty() && !self.text.ends_with('\n') {
+                self.text.push('\n');
+            }
+            self.text.push_str(&other.text);
+            if !self.text.ends_with('\n') {
+                self.text.push('\n');
+            }
+            self.kind = RegisterKind::Lines;
+        } else {
+            self.text.push_str(&other.text);
+        }
+    }
+}
+
+/// Registers for yanked and deleted text.
+///
+/// `"` holds the latest text, `0` the latest yank, `-` the latest delete
+/// within a line and `1` to `9` the latest larger deletes, newest first. `a` to `z` are set by name and `A` to `Z` append
+/// to them. Text stored in `_` is discarded.
+#[derive(Debug, Clone, Default)]
+pub struct Registers {
+    /// Register values by name.
+    values: HashMap<char, Register>,
+}
+
+impl Registers {
+    /// Get a register by name; `"` is the unnamed register.
+    #[must_use]
+    pub fn get(&self, name: char) -> Option<&Register> {
+        self.values.get(&name.to_ascii_lowercase())
+    }
+
+    /// Store yanked or deleted text in register `name`, or in the numbered
+    /// registers when no name is given. The unnamed register gets it too.
+    pub fn store(&mut self, name: Option<char>, register: Register, yank: bool) {
+        let register = match name {
+            Some('_') => return,
+            Some(name) if name.is_ascii_uppercase() => {
+                let name = name.to_ascii_lowercase();
+                let value = match self.values.remove(&name) {
+                    Some(mut value) => {
+                        value.append(&register);
+                        value
+                    }
+                    None => register,
+                };
+                self.values.insert(name, value.clone());
+                value
+            }
+            Some(name) if name != '"' => {
+                self.values.insert(name, register.clone());
+                register
+            }
+            _ if yank => {
+                self.values.insert('0', register.clone());
+                register
+            }
+            _ if register.kind == RegisterKind::Characters && !register.text.contains('\n') => {
+                self.values.insert('-', register.clone());
+                register
+            }
+            _ => {
+                for number in (1..9u8).rev() {
+                    let from = char::from(b'0' + number);
+                    if let Some(value) = self.values.remove(&from) {
+                        self.values.insert(char::from(b'1' + number), value);
+                    }
+                }
+                self.values.insert('1', register.clone());
+                register
+            }
+        };
+        self.values.insert('"', register);
+    }
+}
+
+/// Where a motion moves the cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Motion {
+    Left,
+    Right,
+    Up,
+    Down,
+    WordForward,
+    WordBackward,
+    WordEnd,
+    LineStart,
+    FirstNonBlank,
+    LineEnd,
+    FirstLine,
+    LastLine,
+    PageDown,
+    PageUp,
+    HalfPageDown,
+    HalfPageUp,
+    /// `f`, `F`, `t` and `T`: to or just before a character on the line.
+    Find {
+        target: char,
+        forward: bool,
+        till: bool,
+    },
+}
+
+/// How much text a motion covers when an operator uses it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Reach {
+    /// Up to the target, not including it.
+    Exclusive,
+    /// Up to and including the character at the target.
+    Inclusive,
+    /// Every line from the cursor to the target.
+    Linewise,
+}
+
+impl Motion {
+    /// Get how much text the motion covers.
+    const fn reach(self) -> Reach {
+        match self {
+            Self::Up
+            | Self::Down
+            | Self::FirstLine
+            | Self::LastLine
+            | Self::PageDown
+            | Self::PageUp
+            | Self::HalfPageDown
+            | Self::HalfPageUp => Reach::Linewise,
+            Self::WordEnd | Self::Find { forward: true, .. } => Reach::Inclusive,
+            Self::Left
+            | Self::Right
+            | Self::WordForward
+            | Self::WordBackward
+            | Self::LineStart
+            | Self::FirstNonBlank
+            | Self::LineEnd
+            | Self::Find { .. } => Reach::Exclusive,
+        }
+    }
+
+    /// Check whether the motion keeps the column the cursor aims for.
+    const fn is_vertical(self) -> bool {
+        matches!(
+            self,
+            Self::Up
+                | Self::Down
+                | Self::PageDown
+                | Self::PageUp
+                | Self::HalfPageDown
+                | Self::HalfPageUp
+        )
+    }
+}
+
+/// An operator, applied to the text covered by a motion or object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Delete,
+    Change,
+    Yank,
+    Indent,
+    Outdent,
+}
+
+/// Text around the cursor picked by `i` or `a` and a character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TextObject {
+    /// A run of word characters, punctuation or whitespace.
+    Word,
+    /// A run of non-whitespace characters.
+    BigWord,
+    /// Text between two of the same quote on a line.
+    Quote(char),
+    /// Text between a pair of brackets, which may span lines.
+    Pair(char, char),
+}
+
+/// The text an operator applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Motion(Motion),
+    Object {
+        object: TextObject,
+        around: bool,
+    },
+    /// Whole lines, as with `dd`, or the lines of a visual selection.
+    Lines,
+    /// The visual selection.
+    Selection,
+}
+
+/// Where insert mode starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InsertAt {
+    Cursor,
+    AfterCursor,
+    LineStart,
+    LineEnd,
+    LineBelow,
+    LineAbove,
+}
+
+/// What a command does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Move(Motion),
+    Operate(Operator, Target),
+    Insert(InsertAt),
+    ReplaceChar(char),
+    Put { before: bool },
+    JoinLines,
+    Undo,
+    Redo,
+    Visual(VimMode),
+    SelectObject { object: TextObject, around: bool },
+    SwapEnds,
+    Repeat,
+    CommandLine,
+}
+
+/// A complete command typed in normal or visual mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct NormalCommand {
+    /// Register named with `"`.
+    register: Option<char>,
+    /// Count typed before the command or its motion.
+    count: Option<usize>,
+    /// What to do.
+    action: Action,
+}
+
+impl NormalCommand {
+    /// Check whether the command changes the buffer.
+    fn is_change(&self) -> bool {
+        match self.action {
+            Action::Operate(operator, _) => operator != Operator::Yank,
+            Action::Insert(_) | Action::ReplaceChar(_) | Action::Put { .. } | Action::JoinLines => {
+                true
+            }
+            Action::Move(_)
+            | Action::Undo
+            | Action::Redo
+            | Action::Visual(_)
+            | Action::SelectObject { .. }
+            | Action::SwapEnds
+            | Action::Repeat
+            | Action::CommandLine => false,
+        }
+    }
+}
+
+/// Result of parsing some of the keys of a command.
+#[derive(Debug, PartialEq, Eq)]
+enum Partial<T> {
+    /// More keys are needed.
+    Incomplete,
+    /// The keys are not a command.
+    Invalid,
+    /// The keys make up a command.
+    Done(T),
+}
+
+/// A change that `.` repeats.
+#[derive(Debug, Clone)]
+struct Change {
+    /// The command that made the change.
+    command: NormalCommand,
+    /// Keys typed in insert mode after the command.
+    typed: Vec<VimKey>,
+}
+
+/// Text typed since entering insert mode.
+#[derive(Debug, Default)]
+struct InsertSession {
+    /// Times to type the text, from the count of the command.
+    count: usize,
+    /// Whether each repeat of the text goes on a new line, as after `3o`.
+    new_line: bool,
+    /// Keys typed so far.
+    typed: Vec<VimKey>,
+    /// The command that started insert mode, recorded for `.` on Escape.
+    command: Option<NormalCommand>,
+}
+
+/// Text an operator acts on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Span {
+    /// Characters from the first position up to the second.
+    Chars(Cursor, Cursor),
+    /// Whole lines, from the first to the last.
+    Lines(usize, usize),
+    /// A rectangle between two corners, both included.
+    Block(Cursor, Cursor),
+}
+
+/// Modal input layer in the style of Vim.
+#[derive(Debug, Default)]
+pub struct Vim {
+    /// Current mode.
+    mode: VimMode,
+    /// Keys of the command being typed.
+    pending: Vec<VimKey>,
+    /// Ex command typed after `:`, while typing it.
+    command_line: Option<String>,
+    /// Yanked and deleted text.
+    registers: Registers,
+    /// Fixed end of the visual selection.
+    anchor: Cursor,
+    /// Moving end of the visual selection.
+    head: Cursor,
+    /// Column vertical motions aim for.
+    desired_column: Option<usize>,
+    /// The change `.` repeats.
+    last_change: Option<Change>,
+    /// Text typed in the current insert mode.
+    insert: InsertSession,
+    /// Undo mark of the change being made.
+    undo_mark: usize,
+    /// Whether `.` is making the change again.
+    replaying: bool,
+}
+
+impl Vim {
+    /// Create a Vim layer in normal mode.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the current mode.
+    #[must_use]
+    pub const fn mode(&self) -> VimMode {
+        self.mode
+    }
+
+    /// Get the ex command being typed, without the `:`.
+    #[must_use]
+    pub fn command_line(&self) -> Option<&str> {
+        self.command_line.as_deref()
+    }
+
+    /// Get the registers.
+    #[must_use]
+    pub const fn registers(&self) -> &Registers {
+        &self.registers
+    }
+
+    /// Get the registers for changing.
+    pub fn registers_mut(&mut self) -> &mut Registers {
+        &mut self.registers
+    }
+
+    /// Get where the cursor is shown: the moving end of a visual selection,
+    /// or `None` to use the editor's cursor.
+    #[must_use]
+    pub fn visual_head(&self) -> Option<(usize, usize)> {
+        self.mode.is_visual().then_some(self.head.position())
+    }
+
+    /// Text for a status bar: the ex command being typed, or the mode and
+    /// the keys of an unfinished command.
+    pub fn status(&self) -> String {
+        if let Some(line) = &self.command_line {
+            return format!(":{line}");
+        }
+        let pending: String = self.pending.iter().map(VimKey::to_string).collect();
+        if self.mode == VimMode::Normal {
+            pending
+        } else if pending.is_empty() {
+            format!("-- {} --", self.mode)
+        } else {
+            format!("-- {} -- {pending}", self.mode)
+        }
+    }
+
+    /// Go back to normal mode, dropping unfinished commands, as when
+    /// another buffer is shown.
+    pub fn reset(&mut self) {
+        self.mode = VimMode::Normal;
+        self.pending.clear();
+        self.command_line = None;
+        self.desired_column = None;
+        self.insert = InsertSession::default();
+    }
+
+    /// Handle a key pressed in the editor.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command fails to change the buffer.
+    pub async fn handle_key(&mut self, editor: &Editor, key: VimKey) -> Result<VimOutcome> {
+        if editor.active_path().await.is_none() {
+            return Ok(VimOutcome::Handled);
+        }
+        if self.command_line.is_some() {
+            return self.command_line_key(editor, key).await;
+        }
+        if self.mode == VimMode::Insert {
+            self.insert_key(editor, key).await?;
+            return Ok(VimOutcome::Handled);
+        }
+
+        if key == VimKey::Escape {
+            self.pending.clear();
+            if self.mode.is_visual() {
+                self.leave_visual(editor).await?;
+            }
+            return Ok(VimOutcome::Handled);
+        }
+        self.pending.push(key);
+        let command = match parse(&self.pending, self.mode.is_visual()) {
+            Partial::Incomplete => return Ok(VimOutcome::Handled),
+            Partial::Invalid => {
+                self.pending.clear();
+                return Ok(VimOutcome::Handled);
+            }
+            Partial::Done(command) => command,
+        };
+        self.pending.clear();
+
+        if command.action == Action::Repeat {
+            return self.repeat(editor, command.count).await;
+        }
+        self.run(editor, command).await
+    }
+
+    /// Run a normal or visual mode command.
+    #[expect(clippy::too_many_lines, reason = "one arm per normal mode action")]
+    async fn run(&mut self, editor: &Editor, command: NormalCommand) -> Result<VimOutcome> {
+        let Some(text) = Text::load(editor).await else {
+            return Ok(VimOutcome::Handled);
+        };
+        let change = command.is_change();
+        if change {
+            self.undo_mark = editor.begin_undo_group().await;
+        }
+        let visual = self.mode.is_visual();
+        let cursor = self.cursor(editor).await;
+        let count = command.count;
+        let n = count.unwrap_or(1);
+
+        match command.action {
+            Action::Move(motion) => {
+                if let Some(target) = motion_target(&text, cursor, motion, count, false) {
+                    self.desired_column = if motion == Motion::LineEnd {
+                        Some(usize::MAX)
+                    } else if motion.is_vertical() {
+                        Some(target.desired_column)
+                    } else {
+                        None
+                    };
+                    let column = text.last_column(target.line).min(target.column);
+                    self.move_to(editor, &text, Cursor::new(target.line, column)).await?;
+                }
+            }
+            Action::Operate(operator, target) => {
+                let span = if visual {
+                    let span = self.visual_span(&text);
+                    self.mode = VimMode::Normal;
+                    match (span, target) {
+                        (span, Target::Lines) => {
+                            let (first, last) = span_lines(span);
+                            Some(Span::Lines(first, last))
+                        }
+                        (span, _) => Some(span),
+                    }
+                } else {
+                    match target {
+                        Target::Motion(motion) => {
+                            motion_span(&text, cursor, motion, count, operator)
+                        }
+                        Target::Object { object, around } => {
+                            object_span(&text, cursor, object, around)
+                                .map(|(start, end)| Span::Chars(start, end))
+                        }
+                        Target::Lines => {
+                            let last = (cursor.line + n - 1).min(text.last_line());
+                            Some(Span::Lines(cursor.line, last))
+                        }
+                        Target::Selection => None,
+                    }
+                };
+                match span {
+                    Some(span) => {
+                        self.operate(editor, &text, cursor, operator, span, command.register)
+                            .await?;
+                    }
+                    None if visual => self.leave_visual(editor).await?,
+                    None => {}
+                }
+            }
+            Action::Insert(at) => self.start_insert(editor, &text, cursor, at).await?,
+            Action::ReplaceChar(c) => {
+                let line = text.line(cursor.line);
+                let end = (0..n).try_fold(cursor.column, |column, _| {
+                    line[column..].chars().next().map(|c| column + c.len_utf8())
+                });
+                if let Some(end) = end {
+                    let replacement = c.to_string().repeat(n);
+                    replace(editor, cursor, Cursor::new(cursor.line, end), &replacement).await?;
+                    let column = cursor.column + replacement.len() - c.len_utf8();
+                    editor.move_cursor(cursor.line, column).await?;
+                }
+            }
+            Action::Put { before } => {
+                let name = command.register.unwrap_or('"');
+                if let Some(register) = self.registers.get(name).cloned() {
+                    if visual {
+                        self.put_over_selection(editor, &text, register, n).await?;
+                    } else {
+                        self.put(editor, &text, cursor, register, before, n).await?;
+                    }
+                } else if visual {
+                    self.leave_visual(editor).await?;
+                }
+            }
+            Action::JoinLines => {
+                let (first, last) = if visual {
+                    let (first, last) = span_lines(self.visual_span(&text));
+                    self.mode = VimMode::Normal;
+                    (first, last.max(first + 1))
+                } else {
+                    (cursor.line, cursor.line + n.max(2) - 1)
+                };
+                join_lines(editor, &text, first, last).await?;
+            }
+            Action::Undo => {
+                for _ in 0..n {
+                    if !editor.undo_buffer(&text.path).await? {
+                        break;
+                    }
+                }
+            }
+            Action::Redo => {
+                for _ in 0..n {
+                    if !editor.redo_buffer(&text.path).await? {
+                        break;
+                    }
+                }
+            }
+            Action::Visual(mode) => {
+                if self.mode == mode {
+                    self.leave_visual(editor).await?;
+                } else {
+                    if !visual {
+                        self.anchor = cursor;
+                        self.head = cursor;
+                    }
+                    self.mode = mode;
+                    self.show_selection(editor, &text).await?;
+                }
+            }
+            Action::SelectObject { object, around } => {
+                if let Some((start, end)) = object_span(&text, self.head, object, around) {
+                    self.anchor = start;
+                    self.head =
+                        text.position(text.offset(end).saturating_sub(1).max(text.offset(start)));
+                    self.head.column = text.floor_column(self.head.line, self.head.column);
+                    if self.mode != VimMode::Visual {
+                        self.mode = VimMode::Visual;
+                    }
+                    self.show_selection(editor, &text).await?;
+                }
+            }
+            Action::SwapEnds => {
+                std::mem::swap(&mut self.anchor, &mut self.head);
+                self.show_selection(editor, &text).await?;
+            }
+            Action::CommandLine => {
+                if visual {
+                    self.leave_visual(editor).await?;
+                }
+                self.command_line = Some(String::new());
+            }
+            Action::Repeat => {}
+        }
+
+        if self.mode == VimMode::Insert {
+            // The change goes on until Escape, with the text typed after it.
+            let repeats = matches!(command.action, Action::Insert(_));
+            self.insert = InsertSession {
+                count: if repeats { n } else { 1 },
+                new_line: matches!(
+                    command.action,
+                    Action::Insert(InsertAt::LineBelow | InsertAt::LineAbove)
+                ),
+                typed: Vec::new(),
+                command: (!visual && !self.replaying).then_some(command),
+            };
+            return Ok(VimOutcome::Handled);
+        }
+
+        if change {
+            editor.end_undo_group(self.undo_mark, "Vim change").await;
+            if !visual && !self.replaying {
+                self.last_change = Some(Change { command, typed: Vec::new() });
+            }
+        }
+        self.settle(editor).await?;
+        Ok(VimOutcome::Handled)
+    }
+
+    /// Repeat the last change, with `count` in place of its own count if given.
+    async fn repeat(&mut self, editor: &Editor, count: Option<usize>) -> Result<VimOutcome> {
+        let Some(change) = self.last_change.clone() else {
+            return Ok(VimOutcome::Handled);
+        };
+        let mut command = change.command;
+        if count.is_some() {
+            command.count = count;
+        }
+
+        self.replaying = true;
+        let result = self.replay(editor, command, &change.typed).await;
+        self.replaying = false;
+        result
+    }
+
+    /// Run a recorded command and type the keys recorded after it.
+    async fn replay(
+        &mut self,
+        editor: &Editor,
+        command: NormalCommand,
+        typed: &[VimKey],
+    ) -> Result<VimOutcome> {
+        let outcome = self.run(editor, command).await?;
+        if self.mode == VimMode::Insert {
+            for &key in typed {
+                self.insert_key(editor, key).await?;
+            }
+            self.insert_key(editor, VimKey::Escape).await?;
+        }
+        Ok(outcome)
+    }
+
+    /// Handle a key in insert mode.
+    async fn insert_key(&mut self, editor: &Editor, key: VimKey) -> Result<()> {
+        if key == VimKey::Escape {
+            return self.finish_insert(editor).await;
+        }
+        self.insert.typed.push(key);
+        self.type_key(editor, key).await
+    }
+
+    /// Type a key in insert mode.
+    async fn type_key(&self, editor: &Editor, key: VimKey) -> Result<()> {
+        let (line, column) = editor.get_cursor_position().await;
+        match key {
+            VimKey::Char(c) => editor.insert_text(&c.to_string()).await,
+            VimKey::Tab => editor.insert_text(&editor.indent_unit().await).await,
+            VimKey::Enter => {
+                let Some(text) = Text::load(editor).await else {
+                    return Ok(());
+                };
+                let current = &text.line(line)[..column.min(text.line(line).len())];
+                let indent = &current[..current.len() - current.trim_start().len()];
+                editor.insert_text(&format!("\n{indent}")).await
+            }
+            VimKey::Backspace => {
+                let Some(text) = Text::load(editor).await else {
+                    return Ok(());
+                };
+                let start = match (column, line) {
+                    (0, 0) => return Ok(()),
+                    (0, line) => Cursor::new(line - 1, text.line(line - 1).len()),
+                    (column, line) => Cursor::new(line, text.prev_column(line, column)),
+                };
+                replace(editor, start, Cursor::new(line, column), "").await
+            }
+            VimKey::Left => arrow_key(editor, Cursor::new(line, column), Motion::Left).await,
+            VimKey::Right => arrow_key(editor, Cursor::new(line, column), Motion::Right).await,
+            VimKey::Up => arrow_key(editor, Cursor::new(line, column), Motion::Up).await,
+            VimKey::Down => arrow_key(editor, Cursor::new(line, column), Motion::Down).await,
+            VimKey::Ctrl(_) | VimKey::Escape => Ok(()),
+        }
+    }
+
+    /// Leave insert mode, typing the text again for a count.
+    async fn finish_insert(&mut self, editor: &Editor) -> Result<()> {
+        let session = std::mem::take(&mut self.insert);
+        for _ in 1..session.count {
+            if session.new_line {
+                self.type_key(editor, VimKey::Enter).await?;
+            }
+            for &key in &session.typed {
+                self.type_key(editor, key).await?;
+            }
+        }
+        editor.end_undo_group(self.undo_mark, "Vim change").await;
+        if let Some(command) = session.command {
+            self.last_change = Some(Change { command, typed: session.typed });
+        }
+
+        self.mode = VimMode::Normal;
+        // The cursor steps back onto the last character typed.
+        let (line, column) = editor.get_cursor_position().await;
+        if let Some(text) = Text::load(editor).await {
+            editor.move_cursor(line, text.prev_column(line, column)).await?;
+        }
+        self.settle(editor).await
+    }
+
+    /// Handle a key while typing an ex command.
+    async fn command_line_key(&mut self, editor: &Editor, key: VimKey) -> Result<VimOutcome> {
+        let Some(line) = self.command_line.as_mut() else {
+            return Ok(VimOutcome::Handled);
+        };
+        match key {
+            VimKey::Char(c) => line.push(c),
+            VimKey::Backspace if line.pop().is_none() => {
+                self.command_line = None;
+            }
+            VimKey::Escape => self.command_line = None,
+            VimKey::Enter => {
+                let command = self.command_line.take().unwrap_or_default();
+                return self.ex_command(editor, command.trim()).await;
+            }
+            VimKey::Ctrl(_)
+            | VimKey::Backspace
+            | VimKey::Tab
+            | VimKey::Left
+            | VimKey::Right
+            | VimKey::Up
+            | VimKey::Down => {}
+        }
+        Ok(VimOutcome::Handled)
+    }
+
+    /// Run an ex command.
+    async fn ex_command(&self, editor: &Editor, command: &str) -> Result<VimOutcome> {
+        const NOT_SAVED: &str = "E37: No write since last change (add ! to override)";
+
+        if let Ok(line) = command.parse::<usize>() {
+            if let Some(text) = Text::load(editor).await {
+                let line = line.saturating_sub(1).min(text.last_line());
+                editor.move_cursor(line, text.first_non_blank(line)).await?;
+                self.settle(editor).await?;
+            }
+            return Ok(VimOutcome::Handled);
+        }
+
+        let Some(path) = editor.active_path().await else {
+            return Ok(VimOutcome::Handled);
+        };
+        let written = || {
+            let name = path.file_name().unwrap_or(path.as_os_str());
+            VimOutcome::Message(format!("\"{}\" written", name.to_string_lossy()))
+        };
+        Ok(match command {
+            "" => VimOutcome::Handled,
+            "w" | "write" => {
+                editor.save_current().await?;
+                written()
+            }
+            "wa" | "wall" => {
+                editor.save_all().await?;
+                VimOutcome::Message("All files written".to_owned())
+            }
+            "q" | "quit" if editor.is_modified(&path).await => {
+                VimOutcome::Message(NOT_SAVED.to_owned())
+            }
+            "qa" | "qall" if editor.has_unsaved_changes().await => {
+                VimOutcome::Message(NOT_SAVED.to_owned())
+            }
+            "q" | "quit" | "qa" | "qall" | "q!" | "quit!" | "qa!" | "qall!" => VimOutcome::Quit,
+            "wq" | "x" | "xit" | "exit" => {
+                if command == "wq" || editor.is_modified(&path).await {
+                    editor.save_current().await?;
+                }
+                VimOutcome::Quit
+            }
+            "wqa" | "wqall" | "xa" | "xall" => {
+                editor.save_all().await?;
+                VimOutcome::Quit
+            }
+            _ => VimOutcome::Message(format!("E492: Not an editor command: {command}")),
+        })
+    }
+
+    /// Get the cursor commands start from: the moving end of a visual
+    /// selection, otherwise the editor's cursor.
+    async fn cursor(&self, editor: &Editor) -> Cursor {
+        let mut cursor = if self.mode.is_visual() {
+            self.head
+        } else {
+            let (line, column) = editor.get_cursor_position().await;
+            Cursor::new(line, column)
+        };
+        cursor.desired_column = self.desired_column.unwrap_or(cursor.column);
+        cursor
+    }
+
+    /// Move the cursor, or the moving end of a visual selection.
+    async fn move_to(&mut self, editor: &Editor, text: &Text, cursor: Cursor) -> Result<()> {
+        if self.mode.is_visual() {
+            self.head = cursor;
+            self.show_selection(editor, text).await
+        } else {
+            editor.move_cursor(cursor.line, cursor.column).await
+        }
+    }
+
+    /// Keep the cursor on a character in normal mode.
+    async fn settle(&self, editor: &Editor) -> Result<()> {
+        if self.mode != VimMode::Normal {
+            return Ok(());
+        }
+        let Some(text) = Text::load(editor).await else {
+            return Ok(());
+        };
+        let (line, column) = editor.get_cursor_position().await;
+        let settled_line = line.min(text.last_line());
+        let settled_column =
+            text.floor_column(settled_line, column.min(text.last_column(settled_line)));
+        if (settled_line, settled_column) != (line, column) {
+            editor.move_cursor(settled_line, settled_column).await?;
+        }
+        Ok(())
+    }
+
+    /// Select the visual selection in the editor.
+    async fn show_selection(&self, editor: &Editor, text: &Text) -> Result<()> {
+        let (anchor, head) = (self.anchor, self.head);
+        let forward = !head.is_before(&anchor);
+        let selection = match self.mode {
+            VimMode::VisualLine => {
+                let line_end = |line: usize| Cursor::new(line, text.line(line).len());
+                let (start, end) = if forward {
+                    (Cursor::new(anchor.line, 0), line_end(head.line))
+                } else {
+                    (line_end(anchor.line), Cursor::new(head.line, 0))
+                };
+                Selection::with_mode(start, end, SelectionMode::Line)
+            }
+            VimMode::VisualBlock => {
+                let (start, end) = block_corners(text, anchor, head);
+                Selection::with_mode(start, end, SelectionMode::Block)
+            }
+            VimMode::Normal | VimMode::Insert | VimMode::Visual => {
+                let after = |cursor: Cursor| {
+                    Cursor::new(cursor.line, text.next_column(cursor.line, cursor.column))
+                };
+                let (start, end) =
+                    if forward { (anchor, after(head)) } else { (after(anchor), head) };
+                Selection::with_mode(start, end, SelectionMode::Character)
+            }
+        };
+        editor.set_selection(selection).await
+    }
+
+    /// Leave a visual mode, keeping the cursor at the moving end.
+    async fn leave_visual(&mut self, editor: &Editor) -> Result<()> {
+        self.mode = VimMode::Normal;
+        editor.move_cursor(self.head.line, self.head.column).await?;
+        self.settle(editor).await
+    }
+
+    /// Get the text covered by the visual selection.
+    fn visual_span(&self, text: &Text) -> Span {
+        let (start, end) = ordered(self.anchor, self.head);
+        match self.mode {
+            VimMode::VisualLine => Span::Lines(start.line, end.line),
+            VimMode::VisualBlock => Span::Block(self.anchor, self.head),
+            VimMode::Normal | VimMode::Insert | VimMode::Visual => {
+                Span::Chars(start, text.after_char(end))
+            }
+        }
+    }
+
+    /// Start insert mode at `at`.
+    async fn start_insert(
+        &mut self,
+        editor: &Editor,
+        text: &Text,
+        cursor: Cursor,
+        at: InsertAt,
+    ) -> Result<()> {
+        let line = cursor.line;
+        let indent = leading_whitespace(text.line(line)).to_owned();
+        match at {
+            InsertAt::Cursor => editor.move_cursor(line, cursor.column).await?,
+            InsertAt::AfterCursor => {
+                editor.move_cursor(line, text.next_column(line, cursor.column)).await?;
+            }
+            InsertAt::LineStart => editor.move_cursor(line, text.first_non_blank(line)).await?,
+            InsertAt::LineEnd => editor.move_cursor(line, text.line(line).len()).await?,
+            InsertAt::LineBelow => {
+                let end = Cursor::new(line, text.line(line).len());
+                replace(editor, end, end, &format!("\n{indent}")).await?;
+            }
+            InsertAt::LineAbove => {
+                let start = Cursor::new(line, 0);
+                replace(editor, start, start, &format!("{indent}\n")).await?;
+                editor.move_cursor(line, indent.len()).await?;
+            }
+        }
+        self.mode = VimMode::Insert;
+        Ok(())
+    }
+
+    /// Apply an operator to a span of text.
+    async fn operate(
+        &mut self,
+        editor: &Editor,
+        text: &Text,
+        cursor: Cursor,
+        operator: Operator,
+        span: Span,
+        register: Option<char>,
+    ) -> Result<()> {
+        if matches!(operator, Operator::Indent | Operator::Outdent) {
+            let (first, last) = span_lines(span);
+            return shift_lines(editor, text, first, last, operator == Operator::Indent).await;
+        }
+
+        let taken = self.take_span(editor, text, span).await?;
+        self.registers.store(register, taken, operator == Operator::Yank);
+        match operator {
+            Operator::Yank => {
+                let start = match span {
+                    Span::Chars(start, end) => ordered(start, end).0,
+                    Span::Lines(first, _) if cursor.line == first => cursor,
+                    Span::Lines(first, _) => Cursor::new(first, text.first_non_blank(first)),
+                    Span::Block(a, b) => Cursor::new(a.line.min(b.line), a.column.min(b.column)),
+                };
+                editor.move_cursor(start.line, start.column).await
+            }
+            Operator::Change => {
+                self.mode = VimMode::Insert;
+                match span {
+                    Span::Lines(first, last) => {
+                        let indent = leading_whitespace(text.line(first));
+                        let end = Cursor::new(last, text.line(last).len());
+                        replace(editor, Cursor::new(first, 0), end, indent).await
+                    }
+                    span @ (Span::Chars(..) | Span::Block(..)) => {
+                        delete_span(editor, text, span).await
+                    }
+                }
+            }
+            Operator::Delete | Operator::Indent | Operator::Outdent => {
+                delete_span(editor, text, span).await
+            }
+        }
+    }
+
+    /// Get the text of a span as a register value. A block span is
+    /// selected in the editor.
+    async fn take_span(&self, editor: &Editor, text: &Text, span: Span) -> Result<Register> {
+        Ok(match span {
+            Span::Chars(start, end) => {
+                Register::new(text.slice(start, end), RegisterKind::Characters)
+            }
+            Span::Lines(first, last) => {
+                let mut lines = text.lines[first..=last].join("\n");
+                lines.push('\n');
+                Register::new(lines, RegisterKind::Lines)
+            }
+            Span::Block(a, b) => {
+                let (start, end) = block_corners(text, a, b);
+                editor
+                    .set_selection(Selection::with_mode(start, end, SelectionMode::Block))
+                    .await?;
+                let lines = editor.copy_block().await.unwrap_or_default();
+                Register::new(lines.join("\n"), RegisterKind::Block)
+            }
+        })
+    }
+
+    /// Put register text `count` times before or after the cursor.
+    async fn put(
+        &self,
+        editor: &Editor,
+        text: &Text,
+        cursor: Cursor,
+        register: Register,
+        before: bool,
+        count: usize,
+    ) -> Result<()> {
+        let line = cursor.line;
+        match register.kind {
+            RegisterKind::Characters => {
+                let column =
+                    if before { cursor.column } else { text.next_column(line, cursor.column) };
+                let at = Cursor::new(line, column);
+                let content = register.text.repeat(count);
+                replace(editor, at, at, &content).await?;
+                if let Some(text) = Text::load(editor).await {
+                    let end = text.position(text.offset(at) + content.len());
+                    editor.move_cursor(end.line, text.prev_column(end.line, end.column)).await?;
+                }
+                Ok(())
+            }
+            RegisterKind::Lines => {
+                let content = register.text.repeat(count);
+                let first = if before {
+                    let at = Cursor::new(line, 0);
+                    replace(editor, at, at, &content).await?;
+                    line
+                } else if line < text.last_line() {
+                    let at = Cursor::new(line + 1, 0);
+                    replace(editor, at, at, &content).await?;
+                    line + 1
+                } else {
+                    let at = Cursor::new(line, text.line(line).len());
+                    let content = format!("\n{}", content.strip_suffix('\n').unwrap_or(&content));
+                    replace(editor, at, at, &content).await?;
+                    line + 1
+                };
+                let column = first_non_blank(register.text.lines().next().unwrap_or(""));
+                editor.move_cursor(first, column).await
+            }
+            RegisterKind::Block => {
+                let column =
+                    if before { cursor.column } else { text.next_column(line, cursor.column) };
+                let lines: Vec<String> =
+                    register.text.split('\n').map(|row| row.repeat(count)).collect();
+                editor.move_cursor(line, column).await?;
+                editor.paste_block(&lines).await?;
+                editor.move_cursor(line, column).await
+            }
+        }
+    }
+
+    /// Replace the visual selection with register text.
+    async fn put_over_selection(
+        &mut self,
+        editor: &Editor,
+        text: &Text,
+        mut register: Register,
+        count: usize,
+    ) -> Result<()> {
+        let span = self.visual_span(text);
+        self.mode = VimMode::Normal;
+        let replaced = self.take_span(editor, text, span).await?;
+        delete_span(editor, text, span).await?;
+        self.registers.store(None, replaced, false);
+
+        let before = match span {
+            Span::Lines(first, last) => {
+                if register.kind == RegisterKind::Characters {
+                    register.text.push('\n');
+                    register.kind = RegisterKind::Lines;
+                }
+                !(last == text.last_line() && first > 0)
+            }
+            Span::Chars(..) | Span::Block(..) => true,
+        };
+        let Some(text) = Text::load(editor).await else {
+            return Ok(());
+        };
+        let cursor = self.cursor(editor).await;
+        self.put(editor, &text, cursor, register, before, count).await
+    }
+}
+
+/// Parse the keys of a normal or visual mode command.
+fn parse(keys: &[VimKey], visual: bool) -> Partial<NormalCommand> {
+    let mut rest = keys;
+    let mut register = None;
+    if let [VimKey::Char('"'), tail @ ..] = rest {
+        match tail.first() {
+            None => return Partial::Incomplete,
+            Some(&VimKey::Char(name)) if is_register_name(name) => register = Some(name),
+            Some(_) => return Partial::Invalid,
+        }
+        rest = &tail[1..];
+    }
+
+    let (count, rest) = parse_count(rest);
+    let Some((&key, rest)) = rest.split_first() else {
+        return Partial::Incomplete;
+    };
+    let action = match parse_action(key, rest, visual) {
+        Partial::Done(action) => action,
+        Partial::Incomplete => return Partial::Incomplete,
+        Partial::Invalid => return Partial::Invalid,
+    };
+
+    // A count after the operator multiplies the one before it.
+    let count = match action {
+        (action, Some(inner)) => (action, Some(count.unwrap_or(1) * inner)),
+        (action, None) => (action, count),
+    };
+    Partial::Done(NormalCommand { register, count: count.1, action: count.0 })
+}
+
+/// Parse a command starting with `key`, followed by `rest`. Returns the
+/// action and any count typed after an operator.
+fn parse_action(key: VimKey, rest: &[VimKey], visual: bool) -> Partial<(Action, Option<usize>)> {
+    use VimKey::Char;
+
+    let done = |action| Partial::Done((action, None));
+    let operate = |operator, motion| done(Action::Operate(operator, Target::Motion(motion)));
+    let selection = |operator| done(Action::Operate(operator, Target::Selection));
+
+    if let Some(operator) = operator_for(key) {
+        if visual {
+            return selection(operator);
+        }
+        return parse_operator_target(key, operator, rest);
+    }
+    if let Some(motion) = parse_motion(key, rest) {
+        return match motion {
+            Partial::Done(motion) => done(Action::Move(motion)),
+            Partial::Incomplete => Partial::Incomplete,
+            Partial::Invalid => Partial::Invalid,
+        };
+    }
+
+    match (key, visual) {
+        (Char('x'), true) => selection(Operator::Delete),
+        (Char('s'), true) => selection(Operator::Change),
+        (Char('X' | 'D'), true) => done(Action::Operate(Operator::Delete, Target::Lines)),
+        (Char('Y'), _) => done(Action::Operate(Operator::Yank, Target::Lines)),
+        (Char('C' | 'S' | 'R'), true) | (Char('S'), false) => {
+            done(Action::Operate(Operator::Change, Target::Lines))
+        }
+        (Char('o' | 'O'), true) => done(Action::SwapEnds),
+        (Char(around @ ('i' | 'a')), true) => match rest.first() {
+            None => Partial::Incomplete,
+            Some(&key) => text_object(key).map_or(Partial::Invalid, |object| {
+                done(Action::SelectObject { object, around: around == 'a' })
+            }),
+        },
+        (Char('x'), false) => operate(Operator::Delete, Motion::Right),
+        (Char('X'), false) => operate(Operator::Delete, Motion::Left),
+        (Char('D'), false) => operate(Operator::Delete, Motion::LineEnd),
+        (Char('C'), false) => operate(Operator::Change, Motion::LineEnd),
+        (Char('s'), false) => operate(Operator::Change, Motion::Right),
+        (Char('i'), false) => done(Action::Insert(InsertAt::Cursor)),
+        (Char('a'), false) => done(Action::Insert(InsertAt::AfterCursor)),
+        (Char('I'), false) => done(Action::Insert(InsertAt::LineStart)),
+        (Char('A'), false) => done(Action::Insert(InsertAt::LineEnd)),
+        (Char('o'), false) => done(Action::Insert(InsertAt::LineBelow)),
+        (Char('O'), false) => done(Action::Insert(InsertAt::LineAbove)),
+        (Char('r'), false) => match rest.first() {
+            None => Partial::Incomplete,
+            Some(&Char(c)) => done(Action::ReplaceChar(c)),
+            Some(_) => Partial::Invalid,
+        },
+        (Char('u'), false) => done(Action::Undo),
+        (VimKey::Ctrl('r'), false) => done(Action::Redo),
+        (Char('.'), false) => done(Action::Repeat),
+        (Char('p'), _) => done(Action::Put { before: false }),
+        (Char('P'), _) => done(Action::Put { before: true }),
+        (Char('J'), _) => done(Action::JoinLines),
+        (Char('v'), _) => done(Action::Visual(VimMode::Visual)),
+        (Char('V'), _) => done(Action::Visual(VimMode::VisualLine)),
+        (VimKey::Ctrl('v'), _) => done(Action::Visual(VimMode::VisualBlock)),
+        (Char(':'), _) => done(Action::CommandLine),
+        _ => Partial::Invalid,
+    }
+}
+
+/// Parse what an operator typed as `key` applies to.
+fn parse_operator_target(
+    key: VimKey,
+    operator: Operator,
+    rest: &[VimKey],
+) -> Partial<(Action, Option<usize>)> {
+    let (count, rest) = parse_count(rest);
+    let Some((&next, rest)) = rest.split_first() else {
+        return Partial::Incomplete;
+    };
+    let target = if next == key {
+        Target::Lines
+    } else if let VimKey::Char(around @ ('i' | 'a')) = next {
+        let Some(&object) = rest.first() else {
+            return Partial::Incomplete;
+        };
+        match text_object(object) {
+            Some(object) => Target::Object { object, around: around == 'a' },
+            None => return Partial::Invalid,
+        }
+    } else {
+        match parse_motion(next, rest) {
+            Some(Partial::Done(motion)) => Target::Motion(motion),
+            Some(Partial::Incomplete) => return Partial::Incomplete,
+            Some(Partial::Invalid) | None => return Partial::Invalid,
+        }
+    };
+    Partial::Done((Action::Operate(operator, target), count))
+}
+
+/// Parse a motion starting with `key`, or `None` if `key` starts none.
+const fn parse_motion(key: VimKey, rest: &[VimKey]) -> Option<Partial<Motion>> {
+    use VimKey::{Char, Ctrl};
+
+    let motion = match key {
+        Char('h') | VimKey::Left | VimKey::Backspace => Motion::Left,
+        Char('l' | ' ') | VimKey::Right => Motion::Right,
+        Char('k') | VimKey::Up => Motion::Up,
+        Char('j') | VimKey::Down | VimKey::Enter => Motion::Down,
+        Char('w' | 'W') => Motion::WordForward,
+        Char('b' | 'B') => Motion::WordBackward,
+        Char('e' | 'E') => Motion::WordEnd,
+        Char('0') => Motion::LineStart,
+        Char('^') => Motion::FirstNonBlank,
+        Char('$') => Motion::LineEnd,
+        Char('G') => Motion::LastLine,
+        Ctrl('f') => Motion::PageDown,
+        Ctrl('b') => Motion::PageUp,
+        Ctrl('d') => Motion::HalfPageDown,
+        Ctrl('u') => Motion::HalfPageUp,
+        Char('g') => {
+            return Some(match rest.first() {
+                None => Partial::Incomplete,
+                Some(Char('g')) => Partial::Done(Motion::FirstLine),
+                Some(_) => Partial::Invalid,
+            });
+        }
+        Char(find @ ('f' | 'F' | 't' | 'T')) => {
+            return Some(match rest.first() {
+                None => Partial::Incomplete,
+                Some(&Char(target)) => Partial::Done(Motion::Find {
+                    target,
+                    forward: find.is_ascii_lowercase(),
+                    till: find.eq_ignore_ascii_case(&'t'),
+                }),
+                Some(_) => Partial::Invalid,
+            });
+        }
+        Char(_) | Ctrl(_) | VimKey::Escape | VimKey::Tab => return None,
+    };
+    Some(Partial::Done(motion))
+}
+
+/// Parse a count at the start of `keys`, returning it and the keys after it.
+fn parse_count(keys: &[VimKey]) -> (Option<usize>, &[VimKey]) {
+    let mut count: Option<usize> = None;
+    let mut rest = keys;
+    while let Some((&VimKey::Char(c), tail)) = rest.split_first() {
+        match c.to_digit(10) {
+            // A leading zero is the motion to the start of the line.
+            Some(0) if count.is_none() => break,
+            Some(digit) => {
+                count = Some(count.unwrap_or(0).saturating_mul(10).saturating_add(digit as usize));
+                rest = tail;
+            }
+            None => break,
+        }
+    }
+    (count, rest)
+}
+
+/// Get the operator typed as `key`.
+const fn operator_for(key: VimKey) -> Option<Operator> {
+    match key {
+        VimKey::Char('d') => Some(Operator::Delete),
+        VimKey::Char('c') => Some(Operator::Change),
+        VimKey::Char('y') => Some(Operator::Yank),
+        VimKey::Char('>') => Some(Operator::Indent),
+        VimKey::Char('<') => Some(Operator::Outdent),
+        VimKey::Char(_)
+        | VimKey::Ctrl(_)
+        | VimKey::Escape
+        | VimKey::Enter
+        | VimKey::Backspace
+        | VimKey::Tab
+        | VimKey::Left
+        | VimKey::Right
+        | VimKey::Up
+        | VimKey::Down => None,
+    }
+}
+
+/// Get the text object typed as `key` after `i` or `a`.
+const fn text_object(key: VimKey) -> Option<TextObject> {
+    let VimKey::Char(c) = key else {
+        return None;
+    };
+    Some(match c {
+        'w' => TextObject::Word,
+        'W' => TextObject::BigWord,
+        '"' | '\'' | '`' => TextObject::Quote(c),
+        '(' | ')' | 'b' => TextObject::Pair('(', ')'),
+        '[' | ']' => TextObject::Pair('[', ']'),
+        '{' | '}' | 'B' => TextObject::Pair('{', '}'),
+        '<' | '>' => TextObject::Pair('<', '>'),
+        _ => return None,
+    })
+}
+
+/// Check whether `name` can follow `"` as a register name.
+const fn is_register_name(name: char) -> bool {
+    name.is_ascii_alphanumeric() || matches!(name, '"' | '_' | '-' | '+' | '*')
+}
+
+/// Lines of the active buffer, read for one command.
+struct Text {
+    /// Path of the buffer.
+    path: PathBuf,
+    /// The lines, without line endings.
+    lines: Vec<String>,
+    /// Byte offset of the start of each line in the whole text.
+    starts: Vec<usize>,
+}
+
+impl Text {
+    /// Read the active buffer.
+    async fn load(editor: &Editor) -> Option<Self> {
+        let path = editor.active_path().await?;
+        let snapshot = editor.snapshot(&path).await?;
+        Some(Self::new(path, &snapshot.to_string()))
+    }
+
+    /// Split `content` of the buffer for `path` into lines.
+    fn new(path: PathBuf, content: &str) -> Self {
+        let lines: Vec<String> = content.split('\n').map(String::from).collect();
+        let starts = lines
+            .iter()
+            .scan(0, |offset, line| {
+                let start = *offset;
+                *offset += line.len() + 1;
+                Some(start)
+            })
+            .collect();
+        Self { path, lines, starts }
+    }
+
+    /// Get a line, or an empty string past the end.
+    fn line(&self, line: usize) -> &str {
+        self.lines.get(line).map_or("", String::as_str)
+    }
+
+    /// Get the index of the last line.
+    fn last_line(&self) -> usize {
+        self.lines.len() - 1
+    }
+
+    /// Get the byte length of each line.
+    fn lengths(&self) -> Vec<usize> {
+        self.lines.iter().map(String::len).collect()
+    }
+
+    /// Get the column after the character at `column`, or the line length
+    /// at the end of the line.
+    fn next_column(&self, line: usize, column: usize) -> usize {
+        let text = self.line(line);
+        let column = column.min(text.len());
+        text[column..].chars().next().map_or(text.len(), |c| column + c.len_utf8())
+    }
+
+    /// Get the column of the character before `column`.
+    fn prev_column(&self, line: usize, column: usize) -> usize {
+        let text = self.line(line);
+        let column = self.floor_column(line, column);
+        text[..column].chars().next_back().map_or(0, |c| column - c.len_utf8())
+    }
+
+    /// Round a column down to a character boundary within the line.
+    fn floor_column(&self, line: usize, column: usize) -> usize {
+        let text = self.line(line);
+        (0..=column.min(text.len())).rev().find(|&c| text.is_char_boundary(c)).unwrap_or(0)
+    }
+
+    /// Round a column up to a character boundary within the line.
+    fn ceil_column(&self, line: usize, column: usize) -> usize {
+        let text = self.line(line);
+        (column.min(text.len())..=text.len()).find(|&c| text.is_char_boundary(c)).unwrap_or(0)
+    }
+
+    /// Get the last column normal mode rests on: the line's last character.
+    fn last_column(&self, line: usize) -> usize {
+        self.prev_column(line, self.line(line).len())
+    }
+
+    /// Get the column of the first non-blank character of a line.
+    fn first_non_blank(&self, line: usize) -> usize {
+        first_non_blank(self.line(line))
+    }
+
+    /// Get the position after the character at `cursor`, which is the start
+    /// of the next line at the end of a line.
+    fn after_char(&self, cursor: Cursor) -> Cursor {
+        if cursor.column >= self.line(cursor.line).len() && cursor.line < self.last_line() {
+            Cursor::new(cursor.line + 1, 0)
+        } else {
+            Cursor::new(cursor.line, self.next_column(cursor.line, cursor.column))
+        }
+    }
+
+    /// Convert a position to a byte offset in the whole text.
+    fn offset(&self, cursor: Cursor) -> usize {
+        let line = cursor.line.min(self.last_line());
+        self.starts[line] + cursor.column.min(self.lines[line].len())
+    }
+
+    /// Convert a byte offset in the whole text to a position.
+    fn position(&self, offset: usize) -> Cursor {
+        let line = self.starts.partition_point(|&start| start <= offset).saturating_sub(1);
+        Cursor::new(line, (offset - self.starts[line]).min(self.lines[line].len()))
+    }
+
+    /// Get the whole text.
+    fn content(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    /// Get the text between two positions.
+    fn slice(&self, start: Cursor, end: Cursor) -> String {
+        let (start, end) = ordered(start, end);
+        self.content()[self.offset(start)..self.offset(end)].to_string()
+    }
+
+    /// Convert a byte column to a character index within the line.
+    fn char_index(&self, line: usize, column: usize) -> usize {
+        let text = self.line(line);
+        text[..self.floor_column(line, column)].chars().count()
+    }
+
+    /// Convert a character index within the line to a byte column.
+    fn byte_column(&self, line: usize, index: usize) -> usize {
+        let text = self.line(line);
+        text.char_indices().nth(index).map_or(text.len(), |(column, _)| column)
+    }
+}
+
+/// Get where `motion` moves `from`, or `None` when it cannot move.
+///
+/// An operator's word motion stops at the end of the line on its last step.
+fn motion_target(
+    text: &Text,
+    from: Cursor,
+    motion: Motion,
+    count: Option<usize>,
+    for_operator: bool,
+) -> Option<Cursor> {
+    let n = count.unwrap_or(1);
+    let lengths = text.lengths();
+    let mut cursor = from;
+    match motion {
+        Motion::Left => {
+            for _ in 0..n.min(cursor.column) {
+                if cursor.column == 0 {
+                    break;
+                }
+                cursor.move_left(&lengths);
+                cursor.move_to(cursor.line, text.floor_column(cursor.line, cursor.column));
+            }
+        }
+        Motion::Right => {
+            for _ in 0..n {
+                if cursor.column >= lengths[cursor.line] {
+                    break;
+                }
+                cursor.move_right(&lengths);
+                cursor.move_to(cursor.line, text.ceil_column(cursor.line, cursor.column));
+            }
+        }
+        Motion::Up | Motion::Down => {
+            for _ in 0..n {
+                if motion == Motion::Up {
+                    cursor.move_up(&lengths);
+                } else {
+                    cursor.move_down(&lengths);
+                }
+            }
+            if cursor.line == from.line {
+                return None;
+            }
+            cursor.column = text.floor_column(cursor.line, cursor.column);
+        }
+        Motion::WordForward => {
+            for step in 1..=n {
+                cursor = by_char_index(text, cursor, |cursor| cursor.move_word_right(&text.lines));
+                let at_line_end = cursor.column >= lengths[cursor.line];
+                if at_line_end && cursor.line < text.last_line() && !(for_operator && step == n) {
+                    let line = cursor.line + 1;
+                    cursor.move_to(line, text.first_non_blank(line));
+                }
+            }
+        }
+        Motion::WordBackward => {
+            for _ in 0..n {
+                let line = cursor.line;
+                cursor = by_char_index(text, cursor, |cursor| cursor.move_word_left(&text.lines));
+                // Onto the previous line: go on to the start of its last word.
+                if cursor.line < line && !text.line(cursor.line).trim().is_empty() {
+                    cursor =
+                        by_char_index(text, cursor, |cursor| cursor.move_word_left(&text.lines));
+                }
+            }
+        }
+        Motion::WordEnd => cursor = word_end(text, from, n, false),
+        Motion::LineStart => cursor.move_to_line_start(),
+        Motion::FirstNonBlank => cursor.move_to(cursor.line, text.first_non_blank(cursor.line)),
+        Motion::LineEnd => {
+            for _ in 1..n {
+                cursor.move_down(&lengths);
+            }
+            cursor.move_to_line_end(&lengths);
+        }
+        Motion::FirstLine | Motion::LastLine => {
+            let line = match (motion, count) {
+                (_, Some(line)) => line.saturating_sub(1).min(text.last_line()),
+                (Motion::FirstLine, None) => 0,
+                _ => text.last_line(),
+            };
+            cursor.move_to(line, text.first_non_blank(line));
+        }
+        Motion::PageDown | Motion::HalfPageDown => {
+            let page = if motion == Motion::PageDown { PAGE_LINES } else { PAGE_LINES / 2 };
+            cursor.move_page_down(page * n, &lengths);
+            cursor.column = text.floor_column(cursor.line, cursor.column);
+        }
+        Motion::PageUp | Motion::HalfPageUp => {
+            let page = if motion == Motion::PageUp { PAGE_LINES } else { PAGE_LINES / 2 };
+            cursor.move_page_up(page * n, &lengths);
+            cursor.column = text.floor_column(cursor.line, cursor.column);
+        }
+        Motion::Find { target, forward, till } => {
+            let line = text.line(cursor.line);
+            let column = if forward {
+                let start = text.next_column(cursor.line, cursor.column);
+                let found = line[start..].match_indices(target).nth(n - 1)?.0 + start;
+                if till { text.prev_column(cursor.line, found) } else { found }
+            } else {
+                let found = line[..cursor.column].rmatch_indices(target).nth(n - 1)?.0;
+                if till { text.next_column(cursor.line, found) } else { found }
+            };
+            cursor.move_to(cursor.line, column);
+        }
+    }
+    Some(cursor)
+}
+
+/// Run a [`Cursor`] word motion, which counts columns in characters, on a
+/// cursor with a byte column.
+fn by_char_index(text: &Text, cursor: Cursor, motion: impl FnOnce(&mut Cursor)) -> Cursor {
+    let mut moved = Cursor::new(cursor.line, text.char_index(cursor.line, cursor.column));
+    motion(&mut moved);
+    Cursor::new(moved.line, text.byte_column(moved.line, moved.column))
+}
+
+/// Get the last character of the `count`th word end after `from`.
+///
+/// With `stay`, a cursor inside a word counts the end of that word first,
+/// as `cw` does.
+fn word_end(text: &Text, from: Cursor, count: usize, stay: bool) -> Cursor {
+    let content = text.content();
+    let char_at = |offset: usize| content.get(offset..).and_then(|rest| rest.chars().next());
+    let next = |offset: usize| char_at(offset).map(|c| offset + c.len_utf8());
+    let is_blank = |offset: usize| char_at(offset).map_or(true, char::is_whitespace);
+
+    let mut offset = text.offset(from);
+    for step in 0..count {
+        if !(stay && step == 0) {
+            match next(offset) {
+                Some(after) if after < content.len() => offset = after,
+                _ => break,
+            }
+        }
+        while offset < content.len() && is_blank(offset) {
+            offset = next(offset).unwrap_or(content.len());
+        }
+        while let Some(after) = next(offset).filter(|&after| !is_blank(after)) {
+            offset = after;
+        }
+    }
+    text.position(offset.min(content.len()))
+}
+
+/// Get the text an operator covers with a motion.
+fn motion_span(
+    text: &Text,
+    from: Cursor,
+    motion: Motion,
+    count: Option<usize>,
+    operator: Operator,
+) -> Option<Span> {
+    let on_word = text.line(from.line)[from.column.min(text.line(from.line).len())..]
+        .chars()
+        .next()
+        .is_some_and(|c| !c.is_whitespace());
+
+    // `cw` on a word changes to its end, like `ce`.
+    let (target, reach) =
+        if operator == Operator::Change && motion == Motion::WordForward && on_word {
+            (word_end(text, from, count.unwrap_or(1), true), Reach::Inclusive)
+        } else {
+            (motion_target(text, from, motion, count, true)?, motion.reach())
+        };
+
+    let (start, end) = ordered(from, target);
+    Some(match reach {
+        Reach::Linewise => Span::Lines(start.line, end.line),
+        Reach::Exclusive => Span::Chars(start, end),
+        Reach::Inclusive => Span::Chars(start, text.after_char(end)),
+    })
+}
+
+/// Get the start and end of a text object around `cursor`.
+fn object_span(
+    text: &Text,
+    cursor: Cursor,
+    object: TextObject,
+    around: bool,
+) -> Option<(Cursor, Cursor)> {
+    match object {
+        TextObject::Word | TextObject::BigWord => {
+            word_object(text, cursor, object == TextObject::BigWord, around)
+        }
+        TextObject::Quote(quote) => quote_object(text, cursor, quote, around),
+        TextObject::Pair(open, close) => pair_object(text, cursor, open, close, around),
+    }
+}
+
+/// Select the word, punctuation or whitespace under the cursor. `around`
+/// adds the whitespace after it, or before it when there is none after.
+fn word_object(text: &Text, cursor: Cursor, big: bool, around: bool) -> Option<(Cursor, Cursor)> {
+    let line = text.line(cursor.line);
+    let chars: Vec<(usize, char)> = line.char_indices().collect();
+    let class = |c: char| match c {
+        c if c.is_whitespace() => 0,
+        c if big || c.is_alphanumeric() || c == '_' => 1,
+        _ => 2,
+    };
+    let index = chars.iter().position(|&(column, _)| column >= cursor.column)?;
+    let same = |i: usize, kind| chars.get(i).is_some_and(|&(_, c)| class(c) == kind);
+
+    let kind = class(chars[index].1);
+    let (mut start, mut end) = (index, index + 1);
+    while start > 0 && same(start - 1, kind) {
+        start -= 1;
+    }
+    while same(end, kind) {
+        end += 1;
+    }
+
+    if around {
+        if kind == 0 {
+            if let Some(&(_, c)) = chars.get(end) {
+                let next = class(c);
+                while same(end, next) {
+                    end += 1;
+                }
+            }
+        } else if same(end, 0) {
+            while same(end, 0) {
+                end += 1;
+            }
+        } else {
+            while start > 0 && same(start - 1, 0) {
+                start -= 1;
+            }
+        }
+    }
+
+    let column = |i: usize| chars.get(i).map_or(line.len(), |&(column, _)| column);
+    Some((Cursor::new(cursor.line, column(start)), Cursor::new(cursor.line, column(end))))
+}
+
+/// Select between the pair of quotes around or after the cursor on its line.
+/// `around` adds the quotes and the whitespace after them.
+fn quote_object(
+    text: &Text,
+    cursor: Cursor,
+    quote: char,
+    around: bool,
+) -> Option<(Cursor, Cursor)> {
+    let line = text.line(cursor.line);
+    let mut quotes = Vec::new();
+    let mut escaped = false;
+    for (column, c) in line.char_indices() {
+        if c == quote && !escaped {
+            quotes.push(column);
+        }
+        escaped = c == '\\' && !escaped;
+    }
+
+    let (open, close) = quotes
+        .chunks_exact(2)
+        .map(|pair| (pair[0], pair[1]))
+        .find(|&(_, close)| cursor.column <= close)?;
+    let (start, end) = if around {
+        let after = close + quote.len_utf8();
+        let trailing = line[after..].len() - line[after..].trim_start().len();
+        (open, after + trailing)
+    } else {
+        (open + quote.len_utf8(), close)
+    };
+    Some((Cursor::new(cursor.line, start), Cursor::new(cursor.line, end)))
+}
+
+/// Select between the brackets around the cursor, which may span lines.
+///
+/// Inside brackets that start and end their own lines, only the lines
+/// between them are selected.
+fn pair_object(
+    text: &Text,
+    cursor: Cursor,
+    open: char,
+    close: char,
+    around: bool,
+) -> Option<(Cursor, Cursor)> {
+    let content = text.content();
+    let offset = text.offset(cursor);
+
+    let open_at = if content[offset..].starts_with(open) {
+        offset
+    } else {
+        let mut depth = 0;
+        content[..offset].char_indices().rev().find_map(|(i, c)| {
+            if c == close {
+                depth += 1;
+            } else if c == open {
+                if depth == 0 {
+                    return Some(i);
+                }
+                depth -= 1;
+            }
+            None
+        })?
+    };
+
+    let inner_start = open_at + open.len_utf8();
+    let mut depth = 0;
+    let close_at = content[inner_start..].char_indices().find_map(|(i, c)| {
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            if depth == 0 {
+                return Some(inner_start + i);
+            }
+            depth -= 1;
+        }
+        None
+    })?;
+
+    let (start, end) = if around {
+        (open_at, close_at + close.len_utf8())
+    } else {
+        let mut start = inner_start;
+        let mut end = close_at;
+        if content[start..].starts_with('\n') {
+            start += 1;
+            let line_start = content[..close_at].rfind('\n').map_or(0, |i| i + 1);
+            if content[line_start..close_at].trim().is_empty() && line_start >= start {
+                end = line_start;
+            }
+        }
+        (start, end.max(start))
+    };
+    Some((text.position(start), text.position(end)))
+}
+
+/// Delete a span, leaving the cursor where it started.
+async fn delete_span(editor: &Editor, text: &Text, span: Span) -> Result<()> {
+    match span {
+        Span::Chars(start, end) => replace(editor, start, end, "").await,
+        Span::Lines(first, last) => {
+            let line_end = |line: usize| Cursor::new(line, text.line(line).len());
+            let (start, end) = if last < text.last_line() {
+                (Cursor::new(first, 0), Cursor::new(last + 1, 0))
+            } else if first > 0 {
+                (line_end(first - 1), line_end(last))
+            } else {
+                (Cursor::new(0, 0), line_end(last))
+            };
+            replace(editor, start, end, "").await?;
+            if let Some(text) = Text::load(editor).await {
+                let line = first.min(text.last_line());
+                editor.move_cursor(line, text.first_non_blank(line)).await?;
+            }
+            Ok(())
+        }
+        Span::Block(a, b) => {
+            let (start, end) = block_corners(text, a, b);
+            editor.set_selection(Selection::with_mode(start, end, SelectionMode::Block)).await?;
+            editor.delete_text(0).await
+        }
+    }
+}
+
+/// Indent or outdent lines by one level.
+async fn shift_lines(
+    editor: &Editor,
+    text: &Text,
+    first: usize,
+    last: usize,
+    indent: bool,
+) -> Result<()> {
+    let unit = editor.indent_unit().await;
+    let shifted: Vec<String> = text.lines[first..=last]
+        .iter()
+        .map(|line| {
+            if indent {
+                if line.is_empty() { String::new() } else { format!("{unit}{line}") }
+            } else if let Some(rest) = line.strip_prefix(unit.as_str()) {
+                rest.to_owned()
+            } else if let Some(rest) = line.strip_prefix('\t') {
+                rest.to_owned()
+            } else {
+                let spaces = line.len() - line.trim_start_matches(' ').len();
+                line[spaces.min(unit.len())..].to_string()
+            }
+        })
+        .collect();
+
+    let end = Cursor::new(last, text.line(last).len());
+    replace(editor, Cursor::new(first, 0), end, &shifted.join("\n")).await?;
+    editor.move_cursor(first, first_non_blank(&shifted[0])).await
+}
+
+/// Move the cursor from `from` for an arrow key typed in insert mode.
+async fn arrow_key(editor: &Editor, from: Cursor, motion: Motion) -> Result<()> {
+    let Some(text) = Text::load(editor).await else {
+        return Ok(());
+    };
+    match motion_target(&text, from, motion, None, false) {
+        Some(target) => editor.move_cursor(target.line, target.column).await,
+        None => Ok(()),
+    }
+}
+
+/// Join lines `first` to `last` with single spaces, leaving the cursor at
+/// the last join.
+async fn join_lines(editor: &Editor, text: &Text, first: usize, last: usize) -> Result<()> {
+    let last = last.min(text.last_line());
+    if last <= first {
+        return Ok(());
+    }
+    let mut joined = text.line(first).to_owned();
+    let mut column = 0;
+    for line in first + 1..=last {
+        let next = text.line(line).trim_start();
+        column = joined.len();
+        if !next.is_empty() && !joined.is_empty() && !joined.ends_with(char::is_whitespace) {
+            joined.push(' ');
+        }
+        joined.push_str(next);
+    }
+
+    let end = Cursor::new(last, text.line(last).len());
+    replace(editor, Cursor::new(first, 0), end, &joined).await?;
+    editor.move_cursor(first, column).await
+}
+
+/// Replace the text between two positions.
+async fn replace(editor: &Editor, start: Cursor, end: Cursor, text: &str) -> Result<()> {
+    let (start, end) = ordered(start, end);
+    let selection =
+        Selection::new(Cursor::new(start.line, start.column), Cursor::new(end.line, end.column));
+    editor.set_selection(selection).await?;
+    if text.is_empty() { editor.delete_text(0).await } else { editor.insert_text(text).await }
+}
+
+/// Get the corners of a block selection that includes the characters at
+/// both `a` and `b`.
+fn block_corners(text: &Text, a: Cursor, b: Cursor) -> (Cursor, Cursor) {
+    let after =
+        |cursor: Cursor| Cursor::new(cursor.line, text.next_column(cursor.line, cursor.column));
+    if a.column <= b.column { (a, after(b)) } else { (after(a), b) }
+}
+
+/// Get the first and last line a span touches.
+fn span_lines(span: Span) -> (usize, usize) {
+    match span {
+        Span::Chars(start, end) => {
+            let (start, end) = ordered(start, end);
+            let last =
+                if end.column == 0 && end.line > start.line { end.line - 1 } else { end.line };
+            (start.line, last)
+        }
+        Span::Lines(first, last) => (first, last),
+        Span::Block(a, b) => (a.line.min(b.line), a.line.max(b.line)),
+    }
+}
+
+/// Put two positions in document order.
+fn ordered(a: Cursor, b: Cursor) -> (Cursor, Cursor) {
+    if b.is_before(&a) { (b, a) } else { (a, b) }
+}
+
+/// Get the whitespace at the start of a line.
+fn leading_whitespace(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// Get the column of the first non-blank character of a line.
+fn first_non_blank(line: &str) -> usize {
+    leading_whitespace(line).len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Config, EventBus};
+    use std::sync::Arc;
+    use tempfile::TempDir;
+    use tokio::sync::RwLock;
+
+    async fn open(content: &str) -> (TempDir, Editor, PathBuf) {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.editor.undo.persist = false;
+        let config = Arc::new(RwLock::new(config));
+        let editor = Editor::new(config, Arc::new(EventBus::new())).await.unwrap();
+
+        let path = temp_dir.path().join("test.txt");
+        std::fs::write(&path, content).unwrap();
+        editor.open_file(&path).await.unwrap();
+        (temp_dir, editor, path)
+    }
+
+    /// Convert key notation such as `ci"x<Esc>` to keys.
+    fn keys(notation: &str) -> Vec<VimKey> {
+        let mut keys = Vec::new();
+        let mut rest = notation;
+        while let Some(c) = rest.chars().next() {
+            let special = [
+                ("<Esc>", VimKey::Escape),
+                ("<CR>", VimKey::Enter),
+                ("<BS>", VimKey::Backspace),
+                ("<C-r>", VimKey::Ctrl('r')),
+                ("<C-v>", VimKey::Ctrl('v')),
+            ];
+            match special.iter().find(|(name, _)| rest.starts_with(name)) {
+                Some((name, key)) => {
+                    keys.push(*key);
+                    rest = &rest[name.len()..];
+                }
+                None => {
+                    keys.push(VimKey::Char(c));
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+        }
+        keys
+    }
+
+    async fn type_keys(vim: &mut Vim, editor: &Editor, notation: &str) -> VimOutcome {
+        let mut outcome = VimOutcome::Handled;
+        for key in keys(notation) {
+            outcome = vim.handle_key(editor, key).await.unwrap();
+        }
+        outcome
+    }
+
+    #[test]
+    fn test_parse_counts_and_registers() {
+        let parsed = |notation: &str| parse(&keys(notation), false);
+        let done =
+            |register, count, action| Partial::Done(NormalCommand { register, count, action });
+
+        let delete_words = Action::Operate(Operator::Delete, Target::Motion(Motion::WordForward));
+        assert_eq!(parsed("3dw"), done(None, Some(3), delete_words));
+        assert_eq!(parsed("2d3w"), done(None, Some(6), delete_words));
+        assert_eq!(
+            parsed("\"ayy"),
+            done(Some('a'), None, Action::Operate(Operator::Yank, Target::Lines))
+        );
+        assert_eq!(parsed("10G"), done(None, Some(10), Action::Move(Motion::LastLine)));
+        assert_eq!(parsed("0"), done(None, None, Action::Move(Motion::LineStart)));
+        assert_eq!(parsed("d"), Partial::Incomplete);
+        assert_eq!(parsed("di"), Partial::Incomplete);
+        assert_eq!(parsed("dz"), Partial::Invalid);
+        assert_eq!(
+            parse(&keys("d"), true),
+            done(None, None, Action::Operate(Operator::Delete, Target::Selection))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_motions_and_operators() {
+        let (_dir, editor, _) = open("one two three\n  four five\nsix").await;
+        let mut vim = Vim::new();
+
+        type_keys(&mut vim, &editor, "w").await;
+        assert_eq!(editor.get_cursor_position().await, (0, 4));
+        type_keys(&mut vim, &editor, "dw").await;
+        assert_eq!(editor.get_active_content().await.unwrap(), "one three\n  four five\nsix");
+        type_keys(&mut vim, &editor, "$").await;
+        assert_eq!(editor.get_cursor_position().await, (0, 8));
+        type_keys(&mut vim, &editor, "j").await;
+        assert_eq!(editor.get_cursor_position().await, (1, 10));
+
+        type_keys(&mut vim, &editor, "^dfv").await;
+        assert_eq!(editor.get_active_content().await.unwrap(), "one three\n  e\nsix");
+        type_keys(&mut vim, &editor, "2dd").await;
+        assert_eq!(editor.get_active_content().await.unwrap(), "one three");
+        type_keys(&mut vim, &editor, "u").await;
+        assert_eq!(editor.get_active_content().await.unwrap(), "one three\n  e\nsix");
+
+        type_keys(&mut vim, &editor, "ggcwzero<Esc>").await;
+        assert_eq!(editor.get_active_content().await.unwrap(), "zero three\n  e\nsix");
+        assert_eq!(editor.get_cursor_position().await, (0, 3));
+        type_keys(&mut vim, &editor, "Gx>>").await;
+        assert_eq!(editor.get_active_content().await.unwrap(), "zero three\n  e\n    ix");
+        assert_eq!(vim.mode(), VimMode::Normal);
+    }
+
+    #[tokio::test]
+    async fn test_text_objects() {
+        let (_dir, editor, _) = open("call(foo, \"bar baz\")\nfn x() {\n    body\n}").await;
+        let mut vim = Vim::new();
+
+        type_keys(&mut vim, &editor, "fbci\"qux<Esc>").await;
+        assert_eq!(
+            editor.get_active_content().await.unwrap().lines().next(),
+            Some("call(foo, \"qux\")")
+        );
+        type_keys(&mut vim, &editor, "0fodiw").await;
+        assert_eq!(
+            editor.get_active_content().await.unwrap().lines().next(),
+            Some("call(, \"qux\")")
+        );
+        type_keys(&mut vim, &editor, "da(").await;
+        assert_eq!(editor.get_active_content().await.unwrap().lines().next(), Some("call"));
+
+        type_keys(&mut vim, &editor, "jjdi{").await;
+        assert_eq!(editor.get_active_content().await.unwrap(), "call\nfn x() {\n}");
+    }
+
+    #[tokio::test]
+    async fn test_visual_modes() {
+        let (_dir, editor, _) = open("alpha beta\ngamma delta\nepsilon").await;
+        let mut vim = Vim::new();
+
+        type_keys(&mut vim, &editor, "vey").await;
+        assert_eq!(vim.registers().get('0').unwrap().text, "alpha");
+        assert_eq!(vim.mode(), VimMode::Normal);
+
+        type_keys(&mut vim, &editor, "Vjd").await;
+        assert_eq!(editor.get_active_content().await.unwrap(), "epsilon");
+        assert_eq!(vim.registers().get('"').unwrap().kind, RegisterKind::Lines);
+        type_keys(&mut vim, &editor, "p").await;
+        assert_eq!(editor.get_active_content().await.unwrap(), "epsilon\nalpha beta\ngamma delta");
+
+        type_keys(&mut vim, &editor, "gg<C-v>jld").await;
+        assert_eq!(editor.get_active_content().await.unwrap(), "silon\npha beta\ngamma delta");
+        assert_eq!(vim.registers().get('"').unwrap().text, "ep\nal");
+        assert_eq!(vim.mode(), VimMode::Normal);
+    }
+
+    #[tokio::test]
+    async fn test_repeat_and_insert_count() {
+        let (_dir, editor, _) = open("a b c\n").await;
+        let mut vim = Vim::new();
+
+        type_keys(&mut vim, &editor, "3ihi <Esc>").await;
+        assert_eq!(editor.get_active_content().await.unwrap(), "hi hi hi a b c\n");
+        assert_eq!(editor.get_cursor_position().await, (0, 8));
+        type_keys(&mut vim, &editor, "u").await;
+        assert_eq!(editor.get_active_content().await.unwrap(), "a b c\n");
+
+        type_keys(&mut vim, &editor, "0cwx<Esc>").await;
+        assert_eq!(editor.get_active_content().await.unwrap(), "x b c\n");
+        type_keys(&mut vim, &editor, "w").await;
+        assert_eq!(editor.get_cursor_position().await, (0, 2));
+        type_keys(&mut vim, &editor, ".").await;
+        assert_eq!(editor.get_active_content().await.unwrap(), "x x c\n");
+        type_keys(&mut vim, &editor, "w.").await;
+        assert_eq!(editor.get_active_content().await.unwrap(), "x x x\n");
+        type_keys(&mut vim, &editor, "0x2.").await;
+        assert_eq!(editor.get_active_content().await.unwrap(), " x\n");
+        type_keys(&mut vim, &editor, "u<C-r>").await;
+        assert_eq!(editor.get_active_content().await.unwrap(), " x\n");
+
+        type_keys(&mut vim, &editor, "oline<Esc>.").await;
+        assert_eq!(editor.get_active_content().await.unwrap(), " x\n line\n line\n");
+    }
+
+    #[test]
+    fn test_registers() {
+        let mut registers = Registers::default();
+        let chars = |text: &str| Register::new(text, RegisterKind::Characters);
+
+        let lines = |text: &str| Register::new(text, RegisterKind::Lines);
+
+        registers.store(None, chars("yanked"), true);
+        registers.store(None, lines("first\n"), false);
+        registers.store(None, chars("sec\nond"), false);
+        assert_eq!(registers.get('0').unwrap().text, "yanked");
+        assert_eq!(registers.get('1').unwrap().text, "sec\nond");
+        assert_eq!(registers.get('2').unwrap().text, "first\n");
+        assert_eq!(registers.get('"').unwrap().text, "sec\nond");
+
+        registers.store(None, chars("word"), false);
+        assert_eq!(registers.get('-').unwrap().text, "word");
+        assert_eq!(registers.get('1').unwrap().text, "sec\nond");
+        assert_eq!(registers.get('"').unwrap().text, "word");
+
+        registers.store(Some('a'), chars("one"), true);
+        registers.store(Some('A'), lines("two\n"), true);
+        assert_eq!(registers.get('a'), Some(&Register::new("one\ntwo\n", RegisterKind::Lines)));
+
+        registers.store(Some('_'), chars("gone"), false);
+        assert_eq!(registers.get('"').unwrap().text, "one\ntwo\n");
+        assert!(registers.get('_').is_none());
+    }
+
+    #[tokio::test]
+    async fn test_ex_commands() {
+        let (_dir, editor, path) = open("first\nsecond\nthird").await;
+        let mut vim = Vim::new();
+
+        type_keys(&mut vim, &editor, ":3").await;
+        assert_eq!(vim.status(), ":3");
+        type_keys(&mut vim, &editor, "<CR>").await;
+        assert_eq!(editor.get_cursor_position().await, (2, 0));
+
+        type_keys(&mut vim, &editor, "dd").await;
+        let outcome = type_keys(&mut vim, &editor, ":q<CR>").await;
+        assert!(matches!(outcome, VimOutcome::Message(message) if message.starts_with("E37")));
+        let outcome = type_keys(&mut vim, &editor, ":w<CR>").await;
+        assert_eq!(outcome, VimOutcome::Message("\"test.txt\" written".to_string()));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "first\nsecond");
+        assert_eq!(type_keys(&mut vim, &editor, ":q<CR>").await, VimOutcome::Quit);
+
+        let outcome = type_keys(&mut vim, &editor, ":nope<CR>").await;
+        assert_eq!(outcome, VimOutcome::Message("E492: Not an editor command: nope".to_string()));
+        assert_eq!(type_keys(&mut vim, &editor, "x:q!<CR>").await, VimOutcome::Quit);
+    }
+}
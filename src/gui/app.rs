@@ -5,21 +5,32 @@
 use std::fs;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, broadcast};
+use tracing::{debug, warn};
 
 use super::ToolsWindow;
-use crate::core::{Config, EventBus};
-use crate::editor::Editor;
-use crate::project::ProjectManager;
+use crate::core::{
+    Config, ConfigLoader, ConfigWatcher, Event, EventBus, EventMessage, EventSubscription,
+    KeyContext, KeyResolution, KeyStroke, Keymap, SystemEvent, UiEvent,
+};
+use crate::editor::{Editor, Vim, VimKey, VimOutcome};
+use crate::project::{FileIndex, ProjectManager};
 use crate::syntax::SyntaxManager;
+use crate::syntax::lsp_client::{Location, utf16_to_byte_offset};
 
+use crate::gui::commands::{CommandRegistry, IdeCommand, key_stroke};
 use crate::gui::editor::EditorWidget;
+use crate::gui::file_finder::FileFinderWidget;
 use crate::gui::file_tree::FileTreeWidget;
 use crate::gui::menu::{MenuAction, MenuBarWidget};
+use crate::gui::palette::CommandPaletteWidget;
 use crate::gui::statusbar::{StatusBarWidget, StatusInfo};
 
 /// Main Xylux IDE application (GUI-only)
+#[expect(clippy::struct_excessive_bools, reason = "independent panel and dialog toggles")]
 pub struct XyluxIdeApp {
+    config: Arc<RwLock<Config>>,
+    event_bus: Arc<EventBus>,
     editor: Editor,
     syntax_manager: SyntaxManager,
     project_manager: ProjectManager,
@@ -30,46 +41,109 @@ pub struct XyluxIdeApp {
     status_message: String,
     file_dialog_open: bool,
     about_dialog_open: bool,
+    rename_dialog_open: bool,
+    rename_input: String,
+    references: Vec<Location>,
+    references_open: bool,
+    commands: CommandRegistry<Self>,
+    keymap: Keymap,
+    pending_keys: Vec<KeyStroke>,
+    focus: KeyContext,
+    file_tree_focused: bool,
+    shortcuts_open: bool,
+    vim: Vim,
+    vim_enabled: bool,
+    /// Reloads the configuration when its files change
+    config_watcher: Option<ConfigWatcher>,
+    /// Events published on the bus, polled every frame
+    events: broadcast::Receiver<EventMessage>,
 
     // Widgets
     menu: MenuBarWidget,
     status_bar: StatusBarWidget,
     file_tree: FileTreeWidget,
+    file_finder: FileFinderWidget,
+    palette: CommandPaletteWidget,
     editor_widget: EditorWidget,
     tools_window: ToolsWindow,
 }
 
 impl XyluxIdeApp {
+    /// Create the application and the IDE components behind it
+    ///
+    /// # Panics
+    ///
+    /// Panics if the Tokio runtime, the editor or the syntax manager cannot be created
+    #[must_use]
+    #[expect(clippy::expect_used, reason = "the IDE cannot run without these components")]
     pub fn new(cc: &eframe::CreationContext<'_>, config: Config) -> Self {
         Self::configure_style(&cc.egui_ctx);
+        Self::apply_theme(&cc.egui_ctx, &config.ui.theme);
+        Self::apply_font_size(&cc.egui_ctx, config.ui.font_size);
 
+        let vim_enabled = config.editor.vim_mode;
+        let show_file_explorer = config.ui.show_file_explorer;
         let config = Arc::new(RwLock::new(config));
         let event_bus = Arc::new(EventBus::new());
+        let events = event_bus.subscribe(EventSubscription::all());
         let rt = tokio::runtime::Runtime::new().expect("tokio runtime");
+        let current_directory = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
 
-        let editor =
-            rt.block_on(Editor::new(config.clone(), event_bus.clone())).expect("editor init");
+        let editor = rt
+            .block_on(Editor::new(Arc::clone(&config), Arc::clone(&event_bus)))
+            .expect("editor init");
         let syntax_manager = rt
-            .block_on(SyntaxManager::new(config.clone(), event_bus.clone()))
+            .block_on(SyntaxManager::new(Arc::clone(&config), Arc::clone(&event_bus)))
             .expect("syntax init");
-        let project_manager = ProjectManager::new(config.clone(), event_bus.clone());
+        rt.block_on(syntax_manager.document_sync().set_source(editor.document_source()));
+        let project_manager = ProjectManager::new(Arc::clone(&config), Arc::clone(&event_bus));
+        let config_watcher = {
+            let _guard = rt.enter();
+            ConfigLoader::new()
+                .map(|loader| loader.with_project_root(current_directory.clone()))
+                .and_then(|loader| {
+                    ConfigWatcher::start(loader, Arc::clone(&config), Arc::clone(&event_bus))
+                })
+                .map_err(|e| warn!("Configuration changes will not be reloaded: {}", e))
+                .ok()
+        };
 
-        Self {
+        let mut app = Self {
+            config,
+            event_bus,
             editor,
             syntax_manager,
             project_manager,
             rt,
-            show_file_explorer: true,
-            current_directory: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+            show_file_explorer,
+            current_directory,
             status_message: "Ready".into(),
             file_dialog_open: false,
             about_dialog_open: false,
+            rename_dialog_open: false,
+            rename_input: String::new(),
+            references: Vec::new(),
+            references_open: false,
+            commands: Self::default_commands(),
+            keymap: Keymap::with_defaults(),
+            pending_keys: Vec::new(),
+            focus: KeyContext::Global,
+            file_tree_focused: false,
+            shortcuts_open: false,
+            vim: Vim::new(),
+            vim_enabled,
+            config_watcher,
+            events,
             menu: MenuBarWidget::new(),
             status_bar: StatusBarWidget::new(),
             file_tree: FileTreeWidget::default(),
+            file_finder: FileFinderWidget::new(),
+            palette: CommandPaletteWidget::new(),
             editor_widget: EditorWidget::new(),
             tools_window: ToolsWindow::new(),
-        }
+        };
+        app.reload_keymap();
+        app
     }
 
     fn configure_style(ctx: &egui::Context) {
@@ -84,91 +158,590 @@ impl XyluxIdeApp {
         ctx.set_style(style);
     }
 
-    fn handle_menu_action(&mut self, ctx: &egui::Context, action: MenuAction) {
-        match action {
-            MenuAction::NewFile => self.new_file(),
-            MenuAction::OpenFile => self.file_dialog_open = true,
-            MenuAction::OpenFolder => {
-                // Basic: switch file tree root to current dir's parent
-                if let Some(parent) = self.current_directory.parent() {
-                    self.current_directory = parent.to_path_buf();
-                    self.file_tree.set_root_directory(self.current_directory.clone());
+    /// Switch between the dark and light visuals for a theme name
+    fn apply_theme(ctx: &egui::Context, theme: &str) {
+        let mut style = (*ctx.style()).clone();
+        if theme.to_ascii_lowercase().contains("light") {
+            style.visuals = egui::Visuals::light();
+        } else {
+            style.visuals = egui::Visuals::dark();
+            style.visuals.override_text_color = Some(egui::Color32::from_rgb(220, 220, 220));
+            style.visuals.panel_fill = egui::Color32::from_rgb(30, 30, 30);
+            style.visuals.window_fill = egui::Color32::from_rgb(40, 40, 40);
+            style.visuals.extreme_bg_color = egui::Color32::from_rgb(20, 20, 20);
+        }
+        ctx.set_style(style);
+    }
+
+    /// Scale the body and monospace text to the configured font size
+    fn apply_font_size(ctx: &egui::Context, size: u16) {
+        let size = f32::from(size.max(1));
+        let mut style = (*ctx.style()).clone();
+        for style_name in [egui::TextStyle::Body, egui::TextStyle::Monospace] {
+            if let Some(font) = style.text_styles.get_mut(&style_name) {
+                font.size = size;
+            }
+        }
+        ctx.set_style(style);
+    }
+
+    /// Handle events published since the last frame, such as configuration reloads
+    fn poll_events(&mut self, ctx: &egui::Context) {
+        loop {
+            let message = match self.events.try_recv() {
+                Ok(message) => message,
+                Err(broadcast::error::TryRecvError::Lagged(skipped)) => {
+                    debug!("Skipped {} events", skipped);
+                    continue;
                 }
+                Err(_) => break,
+            };
+            let Ok(event) = serde_json::from_value::<Event>(message.data) else {
+                continue;
+            };
+            match event {
+                Event::System(SystemEvent::ConfigReloaded { changed }) => {
+                    self.apply_config_changes(&changed);
+                }
+                Event::Ui(UiEvent::ThemeChanged { theme }) => Self::apply_theme(ctx, &theme),
+                Event::Ui(UiEvent::FontChanged { size, .. }) => Self::apply_font_size(ctx, size),
+                Event::Ui(UiEvent::StatusMessage { message, .. }) => {
+                    self.status_message = message;
+                }
+                Event::Editor(_)
+                | Event::Ui(_)
+                | Event::FileSystem(_)
+                | Event::Lsp(_)
+                | Event::Project(_)
+                | Event::Build(_)
+                | Event::Alux(_)
+                | Event::Xylux(_)
+                | Event::Plugin(_)
+                | Event::System(_) => {}
             }
-            MenuAction::Save => self.save_file(),
-            MenuAction::CloseFile => self.close_file(),
-            MenuAction::Exit => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
-            MenuAction::ToggleSpecializedTools => self.tools_window.toggle(),
-            _ => {}
         }
     }
 
-    fn new_file(&mut self) {
-        if self.rt.block_on(self.editor.open_file(&PathBuf::from(""))).is_err() {
-            self.status_message = "Error creating new file".into();
+    /// Apply reloaded settings that the GUI and language servers keep their own copy of
+    fn apply_config_changes(&mut self, changed: &[String]) {
+        let config = self.rt.block_on(self.config.read()).clone();
+        if changed.iter().any(|key| key == "editor.vim_mode") {
+            self.vim_enabled = config.editor.vim_mode;
+            self.vim.reset();
+        }
+        if changed.iter().any(|key| key == "ui.show_file_explorer") {
+            self.show_file_explorer = config.ui.show_file_explorer;
+        }
+        if let Err(e) = self.rt.block_on(self.syntax_manager.apply_config_changes(changed)) {
+            warn!("Failed to apply language server settings: {}", e);
+        }
+        self.status_message = format!("Configuration reloaded: {}", changed.join(", "));
+    }
+
+    /// Commands available from the menu, the keymap and the command palette
+    #[must_use]
+    pub fn default_commands() -> CommandRegistry<Self> {
+        type Command = IdeCommand<XyluxIdeApp>;
+
+        let mut commands = CommandRegistry::new();
+        for command in [
+            Command::new("file.new", "File: New File", |app, _| app.new_file())
+                .with_menu_action(MenuAction::NewFile),
+            Command::new("file.open", "File: Open File...", |app, _| app.file_dialog_open = true)
+                .with_menu_action(MenuAction::OpenFile),
+            Command::new("file.go_to", "File: Go to File...", |app, _| app.go_to_file())
+                .with_menu_action(MenuAction::GoToFile),
+            Command::new("file.open_folder", "File: Open Parent Folder", |app, _| {
+                app.open_parent_folder();
+            })
+            .with_menu_action(MenuAction::OpenFolder),
+            Command::new("file.save", "File: Save", |app, _| app.save_file())
+                .with_menu_action(MenuAction::Save),
+            Command::new("file.save_all", "File: Save All", |app, _| app.save_all())
+                .with_menu_action(MenuAction::SaveAll),
+            Command::new("file.close", "File: Close File", |app, _| app.close_file())
+                .with_menu_action(MenuAction::CloseFile),
+            Command::new("app.quit", "Quit Xylux IDE", |_, ctx| {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            })
+            .with_menu_action(MenuAction::Exit),
+            Command::new("edit.undo", "Edit: Undo", |app, _| app.undo())
+                .with_menu_action(MenuAction::Undo),
+            Command::new("edit.redo", "Edit: Redo", |app, _| app.redo())
+                .with_menu_action(MenuAction::Redo),
+            Command::new("editor.toggle_vim", "Editor: Toggle Vim Mode", |app, _| {
+                app.vim_enabled = !app.vim_enabled;
+                app.vim.reset();
+                app.status_message =
+                    if app.vim_enabled { "Vim mode on" } else { "Vim mode off" }.into();
+            }),
+            Command::new("view.file_explorer", "View: Toggle File Explorer", |app, _| {
+                app.show_file_explorer = !app.show_file_explorer;
+            })
+            .with_menu_action(MenuAction::ToggleFileExplorer),
+            Command::new("view.command_palette", "View: Command Palette", |app, _| {
+                app.palette.open();
+            })
+            .with_menu_action(MenuAction::CommandPalette),
+            Command::new("tools.specialized", "Tools: Toggle Specialized Tools", |app, _| {
+                app.tools_window.toggle();
+            })
+            .with_menu_action(MenuAction::ToggleSpecializedTools),
+            Command::new("code.format", "Code: Format Document", |app, _| app.format_document())
+                .with_menu_action(MenuAction::FormatDocument),
+            Command::new("code.definition", "Code: Go to Definition", |app, _| {
+                app.goto_definition();
+            })
+            .with_menu_action(MenuAction::GoToDefinition),
+            Command::new("code.references", "Code: Find References", |app, _| {
+                app.find_references();
+            })
+            .with_menu_action(MenuAction::FindReferences),
+            Command::new("code.rename", "Code: Rename Symbol...", |app, _| {
+                app.rename_input.clear();
+                app.rename_dialog_open = true;
+            })
+            .with_menu_action(MenuAction::RenameSymbol),
+            Command::new("preferences.reload_keymap", "Preferences: Reload Keymap", |app, _| {
+                if app.reload_keymap() {
+                    app.status_message = "Keymap reloaded".into();
+                }
+            }),
+            Command::new(
+                "preferences.reload_config",
+                "Preferences: Reload Configuration",
+                |app, _| {
+                    let Some(watcher) = &app.config_watcher else {
+                        app.status_message = "Configuration reloading is not available".into();
+                        return;
+                    };
+                    // Failures are reported on the event bus
+                    let reloaded = app.rt.block_on(watcher.reload_now());
+                    if reloaded.is_ok_and(|changed| changed.is_empty()) {
+                        app.status_message = "Configuration unchanged".into();
+                    }
+                },
+            ),
+            Command::new("help.shortcuts", "Help: Keyboard Shortcuts", |app, _| {
+                app.shortcuts_open = true;
+            })
+            .with_menu_action(MenuAction::ShowShortcuts),
+            Command::new("help.about", "Help: About", |app, _| app.about_dialog_open = true)
+                .with_menu_action(MenuAction::About),
+        ] {
+            commands.register(command);
+        }
+        commands
+    }
+
+    fn handle_menu_action(&mut self, ctx: &egui::Context, action: &MenuAction) {
+        if *action == MenuAction::None {
+            return;
+        }
+        match self.commands.for_menu_action(action).map(IdeCommand::id) {
+            Some(id) => self.execute_command(ctx, id),
+            None => self.status_message = format!("{action:?} is not available yet"),
+        }
+    }
+
+    /// Run a registered command and announce it on the event bus
+    fn execute_command(&mut self, ctx: &egui::Context, id: &str) {
+        let Some(handler) = self.commands.get(id).map(IdeCommand::handler) else {
+            self.status_message = format!("Unknown command: {id}");
+            return;
+        };
+        handler(self, ctx);
+
+        let event = EventMessage::from_event(Event::Ui(UiEvent::CommandExecuted {
+            command: id.to_owned(),
+            args: Vec::new(),
+        }))
+        .with_source("command_registry");
+        if let Err(e) = self.rt.block_on(self.event_bus.publish(event)) {
+            debug!("Failed to publish command {}: {}", id, e);
+        }
+    }
+
+    /// Load the keymap layers for the current directory
+    ///
+    /// Returns whether the keymap loaded without conflicts. An invalid keymap
+    /// file keeps the current bindings.
+    fn reload_keymap(&mut self) -> bool {
+        self.pending_keys.clear();
+        let keymap = match Keymap::load(Some(&self.current_directory)) {
+            Ok(keymap) => keymap,
+            Err(e) => {
+                warn!("Failed to load keymap: {}", e);
+                self.status_message = format!("Keymap not loaded: {e}");
+                return false;
+            }
+        };
+
+        let conflicts = keymap.conflicts();
+        for conflict in &conflicts {
+            warn!("Keymap conflict: {}", conflict);
+        }
+        self.keymap = keymap;
+        match conflicts.as_slice() {
+            [] => true,
+            [conflict] => {
+                self.status_message = format!("Keymap conflict: {conflict}");
+                false
+            }
+            [conflict, rest @ ..] => {
+                self.status_message =
+                    format!("Keymap conflict: {} (and {} more)", conflict, rest.len());
+                false
+            }
+        }
+    }
+
+    /// Run the commands bound to the keys pressed this frame
+    ///
+    /// Keys that complete or continue a chord are removed from the input, with
+    /// the text they type, so the focused widget does not handle them too.
+    fn dispatch_keys(&mut self, ctx: &egui::Context) {
+        let mut ids = Vec::new();
+        let mut status = None;
+        ctx.input_mut(|input| {
+            let mut consumed = false;
+            input.events.retain(|event| {
+                if std::mem::take(&mut consumed) && matches!(event, egui::Event::Text(_)) {
+                    return false;
+                }
+                let Some(stroke) = key_stroke(event) else {
+                    return true;
+                };
+                match self.keymap.resolve(&mut self.pending_keys, stroke, self.focus) {
+                    KeyResolution::Command(id) => ids.push(id),
+                    KeyResolution::Pending(chord) => {
+                        status = Some(format!("{chord} was pressed, waiting for next key"));
+                    }
+                    KeyResolution::Cancelled(chord) => {
+                        status = Some(format!("{chord} is not bound to a command"));
+                    }
+                    KeyResolution::Unbound => return true,
+                }
+                consumed = true;
+                false
+            });
+        });
+
+        if let Some(status) = status {
+            self.status_message = status;
+        }
+        for id in ids {
+            self.execute_command(ctx, &id);
+        }
+    }
+
+    /// Send the keys pressed in the editor to the Vim layer
+    ///
+    /// The keys are removed from the input so the editor widget does not type
+    /// them. Letter key presses come with a text event, which is used instead.
+    fn dispatch_vim_keys(&mut self, ctx: &egui::Context) {
+        if !self.vim_enabled
+            || self.focus != KeyContext::EditorFocus
+            || self.editor_widget.path().is_none()
+        {
+            return;
+        }
+
+        let mut keys = Vec::new();
+        ctx.input_mut(|input| {
+            input.events.retain(|event| {
+                if let egui::Event::Text(text) | egui::Event::Paste(text) = event {
+                    keys.extend(text.chars().map(VimKey::Char));
+                } else if let egui::Event::Key { key, pressed, modifiers, .. } = event {
+                    if *pressed {
+                        keys.extend(vim_key(*key, *modifiers));
+                    }
+                } else {
+                    return true;
+                }
+                false
+            });
+        });
+        if keys.is_empty() {
+            return;
+        }
+
+        self.apply_typed_changes();
+        for key in keys {
+            match self.rt.block_on(self.vim.handle_key(&self.editor, key)) {
+                Ok(VimOutcome::Handled) => {}
+                Ok(VimOutcome::Quit) => ctx.send_viewport_cmd(egui::ViewportCommand::Close),
+                Ok(VimOutcome::Message(message)) => self.status_message = message,
+                Err(e) => self.status_message = format!("Vim error: {e}"),
+            }
+        }
+        self.refresh_from_editor();
+        let (line, column) = match self.vim.visual_head() {
+            Some(head) => head,
+            None => self.rt.block_on(self.editor.get_cursor_position()),
+        };
+        self.editor_widget.set_cursor(line, column);
+    }
+
+    /// Show the parent of the current directory in the file tree
+    fn open_parent_folder(&mut self) {
+        if let Some(parent) = self.current_directory.parent() {
+            self.current_directory = parent.to_path_buf();
+            self.file_tree.set_root_directory(self.current_directory.clone());
+        }
+    }
+
+    /// The current file and cursor position in LSP (UTF-16) units
+    fn lsp_position(&self) -> Option<(PathBuf, usize, usize)> {
+        let path = self.editor_widget.path()?.clone();
+        let (line, column) = self.editor_widget.cursor();
+        let text = self.editor_widget.text().split('\n').nth(line).unwrap_or("");
+        let column = text.get(..column).unwrap_or(text).encode_utf16().count();
+        Some((path, line, column))
+    }
+
+    fn goto_definition(&mut self) {
+        let Some((path, line, column)) = self.lsp_position() else {
+            return;
+        };
+        match self.rt.block_on(self.syntax_manager.goto_definition(&path, line, column)) {
+            Ok(Some(location)) => self.jump_to(&location),
+            Ok(None) => self.status_message = "No definition found".into(),
+            Err(e) => self.status_message = format!("Definition error: {e}"),
+        }
+    }
+
+    fn find_references(&mut self) {
+        let Some((path, line, column)) = self.lsp_position() else {
+            return;
+        };
+        match self.rt.block_on(self.syntax_manager.find_references(&path, line, column)) {
+            Ok(references) => {
+                self.status_message = format!("{} references", references.len());
+                self.references = references;
+                self.references_open = true;
+            }
+            Err(e) => self.status_message = format!("References error: {e}"),
+        }
+    }
+
+    fn rename_symbol(&mut self, new_name: &str) {
+        let Some((path, line, column)) = self.lsp_position() else {
+            return;
+        };
+        let edit = match self
+            .rt
+            .block_on(self.syntax_manager.rename_symbol(&path, line, column, new_name))
+        {
+            Ok(Some(edit)) => edit,
+            Ok(None) => {
+                self.status_message = "Nothing to rename".into();
+                return;
+            }
+            Err(e) => {
+                self.status_message = format!("Rename error: {e}");
+                return;
+            }
+        };
+
+        let description = format!("Rename to '{new_name}'");
+        if let Err(e) = self.rt.block_on(self.editor.apply_workspace_edit(&edit, &description)) {
+            self.status_message = format!("Rename error: {e}");
         } else {
-            self.editor_widget.set_buffer(super::FileBuffer::new());
-            self.status_message = "New file".into();
+            self.refresh_from_editor();
+            self.status_message = format!("Renamed in {} files", edit.changes.len());
         }
     }
 
-    pub fn open_file(&mut self, path: PathBuf) {
-        match fs::read_to_string(&path) {
-            Ok(content) => {
-                self.editor_widget.set_buffer(super::FileBuffer::from_file(path.clone(), content));
-                if let Err(e) = self.rt.block_on(self.editor.open_file(&path)) {
-                    self.status_message = format!("Open error: {}", e);
+    fn format_document(&mut self) {
+        let Some(path) = self.editor_widget.path().cloned() else {
+            return;
+        };
+        let edits = match self.rt.block_on(self.syntax_manager.format_document(&path)) {
+            Ok(Some(edits)) => edits,
+            Ok(None) => {
+                self.status_message = "Formatting is not available".into();
+                return;
+            }
+            Err(e) => {
+                self.status_message = format!("Format error: {e}");
+                return;
+            }
+        };
+
+        if let Err(e) =
+            self.rt.block_on(self.editor.apply_text_edits(&path, &edits, "Format document"))
+        {
+            self.status_message = format!("Format error: {e}");
         } else {
-                    let _unused = self.rt.block_on(
-                        self.syntax_manager
-                            .highlight_file(&path, &self.editor_widget.get_buffer().content),
-                    );
-            self.status_message = format!("Opened: {}", path.display());
-                }
+            self.refresh_from_editor();
+            self.status_message = "Formatted".into();
+        }
+    }
+
+    /// Open a location and move the cursor to its start
+    fn jump_to(&mut self, location: &Location) {
+        let path = PathBuf::from(location.path());
+        if self.editor_widget.path() != Some(&path) {
+            self.open_file(path);
+        }
+
+        let line = location.range.start.line;
+        let text = self.editor_widget.text().split('\n').nth(line).unwrap_or("");
+        let column = utf16_to_byte_offset(text, location.range.start.character);
+        self.editor_widget.set_cursor(line, column);
+    }
+
+    /// Show edits made through the editor in the active buffer
+    fn refresh_from_editor(&mut self) {
+        let Some(path) = self.editor_widget.path().cloned() else {
+            return;
+        };
+        if let Some(text) = self.rt.block_on(self.editor.snapshot(&path)) {
+            let modified = self.rt.block_on(self.editor.is_modified(&path));
+            self.editor_widget.update_text(text.to_string(), modified);
+        }
+    }
+
+    /// Apply edits typed into the widget to the active buffer
+    fn apply_typed_changes(&mut self) {
+        let changes = self.editor_widget.take_changes();
+        let Some(path) = self.editor_widget.path().cloned() else {
+            return;
+        };
+
+        for (range, text) in changes {
+            if let Err(e) = self.rt.block_on(self.editor.replace_chars(&path, range, &text)) {
+                self.status_message = format!("Edit error: {e}");
+                // Show the buffer as the editor has it rather than the rejected edit
+                self.refresh_from_editor();
+                return;
+            }
+        }
+
+        let (line, column) = self.editor_widget.cursor();
+        if self.rt.block_on(self.editor.get_cursor_position()) != (line, column) {
+            if let Err(e) = self.rt.block_on(self.editor.move_cursor(line, column)) {
+                self.status_message = format!("Cursor error: {e}");
             }
-            Err(e) => self.status_message = format!("Read error: {}", e),
         }
     }
 
-    fn save_file(&mut self) {
-        let content = self.editor_widget.get_buffer().content.clone();
-        if let Err(e) = self.rt.block_on(self.editor.insert_text(&content)) {
-            self.status_message = format!("Update error: {}", e);
+    fn undo(&mut self) {
+        self.apply_typed_changes();
+        match self.rt.block_on(self.editor.undo()) {
+            Ok(true) => self.refresh_from_editor(),
+            Ok(false) => self.status_message = "Nothing to undo".into(),
+            Err(e) => self.status_message = format!("Undo error: {e}"),
+        }
+    }
+
+    fn redo(&mut self) {
+        self.apply_typed_changes();
+        match self.rt.block_on(self.editor.redo()) {
+            Ok(true) => self.refresh_from_editor(),
+            Ok(false) => self.status_message = "Nothing to redo".into(),
+            Err(e) => self.status_message = format!("Redo error: {e}"),
+        }
+    }
+
+    fn new_file(&mut self) {
+        self.apply_typed_changes();
+        let path = self.rt.block_on(self.editor.new_file());
+        self.editor_widget.set_document(Some(path), String::new(), false);
+        self.vim.reset();
+        self.status_message = "New file".into();
+    }
+
+    pub fn open_file(&mut self, path: PathBuf) {
+        // Files already open keep their unsaved changes and history
+        let result = if self.rt.block_on(self.editor.get_open_files()).contains(&path) {
+            self.rt.block_on(self.editor.set_active_buffer(&path))
+        } else {
+            self.rt.block_on(self.editor.open_file(&path))
+        };
+        if let Err(e) = result {
+            self.status_message = format!("Open error: {e}");
             return;
         }
+
+        let text = self.rt.block_on(self.editor.snapshot(&path)).unwrap_or_default();
+        let modified = self.rt.block_on(self.editor.is_modified(&path));
+        self.rt.block_on(self.project_manager.record_recent_file(&path));
+        self.status_message = format!("Opened: {}", path.display());
+        self.editor_widget.set_document(Some(path), text.to_string(), modified);
+        self.vim.reset();
+    }
+
+    /// Open the fuzzy finder over the active project, or the current directory
+    fn go_to_file(&mut self) {
+        let (root, recent) = match self.rt.block_on(self.project_manager.active_project()) {
+            Some(project) => (project.root_path, project.recent_files),
+            None => {
+                (self.current_directory.clone(), self.rt.block_on(self.editor.get_open_files()))
+            }
+        };
+        let ignore_patterns = self.rt.block_on(self.config.read()).project.ignore_patterns.clone();
+
+        match self.rt.block_on(FileIndex::build(&root, &ignore_patterns)) {
+            Ok(index) => self.file_finder.open(index, recent),
+            Err(e) => self.status_message = format!("File index error: {e}"),
+        }
+    }
+
+    fn save_file(&mut self) {
+        self.apply_typed_changes();
         if let Err(e) = self.rt.block_on(self.editor.save_current()) {
-            self.status_message = format!("Save error: {}", e);
+            self.status_message = format!("Save error: {e}");
         } else {
+            self.refresh_from_editor();
             self.status_message = "Saved".into();
         }
     }
 
+    fn save_all(&mut self) {
+        self.apply_typed_changes();
+        if let Err(e) = self.rt.block_on(self.editor.save_all()) {
+            self.status_message = format!("Save error: {e}");
+        } else {
+            self.refresh_from_editor();
+            self.status_message = "Saved all".into();
+        }
+    }
+
     fn close_file(&mut self) {
-        let files = self.rt.block_on(self.editor.get_open_files());
-        if let Some(path) = files.first() {
-            if let Err(e) = self.rt.block_on(self.editor.close_file(path)) {
-                self.status_message = format!("Close error: {}", e);
-            } else {
-                self.editor_widget.set_buffer(super::FileBuffer::new());
-                self.status_message = "Closed".into();
-            }
+        let Some(path) = self.editor_widget.path().cloned() else {
+            return;
+        };
+        if let Err(e) = self.rt.block_on(self.editor.close_file(&path)) {
+            self.status_message = format!("Close error: {e}");
+        } else {
+            self.editor_widget.clear();
+            self.status_message = "Closed".into();
         }
     }
 
     fn update_status_from_buffer(&mut self) {
-        let buf = self.editor_widget.get_buffer();
+        let path = self.editor_widget.path().cloned();
+        let (line, column) = self.editor_widget.cursor();
+        let modified = self.editor_widget.is_modified();
         let mut info = StatusInfo::new();
-        info.update_from_buffer(&buf.content, buf.cursor_line, buf.cursor_column, buf.modified);
-        info.set_file_path(buf.path.clone());
-        self.status_bar.set_status_message(self.status_message.clone());
-        self.status_bar.set_current_file(buf.path.clone());
+        info.update_from_buffer(self.editor_widget.text(), line, column, modified);
+        info.set_file_path(path.clone());
+        let vim_status = if self.vim_enabled { self.vim.status() } else { String::new() };
+        if vim_status.is_empty() {
+            self.status_bar.set_status_message(self.status_message.clone());
+        } else {
+            self.status_bar.set_status_message(vim_status);
+        }
+        self.status_bar.set_current_file(path);
         self.status_bar.set_cursor_position(info.cursor_line, info.cursor_column);
-        self.status_bar.set_modified(buf.modified);
+        self.status_bar.set_modified(modified);
     }
 
     fn update_tools_data(&mut self) {
         let current_project = self.rt.block_on(self.project_manager.active_project());
-        if let Some(active_path) = self.editor_widget.get_buffer().path.clone() {
+        if let Some(active_path) = self.editor_widget.path().cloned() {
             let ext = active_path.extension().and_then(|e| e.to_str());
             match ext {
                 Some("rs") => self.tools_window.update_rust_tools_from_project(
@@ -176,7 +749,7 @@ impl XyluxIdeApp {
                     &active_path,
                     &self.rt.block_on(self.editor.get_open_files()),
                 ),
-                Some("alux") | Some("alx") => self.tools_window.update_alux_tools_from_project(
+                Some("alux" | "alx") => self.tools_window.update_alux_tools_from_project(
                     current_project.as_ref(),
                     &active_path,
                     &self.rt.block_on(self.editor.get_open_files()),
@@ -198,18 +771,18 @@ impl XyluxIdeApp {
         }
         egui::Window::new("Open File").collapsible(false).resizable(true).show(ctx, |ui| {
             ui.label("Select a file:");
-                    ui.separator();
+            ui.separator();
             egui::ScrollArea::vertical().show(ui, |ui| {
                 if let Ok(entries) = fs::read_dir(&self.current_directory) {
                     for entry in entries.flatten() {
                         let path = entry.path();
                         let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("?");
                         if path.is_dir() {
-                            if ui.button(format!("📁 {}", name)).clicked() {
+                            if ui.button(format!("📁 {name}")).clicked() {
                                 self.current_directory = path;
                                 self.file_tree.set_root_directory(self.current_directory.clone());
                             }
-                        } else if ui.button(format!("📄 {}", name)).clicked() {
+                        } else if ui.button(format!("📄 {name}")).clicked() {
                             self.open_file(path);
                             self.file_dialog_open = false;
                         }
@@ -230,60 +803,166 @@ impl XyluxIdeApp {
             });
         });
     }
+
+    fn draw_rename_dialog(&mut self, ctx: &egui::Context) {
+        if !self.rename_dialog_open {
+            return;
+        }
+        egui::Window::new("Rename Symbol").collapsible(false).resizable(false).show(ctx, |ui| {
+            ui.label("New name:");
+            let response = ui.text_edit_singleline(&mut self.rename_input);
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            ui.horizontal(|ui| {
+                if (ui.button("Rename").clicked() || submitted) && !self.rename_input.is_empty() {
+                    let new_name = std::mem::take(&mut self.rename_input);
+                    self.rename_dialog_open = false;
+                    self.rename_symbol(&new_name);
+                }
+                if ui.button("Cancel").clicked() {
+                    self.rename_dialog_open = false;
+                }
+            });
+        });
+    }
+
+    fn draw_references(&mut self, ctx: &egui::Context) {
+        if !self.references_open {
+            return;
+        }
+        let mut selected = None;
+        egui::Window::new("References").collapsible(true).resizable(true).show(ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for location in &self.references {
+                    let label = format!(
+                        "{}:{}:{}",
+                        location.path(),
+                        location.range.start.line + 1,
+                        location.range.start.character + 1
+                    );
+                    if ui.button(label).clicked() {
+                        selected = Some(location.clone());
+                    }
+                }
+            });
+            ui.separator();
+            if ui.button("Close").clicked() {
+                self.references_open = false;
+            }
+        });
+        if let Some(location) = selected {
+            self.jump_to(&location);
+        }
+    }
+
+    fn draw_shortcuts(&mut self, ctx: &egui::Context) {
+        if !self.shortcuts_open {
+            return;
+        }
+        let mut open = true;
+        egui::Window::new("Keyboard Shortcuts").open(&mut open).resizable(true).show(ctx, |ui| {
+            egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                egui::Grid::new("shortcuts").striped(true).num_columns(3).show(ui, |ui| {
+                    for binding in self.keymap.bindings() {
+                        let title = self
+                            .commands
+                            .get(&binding.command)
+                            .map_or(binding.command.as_str(), |command| command.title());
+                        ui.monospace(binding.chord.to_string());
+                        ui.label(title);
+                        ui.weak(binding.when.to_string());
+                        ui.end_row();
+                    }
+                });
+            });
+
+            let conflicts = self.keymap.conflicts();
+            if !conflicts.is_empty() {
+                ui.separator();
+                for conflict in conflicts {
+                    ui.colored_label(egui::Color32::from_rgb(230, 120, 90), conflict.to_string());
+                }
+            }
+            ui.separator();
+            for path in Keymap::layer_paths(Some(&self.current_directory)) {
+                ui.weak(path.display().to_string());
+            }
+        });
+        self.shortcuts_open = open;
+    }
 }
 
 impl eframe::App for XyluxIdeApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        if ctx.input(|i| i.key_pressed(egui::Key::Q) && i.modifiers.ctrl) {
-            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
-        }
+        self.poll_events(ctx);
+        self.dispatch_keys(ctx);
+        self.dispatch_vim_keys(ctx);
+        let pressed_at =
+            ctx.input(|i| i.pointer.interact_pos().filter(|_| i.pointer.any_pressed()));
 
         egui::TopBottomPanel::top("menu")
             .frame(egui::Frame::none().fill(egui::Color32::from_rgb(50, 50, 60)))
             .show(ctx, |ui| {
-            let action = self.menu.show(ui);
-            self.handle_menu_action(ctx, action);
-        });
+                let action = self.menu.show(ui);
+                self.handle_menu_action(ctx, &action);
+            });
 
         if self.show_file_explorer {
-            egui::SidePanel::left("explorer")
+            let panel = egui::SidePanel::left("explorer")
                 .resizable(true)
                 .default_width(240.0)
-                .frame(egui::Frame::side_top_panel(&ctx.style()).fill(egui::Color32::from_rgb(40, 40, 48)))
-                .show(
-                ctx,
-                |ui| {
+                .frame(
+                    egui::Frame::side_top_panel(&ctx.style())
+                        .fill(egui::Color32::from_rgb(40, 40, 48)),
+                )
+                .show(ctx, |ui| {
                     if let Some(path) = self.file_tree.show(ui) {
-                                        self.open_file(path);
+                        self.open_file(path);
                     }
-                },
-            );
+                });
+            if let Some(pos) = pressed_at {
+                self.file_tree_focused = panel.response.rect.contains(pos);
+            }
+        } else {
+            self.file_tree_focused = false;
         }
 
+        let mut editor_focused = false;
         egui::CentralPanel::default().show(ctx, |ui| {
-            // Update syntax highlight tokens for Rust/TOML/JSON/MD using current buffer
-            if let Some(path) = self.editor_widget.get_buffer().path.clone() {
-                let content = self.editor_widget.get_buffer().content.clone();
-                if let Ok(tokens) = self
-                    .rt
-                    .block_on(self.syntax_manager.highlight_file(&path, &content))
-                {
-                    self.editor_widget.set_highlight_tokens(tokens);
+            // Highlight the current buffer again after it changed
+            if let Some(path) = self.editor_widget.path().cloned() {
+                if self.editor_widget.needs_highlight() {
+                    let content = self.editor_widget.text();
+                    let tokens =
+                        self.rt.block_on(self.syntax_manager.highlight_file(&path, content));
+                    self.editor_widget.set_highlight_tokens(tokens.unwrap_or_default());
                 }
             }
 
             let resp = self.editor_widget.show(ui);
+            editor_focused = resp.has_focus();
+            self.apply_typed_changes();
             if resp.changed() {
                 self.status_message = "Modified".into();
             }
         });
 
+        self.focus = if editor_focused {
+            KeyContext::EditorFocus
+        } else if self.file_tree_focused {
+            KeyContext::FileTreeFocus
+        } else {
+            KeyContext::Global
+        };
+
+        let action = self.editor_widget.take_action();
+        self.handle_menu_action(ctx, &action);
+
         egui::TopBottomPanel::bottom("status")
             .frame(egui::Frame::none().fill(egui::Color32::from_rgb(45, 45, 55)))
             .show(ctx, |ui| {
-            self.update_status_from_buffer();
-            self.status_bar.show(ui);
-        });
+                self.update_status_from_buffer();
+                self.status_bar.show(ui);
+            });
 
         // Dialogs and tools
         if self.about_dialog_open {
@@ -292,27 +971,52 @@ impl eframe::App for XyluxIdeApp {
                 |ui| {
                     ui.label("Xylux IDE");
                     ui.label(format!("Version: {}", env!("CARGO_PKG_VERSION")));
-                        if ui.button("Close").clicked() {
-                            self.about_dialog_open = false;
-                        }
+                    if ui.button("Close").clicked() {
+                        self.about_dialog_open = false;
+                    }
                 },
             );
         }
         self.draw_file_dialog(ctx);
-        self.update_tools_data();
-        self.tools_window.show(ctx);
-
-        // Shortcuts
-        if ctx.input(|i| i.key_pressed(egui::Key::S) && i.modifiers.ctrl) {
-            self.save_file();
-        }
-        if ctx.input(|i| i.key_pressed(egui::Key::O) && i.modifiers.ctrl) {
-            self.file_dialog_open = true;
+        if let Some(path) = self.file_finder.show(ctx) {
+            self.open_file(path);
         }
-        if ctx.input(|i| i.key_pressed(egui::Key::N) && i.modifiers.ctrl) {
-            self.new_file();
+        if let Some(id) = self.palette.show(ctx, &self.commands, &self.keymap) {
+            self.execute_command(ctx, id);
         }
+        self.draw_rename_dialog(ctx);
+        self.draw_references(ctx);
+        self.draw_shortcuts(ctx);
+        self.update_tools_data();
+        self.tools_window.show(ctx);
 
         ctx.request_repaint_after(std::time::Duration::from_millis(250));
     }
 }
+
+/// Keys that do not type text, with their Vim names
+const NAMED_KEYS: [(egui::Key, VimKey); 8] = [
+    (egui::Key::Escape, VimKey::Escape),
+    (egui::Key::Enter, VimKey::Enter),
+    (egui::Key::Backspace, VimKey::Backspace),
+    (egui::Key::Tab, VimKey::Tab),
+    (egui::Key::ArrowLeft, VimKey::Left),
+    (egui::Key::ArrowRight, VimKey::Right),
+    (egui::Key::ArrowUp, VimKey::Up),
+    (egui::Key::ArrowDown, VimKey::Down),
+];
+
+/// The Vim key for a key press, if the press does not also type text
+fn vim_key(key: egui::Key, modifiers: egui::Modifiers) -> Option<VimKey> {
+    let name = key.name();
+    if modifiers.command || modifiers.ctrl {
+        let mut chars = name.chars();
+        return match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii_alphabetic() => {
+                Some(VimKey::Ctrl(c.to_ascii_lowercase()))
+            }
+            _ => None,
+        };
+    }
+    NAMED_KEYS.iter().find(|(named, _)| *named == key).map(|&(_, vim_key)| vim_key)
+}
@@ -0,0 +1,214 @@
+//! # Commands Module
+//!
+//! Registry of named IDE commands. The menu bar, keyboard shortcuts and the
+//! command palette all look commands up here, so each action has one handler.
+//! Shortcuts are bound to command identifiers by the [`Keymap`].
+//!
+//! [`Keymap`]: crate::core::Keymap
+
+use std::cmp::Reverse;
+
+use super::menu::MenuAction;
+use crate::core::KeyStroke;
+use crate::project::fuzzy_match;
+
+/// Function run when a command is executed on the application `A`
+pub type CommandHandler<A> = fn(&mut A, &egui::Context);
+
+/// A named command the user can run
+pub struct IdeCommand<A> {
+    /// Stable identifier, such as `file.save`
+    id: &'static str,
+    /// Title shown in the command palette
+    title: &'static str,
+    /// Menu entry that runs the command
+    menu_action: Option<MenuAction>,
+    /// Function run by the command
+    handler: CommandHandler<A>,
+}
+
+impl<A> IdeCommand<A> {
+    /// Create a command without a menu entry
+    pub fn new(id: &'static str, title: &'static str, handler: CommandHandler<A>) -> Self {
+        Self { id, title, menu_action: None, handler }
+    }
+
+    /// Run the command from a menu entry
+    #[must_use]
+    pub const fn with_menu_action(mut self, action: MenuAction) -> Self {
+        self.menu_action = Some(action);
+        self
+    }
+
+    /// Get the command identifier
+    #[must_use]
+    pub const fn id(&self) -> &'static str {
+        self.id
+    }
+
+    /// Get the command title
+    #[must_use]
+    pub const fn title(&self) -> &'static str {
+        self.title
+    }
+
+    /// Get the function run by the command
+    #[must_use]
+    pub fn handler(&self) -> CommandHandler<A> {
+        self.handler
+    }
+}
+
+/// A command matching a palette query
+pub struct CommandMatch<'a, A> {
+    /// The command
+    pub command: &'a IdeCommand<A>,
+    /// Match score; higher is better
+    pub score: i32,
+    /// Character positions in the title matched by the query
+    pub positions: Vec<usize>,
+}
+
+/// The commands available in the IDE
+pub struct CommandRegistry<A> {
+    /// Commands in registration order
+    commands: Vec<IdeCommand<A>>,
+}
+
+impl<A> CommandRegistry<A> {
+    /// Create an empty registry
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { commands: Vec::new() }
+    }
+
+    /// Add a command, replacing any with the same identifier
+    pub fn register(&mut self, command: IdeCommand<A>) {
+        match self.commands.iter_mut().find(|existing| existing.id == command.id) {
+            Some(existing) => *existing = command,
+            None => self.commands.push(command),
+        }
+    }
+
+    /// Get a command by identifier
+    #[must_use]
+    pub fn get(&self, id: &str) -> Option<&IdeCommand<A>> {
+        self.commands.iter().find(|command| command.id == id)
+    }
+
+    /// Get all commands in registration order
+    #[must_use]
+    pub fn commands(&self) -> &[IdeCommand<A>] {
+        &self.commands
+    }
+
+    /// Get the command run by a menu entry
+    #[must_use]
+    pub fn for_menu_action(&self, action: &MenuAction) -> Option<&IdeCommand<A>> {
+        self.commands.iter().find(|command| command.menu_action.as_ref() == Some(action))
+    }
+
+    /// Find the commands whose title fuzzy-matches `query`, best first
+    ///
+    /// An empty query lists every command in registration order.
+    #[must_use]
+    pub fn search(&self, query: &str) -> Vec<CommandMatch<'_, A>> {
+        let mut matches: Vec<CommandMatch<'_, A>> = self
+            .commands
+            .iter()
+            .filter_map(|command| {
+                let (score, positions) = fuzzy_match(query, command.title)?;
+                Some(CommandMatch { command, score, positions })
+            })
+            .collect();
+        matches.sort_by_key(|found| Reverse(found.score));
+        matches
+    }
+}
+
+impl<A> Default for CommandRegistry<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The keystroke of a key press event, if it is one
+#[must_use]
+pub fn key_stroke(event: &egui::Event) -> Option<KeyStroke> {
+    let egui::Event::Key { key, pressed: true, modifiers, .. } = event else {
+        return None;
+    };
+    Some(KeyStroke {
+        ctrl: modifiers.command || modifiers.ctrl,
+        alt: modifiers.alt,
+        shift: modifiers.shift,
+        key: key.name().to_owned(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Counter {
+        undos: usize,
+        redos: usize,
+    }
+
+    fn registry() -> CommandRegistry<Counter> {
+        let mut registry = CommandRegistry::new();
+        registry.register(
+            IdeCommand::new("edit.undo", "Edit: Undo", |counter: &mut Counter, _| {
+                counter.undos += 1;
+            })
+            .with_menu_action(MenuAction::Undo),
+        );
+        registry.register(
+            IdeCommand::new("edit.redo", "Edit: Redo", |counter: &mut Counter, _| {
+                counter.redos += 1;
+            })
+            .with_menu_action(MenuAction::Redo),
+        );
+        registry
+    }
+
+    #[test]
+    fn test_dispatch_by_id_and_menu() {
+        let registry = registry();
+        let ctx = egui::Context::default();
+        let mut counter = Counter::default();
+
+        (registry.get("edit.undo").unwrap().handler())(&mut counter, &ctx);
+        (registry.for_menu_action(&MenuAction::Redo).unwrap().handler())(&mut counter, &ctx);
+        assert_eq!((counter.undos, counter.redos), (1, 1));
+        assert!(registry.get("edit.paste").is_none());
+        assert!(registry.for_menu_action(&MenuAction::Paste).is_none());
+
+        let event = egui::Event::Key {
+            key: egui::Key::Slash,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers: egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+        };
+        assert_eq!(key_stroke(&event), Some(KeyStroke::parse("Ctrl+Shift+/").unwrap()));
+        assert_eq!(key_stroke(&egui::Event::Text("/".into())), None);
+    }
+
+    #[test]
+    fn test_search_and_replace_registration() {
+        let mut registry = registry();
+        let titles = |registry: &CommandRegistry<Counter>, query: &str| -> Vec<&str> {
+            registry.search(query).iter().map(|found| found.command.title()).collect()
+        };
+
+        assert_eq!(titles(&registry, ""), vec!["Edit: Undo", "Edit: Redo"]);
+        assert_eq!(titles(&registry, "redo"), vec!["Edit: Redo"]);
+        assert_eq!(registry.search("eun")[0].positions, vec![0, 6, 7]);
+
+        registry.register(IdeCommand::new("edit.undo", "Edit: Undo Last Change", |_, _| {}));
+        assert_eq!(registry.commands().len(), 2);
+        assert_eq!(registry.get("edit.undo").unwrap().title(), "Edit: Undo Last Change");
+    }
+}
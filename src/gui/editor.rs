@@ -2,52 +2,209 @@
 //!
 //! Text editor component for the GUI interface
 
-use super::FileBuffer;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use super::menu::{ContextMenuWidget, MenuAction};
 use crate::syntax::{HighlightToken, TokenType};
 
+/// Text shown by the editor widget.
+///
+/// Edits typed into the widget show up immediately and are queued as
+/// character range replacements for the [`crate::editor::Editor`], which owns
+/// the buffer, its undo history and change events.
+#[derive(Debug, Default)]
+pub struct EditorText {
+    /// Displayed text, with `\n` line endings
+    text: String,
+    /// Replacements not yet applied to the editor, in the order they were made
+    changes: Vec<(Range<usize>, String)>,
+    /// Number of times the text changed, to tell when highlighting is stale
+    version: u64,
+}
+
+impl EditorText {
+    /// Take the replacements made since the last call
+    pub fn take_changes(&mut self) -> Vec<(Range<usize>, String)> {
+        std::mem::take(&mut self.changes)
+    }
+}
+
+impl egui::TextBuffer for EditorText {
+    fn is_mutable(&self) -> bool {
+        true
+    }
+
+    fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    fn insert_text(&mut self, text: &str, char_index: usize) -> usize {
+        let inserted = egui::TextBuffer::insert_text(&mut self.text, text, char_index);
+        self.version += 1;
+        let text: String = text.chars().take(inserted).collect();
+
+        // Typing over a selection deletes it first; record both as one replacement.
+        match self.changes.last_mut() {
+            Some((range, replacement))
+                if range.start + replacement.chars().count() == char_index =>
+            {
+                replacement.push_str(&text);
+            }
+            _ => self.changes.push((char_index..char_index, text)),
+        }
+        inserted
+    }
+
+    fn delete_char_range(&mut self, char_range: Range<usize>) {
+        if char_range.is_empty() {
+            return;
+        }
+        egui::TextBuffer::delete_char_range(&mut self.text, char_range.clone());
+        self.version += 1;
+        self.changes.push((char_range, String::new()));
+    }
+
+    fn clear(&mut self) {
+        self.delete_char_range(0..self.text.chars().count());
+    }
+}
+
 /// Text editor widget for the GUI
 pub struct EditorWidget {
-    /// Current file buffer
-    buffer: FileBuffer,
+    /// File shown in the editor, if any
+    path: Option<PathBuf>,
+    /// Text of the active buffer
+    text: EditorText,
+    /// Whether the buffer has unsaved changes
+    modified: bool,
+    /// Cursor line
+    cursor_line: usize,
+    /// Cursor byte column
+    cursor_column: usize,
     /// Highlight tokens for current content
     highlight_tokens: Vec<HighlightToken>,
+    /// Text version the highlight tokens were computed for
+    highlighted_version: Option<u64>,
+    /// Cursor position to move to on the next render
+    pending_cursor: Option<(usize, usize)>,
+    /// Action picked from the context menu since the last call to `take_action`
+    context_action: MenuAction,
 }
 
 impl EditorWidget {
     /// Create a new editor widget
+    #[must_use]
     pub fn new() -> Self {
-        Self { buffer: FileBuffer::new(), highlight_tokens: Vec::new() }
+        Self {
+            path: None,
+            text: EditorText::default(),
+            modified: false,
+            cursor_line: 0,
+            cursor_column: 0,
+            highlight_tokens: Vec::new(),
+            highlighted_version: None,
+            pending_cursor: None,
+            context_action: MenuAction::None,
+        }
+    }
+
+    /// Show a buffer, moving the cursor to its start
+    pub fn set_document(&mut self, path: Option<PathBuf>, text: String, modified: bool) {
+        self.path = path;
+        self.text = EditorText { text, ..EditorText::default() };
+        self.modified = modified;
+        self.highlight_tokens.clear();
+        self.highlighted_version = None;
+        self.set_cursor(0, 0);
     }
 
-    /// Set the current buffer
-    pub fn set_buffer(&mut self, buffer: FileBuffer) {
-        self.buffer = buffer;
+    /// Replace the shown text after the buffer changed outside the widget,
+    /// keeping the cursor where it was
+    pub fn update_text(&mut self, text: String, modified: bool) {
+        self.modified = modified;
+        if self.text.text != text {
+            let version = self.text.version + 1;
+            self.text = EditorText { text, changes: Vec::new(), version };
+            self.pending_cursor = Some((self.cursor_line, self.cursor_column));
+        }
     }
 
-    /// Get the current buffer
-    pub fn get_buffer(&self) -> &FileBuffer {
-        &self.buffer
+    /// Stop showing any buffer
+    pub fn clear(&mut self) {
+        self.set_document(None, String::new(), false);
     }
 
-    /// Get the current buffer mutably
-    pub fn get_buffer_mut(&mut self) -> &mut FileBuffer {
-        &mut self.buffer
+    /// Path of the shown buffer
+    #[must_use]
+    pub const fn path(&self) -> Option<&PathBuf> {
+        self.path.as_ref()
+    }
+
+    /// Text of the shown buffer
+    #[must_use]
+    pub fn text(&self) -> &str {
+        &self.text.text
+    }
+
+    /// Whether the shown buffer has unsaved changes
+    #[must_use]
+    pub const fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    /// Cursor line and byte column
+    #[must_use]
+    pub const fn cursor(&self) -> (usize, usize) {
+        (self.cursor_line, self.cursor_column)
+    }
+
+    /// Take the edits typed since the last call, as character range replacements
+    pub fn take_changes(&mut self) -> Vec<(Range<usize>, String)> {
+        self.text.take_changes()
+    }
+
+    /// Move the cursor to a line and byte column on the next render
+    pub fn set_cursor(&mut self, line: usize, column: usize) {
+        self.cursor_line = line;
+        self.cursor_column = column;
+        self.pending_cursor = Some((line, column));
+    }
+
+    /// Take the action picked from the context menu, if any
+    pub fn take_action(&mut self) -> MenuAction {
+        std::mem::replace(&mut self.context_action, MenuAction::None)
+    }
+
+    /// Whether the text changed since highlight tokens were last set
+    #[must_use]
+    pub fn needs_highlight(&self) -> bool {
+        self.highlighted_version != Some(self.text.version)
     }
 
     /// Update highlight tokens to be used on next render
     pub fn set_highlight_tokens(&mut self, tokens: Vec<HighlightToken>) {
         self.highlight_tokens = tokens;
+        self.highlighted_version = Some(self.text.version);
     }
 
-    fn color_for_token(token_type: &TokenType) -> egui::Color32 {
+    const fn color_for_token(token_type: &TokenType) -> egui::Color32 {
         match token_type {
-            TokenType::Keyword => egui::Color32::from_rgb(86, 156, 214),
+            TokenType::Keyword | TokenType::Constant | TokenType::Heading => {
+                egui::Color32::from_rgb(86, 156, 214)
+            }
             TokenType::String => egui::Color32::from_rgb(206, 145, 120),
             TokenType::Number => egui::Color32::from_rgb(181, 206, 168),
             TokenType::Comment => egui::Color32::from_rgb(106, 153, 85),
             TokenType::Function => egui::Color32::from_rgb(220, 220, 170),
-            TokenType::Variable => egui::Color32::from_rgb(156, 220, 254),
+            TokenType::Variable | TokenType::Property => egui::Color32::from_rgb(156, 220, 254),
             TokenType::Type => egui::Color32::from_rgb(78, 201, 176),
+            TokenType::Macro => egui::Color32::from_rgb(79, 193, 255),
+            TokenType::Lifetime | TokenType::Emphasis => egui::Color32::from_rgb(197, 134, 192),
+            TokenType::Attribute | TokenType::Escape => egui::Color32::from_rgb(215, 186, 125),
+            TokenType::DocComment => egui::Color32::from_rgb(96, 139, 78),
+            TokenType::Date => egui::Color32::from_rgb(184, 215, 163),
+            TokenType::Link => egui::Color32::from_rgb(55, 148, 255),
             TokenType::Operator | TokenType::Punctuation | TokenType::Text => {
                 egui::Color32::from_rgb(212, 212, 212)
             }
@@ -62,7 +219,7 @@ impl EditorWidget {
             .id_source("editor_scroll")
             .show(ui, |ui| {
                 // Text editor
-                let mut text_edit = egui::TextEdit::multiline(&mut self.buffer.content)
+                let mut text_edit = egui::TextEdit::multiline(&mut self.text)
                     .font(egui::TextStyle::Monospace)
                     .desired_width(f32::INFINITY)
                     .desired_rows(30)
@@ -76,21 +233,22 @@ impl EditorWidget {
                     job.wrap.max_width = wrap_width;
 
                     // Fallback color
+                    let font_id = egui::TextStyle::Monospace.resolve(ui.style());
                     let default = TextFormat {
-                        font_id: egui::FontId::monospace(14.0),
+                        font_id: font_id.clone(),
                         color: egui::Color32::from_rgb(212, 212, 212),
                         ..Default::default()
                     };
 
                     let mut cursor = 0usize;
-                    for t in tokens.iter() {
+                    for t in &tokens {
                         let start = t.start.min(text.len());
                         let end = t.end.min(text.len());
                         if cursor < start {
                             job.append(&text[cursor..start], 0.0, default.clone());
                         }
                         let format = TextFormat {
-                            font_id: egui::FontId::monospace(14.0),
+                            font_id: font_id.clone(),
                             color: Self::color_for_token(&t.token_type),
                             ..Default::default()
                         };
@@ -110,15 +268,59 @@ impl EditorWidget {
                 let response = ui.add_sized(available_rect.size(), text_edit);
 
                 if response.changed() {
-                    self.buffer.modified = true;
+                    self.modified = true;
+                }
+
+                if let Some(mut state) = egui::TextEdit::load_state(ui.ctx(), response.id) {
+                    if let Some((line, column)) = self.pending_cursor.take() {
+                        let index = char_index(&self.text.text, line, column);
+                        let cursor = egui::text::CCursor::new(index);
+                        state.cursor.set_char_range(Some(egui::text::CCursorRange::one(cursor)));
+                        state.store(ui.ctx(), response.id);
+                    } else if let Some(range) = state.cursor.char_range() {
+                        let (line, column) = line_column(&self.text.text, range.primary.index);
+                        self.cursor_line = line;
+                        self.cursor_column = column;
+                    }
                 }
 
+                response.context_menu(|ui| {
+                    let action = ContextMenuWidget::editor_context_menu_items(ui);
+                    if action != MenuAction::None {
+                        self.context_action = action;
+                    }
+                });
+
                 response
             })
             .inner
     }
 }
 
+/// Convert a character index into a line and byte column
+fn line_column(content: &str, char_index: usize) -> (usize, usize) {
+    let offset = content.char_indices().nth(char_index).map_or(content.len(), |(offset, _)| offset);
+    let before = &content[..offset];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    (before.matches('\n').count(), offset - line_start)
+}
+
+/// Convert a line and byte column into a character index, clamped to the content
+fn char_index(content: &str, line: usize, column: usize) -> usize {
+    let mut index = 0;
+    for (line_index, text) in content.split('\n').enumerate() {
+        if line_index == line {
+            let column = (0..=column.min(text.len()))
+                .rev()
+                .find(|&column| text.is_char_boundary(column))
+                .unwrap_or(0);
+            return index + text[..column].chars().count();
+        }
+        index += text.chars().count() + 1;
+    }
+    content.chars().count()
+}
+
 impl Default for EditorWidget {
     fn default() -> Self {
         Self::new()
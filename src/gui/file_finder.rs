@@ -0,0 +1,159 @@
+//! # File Finder Module
+//!
+//! "Go to File" popup that fuzzy-matches the files of the project
+
+use std::path::PathBuf;
+
+use egui::text::{LayoutJob, TextFormat};
+
+use crate::project::{FileIndex, FileMatch};
+
+/// Number of matches listed at once
+const MAX_RESULTS: usize = 50;
+
+/// Fuzzy file finder popup
+#[derive(Default)]
+pub struct FileFinderWidget {
+    /// Whether the popup is shown
+    open: bool,
+    /// Query typed so far
+    query: String,
+    /// Files of the project
+    index: Option<FileIndex>,
+    /// Recently opened files, most recent first
+    recent: Vec<PathBuf>,
+    /// Matches for the current query, best first
+    matches: Vec<FileMatch>,
+    /// Highlighted match
+    selected: usize,
+}
+
+impl FileFinderWidget {
+    /// Create a new, closed file finder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Show the finder over `index`, ranking `recent` files first
+    pub fn open(&mut self, index: FileIndex, recent: Vec<PathBuf>) {
+        self.index = Some(index);
+        self.recent = recent;
+        self.query.clear();
+        self.open = true;
+        self.refresh();
+    }
+
+    /// Hide the finder
+    pub fn close(&mut self) {
+        self.open = false;
+        self.index = None;
+        self.matches.clear();
+    }
+
+    /// Check whether the finder is shown
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Match the query against the index again
+    fn refresh(&mut self) {
+        self.matches = self
+            .index
+            .as_ref()
+            .map(|index| index.find(&self.query, &self.recent, MAX_RESULTS))
+            .unwrap_or_default();
+        self.selected = 0;
+    }
+
+    /// Draw the finder, returning the file picked
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<PathBuf> {
+        if !self.open {
+            return None;
+        }
+
+        let mut picked = None;
+        let (up, down, enter, escape) = ctx.input(|i| {
+            (
+                i.key_pressed(egui::Key::ArrowUp),
+                i.key_pressed(egui::Key::ArrowDown),
+                i.key_pressed(egui::Key::Enter),
+                i.key_pressed(egui::Key::Escape),
+            )
+        });
+        if down && self.selected + 1 < self.matches.len() {
+            self.selected += 1;
+        }
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+
+        egui::Window::new("Go to File")
+            .collapsible(false)
+            .resizable(true)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 60.0))
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.query)
+                        .hint_text("File name")
+                        .desired_width(f32::INFINITY),
+                );
+                response.request_focus();
+                if response.changed() {
+                    self.refresh();
+                }
+
+                let total = self.index.as_ref().map_or(0, FileIndex::len);
+                ui.label(format!("{} of {} files", self.matches.len(), total));
+                ui.separator();
+
+                egui::ScrollArea::vertical().max_height(360.0).show(ui, |ui| {
+                    for (i, found) in self.matches.iter().enumerate() {
+                        let selected = i == self.selected;
+                        let text = highlighted(&found.relative, &found.positions);
+                        let label = ui.selectable_label(selected, text);
+                        if selected && (up || down) {
+                            label.scroll_to_me(None);
+                        }
+                        if label.clicked() {
+                            picked = Some(found.path.clone());
+                        }
+                    }
+                });
+            });
+
+        if enter {
+            picked = picked.or_else(|| self.matches.get(self.selected).map(|m| m.path.clone()));
+        }
+        if picked.is_some() || escape {
+            self.close();
+        }
+        picked
+    }
+}
+
+/// Lay out fuzzy-matched text with the characters at `positions` emphasized
+pub(crate) fn highlighted(text: &str, positions: &[usize]) -> LayoutJob {
+    let plain = TextFormat {
+        font_id: egui::FontId::proportional(14.0),
+        color: egui::Color32::from_rgb(200, 200, 200),
+        ..Default::default()
+    };
+    let matched = TextFormat { color: egui::Color32::from_rgb(255, 200, 80), ..plain.clone() };
+
+    let mut job = LayoutJob::default();
+    let mut positions = positions.iter().peekable();
+    let mut run = String::new();
+    let mut run_matched = false;
+    for (i, c) in text.chars().enumerate() {
+        let is_matched = positions.next_if_eq(&&i).is_some();
+        if is_matched != run_matched && !run.is_empty() {
+            let format = if run_matched { &matched } else { &plain };
+            job.append(&std::mem::take(&mut run), 0.0, format.clone());
+        }
+        run_matched = is_matched;
+        run.push(c);
+    }
+    job.append(&run, 0.0, if run_matched { matched } else { plain });
+    job
+}
@@ -25,6 +25,10 @@ impl MenuBarWidget {
                     action = MenuAction::OpenFile;
                     ui.close_menu();
                 }
+                if ui.button("Go to File...").clicked() {
+                    action = MenuAction::GoToFile;
+                    ui.close_menu();
+                }
                 if ui.button("Open Folder...").clicked() {
                     action = MenuAction::OpenFolder;
                     ui.close_menu();
@@ -206,6 +210,7 @@ pub enum MenuAction {
     // File menu actions
     NewFile,
     OpenFile,
+    GoToFile,
     OpenFolder,
     Save,
     SaveAs,
@@ -240,6 +245,10 @@ pub enum MenuAction {
     Settings,
     FormatDocument,
     GoToLine,
+    // Editor context menu actions
+    GoToDefinition,
+    FindReferences,
+    RenameSymbol,
     // Help menu actions
     ShowDocumentation,
     ShowShortcuts,
@@ -257,38 +266,60 @@ impl ContextMenuWidget {
 
         ui.allocate_ui_at_rect(egui::Rect::from_min_size(pos, egui::Vec2::splat(1.0)), |ui| {
             egui::menu::menu_button(ui, "Context", |ui| {
-                if ui.button("Cut").clicked() {
-                    action = MenuAction::Cut;
-                    ui.close_menu();
-                }
-                if ui.button("Copy").clicked() {
-                    action = MenuAction::Copy;
-                    ui.close_menu();
-                }
-                if ui.button("Paste").clicked() {
-                    action = MenuAction::Paste;
-                    ui.close_menu();
-                }
-                ui.separator();
-                if ui.button("Select All").clicked() {
-                    action = MenuAction::SelectAll;
-                    ui.close_menu();
-                }
-                ui.separator();
-                if ui.button("Go to Line...").clicked() {
-                    action = MenuAction::GoToLine;
-                    ui.close_menu();
-                }
-                if ui.button("Format Document").clicked() {
-                    action = MenuAction::FormatDocument;
-                    ui.close_menu();
-                }
+                action = Self::editor_context_menu_items(ui);
             });
         });
 
         action
     }
 
+    /// Draw the entries of the editor context menu into an open menu
+    pub fn editor_context_menu_items(ui: &mut egui::Ui) -> MenuAction {
+        let mut action = MenuAction::None;
+
+        if ui.button("Go to Definition").clicked() {
+            action = MenuAction::GoToDefinition;
+            ui.close_menu();
+        }
+        if ui.button("Find References").clicked() {
+            action = MenuAction::FindReferences;
+            ui.close_menu();
+        }
+        if ui.button("Rename...").clicked() {
+            action = MenuAction::RenameSymbol;
+            ui.close_menu();
+        }
+        ui.separator();
+        if ui.button("Cut").clicked() {
+            action = MenuAction::Cut;
+            ui.close_menu();
+        }
+        if ui.button("Copy").clicked() {
+            action = MenuAction::Copy;
+            ui.close_menu();
+        }
+        if ui.button("Paste").clicked() {
+            action = MenuAction::Paste;
+            ui.close_menu();
+        }
+        ui.separator();
+        if ui.button("Select All").clicked() {
+            action = MenuAction::SelectAll;
+            ui.close_menu();
+        }
+        ui.separator();
+        if ui.button("Go to Line...").clicked() {
+            action = MenuAction::GoToLine;
+            ui.close_menu();
+        }
+        if ui.button("Format Document").clicked() {
+            action = MenuAction::FormatDocument;
+            ui.close_menu();
+        }
+
+        action
+    }
+
     /// Show a context menu for the file explorer
     pub fn show_file_context_menu(ui: &mut egui::Ui, pos: egui::Pos2) -> MenuAction {
         let mut action = MenuAction::None;
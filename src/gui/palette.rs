@@ -0,0 +1,123 @@
+//! # Command Palette Module
+//!
+//! Popup listing the registered commands, filtered by a fuzzy query
+
+use super::commands::CommandRegistry;
+use super::file_finder::highlighted;
+use crate::core::Keymap;
+
+/// Command palette popup
+#[derive(Default)]
+pub struct CommandPaletteWidget {
+    /// Whether the popup is shown
+    open: bool,
+    /// Query typed so far
+    query: String,
+    /// Highlighted match
+    selected: usize,
+}
+
+impl CommandPaletteWidget {
+    /// Create a new, closed command palette
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Show the palette with an empty query
+    pub fn open(&mut self) {
+        self.query.clear();
+        self.selected = 0;
+        self.open = true;
+    }
+
+    /// Hide the palette
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+
+    /// Check whether the palette is shown
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Draw the palette, returning the identifier of the command picked
+    pub fn show<A>(
+        &mut self,
+        ctx: &egui::Context,
+        registry: &CommandRegistry<A>,
+        keymap: &Keymap,
+    ) -> Option<&'static str> {
+        if !self.open {
+            return None;
+        }
+
+        let (up, down, enter, escape) = ctx.input(|i| {
+            (
+                i.key_pressed(egui::Key::ArrowUp),
+                i.key_pressed(egui::Key::ArrowDown),
+                i.key_pressed(egui::Key::Enter),
+                i.key_pressed(egui::Key::Escape),
+            )
+        });
+
+        let mut matches = registry.search(&self.query);
+        let mut picked = None;
+        if down && self.selected + 1 < matches.len() {
+            self.selected += 1;
+        }
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+
+        egui::Window::new("Command Palette")
+            .collapsible(false)
+            .resizable(true)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 60.0))
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.query)
+                        .hint_text("Command")
+                        .desired_width(f32::INFINITY),
+                );
+                response.request_focus();
+                if response.changed() {
+                    matches = registry.search(&self.query);
+                    self.selected = 0;
+                }
+                ui.separator();
+
+                egui::ScrollArea::vertical().max_height(360.0).show(ui, |ui| {
+                    for (i, found) in matches.iter().enumerate() {
+                        let selected = i == self.selected;
+                        let row = ui.horizontal(|ui| {
+                            let title = highlighted(found.command.title(), &found.positions);
+                            let label = ui.selectable_label(selected, title);
+                            let bindings = keymap.bindings_for(found.command.id());
+                            if let Some(binding) = bindings.first() {
+                                ui.with_layout(
+                                    egui::Layout::right_to_left(egui::Align::Center),
+                                    |ui| ui.weak(binding.chord.to_string()),
+                                );
+                            }
+                            label
+                        });
+                        if selected && (up || down) {
+                            row.inner.scroll_to_me(None);
+                        }
+                        if row.inner.clicked() {
+                            picked = Some(found.command.id());
+                        }
+                    }
+                });
+            });
+
+        if enter {
+            picked = picked.or_else(|| matches.get(self.selected).map(|m| m.command.id()));
+        }
+        if picked.is_some() || escape {
+            self.close();
+        }
+        picked
+    }
+}
@@ -12,7 +12,8 @@ use tracing::{debug, error, info, warn};
 
 use crate::build::BuildManager;
 use crate::core::{
-    Config, Event, EventBus, EventHandler, EventMessage, EventPriority, Result, SystemEvent,
+    Config, ConfigLoader, ConfigWatcher, Event, EventBus, EventHandler, EventMessage,
+    EventPriority, Result, SystemEvent,
 };
 use crate::editor::Editor;
 use crate::project::ProjectManager;
@@ -40,6 +41,8 @@ pub struct XyluxIde {
     shutdown_requested: Arc<RwLock<bool>>,
     /// Current project path.
     current_project: Arc<RwLock<Option<PathBuf>>>,
+    /// Watcher that reloads the configuration when its files change.
+    config_watcher: Option<Arc<ConfigWatcher>>,
 }
 
 impl XyluxIde {
@@ -60,6 +63,15 @@ impl XyluxIde {
         let project_manager = Arc::new(ProjectManager::new(config.clone(), event_bus.clone()));
         let syntax_manager = Arc::new(SyntaxManager::new(config.clone(), event_bus.clone()).await?);
         let build_manager = Arc::new(BuildManager::new(config.clone(), event_bus.clone()).await?);
+        let config_watcher = ConfigLoader::new()
+            .map(|loader| match std::env::current_dir() {
+                Ok(dir) => loader.with_project_root(dir),
+                Err(_) => loader,
+            })
+            .and_then(|loader| ConfigWatcher::start(loader, config.clone(), event_bus.clone()))
+            .map(Arc::new)
+            .map_err(|e| warn!("Configuration changes will not be reloaded: {}", e))
+            .ok();
 
         let ide = Self {
             config,
@@ -71,6 +83,7 @@ impl XyluxIde {
             build_manager,
             shutdown_requested,
             current_project,
+            config_watcher,
         };
 
         // Register event handlers
@@ -239,9 +252,11 @@ impl XyluxIde {
         info!("Updating configuration");
 
         let mut config = self.config.write().await;
+        let changed = config.diff(&new_config);
         *config = new_config;
+        drop(config);
 
-        let event = EventMessage::from_event(Event::System(SystemEvent::ConfigReloaded))
+        let event = EventMessage::from_event(Event::System(SystemEvent::ConfigReloaded { changed }))
             .with_priority(EventPriority::High)
             .with_source("ide");
 
@@ -270,6 +285,10 @@ impl XyluxIde {
         };
         self.event_bus.register_handler("ide_project_change", Arc::new(project_handler)).await?;
 
+        // Register configuration reload handler
+        let config_handler = ConfigReloadHandler { syntax_manager: self.syntax_manager.clone() };
+        self.event_bus.register_handler("ide_config_reload", Arc::new(config_handler)).await?;
+
         Ok(())
     }
 
@@ -486,6 +505,34 @@ impl EventHandler for ProjectChangeHandler {
     }
 }
 
+/// Event handler that applies reloaded configuration to the language servers.
+struct ConfigReloadHandler {
+    syntax_manager: Arc<SyntaxManager>,
+}
+
+#[async_trait::async_trait]
+impl EventHandler for ConfigReloadHandler {
+    async fn handle(&self, event: &EventMessage) -> Result<()> {
+        // The syntax manager announces its own changes; they are applied already
+        if event.source.as_deref() == Some("syntax_manager") {
+            return Ok(());
+        }
+        if let Ok(Event::System(SystemEvent::ConfigReloaded { changed })) =
+            serde_json::from_value::<Event>(event.data.clone())
+        {
+            debug!("Configuration reloaded, applying {} changed settings", changed.len());
+            if let Err(e) = self.syntax_manager.apply_config_changes(&changed).await {
+                error!("Failed to apply configuration changes: {}", e);
+            }
+        }
+        Ok(())
+    }
+
+    fn can_handle(&self, event_type: &str) -> bool {
+        event_type == "system"
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -4,7 +4,7 @@
 
 use std::env;
 
-use xylux_ide::core::Config;
+use xylux_ide::core::{Config, ConfigLoader, KeyContext, Keymap};
 use xylux_ide::gui::XyluxIdeApp;
 
 /// Main entry point for Xylux IDE
@@ -66,7 +66,10 @@ fn run_gui_app(file_to_open: Option<String>) -> eframe::Result<()> {
         "Xylux IDE",
         options,
         Box::new(move |cc| {
-            let config = Config::default();
+            let config = ConfigLoader::new().and_then(|loader| loader.load()).unwrap_or_else(|e| {
+                eprintln!("Warning: using the default configuration: {}", e);
+                Config::default()
+            });
             let mut app = XyluxIdeApp::new(cc, config);
 
             // Open file if specified
@@ -120,10 +123,21 @@ fn print_help(program_name: &str) {
     println!("    -V, --version    Print version information");
     println!();
     println!("GUI CONTROLS:");
-    println!("    Ctrl+N           New file");
-    println!("    Ctrl+O           Open file");
-    println!("    Ctrl+S           Save file");
-    println!("    Ctrl+Q           Quit application");
+    let project_root = env::current_dir().ok();
+    let keymap = Keymap::load(project_root.as_deref()).unwrap_or_default();
+    let commands = XyluxIdeApp::default_commands();
+    for binding in keymap.bindings() {
+        let title = commands.get(&binding.command).map_or(binding.command.as_str(), |c| c.title());
+        match binding.when {
+            KeyContext::Global => println!("    {:<16} {}", binding.chord.to_string(), title),
+            when => println!("    {:<16} {} ({})", binding.chord.to_string(), title, when),
+        }
+    }
+    println!();
+    println!("KEYMAP:");
+    for path in Keymap::layer_paths(project_root.as_deref()) {
+        println!("    {}", path.display());
+    }
     println!();
     println!("MENU:");
     println!("    File menu provides file operations");
@@ -34,6 +34,12 @@ pub trait Platform {
     fn env_var(&self, key: &str) -> Option<String>;
 
     /// Set environment variable.
+    ///
+    /// Only call this while the process is single-threaded, before the Tokio
+    /// runtime or any other thread is started. Modifying the environment
+    /// while another thread reads it is undefined behavior on most
+    /// platforms. To configure a child process, use [`std::process::Command::env`]
+    /// instead.
     fn set_env_var(&self, key: &str, value: &str) -> Result<()>;
 
     /// Check if a command exists in PATH.
@@ -109,7 +109,11 @@ impl Platform for UnixPlatform {
     }
 
     fn set_env_var(&self, key: &str, value: &str) -> Result<()> {
-        env::set_var(key, value);
+        // SAFETY: callers must only set variables before any other thread is
+        // started, as required by `Platform::set_env_var`.
+        unsafe {
+            env::set_var(key, value);
+        }
         Ok(())
     }
 
@@ -86,7 +86,11 @@ impl Platform for WasiPlatform {
     }
 
     fn set_env_var(&self, key: &str, value: &str) -> Result<()> {
-        env::set_var(key, value);
+        // SAFETY: callers must only set variables before any other thread is
+        // started, as required by `Platform::set_env_var`.
+        unsafe {
+            env::set_var(key, value);
+        }
         Ok(())
     }
 
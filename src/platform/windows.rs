@@ -125,6 +125,8 @@ impl Platform for WindowsPlatform {
     }
 
     fn set_env_var(&self, key: &str, value: &str) -> Result<()> {
+        // SAFETY: callers must only set variables before any other thread is
+        // started, as required by `Platform::set_env_var`.
         unsafe {
             env::set_var(key, value);
         }
@@ -0,0 +1,401 @@
+//! # File Finder
+//!
+//! Fuzzy "Go to File" matching over an index of project files.
+//!
+//! A query matches a file when its characters appear in order in the path
+//! relative to the project root, ignoring case and whitespace. Matches are
+//! scored higher when they start path segments or words, run consecutively
+//! and fall in the file name, and lower for gaps between them. Recently
+//! opened files are ranked above others with a similar score. The same scoring
+//! is available for other text, such as command titles, through
+//! [`fuzzy_match`].
+//!
+//! The lowercased path and per-character bonuses are computed once when the
+//! index is built, so a query costs one subsequence check per file and a
+//! full score only for the files that match, which keeps projects of tens of
+//! thousands of files responsive.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::core::{Result, XyluxError};
+
+use super::search::{SearchFilter, walk};
+
+/// Score for each matched character.
+const SCORE_MATCH: i32 = 16;
+/// Bonus for a match at the start of a path segment.
+const BONUS_SEGMENT: i32 = 32;
+/// Bonus for a match at the start of a word within a segment.
+const BONUS_WORD: i32 = 20;
+/// Bonus for a match right after the previous one.
+const BONUS_CONSECUTIVE: i32 = 24;
+/// Bonus for a match in the file name.
+const BONUS_FILE_NAME: i32 = 12;
+/// Penalty for each character skipped between two matches.
+const PENALTY_GAP: i32 = 1;
+/// Bonus for the most recently opened file, shrinking down the recent list.
+const BONUS_RECENT: i32 = 64;
+
+/// Score of positions that cannot be part of a match.
+const UNMATCHED: i32 = i32::MIN / 2;
+
+/// Text prepared for fuzzy matching.
+#[derive(Debug, Clone)]
+struct Target {
+    /// Lowercased characters of the text.
+    chars: Box<[char]>,
+    /// Position bonus of each character of the text.
+    bonuses: Box<[i32]>,
+}
+
+impl Target {
+    fn new(text: &str) -> Self {
+        let original: Vec<char> = text.chars().collect();
+        let name_start = original.iter().rposition(|&c| c == '/').map_or(0, |slash| slash + 1);
+
+        let chars = original.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+        let bonuses = original
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| {
+                let previous = i.checked_sub(1).map(|p| original[p]);
+                let position = match previous {
+                    None | Some('/') => BONUS_SEGMENT,
+                    Some('_' | '-' | '.' | ' ') => BONUS_WORD,
+                    Some(p) if p.is_lowercase() && c.is_uppercase() => BONUS_WORD,
+                    _ => 0,
+                };
+                if i >= name_start { position + BONUS_FILE_NAME } else { position }
+            })
+            .collect();
+
+        Self { chars, bonuses }
+    }
+}
+
+/// A file in the index.
+#[derive(Debug, Clone)]
+struct IndexEntry {
+    /// The file.
+    path: PathBuf,
+    /// Path relative to the index root, with `/` separators.
+    relative: String,
+    /// `relative` prepared for matching.
+    target: Target,
+}
+
+impl IndexEntry {
+    fn new(root: &Path, path: PathBuf) -> Self {
+        let relative =
+            path.strip_prefix(root).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+        let target = Target::new(&relative);
+        Self { path, relative, target }
+    }
+}
+
+/// A file matching a finder query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileMatch {
+    /// The file.
+    pub path: PathBuf,
+    /// Path relative to the index root, with `/` separators.
+    pub relative: String,
+    /// Match score; higher is better.
+    pub score: i32,
+    /// Character positions in `relative` matched by the query.
+    pub positions: Vec<usize>,
+}
+
+/// An index of the files of a project for fuzzy finding.
+#[derive(Debug, Clone)]
+pub struct FileIndex {
+    /// Directory the index covers.
+    root: PathBuf,
+    /// Indexed files, in walk order.
+    entries: Vec<IndexEntry>,
+}
+
+impl FileIndex {
+    /// Create an index of the given files under `root`.
+    pub fn new(root: PathBuf, paths: impl IntoIterator<Item = PathBuf>) -> Self {
+        let entries = paths.into_iter().map(|path| IndexEntry::new(&root, path)).collect();
+        Self { root, entries }
+    }
+
+    /// Index the files under `root`, honouring ignore files and
+    /// `ignore_patterns` like a workspace search.
+    pub async fn build(root: &Path, ignore_patterns: &[String]) -> Result<Self> {
+        let root = root.to_path_buf();
+        let ignore_patterns = ignore_patterns.to_vec();
+
+        tokio::task::spawn_blocking(move || {
+            let files = walk(&root, &ignore_patterns, &SearchFilter::default())?;
+            Ok(Self::new(root, files))
+        })
+        .await
+        .map_err(|e| XyluxError::project_error(format!("File index walk failed: {}", e)))?
+    }
+
+    /// Get the directory the index covers.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Get the number of indexed files.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check whether the index has no files.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Find the best `limit` files matching `query`, best first.
+    ///
+    /// `recent` lists recently opened files, most recent first. An empty
+    /// query returns the recent files that are in the index, followed by the
+    /// rest in index order.
+    pub fn find(&self, query: &str, recent: &[PathBuf], limit: usize) -> Vec<FileMatch> {
+        let query = normalize_query(query);
+        let recency: HashMap<&Path, i32> = recent
+            .iter()
+            .enumerate()
+            .map(|(rank, path)| {
+                let bonus = BONUS_RECENT * (recent.len() - rank) as i32 / recent.len() as i32;
+                (path.as_path(), bonus)
+            })
+            // Reversed so a file listed twice keeps its most recent rank.
+            .rev()
+            .collect();
+        let recency_of =
+            |entry: &IndexEntry| recency.get(entry.path.as_path()).copied().unwrap_or(0);
+
+        if limit == 0 {
+            return Vec::new();
+        }
+        if query.is_empty() {
+            let mut matches: Vec<FileMatch> = self
+                .entries
+                .iter()
+                .map(|entry| file_match(entry, recency_of(entry), Vec::new()))
+                .collect();
+            // Stable, so files that are not recent keep their index order.
+            matches.sort_by(|a, b| b.score.cmp(&a.score));
+            matches.truncate(limit);
+            return matches;
+        }
+
+        let mut scratch = Scratch::default();
+        let mut matches: Vec<FileMatch> = self
+            .entries
+            .iter()
+            .filter(|entry| is_subsequence(&query, &entry.target.chars))
+            .filter_map(|entry| {
+                let (score, positions) = score(&entry.target, &query, &mut scratch)?;
+                Some(file_match(entry, score + recency_of(entry), positions))
+            })
+            .collect();
+
+        if matches.len() > limit {
+            matches.select_nth_unstable_by(limit - 1, rank);
+            matches.truncate(limit);
+        }
+        matches.sort_by(rank);
+        matches
+    }
+}
+
+/// Fuzzy-match `query` against a single text, such as a command title.
+///
+/// Returns the score and the matched character positions, or `None` when the
+/// text does not match. An empty query matches everything with score 0.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<(i32, Vec<usize>)> {
+    let query = normalize_query(query);
+    if query.is_empty() {
+        return Some((0, Vec::new()));
+    }
+
+    let target = Target::new(text);
+    if !is_subsequence(&query, &target.chars) {
+        return None;
+    }
+    score(&target, &query, &mut Scratch::default())
+}
+
+/// Lowercase a query and drop its whitespace.
+fn normalize_query(query: &str) -> Vec<char> {
+    query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_lowercase().next().unwrap_or(c))
+        .collect()
+}
+
+/// Order matches best first, then by path.
+fn rank(a: &FileMatch, b: &FileMatch) -> Ordering {
+    b.score.cmp(&a.score).then_with(|| a.relative.cmp(&b.relative))
+}
+
+fn file_match(entry: &IndexEntry, score: i32, positions: Vec<usize>) -> FileMatch {
+    FileMatch { path: entry.path.clone(), relative: entry.relative.clone(), score, positions }
+}
+
+/// Check whether `query` appears in order within `chars`.
+fn is_subsequence(query: &[char], chars: &[char]) -> bool {
+    let mut remaining = chars.iter();
+    query.iter().all(|q| remaining.any(|c| c == q))
+}
+
+/// Buffers reused across the files scored for one query.
+#[derive(Debug, Default)]
+struct Scratch {
+    /// Best score with query character `i` matched at path position `j`.
+    scores: Vec<i32>,
+    /// Position of query character `i - 1` in that best match.
+    previous: Vec<usize>,
+}
+
+/// Score the best alignment of `query` within `target`, returning the score
+/// and the matched positions. `query` must be a non-empty subsequence.
+fn score(target: &Target, query: &[char], scratch: &mut Scratch) -> Option<(i32, Vec<usize>)> {
+    let n = target.chars.len();
+    let m = query.len();
+    scratch.scores.clear();
+    scratch.scores.resize(m * n, UNMATCHED);
+    scratch.previous.clear();
+    scratch.previous.resize(m * n, 0);
+    let Scratch { scores, previous } = scratch;
+
+    for (j, &c) in target.chars.iter().enumerate() {
+        if c == query[0] {
+            scores[j] = SCORE_MATCH + target.bonuses[j];
+        }
+    }
+
+    for i in 1..m {
+        let (done, row) = scores.split_at_mut(i * n);
+        let above = &done[(i - 1) * n..];
+        let row = &mut row[..n];
+        let from = &mut previous[i * n..(i + 1) * n];
+
+        // Best earlier match of the previous character, less its gap so far.
+        let mut carry = UNMATCHED;
+        let mut carry_at = 0;
+        for j in 1..n {
+            carry -= PENALTY_GAP;
+            if above[j - 1] > carry {
+                carry = above[j - 1];
+                carry_at = j - 1;
+            }
+            if target.chars[j] != query[i] {
+                continue;
+            }
+
+            let consecutive = above[j - 1] + BONUS_CONSECUTIVE;
+            let (best, at) =
+                if consecutive >= carry { (consecutive, j - 1) } else { (carry, carry_at) };
+            if best > UNMATCHED / 2 {
+                row[j] = best + SCORE_MATCH + target.bonuses[j];
+                from[j] = at;
+            }
+        }
+    }
+
+    let last = &scores[(m - 1) * n..];
+    let (mut j, &best) = last.iter().enumerate().max_by_key(|&(j, &score)| (score, Reverse(j)))?;
+    if best <= UNMATCHED / 2 {
+        return None;
+    }
+
+    let mut positions = vec![0; m];
+    for i in (0..m).rev() {
+        positions[i] = j;
+        j = previous[i * n + j];
+    }
+
+    // Prefer shorter texts among otherwise equal matches.
+    Some((best - n as i32 / 4, positions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(paths: &[&str]) -> FileIndex {
+        let root = PathBuf::from("/project");
+        FileIndex::new(root.clone(), paths.iter().map(|path| root.join(path)))
+    }
+
+    fn found(index: &FileIndex, query: &str, recent: &[PathBuf]) -> Vec<String> {
+        index.find(query, recent, 10).into_iter().map(|found| found.relative).collect()
+    }
+
+    #[test]
+    fn test_segment_matches_rank_first() {
+        let index = index(&[
+            "assets/fonts/mono.ttf",
+            "src/editor/mod.rs",
+            "src/editor/multi_cursor.rs",
+            "src/gui/menu.rs",
+            "docs/EditorModes.md",
+        ]);
+
+        assert_eq!(found(&index, "editor/mod", &[])[0], "src/editor/mod.rs");
+        assert_eq!(found(&index, "multcur", &[]), vec!["src/editor/multi_cursor.rs"]);
+        assert_eq!(found(&index, "gui menu", &[]), vec!["src/gui/menu.rs"]);
+        assert_eq!(found(&index, "EdModes", &[]), vec!["docs/EditorModes.md"]);
+        assert!(found(&index, "zzz", &[]).is_empty());
+
+        let best = &index.find("mcr", &[], 1)[0];
+        assert_eq!(best.relative, "src/editor/multi_cursor.rs");
+        assert_eq!(best.positions, vec![11, 17, 24]);
+
+        assert_eq!(fuzzy_match("fsav", "File: Save All").map(|(_, at)| at), Some(vec![0, 6, 7, 8]));
+        assert!(fuzzy_match("xyz", "File: Save All").is_none());
+    }
+
+    #[test]
+    fn test_recent_files_rank_higher() {
+        let index = index(&["src/lib.rs", "src/main.rs", "tests/main.rs", "README.md"]);
+        let recent = vec![PathBuf::from("/project/tests/main.rs")];
+
+        assert_eq!(found(&index, "main", &[])[0], "src/main.rs");
+        assert_eq!(found(&index, "main", &recent)[0], "tests/main.rs");
+        assert_eq!(
+            found(&index, "", &recent),
+            vec!["tests/main.rs", "src/lib.rs", "src/main.rs", "README.md"]
+        );
+    }
+
+    #[test]
+    fn test_large_index_limits_results() {
+        let paths: Vec<String> = (0..50_000)
+            .map(|i| format!("crates/crate_{}/src/module_{}/file_{}.rs", i % 50, i % 997, i))
+            .collect();
+        let index = FileIndex::new(
+            PathBuf::from("/project"),
+            paths.iter().map(|path| PathBuf::from("/project").join(path)),
+        );
+
+        let matches = index.find("f4512", &[], 20);
+        assert_eq!(matches.len(), 20);
+        assert_eq!(matches[0].relative, "crates/crate_12/src/module_524/file_4512.rs");
+        assert!(matches.windows(2).all(|pair| rank(&pair[0], &pair[1]) != Ordering::Greater));
+    }
+
+    #[tokio::test]
+    async fn test_build_respects_ignore_patterns() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::create_dir_all(dir.path().join("target/debug")).unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "").unwrap();
+        std::fs::write(dir.path().join("target/debug/main.d"), "").unwrap();
+
+        let index = FileIndex::build(dir.path(), &["target".to_string()]).await.unwrap();
+        assert_eq!(index.len(), 1);
+        assert_eq!(found(&index, "main", &[]), vec!["src/main.rs"]);
+    }
+}
@@ -235,6 +235,25 @@ impl ProjectManager {
         Ok(())
     }
 
+    /// Record that a file was opened in the open project containing it.
+    ///
+    /// Returns whether a project contains the file.
+    pub async fn record_recent_file(&self, path: &Path) -> bool {
+        let mut projects = self.projects.write().await;
+        let project = projects
+            .values_mut()
+            .filter(|project| path.starts_with(&project.root_path))
+            .max_by_key(|project| project.root_path.components().count());
+
+        match project {
+            Some(project) => {
+                project.add_recent_file(path.to_path_buf());
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Get all open projects.
     pub async fn open_projects(&self) -> Vec<Project> {
         let projects = self.projects.read().await;
@@ -789,6 +808,30 @@ mod tests {
         assert_eq!(recent[1], project1);
     }
 
+    #[tokio::test]
+    async fn test_recent_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Arc::new(RwLock::new(Config::default()));
+        let event_bus = Arc::new(EventBus::new());
+        let manager = ProjectManager::new(config, event_bus);
+
+        let project_path = manager
+            .create_project("recent", temp_dir.path(), ProjectType::Rust, None)
+            .await
+            .unwrap();
+        let main = project_path.join("src").join("main.rs");
+        let cargo = project_path.join("Cargo.toml");
+
+        assert!(manager.record_recent_file(&cargo).await);
+        assert!(manager.record_recent_file(&main).await);
+        assert!(!manager.record_recent_file(&temp_dir.path().join("elsewhere.rs")).await);
+
+        let recent = manager.active_project().await.unwrap().recent_files;
+        assert_eq!(recent[0], main);
+        assert_eq!(recent[1], cargo);
+        assert_eq!(recent.iter().filter(|path| **path == main).count(), 1);
+    }
+
     #[tokio::test]
     async fn test_project_templates() {
         let config = Arc::new(RwLock::new(Config::default()));
@@ -2,10 +2,14 @@
 //!
 //! Project management functionality for Xylux IDE.
 
+pub mod file_finder;
 pub mod manager;
+pub mod search;
 pub mod xylux_project;
 
+pub use file_finder::{FileIndex, FileMatch, fuzzy_match};
 pub use manager::ProjectManager;
+pub use search::WorkspaceSearch;
 pub use xylux_project::XyluxProject;
 
 use std::path::PathBuf;
@@ -16,6 +20,9 @@ use tracing::info;
 
 use crate::core::Result;
 
+/// Maximum number of recent files remembered per project.
+const MAX_RECENT_FILES: usize = 50;
+
 /// Represents different types of projects.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ProjectType {
@@ -115,6 +122,13 @@ impl Project {
         Ok(())
     }
 
+    /// Record that a file was opened, moving it to the front of the recent files.
+    pub fn add_recent_file(&mut self, path: PathBuf) {
+        self.recent_files.retain(|recent| recent != &path);
+        self.recent_files.insert(0, path);
+        self.recent_files.truncate(MAX_RECENT_FILES);
+    }
+
     /// Add a file to recent files if it exists.
     fn add_if_exists(&mut self, relative_path: &str) {
         let file_path = self.root_path.join(relative_path);
@@ -0,0 +1,378 @@
+//! # Workspace Search
+//!
+//! Project-wide search and replace.
+//!
+//! Files are found with the `ignore` crate, so `.gitignore` rules, hidden
+//! files and [`ProjectConfig::ignore_patterns`](crate::core::config::ProjectConfig)
+//! are respected, and can be narrowed further with include and exclude globs.
+//! Files that are open in the [`Editor`] are searched and replaced through
+//! their buffers, so unsaved changes are seen and replacements can be undone.
+//! Results are published per file as [`ProjectEvent::SearchResults`] while
+//! the search runs.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use ignore::WalkBuilder;
+use ignore::overrides::OverrideBuilder;
+use tokio::sync::RwLock;
+use tracing::{debug, warn};
+
+use crate::core::{
+    Config, Event, EventBus, EventMessage, EventPriority, ProjectEvent, Result, SearchHit,
+    XyluxError,
+};
+use crate::editor::{Buffer, Editor, SearchQuery};
+
+/// Globs narrowing which files a workspace search visits.
+///
+/// Globs use `.gitignore` syntax relative to the search root.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchFilter {
+    /// Only search files matching one of these globs; all files when empty.
+    pub include: Vec<String>,
+    /// Skip files and directories matching any of these globs.
+    pub exclude: Vec<String>,
+}
+
+/// The matches found in one file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileMatches {
+    /// The file.
+    pub path: PathBuf,
+    /// The matches, in file order.
+    pub hits: Vec<SearchHit>,
+}
+
+/// Searches and replaces across the files of a project.
+pub struct WorkspaceSearch {
+    /// IDE configuration, for the project ignore patterns.
+    config: Arc<RwLock<Config>>,
+    /// Event bus for streaming results.
+    event_bus: Arc<EventBus>,
+    /// Editor whose open buffers take precedence over files on disk.
+    editor: Arc<Editor>,
+}
+
+impl WorkspaceSearch {
+    /// Create a workspace search service.
+    pub fn new(config: Arc<RwLock<Config>>, event_bus: Arc<EventBus>, editor: Arc<Editor>) -> Self {
+        Self { config, event_bus, editor }
+    }
+
+    /// List the files under `root` that a search with `filter` visits.
+    pub async fn files(&self, root: &Path, filter: &SearchFilter) -> Result<Vec<PathBuf>> {
+        let ignore_patterns = self.config.read().await.project.ignore_patterns.clone();
+        let root = root.to_path_buf();
+        let filter = filter.clone();
+
+        tokio::task::spawn_blocking(move || walk(&root, &ignore_patterns, &filter))
+            .await
+            .map_err(|e| XyluxError::project_error(format!("Search walk failed: {}", e)))?
+    }
+
+    /// Search the files under `root`, publishing the matches of each file as
+    /// they are found.
+    pub async fn search(
+        &self,
+        root: &Path,
+        query: &SearchQuery,
+        filter: &SearchFilter,
+    ) -> Result<Vec<FileMatches>> {
+        self.scan(root, query, None, filter).await
+    }
+
+    /// Search the files under `root` and show what each match would be
+    /// replaced with, without changing anything.
+    pub async fn preview_replace(
+        &self,
+        root: &Path,
+        query: &SearchQuery,
+        replacement: &str,
+        filter: &SearchFilter,
+    ) -> Result<Vec<FileMatches>> {
+        self.scan(root, query, Some(replacement), filter).await
+    }
+
+    /// Replace every match in the given files, such as those returned by
+    /// [`preview_replace`](Self::preview_replace).
+    ///
+    /// Open files are changed through the editor as one undoable command
+    /// each; other files are rewritten on disk. Returns the number of
+    /// replacements.
+    pub async fn apply_replace(
+        &self,
+        query: &SearchQuery,
+        replacement: &str,
+        paths: &[PathBuf],
+    ) -> Result<usize> {
+        let open_files = self.editor.get_open_files().await;
+        let mut files = 0;
+        let mut replacements = 0;
+
+        for path in paths {
+            let count = if open_files.contains(path) {
+                self.editor.replace_all_in(path, query, replacement).await?
+            } else {
+                let Some(content) = read_text(path).await else {
+                    continue;
+                };
+                let mut buffer = Buffer::new(&content, Some(path.clone()));
+                let count = buffer.replace_all(query, replacement)?;
+                if count > 0 {
+                    tokio::fs::write(path, buffer.get_content()).await?;
+                }
+                count
+            };
+
+            if count > 0 {
+                files += 1;
+                replacements += count;
+            }
+        }
+
+        debug!("Replaced {} matches in {} files", replacements, files);
+        self.publish(ProjectEvent::ReplaceCompleted {
+            query: query.pattern().to_string(),
+            files,
+            replacements,
+        })
+        .await?;
+        Ok(replacements)
+    }
+
+    /// Search the files under `root`, with replacements when `replacement`
+    /// is given.
+    async fn scan(
+        &self,
+        root: &Path,
+        query: &SearchQuery,
+        replacement: Option<&str>,
+        filter: &SearchFilter,
+    ) -> Result<Vec<FileMatches>> {
+        let files = self.files(root, filter).await?;
+        let mut results = Vec::new();
+
+        for path in files {
+            let content = match self.editor.get_content(&path).await {
+                Some(content) => content,
+                None => match read_text(&path).await {
+                    Some(content) => content,
+                    None => continue,
+                },
+            };
+
+            let buffer = Buffer::new(&content, None);
+            let hits: Vec<SearchHit> = match replacement {
+                Some(replacement) => buffer
+                    .search_replacements(query, replacement)
+                    .into_iter()
+                    .map(|(found, text)| search_hit(&buffer, found.start, found.end, Some(text)))
+                    .collect(),
+                None => buffer
+                    .search(query)
+                    .into_iter()
+                    .map(|found| search_hit(&buffer, found.start, found.end, None))
+                    .collect(),
+            };
+            if hits.is_empty() {
+                continue;
+            }
+
+            self.publish(ProjectEvent::SearchResults {
+                query: query.pattern().to_string(),
+                path: path.clone(),
+                hits: hits.clone(),
+            })
+            .await?;
+            results.push(FileMatches { path, hits });
+        }
+
+        self.publish(ProjectEvent::SearchCompleted {
+            query: query.pattern().to_string(),
+            files: results.len(),
+            matches: results.iter().map(|file| file.hits.len()).sum(),
+        })
+        .await?;
+        Ok(results)
+    }
+
+    /// Publish a project event from the search service.
+    async fn publish(&self, event: ProjectEvent) -> Result<()> {
+        let event = EventMessage::from_event(Event::Project(event))
+            .with_priority(EventPriority::Low)
+            .with_source("workspace_search");
+        self.event_bus.publish(event).await
+    }
+}
+
+/// Walk `root` for files, honouring ignore files, `ignore_patterns` and `filter`.
+pub(super) fn walk(
+    root: &Path,
+    ignore_patterns: &[String],
+    filter: &SearchFilter,
+) -> Result<Vec<PathBuf>> {
+    // Overrides use inverted `.gitignore` syntax: plain globs whitelist, `!` ignores.
+    let mut overrides = OverrideBuilder::new(root);
+    let globs = filter
+        .include
+        .iter()
+        .cloned()
+        .chain(ignore_patterns.iter().chain(&filter.exclude).map(|glob| format!("!{}", glob)));
+    for glob in globs {
+        overrides.add(&glob).map_err(|e| {
+            XyluxError::project_error(format!("Invalid search glob '{}': {}", glob, e))
+        })?;
+    }
+    let overrides = overrides
+        .build()
+        .map_err(|e| XyluxError::project_error(format!("Invalid search globs: {}", e)))?;
+
+    let mut files = Vec::new();
+    let walker =
+        WalkBuilder::new(root).overrides(overrides).sort_by_file_name(|a, b| a.cmp(b)).build();
+    for entry in walker {
+        match entry {
+            Ok(entry) if entry.file_type().is_some_and(|file_type| file_type.is_file()) => {
+                files.push(entry.into_path());
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Skipping entry during search: {}", e),
+        }
+    }
+    Ok(files)
+}
+
+/// Read a file as text, or `None` for unreadable and binary files.
+async fn read_text(path: &Path) -> Option<String> {
+    match tokio::fs::read_to_string(path).await {
+        Ok(content) => Some(content),
+        Err(e) => {
+            debug!("Not searching {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Describe a match in `buffer` for search results.
+fn search_hit(
+    buffer: &Buffer,
+    (line, column): (usize, usize),
+    (end_line, end_column): (usize, usize),
+    replacement: Option<String>,
+) -> SearchHit {
+    SearchHit {
+        line,
+        column,
+        end_line,
+        end_column,
+        line_text: buffer.get_line(line).unwrap_or_default(),
+        replacement,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::EventSubscription;
+    use crate::editor::SearchOptions;
+
+    async fn workspace() -> (tempfile::TempDir, WorkspaceSearch, Arc<Editor>, Arc<EventBus>) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::write(root.join("src/main.rs"), "fn main() {\n    helper(1);\n}\n").unwrap();
+        std::fs::write(root.join("src/lib.rs"), "pub fn helper(n: u8) {}\r\n").unwrap();
+        std::fs::write(root.join("target/build.rs"), "helper(2);\n").unwrap();
+        std::fs::write(root.join("run.log"), "helper(3)\n").unwrap();
+        std::fs::write(root.join("notes.md"), "Call helper(4).\n").unwrap();
+
+        let mut config = Config::default();
+        config.editor.undo.persist = false;
+        let config = Arc::new(RwLock::new(config));
+        let event_bus = Arc::new(EventBus::new());
+        let editor =
+            Arc::new(Editor::new(Arc::clone(&config), Arc::clone(&event_bus)).await.unwrap());
+        let search = WorkspaceSearch::new(config, Arc::clone(&event_bus), Arc::clone(&editor));
+        (dir, search, editor, event_bus)
+    }
+
+    fn names(root: &Path, paths: impl IntoIterator<Item = PathBuf>) -> Vec<String> {
+        paths
+            .into_iter()
+            .map(|path| path.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_search_respects_ignores_and_globs() {
+        let (dir, search, _, event_bus) = workspace().await;
+        let root = dir.path();
+        let mut events = event_bus.subscribe(EventSubscription::all());
+        let query = SearchQuery::new("helper", SearchOptions::default()).unwrap();
+
+        let results = search.search(root, &query, &SearchFilter::default()).await.unwrap();
+        let files = names(root, results.iter().map(|file| file.path.clone()));
+        assert_eq!(files, vec!["notes.md", "src/lib.rs", "src/main.rs"]);
+        assert_eq!(results[2].hits[0].line_text, "    helper(1);");
+        assert_eq!((results[2].hits[0].line, results[2].hits[0].column), (1, 4));
+
+        let mut streamed = 0;
+        while let Ok(event) = events.try_recv() {
+            if let Ok(Event::Project(ProjectEvent::SearchResults { .. })) =
+                serde_json::from_value(event.data)
+            {
+                streamed += 1;
+            }
+        }
+        assert_eq!(streamed, 3);
+
+        let filter =
+            SearchFilter { include: vec!["*.rs".to_string()], exclude: vec!["lib.rs".to_string()] };
+        let results = search.search(root, &query, &filter).await.unwrap();
+        assert_eq!(names(root, results.into_iter().map(|file| file.path)), vec!["src/main.rs"]);
+
+        let filter = SearchFilter { include: vec!["[".to_string()], exclude: Vec::new() };
+        assert!(search.search(root, &query, &filter).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_replace_across_files() {
+        let (dir, search, editor, _) = workspace().await;
+        let root = dir.path();
+        let main_path = root.join("src/main.rs");
+        let lib_path = root.join("src/lib.rs");
+        editor.open_file(&main_path).await.unwrap();
+
+        let options = SearchOptions { regex: true, case_sensitive: true, whole_word: true };
+        let query = SearchQuery::new(r"helper\((\w+)", options).unwrap();
+        let filter = SearchFilter { include: vec!["src/**".to_string()], exclude: Vec::new() };
+        let preview = search.preview_replace(root, &query, "assist($1", &filter).await.unwrap();
+        assert_eq!(preview.len(), 2);
+        assert_eq!(preview[1].path, main_path);
+        assert_eq!(preview[1].hits[0].replacement.as_deref(), Some("assist(1"));
+
+        let filter = SearchFilter::default();
+        let preview = search.preview_replace(root, &query, "assist($1", &filter).await.unwrap();
+        let paths: Vec<PathBuf> = preview.into_iter().map(|file| file.path).collect();
+        assert_eq!(search.apply_replace(&query, "assist($1", &paths).await.unwrap(), 3);
+
+        // The open file changed in its buffer and can be undone; the others on disk.
+        assert_eq!(
+            std::fs::read_to_string(&main_path).unwrap(),
+            "fn main() {\n    helper(1);\n}\n"
+        );
+        assert_eq!(
+            editor.get_content(&main_path).await.as_deref(),
+            Some("fn main() {\n    assist(1);\n}\n")
+        );
+        assert_eq!(std::fs::read_to_string(&lib_path).unwrap(), "pub fn assist(n: u8) {}\r\n");
+        assert_eq!(std::fs::read_to_string(root.join("notes.md")).unwrap(), "Call assist(4).\n");
+        assert!(editor.undo().await.unwrap());
+        assert_eq!(
+            editor.get_content(&main_path).await.as_deref(),
+            Some("fn main() {\n    helper(1);\n}\n")
+        );
+    }
+}
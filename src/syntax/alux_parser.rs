@@ -0,0 +1,1117 @@
+//! # Alux Parser
+//!
+//! Lexer and structural parser for the Alux scripting language.
+//!
+//! The parser produces a lossless concrete syntax tree: every byte of the
+//! source belongs to exactly one token, and tokens are grouped into nodes for
+//! `component`, `system`, `task`, `scene` and `shader` blocks, function
+//! signatures, imports and exports. The tree drives highlighting, folding and
+//! the outline.
+
+use std::ops::Range;
+
+use crate::syntax::{FoldingRange, FoldingRangeKind, OutlineItem, SymbolKind};
+
+/// Multi-character operators first, so the longest match wins.
+const OPERATORS: &[&str] = &[
+    "==", "!=", "<=", ">=", "++", "--", "+=", "-=", "*=", "/=", "%=", "&&", "||", "<<", ">>", "^=",
+    "&=", "|=", "->", "=>", "+", "-", "*", "/", "%", "=", "!", "<", ">", "&", "|", "^", "~",
+];
+
+/// Characters lexed as punctuation.
+const PUNCTUATION: &str = "(){}[];,.:?@#";
+
+/// Number literal type suffixes.
+const NUMBER_SUFFIXES: &[&str] =
+    &["f32", "f64", "i8", "u8", "i16", "u16", "i32", "u32", "i64", "u64"];
+
+/// Modifiers allowed in front of an item keyword.
+const MODIFIERS: &[&str] = &["pub", "priv", "async"];
+
+/// Check whether a word is an Alux keyword.
+#[must_use]
+pub fn is_keyword(word: &str) -> bool {
+    matches!(
+        word,
+        // Control flow
+        "if" | "else" | "elif" | "while" | "for" | "in" | "loop" | "break" | "continue" | "return"
+        // Declarations
+        | "fn" | "let" | "const" | "mut" | "struct" | "enum" | "trait" | "impl" | "mod" | "use"
+        | "import" | "export" | "as"
+        // Xylux items
+        | "task" | "component" | "system" | "resource" | "entity" | "event" | "scene" | "node"
+        | "shader" | "material" | "texture" | "mesh" | "animation" | "sound"
+        // Async
+        | "async" | "await" | "spawn"
+        // Memory management
+        | "new" | "delete" | "ref" | "deref"
+        // Literals
+        | "true" | "false" | "null"
+        // Visibility
+        | "pub" | "priv"
+    )
+}
+
+/// Check whether a word names a built-in Alux or Xylux type.
+#[must_use]
+pub fn is_builtin_type(word: &str) -> bool {
+    matches!(
+        word,
+        "i8" | "i16"
+            | "i32"
+            | "i64"
+            | "u8"
+            | "u16"
+            | "u32"
+            | "u64"
+            | "f32"
+            | "f64"
+            | "bool"
+            | "char"
+            | "string"
+            | "vec"
+            | "map"
+            | "set"
+            | "Vec2"
+            | "Vec3"
+            | "Vec4"
+            | "Mat3"
+            | "Mat4"
+            | "Quat"
+            | "Color"
+            | "Transform"
+    )
+}
+
+/// Kinds of tokens produced by the lexer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Whitespace,
+    Comment,
+    DocComment,
+    Keyword,
+    Identifier,
+    Number,
+    String,
+    Operator,
+    Punctuation,
+    Unknown,
+}
+
+impl TokenKind {
+    /// Whether the token carries no syntax: whitespace and comments.
+    #[must_use]
+    pub const fn is_trivia(self) -> bool {
+        matches!(self, Self::Whitespace | Self::Comment | Self::DocComment)
+    }
+}
+
+/// A lexed token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    /// Token kind.
+    pub kind: TokenKind,
+    /// Byte range in the source.
+    pub range: Range<usize>,
+}
+
+/// Kinds of syntax tree nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    SourceFile,
+    Import,
+    Export,
+    Component,
+    System,
+    Task,
+    Scene,
+    Shader,
+    Function,
+    ParamList,
+    Param,
+    ReturnType,
+    TypeRef,
+    Field,
+    Block,
+    Statement,
+    Error,
+}
+
+impl NodeKind {
+    /// Whether the node is a named item shown in the outline.
+    #[must_use]
+    pub const fn is_item(self) -> bool {
+        matches!(
+            self,
+            Self::Component
+                | Self::System
+                | Self::Task
+                | Self::Scene
+                | Self::Shader
+                | Self::Function
+        )
+    }
+}
+
+/// A child of a syntax node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyntaxElement {
+    /// A nested node.
+    Node(SyntaxNode),
+    /// A token, by index into [`SyntaxTree::tokens`].
+    Token(usize),
+}
+
+/// A node of the concrete syntax tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxNode {
+    /// Node kind.
+    pub kind: NodeKind,
+    /// Byte range in the source, from the first to the last token.
+    pub range: Range<usize>,
+    /// Child nodes and tokens in source order.
+    pub children: Vec<SyntaxElement>,
+}
+
+impl SyntaxNode {
+    /// Direct child nodes.
+    pub fn nodes(&self) -> impl Iterator<Item = &Self> {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    /// Indices of the direct child tokens.
+    pub fn tokens(&self) -> impl Iterator<Item = usize> + '_ {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxElement::Token(index) => Some(*index),
+            SyntaxElement::Node(_) => None,
+        })
+    }
+
+    /// First direct child node of a kind.
+    #[must_use]
+    pub fn child(&self, kind: NodeKind) -> Option<&Self> {
+        self.nodes().find(|node| node.kind == kind)
+    }
+}
+
+/// Parsed Alux source.
+#[derive(Debug, Clone)]
+pub struct SyntaxTree {
+    /// Source text.
+    text: String,
+    /// All tokens, trivia included.
+    tokens: Vec<Token>,
+    /// Root `SourceFile` node.
+    root: SyntaxNode,
+    /// Byte offset of the start of each line.
+    line_starts: Vec<usize>,
+}
+
+impl SyntaxTree {
+    /// Parse Alux source. Parsing never fails; unexpected input ends up in
+    /// `Error` or `Statement` nodes.
+    #[must_use]
+    pub fn parse(text: &str) -> Self {
+        let tokens = lex(text);
+        let root = Parser::new(text, &tokens).parse();
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(offset, _)| offset + 1))
+            .collect();
+
+        Self { text: text.to_owned(), tokens, root, line_starts }
+    }
+
+    /// Root node.
+    #[must_use]
+    pub const fn root(&self) -> &SyntaxNode {
+        &self.root
+    }
+
+    /// All tokens, trivia included.
+    #[must_use]
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    /// Source text.
+    #[must_use]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Text of a token.
+    #[must_use]
+    pub fn token_text(&self, index: usize) -> &str {
+        self.tokens.get(index).map_or("", |token| &self.text[token.range.clone()])
+    }
+
+    /// Text covered by a node.
+    #[must_use]
+    pub fn node_text(&self, node: &SyntaxNode) -> &str {
+        &self.text[node.range.clone()]
+    }
+
+    /// Zero-based line of a byte offset.
+    #[must_use]
+    pub fn line_of(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|&start| start <= offset).saturating_sub(1)
+    }
+
+    /// Index of the token naming an item: its first direct identifier.
+    #[must_use]
+    pub fn name_of(&self, node: &SyntaxNode) -> Option<usize> {
+        node.tokens().find(|&index| self.tokens[index].kind == TokenKind::Identifier)
+    }
+
+    /// Foldable regions: multi-line blocks, block comments and import groups.
+    #[must_use]
+    pub fn folding_ranges(&self) -> Vec<FoldingRange> {
+        let mut ranges = Vec::new();
+        self.collect_block_folds(&self.root, &mut ranges);
+
+        for token in &self.tokens {
+            let text = &self.text[token.range.clone()];
+            if token.kind == TokenKind::Comment && text.starts_with("/*") {
+                self.push_fold(&mut ranges, token.range.clone(), FoldingRangeKind::Comment);
+            }
+        }
+
+        // Runs of imports on consecutive lines fold together.
+        let mut group: Option<Range<usize>> = None;
+        for node in self.root.nodes() {
+            if node.kind != NodeKind::Import {
+                if let Some(range) = group.take() {
+                    self.push_fold(&mut ranges, range, FoldingRangeKind::Imports);
+                }
+                continue;
+            }
+            group = match group {
+                Some(range) if self.line_of(node.range.start) <= self.line_of(range.end) + 1 => {
+                    Some(range.start..node.range.end)
+                }
+                Some(range) => {
+                    self.push_fold(&mut ranges, range, FoldingRangeKind::Imports);
+                    Some(node.range.clone())
+                }
+                None => Some(node.range.clone()),
+            };
+        }
+        if let Some(range) = group {
+            self.push_fold(&mut ranges, range, FoldingRangeKind::Imports);
+        }
+
+        ranges.sort_by_key(|range| (range.start_line, range.end_line));
+        ranges
+    }
+
+    /// Symbols declared in the file, nested by block.
+    #[must_use]
+    pub fn outline(&self) -> Vec<OutlineItem> {
+        self.outline_items(&self.root, false)
+    }
+
+    /// Add a fold for every block spanning more than one line.
+    fn collect_block_folds(&self, node: &SyntaxNode, ranges: &mut Vec<FoldingRange>) {
+        for child in node.nodes() {
+            if child.kind == NodeKind::Block {
+                self.push_fold(ranges, child.range.clone(), FoldingRangeKind::Region);
+            }
+            self.collect_block_folds(child, ranges);
+        }
+    }
+
+    /// Add a fold for a byte range if it spans more than one line.
+    fn push_fold(
+        &self,
+        ranges: &mut Vec<FoldingRange>,
+        range: Range<usize>,
+        kind: FoldingRangeKind,
+    ) {
+        let start_line = self.line_of(range.start);
+        let end_line = self.line_of(range.end.saturating_sub(1).max(range.start));
+        if end_line > start_line {
+            ranges.push(FoldingRange { start_line, end_line, kind });
+        }
+    }
+
+    /// Outline entries for the items directly inside a node.
+    fn outline_items(&self, node: &SyntaxNode, exported: bool) -> Vec<OutlineItem> {
+        let mut items = Vec::new();
+        for child in node.nodes() {
+            match child.kind {
+                NodeKind::Import => {
+                    items.push(self.outline_entry(
+                        child,
+                        self.declaration_text(child),
+                        SymbolKind::Import,
+                    ));
+                }
+                NodeKind::Export if child.nodes().any(|inner| inner.kind.is_item()) => {
+                    items.extend(self.outline_items(child, true));
+                }
+                NodeKind::Export => {
+                    items.push(self.outline_entry(
+                        child,
+                        self.declaration_text(child),
+                        SymbolKind::Export,
+                    ));
+                }
+                NodeKind::Field => {
+                    let name = self.name_of(child).map_or("", |index| self.token_text(index));
+                    let mut item = self.outline_entry(child, name.to_owned(), SymbolKind::Field);
+                    item.detail =
+                        child.child(NodeKind::TypeRef).map(|ty| self.node_text(ty).to_owned());
+                    items.push(item);
+                }
+                NodeKind::Component
+                | NodeKind::System
+                | NodeKind::Task
+                | NodeKind::Scene
+                | NodeKind::Shader
+                | NodeKind::Function => items.push(self.outline_item(child, exported)),
+                NodeKind::SourceFile
+                | NodeKind::ParamList
+                | NodeKind::Param
+                | NodeKind::ReturnType
+                | NodeKind::TypeRef
+                | NodeKind::Block
+                | NodeKind::Statement
+                | NodeKind::Error => {}
+            }
+        }
+        items
+    }
+
+    /// Outline entry for a named item, with its members as children.
+    fn outline_item(&self, node: &SyntaxNode, exported: bool) -> OutlineItem {
+        let kind = match node.kind {
+            NodeKind::Component => SymbolKind::Component,
+            NodeKind::System => SymbolKind::System,
+            NodeKind::Task => SymbolKind::Task,
+            NodeKind::Scene => SymbolKind::Scene,
+            NodeKind::Shader => SymbolKind::Shader,
+            NodeKind::SourceFile
+            | NodeKind::Import
+            | NodeKind::Export
+            | NodeKind::Function
+            | NodeKind::ParamList
+            | NodeKind::Param
+            | NodeKind::ReturnType
+            | NodeKind::TypeRef
+            | NodeKind::Field
+            | NodeKind::Block
+            | NodeKind::Statement
+            | NodeKind::Error => SymbolKind::Function,
+        };
+        let name = self.name_of(node).map_or("<anonymous>", |index| self.token_text(index));
+
+        let mut item = self.outline_entry(node, name.to_owned(), kind);
+        item.exported = exported;
+        if let Some(params) = node.child(NodeKind::ParamList) {
+            let mut signature = self.node_text(params).to_owned();
+            if let Some(returns) = node.child(NodeKind::ReturnType) {
+                signature.push(' ');
+                signature.push_str(self.node_text(returns));
+            }
+            item.detail = Some(signature);
+        }
+        if let Some(block) = node.child(NodeKind::Block) {
+            item.children = self.outline_items(block, false);
+        }
+        item
+    }
+
+    /// Outline entry without detail or children.
+    fn outline_entry(&self, node: &SyntaxNode, name: String, kind: SymbolKind) -> OutlineItem {
+        OutlineItem {
+            name,
+            kind,
+            detail: None,
+            exported: false,
+            line: self.line_of(node.range.start),
+            range: node.range.clone(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Text of a declaration after its keyword, e.g. the path of an import.
+    fn declaration_text(&self, node: &SyntaxNode) -> String {
+        let text = self.node_text(node);
+        let rest = text.split_once(char::is_whitespace).map_or("", |(_, rest)| rest);
+        rest.trim().trim_end_matches(';').trim().to_owned()
+    }
+}
+
+/// Split Alux source into tokens, trivia included.
+#[must_use]
+pub fn lex(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    while start < text.len() {
+        let (kind, len) = next_token(&text[start..]);
+        tokens.push(Token { kind, range: start..start + len });
+        start += len;
+    }
+    tokens
+}
+
+/// Kind and byte length of the token at the start of `rest`.
+fn next_token(rest: &str) -> (TokenKind, usize) {
+    let mut chars = rest.chars();
+    let first = chars.next().unwrap_or_default();
+    let second = chars.next();
+
+    if first.is_whitespace() {
+        let len = rest.find(|c: char| !c.is_whitespace()).unwrap_or(rest.len());
+        return (TokenKind::Whitespace, len);
+    }
+    if rest.starts_with("//") {
+        let len = rest.find('\n').unwrap_or(rest.len());
+        let doc = rest.starts_with("///") && !rest.starts_with("////");
+        return (if doc { TokenKind::DocComment } else { TokenKind::Comment }, len);
+    }
+    if let Some(body) = rest.strip_prefix("/*") {
+        // Unclosed comments run to the end of the source.
+        let len = body.find("*/").map_or(rest.len(), |end| end + 4);
+        return (TokenKind::Comment, len);
+    }
+    if first == '"' || first == '\'' {
+        return (TokenKind::String, string_length(rest, first));
+    }
+    if first.is_ascii_digit() || (first == '.' && second.is_some_and(|c| c.is_ascii_digit())) {
+        return (TokenKind::Number, number_length(rest));
+    }
+    if first.is_alphabetic() || first == '_' {
+        let len = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+        let kind =
+            if is_keyword(&rest[..len]) { TokenKind::Keyword } else { TokenKind::Identifier };
+        return (kind, len);
+    }
+    if let Some(operator) = OPERATORS.iter().find(|operator| rest.starts_with(*operator)) {
+        return (TokenKind::Operator, operator.len());
+    }
+    if rest.starts_with("::") {
+        return (TokenKind::Punctuation, 2);
+    }
+    if PUNCTUATION.contains(first) {
+        return (TokenKind::Punctuation, 1);
+    }
+    (TokenKind::Unknown, first.len_utf8())
+}
+
+/// Length of a string literal starting with `quote`; unclosed strings run to the end.
+fn string_length(rest: &str, quote: char) -> usize {
+    let mut escaped = false;
+    for (offset, c) in rest.char_indices().skip(1) {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == quote {
+            return offset + c.len_utf8();
+        }
+    }
+    rest.len()
+}
+
+/// Length of a number literal: hex, binary or decimal with optional
+/// fraction, exponent and type suffix.
+fn number_length(rest: &str) -> usize {
+    let bytes = rest.as_bytes();
+    let skip = |from: usize, accept: fn(&u8) -> bool| {
+        from + bytes[from.min(bytes.len())..].iter().take_while(|byte| accept(byte)).count()
+    };
+
+    if bytes.len() > 2 && bytes[0] == b'0' {
+        let end = match bytes[1] {
+            b'x' | b'X' => skip(2, u8::is_ascii_hexdigit),
+            b'b' | b'B' => skip(2, |byte| matches!(byte, b'0' | b'1')),
+            _ => 0,
+        };
+        if end > 2 {
+            return end;
+        }
+    }
+
+    let mut end = skip(0, u8::is_ascii_digit);
+    if bytes.get(end) == Some(&b'.') && bytes.get(end + 1).is_some_and(u8::is_ascii_digit) {
+        end = skip(end + 1, u8::is_ascii_digit);
+    }
+    if matches!(bytes.get(end), Some(b'e' | b'E')) {
+        let mut exponent = end + 1;
+        if matches!(bytes.get(exponent), Some(b'+' | b'-')) {
+            exponent += 1;
+        }
+        if bytes.get(exponent).is_some_and(u8::is_ascii_digit) {
+            end = skip(exponent, u8::is_ascii_digit);
+        }
+    }
+    if let Some(suffix) = NUMBER_SUFFIXES.iter().find(|suffix| rest[end..].starts_with(*suffix)) {
+        end += suffix.len();
+    }
+    end
+}
+
+/// Recursive descent parser building the syntax tree.
+struct Parser<'a> {
+    /// Source text.
+    text: &'a str,
+    /// Tokens of the source.
+    tokens: &'a [Token],
+    /// Index of the next unconsumed token.
+    pos: usize,
+    /// Nodes being built, innermost last.
+    stack: Vec<SyntaxNode>,
+}
+
+impl<'a> Parser<'a> {
+    const fn new(text: &'a str, tokens: &'a [Token]) -> Self {
+        Self { text, tokens, pos: 0, stack: Vec::new() }
+    }
+
+    /// Parse a whole source file.
+    fn parse(mut self) -> SyntaxNode {
+        self.stack.push(SyntaxNode {
+            kind: NodeKind::SourceFile,
+            range: 0..self.text.len(),
+            children: Vec::new(),
+        });
+        while !self.eof() {
+            self.item_or_statement();
+        }
+        self.trivia();
+
+        let mut root = self.stack.pop().unwrap_or_else(|| SyntaxNode {
+            kind: NodeKind::SourceFile,
+            range: 0..0,
+            children: Vec::new(),
+        });
+        root.range = 0..self.text.len();
+        root
+    }
+
+    // --- Token access ---
+
+    /// Index of the `n`-th non-trivia token ahead.
+    fn nth(&self, n: usize) -> Option<usize> {
+        (self.pos..self.tokens.len()).filter(|&index| !self.tokens[index].kind.is_trivia()).nth(n)
+    }
+
+    /// Text of the `n`-th non-trivia token ahead, or `""` at the end.
+    fn nth_text(&self, n: usize) -> &'a str {
+        self.nth(n).map_or("", |index| &self.text[self.tokens[index].range.clone()])
+    }
+
+    /// Whether the next token is punctuation, an operator or a keyword with this text.
+    fn at(&self, text: &str) -> bool {
+        self.nth(0).is_some_and(|index| {
+            !matches!(self.tokens[index].kind, TokenKind::String | TokenKind::Identifier)
+                && &self.text[self.tokens[index].range.clone()] == text
+        })
+    }
+
+    fn at_identifier(&self) -> bool {
+        self.nth(0).is_some_and(|index| self.tokens[index].kind == TokenKind::Identifier)
+    }
+
+    fn eof(&self) -> bool {
+        self.nth(0).is_none()
+    }
+
+    /// Whether a line break separates the previous token from the next one.
+    fn newline_before_next(&self) -> bool {
+        self.tokens[self.pos..].iter().take_while(|token| token.kind.is_trivia()).any(|token| {
+            token.kind == TokenKind::Whitespace && self.text[token.range.clone()].contains('\n')
+        })
+    }
+
+    // --- Tree building ---
+
+    /// Attach pending trivia to the current node.
+    fn trivia(&mut self) {
+        while self.pos < self.tokens.len() && self.tokens[self.pos].kind.is_trivia() {
+            self.push(SyntaxElement::Token(self.pos));
+            self.pos += 1;
+        }
+    }
+
+    /// Consume the next non-trivia token into the current node.
+    fn bump(&mut self) {
+        self.trivia();
+        if self.pos < self.tokens.len() {
+            self.push(SyntaxElement::Token(self.pos));
+            self.pos += 1;
+        }
+    }
+
+    fn push(&mut self, element: SyntaxElement) {
+        if let Some(node) = self.stack.last_mut() {
+            node.children.push(element);
+        }
+    }
+
+    /// Open a node; pending trivia stays with the parent.
+    fn start(&mut self, kind: NodeKind) {
+        self.trivia();
+        self.stack.push(SyntaxNode { kind, range: 0..0, children: Vec::new() });
+    }
+
+    /// Close the current node and attach it to its parent.
+    fn finish(&mut self) {
+        let Some(mut node) = self.stack.pop() else {
+            return;
+        };
+        let range_of = |element: &SyntaxElement| match element {
+            SyntaxElement::Node(node) => node.range.clone(),
+            SyntaxElement::Token(index) => self.tokens[*index].range.clone(),
+        };
+        let here = self.tokens.get(self.pos).map_or(self.text.len(), |token| token.range.start);
+        let start = node.children.first().map_or(here, |child| range_of(child).start);
+        let end = node.children.last().map_or(here, |child| range_of(child).end);
+        node.range = start..end;
+        self.push(SyntaxElement::Node(node));
+    }
+
+    // --- Grammar ---
+
+    /// Number of modifiers ahead and the item keyword after them, if any.
+    fn item_ahead(&self) -> Option<(usize, &'a str)> {
+        let modifiers =
+            (0..self.tokens.len()).take_while(|&n| MODIFIERS.contains(&self.nth_text(n))).count();
+        let keyword = self.nth_text(modifiers);
+        matches!(keyword, "component" | "system" | "task" | "scene" | "shader" | "fn")
+            .then_some((modifiers, keyword))
+    }
+
+    fn item_or_statement(&mut self) {
+        if let Some((modifiers, keyword)) = self.item_ahead() {
+            match keyword {
+                "component" => self.named_block(NodeKind::Component, modifiers),
+                "scene" => self.named_block(NodeKind::Scene, modifiers),
+                "shader" => self.named_block(NodeKind::Shader, modifiers),
+                "system" => self.function(NodeKind::System, modifiers),
+                "task" => self.function(NodeKind::Task, modifiers),
+                _ => self.function(NodeKind::Function, modifiers),
+            }
+        } else if self.at("import") || self.at("use") {
+            self.start(NodeKind::Import);
+            self.bump();
+            self.declaration_rest();
+            self.finish();
+        } else if self.at("export") {
+            self.start(NodeKind::Export);
+            self.bump();
+            if self.item_ahead().is_some() {
+                self.item_or_statement();
+            } else {
+                self.declaration_rest();
+            }
+            self.finish();
+        } else if self.at("}") {
+            self.start(NodeKind::Error);
+            self.bump();
+            self.finish();
+        } else {
+            self.statement();
+        }
+    }
+
+    /// Rest of an import or export list: up to `;` or the end of the line.
+    fn declaration_rest(&mut self) {
+        let mut depth = 0usize;
+        while !self.eof() {
+            if depth == 0 && (self.newline_before_next() || self.at("}")) {
+                break;
+            }
+            let text = self.nth_text(0);
+            self.bump();
+            match text {
+                "{" => depth += 1,
+                "}" => depth = depth.saturating_sub(1),
+                ";" if depth == 0 => break,
+                _ => {}
+            }
+        }
+    }
+
+    /// `system`, `task` and `fn` items: signature and optional body.
+    fn function(&mut self, kind: NodeKind, modifiers: usize) {
+        self.start(kind);
+        for _ in 0..=modifiers {
+            self.bump();
+        }
+        if self.at_identifier() {
+            self.bump();
+        }
+        if self.at("<") {
+            self.balanced("<", ">");
+        }
+        if self.at("(") {
+            self.param_list();
+        }
+        if self.at("->") {
+            self.start(NodeKind::ReturnType);
+            self.bump();
+            self.type_ref();
+            self.finish();
+        }
+        if self.at("{") {
+            self.block(false);
+        } else if self.at(";") {
+            self.bump();
+        }
+        self.finish();
+    }
+
+    /// `component`, `scene` and `shader` items: name, header and body.
+    fn named_block(&mut self, kind: NodeKind, modifiers: usize) {
+        self.start(kind);
+        for _ in 0..=modifiers {
+            self.bump();
+        }
+        if self.at_identifier() {
+            self.bump();
+        }
+        // Anything else on the header line, e.g. `shader Blur: fragment`.
+        while !self.eof() && !self.at("{") && !self.at("}") && !self.at(";") {
+            if self.newline_before_next() {
+                break;
+            }
+            self.bump();
+        }
+        if self.at("{") {
+            self.block(kind == NodeKind::Component);
+        } else if self.at(";") {
+            self.bump();
+        }
+        self.finish();
+    }
+
+    /// Consume tokens from `open` through its matching `close`.
+    fn balanced(&mut self, open: &str, close: &str) {
+        let mut depth = 0usize;
+        while !self.eof() && !self.at("{") {
+            let text = self.nth_text(0);
+            self.bump();
+            if text == open {
+                depth += 1;
+            } else if text == close {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    break;
+                }
+            }
+        }
+    }
+
+    fn param_list(&mut self) {
+        self.start(NodeKind::ParamList);
+        self.bump();
+        loop {
+            if self.eof() || self.at("{") {
+                break;
+            }
+            if self.at(")") {
+                self.bump();
+                break;
+            }
+            if self.at(",") {
+                self.bump();
+                continue;
+            }
+
+            self.start(NodeKind::Param);
+            if self.at("mut") {
+                self.bump();
+            }
+            if !self.at(":") {
+                self.bump();
+            }
+            if self.at(":") {
+                self.bump();
+                self.type_ref();
+            }
+            if self.at("=") {
+                self.bump();
+                self.expression(&[",", ")"]);
+            }
+            self.finish();
+        }
+        self.finish();
+    }
+
+    fn type_ref(&mut self) {
+        self.start(NodeKind::TypeRef);
+        let mut depth = 0usize;
+        let mut consumed = false;
+        while !self.eof() {
+            let text = self.nth_text(0);
+            if depth == 0
+                && (matches!(text, "," | ")" | "{" | "}" | "=" | ";" | ">")
+                    || (consumed && self.newline_before_next()))
+            {
+                break;
+            }
+            match text {
+                "<" | "(" | "[" => depth += 1,
+                ">" | ")" | "]" => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            self.bump();
+            consumed = true;
+        }
+        self.finish();
+    }
+
+    /// A `{ ... }` block. Component blocks hold `name: Type` fields.
+    fn block(&mut self, fields: bool) {
+        self.start(NodeKind::Block);
+        self.bump();
+        loop {
+            if self.eof() {
+                break;
+            }
+            if self.at("}") {
+                self.bump();
+                break;
+            }
+            if fields && self.at_identifier() && self.nth_text(1) == ":" {
+                self.field();
+            } else {
+                self.item_or_statement();
+            }
+        }
+        self.finish();
+    }
+
+    fn field(&mut self) {
+        self.start(NodeKind::Field);
+        self.bump();
+        self.bump();
+        self.type_ref();
+        if self.at("=") {
+            self.bump();
+            self.expression(&[",", ";", "}"]);
+        }
+        if self.at(",") || self.at(";") {
+            self.bump();
+        }
+        self.finish();
+    }
+
+    /// Consume tokens until one of `stops` at nesting depth zero.
+    fn expression(&mut self, stops: &[&str]) {
+        let mut depth = 0usize;
+        while !self.eof() {
+            let text = self.nth_text(0);
+            if depth == 0 && stops.contains(&text) {
+                break;
+            }
+            if text == "{" {
+                self.block(false);
+                continue;
+            }
+            if text == "}" {
+                break;
+            }
+            self.bump();
+            match text {
+                "(" | "[" => depth += 1,
+                ")" | "]" => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+    }
+
+    /// Any other statement: up to `;`, or up to the end of a trailing block.
+    fn statement(&mut self) {
+        self.start(NodeKind::Statement);
+        let mut depth = 0usize;
+        while !self.eof() {
+            let text = self.nth_text(0);
+            if text == "}" {
+                break;
+            }
+            if text == "{" {
+                self.block(false);
+                if depth > 0 || self.at("else") || self.at("elif") {
+                    continue;
+                }
+                if self.at(";") {
+                    self.bump();
+                }
+                break;
+            }
+            self.bump();
+            match text {
+                "(" | "[" => depth += 1,
+                ")" | "]" => depth = depth.saturating_sub(1),
+                ";" if depth == 0 => break,
+                _ => {}
+            }
+        }
+        self.finish();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"import engine.math;
+import engine.physics as phys
+
+/// Position in world space.
+export component Position {
+    x: f32,
+    y: f32 = 0.0,
+}
+
+system movement(query: Query<Position>, dt: f32) -> bool {
+    for p in query {
+        p.x += dt;
+    }
+    return true;
+}
+
+task load_assets() {
+    await spawn(load());
+}
+
+scene Main {
+    fn setup() {}
+}
+
+shader Blur: fragment {
+    /* sampling
+       code */
+}
+
+export { movement, load_assets };
+"#;
+
+    fn texts<'a>(tree: &'a SyntaxTree, kind: TokenKind) -> Vec<&'a str> {
+        (0..tree.tokens().len())
+            .filter(|&index| tree.tokens()[index].kind == kind)
+            .map(|index| tree.token_text(index))
+            .collect()
+    }
+
+    #[test]
+    fn test_lexer_is_lossless() {
+        let tokens = lex(SOURCE);
+        let rebuilt: String = tokens.iter().map(|token| &SOURCE[token.range.clone()]).collect();
+        assert_eq!(rebuilt, SOURCE);
+    }
+
+    #[test]
+    fn test_lex_literals() {
+        let tree = SyntaxTree::parse(
+            "let a = 0xFF + 0b1010 + 1.23e-4 + 42i32 + 3.14; 'c' \"s\\\"q\" /* c */",
+        );
+        assert_eq!(
+            texts(&tree, TokenKind::Number),
+            vec!["0xFF", "0b1010", "1.23e-4", "42i32", "3.14"]
+        );
+        assert_eq!(texts(&tree, TokenKind::String), vec!["'c'", "\"s\\\"q\""]);
+        assert_eq!(texts(&tree, TokenKind::Comment), vec!["/* c */"]);
+
+        let unclosed = SyntaxTree::parse("\"unclosed /* no");
+        assert_eq!(texts(&unclosed, TokenKind::String), vec!["\"unclosed /* no"]);
+    }
+
+    #[test]
+    fn test_parse_items() {
+        let tree = SyntaxTree::parse(SOURCE);
+        let kinds: Vec<NodeKind> = tree.root().nodes().map(|node| node.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                NodeKind::Import,
+                NodeKind::Import,
+                NodeKind::Export,
+                NodeKind::System,
+                NodeKind::Task,
+                NodeKind::Scene,
+                NodeKind::Shader,
+                NodeKind::Export,
+            ]
+        );
+
+        let system = tree.root().child(NodeKind::System).unwrap();
+        let params: Vec<&str> = system
+            .child(NodeKind::ParamList)
+            .unwrap()
+            .nodes()
+            .map(|param| tree.node_text(param))
+            .collect();
+        assert_eq!(params, vec!["query: Query<Position>", "dt: f32"]);
+        assert_eq!(tree.node_text(system.child(NodeKind::ReturnType).unwrap()), "-> bool");
+    }
+
+    #[test]
+    fn test_outline() {
+        let tree = SyntaxTree::parse(SOURCE);
+        let outline = tree.outline();
+        let names: Vec<(&str, SymbolKind)> =
+            outline.iter().map(|item| (item.name.as_str(), item.kind)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("engine.math", SymbolKind::Import),
+                ("engine.physics as phys", SymbolKind::Import),
+                ("Position", SymbolKind::Component),
+                ("movement", SymbolKind::System),
+                ("load_assets", SymbolKind::Task),
+                ("Main", SymbolKind::Scene),
+                ("Blur", SymbolKind::Shader),
+                ("{ movement, load_assets }", SymbolKind::Export),
+            ]
+        );
+
+        let position = &outline[2];
+        assert!(position.exported);
+        assert_eq!(position.line, 4);
+        let fields: Vec<(&str, Option<&str>)> =
+            position.children.iter().map(|f| (f.name.as_str(), f.detail.as_deref())).collect();
+        assert_eq!(fields, vec![("x", Some("f32")), ("y", Some("f32"))]);
+
+        assert_eq!(outline[3].detail.as_deref(), Some("(query: Query<Position>, dt: f32) -> bool"));
+        assert_eq!(outline[5].children[0].name, "setup");
+        assert_eq!(outline[5].children[0].kind, SymbolKind::Function);
+    }
+
+    #[test]
+    fn test_folding_ranges() {
+        let tree = SyntaxTree::parse(SOURCE);
+        let folds: Vec<(usize, usize, FoldingRangeKind)> = tree
+            .folding_ranges()
+            .iter()
+            .map(|fold| (fold.start_line, fold.end_line, fold.kind))
+            .collect();
+        assert_eq!(
+            folds,
+            vec![
+                (0, 1, FoldingRangeKind::Imports),
+                (4, 7, FoldingRangeKind::Region),
+                (9, 14, FoldingRangeKind::Region),
+                (10, 12, FoldingRangeKind::Region),
+                (16, 18, FoldingRangeKind::Region),
+                (20, 22, FoldingRangeKind::Region),
+                (24, 27, FoldingRangeKind::Region),
+                (25, 26, FoldingRangeKind::Comment),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recovers_from_unbalanced_braces() {
+        let tree = SyntaxTree::parse("}\nsystem broken( {\n    let x = (1;\n}\nfn after() {}\n");
+        let kinds: Vec<NodeKind> = tree.root().nodes().map(|node| node.kind).collect();
+        assert_eq!(kinds, vec![NodeKind::Error, NodeKind::System, NodeKind::Function]);
+        assert_eq!(tree.outline()[1].name, "after");
+    }
+}
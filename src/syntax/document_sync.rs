@@ -0,0 +1,630 @@
+//! # Document Synchronization
+//!
+//! Keeps language servers in sync with the documents open in the editor by
+//! translating editor events into `didOpen`/`didChange`/`didSave`/`didClose`
+//! notifications.
+
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use tokio::sync::RwLock;
+use tracing::{debug, error, trace, warn};
+
+use crate::core::{EditorEvent, Event, EventHandler, EventMessage, EventPriority, Result};
+use crate::editor::Buffer;
+use crate::syntax::lsp_client::{
+    Position, Range, TextDocumentContentChange, TextDocumentSyncCapability,
+};
+use crate::syntax::{ExtensionLanguages, LspClient, resolve_language};
+
+/// LSP clients by language, shared with the syntax manager.
+type LspClients = Arc<RwLock<HashMap<String, Box<dyn LspClient + Send + Sync>>>>;
+
+/// Source of the current text of open documents, normally the editor's buffers.
+#[async_trait::async_trait]
+pub trait DocumentSource: Send + Sync {
+    /// Get the text of an open document, with `\n` line endings.
+    async fn document_text(&self, path: &Path) -> Option<String>;
+}
+
+#[async_trait::async_trait]
+impl<S: BuildHasher + Send + Sync> DocumentSource for RwLock<HashMap<PathBuf, Buffer, S>> {
+    async fn document_text(&self, path: &Path) -> Option<String> {
+        self.read().await.get(path).map(|buffer| buffer.snapshot().to_string())
+    }
+}
+
+/// Mirror of a document as last sent to its language server.
+#[derive(Debug, Clone)]
+pub struct SyncedDocument {
+    /// Language the document belongs to.
+    language: String,
+    /// Version of the last change sent.
+    version: i32,
+    /// Document lines, without their `\n` terminators.
+    lines: Vec<String>,
+}
+
+impl SyncedDocument {
+    /// Create a mirror of freshly opened content.
+    pub fn new(language: String, text: &str) -> Self {
+        Self { language, version: 0, lines: text.split('\n').map(String::from).collect() }
+    }
+
+    /// Get the language of the document.
+    #[must_use]
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Get the version of the last change.
+    #[must_use]
+    pub const fn version(&self) -> i32 {
+        self.version
+    }
+
+    /// Get the full text of the document.
+    #[must_use]
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    /// Replace the whole text, as a new version.
+    pub fn replace(&mut self, text: &str) {
+        self.lines = text.split('\n').map(String::from).collect();
+        self.version += 1;
+    }
+
+    /// Apply an insertion at a byte position and describe it as an LSP change.
+    ///
+    /// Returns `None` when the position is outside the mirrored text.
+    pub fn insert(
+        &mut self,
+        line: usize,
+        column: usize,
+        text: &str,
+    ) -> Option<TextDocumentContentChange> {
+        let current = self.lines.get(line)?;
+        if !current.is_char_boundary(column) {
+            return None;
+        }
+
+        let position = Position::new(line, utf16_len(&current[..column]));
+        let tail = current[column..].to_string();
+        let head = current[..column].to_string();
+
+        let mut inserted = text.split('\n').map(String::from).collect::<Vec<_>>();
+        if let Some(first) = inserted.first_mut() {
+            first.insert_str(0, &head);
+        }
+        if let Some(last) = inserted.last_mut() {
+            last.push_str(&tail);
+        }
+        self.lines.splice(line..=line, inserted);
+        self.version += 1;
+
+        Some(TextDocumentContentChange::incremental(
+            Range::new(position.clone(), position),
+            text.to_owned(),
+        ))
+    }
+
+    /// Apply a deletion of a byte range and describe it as an LSP change.
+    ///
+    /// Returns `None` when the range is outside the mirrored text.
+    pub fn delete(
+        &mut self,
+        start_line: usize,
+        start_column: usize,
+        end_line: usize,
+        end_column: usize,
+    ) -> Option<TextDocumentContentChange> {
+        let first = self.lines.get(start_line)?;
+        let last = self.lines.get(end_line)?;
+        let end_column = end_column.min(last.len());
+        if start_line > end_line
+            || !first.is_char_boundary(start_column)
+            || !last.is_char_boundary(end_column)
+            || (start_line == end_line && start_column > end_column)
+        {
+            return None;
+        }
+
+        let range = Range::new(
+            Position::new(start_line, utf16_len(&first[..start_column])),
+            Position::new(end_line, utf16_len(&last[..end_column])),
+        );
+        let merged = format!("{}{}", &first[..start_column], &last[end_column..]);
+        self.lines.splice(start_line..=end_line, [merged]);
+        self.version += 1;
+
+        Some(TextDocumentContentChange::incremental(range, String::new()))
+    }
+}
+
+/// Count the UTF-16 code units in a string, the default LSP position encoding.
+fn utf16_len(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+/// Forwards editor changes to the language server responsible for each file.
+pub struct DocumentSync {
+    /// LSP clients by language.
+    lsp_clients: LspClients,
+    /// Documents currently open, by path.
+    documents: RwLock<HashMap<PathBuf, SyncedDocument>>,
+    /// Whether LSP is enabled.
+    lsp_enabled: Arc<RwLock<bool>>,
+    /// File extensions routed to configured language servers.
+    extension_languages: ExtensionLanguages,
+    /// Where the current text of open documents is read from, if attached.
+    source: RwLock<Option<Arc<dyn DocumentSource>>>,
+}
+
+#[expect(
+    clippy::significant_drop_tightening,
+    reason = "guards are held while the documents and clients they borrow are in use"
+)]
+impl DocumentSync {
+    /// Create a document synchronizer over the given clients.
+    pub fn new(
+        lsp_clients: LspClients,
+        lsp_enabled: Arc<RwLock<bool>>,
+        extension_languages: ExtensionLanguages,
+    ) -> Self {
+        Self {
+            lsp_clients,
+            documents: RwLock::new(HashMap::new()),
+            lsp_enabled,
+            extension_languages,
+            source: RwLock::new(None),
+        }
+    }
+
+    /// Read document contents from `source`, normally the editor's buffers.
+    ///
+    /// Without a source, opened files are read from disk and edits the
+    /// mirror cannot place are resent from the mirror.
+    pub async fn set_source(&self, source: Arc<dyn DocumentSource>) {
+        *self.source.write().await = Some(source);
+    }
+
+    /// Get the current text of a document from the source.
+    ///
+    /// Falls back to the file on disk, with line endings normalized the way
+    /// the editor's buffers do.
+    async fn current_text(&self, path: &Path) -> Option<String> {
+        let source = self.source.read().await.clone();
+        if let Some(source) = source {
+            return source.document_text(path).await;
+        }
+        match tokio::fs::read_to_string(path).await {
+            Ok(text) => Some(Buffer::new(&text, None).snapshot().to_string()),
+            Err(e) => {
+                warn!("Failed to read {} for synchronization: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Get the version last sent for a document, if it is open.
+    pub async fn version(&self, path: &Path) -> Option<i32> {
+        self.documents.read().await.get(path).map(SyncedDocument::version)
+    }
+
+    /// Get the synchronized text of a document, if it is open.
+    pub async fn text(&self, path: &Path) -> Option<String> {
+        self.documents.read().await.get(path).map(SyncedDocument::text)
+    }
+
+    /// Get the text a language server works on for a document: the
+    /// synchronized text while it is open, otherwise its current text.
+    pub async fn server_text(&self, path: &Path) -> Option<String> {
+        match self.text(path).await {
+            Some(text) => Some(text),
+            None => self.current_text(path).await,
+        }
+    }
+
+    /// Start tracking a document and send `didOpen`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a notification cannot be sent to the language server.
+    pub async fn open(&self, path: &Path, text: &str) -> Result<()> {
+        if self.documents.read().await.contains_key(path) {
+            // Reopening replaces the content, so start over with a fresh version.
+            self.close(path).await?;
+        }
+
+        let document = SyncedDocument::new(resolve_language(path, &self.extension_languages), text);
+        self.send_open(path, &document).await?;
+        self.documents.write().await.insert(path.to_path_buf(), document);
+        Ok(())
+    }
+
+    /// Send `didOpen` for every tracked document of a language.
+    ///
+    /// Used when a language server (re)starts after documents were opened.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a notification cannot be sent to the language server.
+    pub async fn reopen_all(&self, language: &str) -> Result<()> {
+        let documents = self.documents.read().await;
+        for (path, document) in documents.iter().filter(|(_, d)| d.language() == language) {
+            self.send_open(path, document).await?;
+        }
+        Ok(())
+    }
+
+    /// Apply an insertion and send `didChange`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a notification cannot be sent to the language server.
+    pub async fn insert(&self, path: &Path, line: usize, column: usize, text: &str) -> Result<()> {
+        let mut documents = self.documents.write().await;
+        let Some(document) = documents.get_mut(path) else {
+            return Ok(());
+        };
+
+        let change = document.insert(line, column, text);
+        self.send_change(path, document, change).await
+    }
+
+    /// Apply a deletion and send `didChange`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a notification cannot be sent to the language server.
+    pub async fn delete(
+        &self,
+        path: &Path,
+        start_line: usize,
+        start_column: usize,
+        end_line: usize,
+        end_column: usize,
+    ) -> Result<()> {
+        let mut documents = self.documents.write().await;
+        let Some(document) = documents.get_mut(path) else {
+            return Ok(());
+        };
+
+        let change = document.delete(start_line, start_column, end_line, end_column);
+        self.send_change(path, document, change).await
+    }
+
+    /// Send `didSave` for a tracked document.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a notification cannot be sent to the language server.
+    pub async fn save(&self, path: &Path) -> Result<()> {
+        let documents = self.documents.read().await;
+        let Some(document) = documents.get(path) else {
+            return Ok(());
+        };
+
+        if !*self.lsp_enabled.read().await {
+            return Ok(());
+        }
+
+        let clients = self.lsp_clients.read().await;
+        match clients.get(document.language()) {
+            Some(client) => client.did_save(&path.to_string_lossy()).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Stop tracking a document and send `didClose`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a notification cannot be sent to the language server.
+    pub async fn close(&self, path: &Path) -> Result<()> {
+        let Some(document) = self.documents.write().await.remove(path) else {
+            return Ok(());
+        };
+
+        if !*self.lsp_enabled.read().await {
+            return Ok(());
+        }
+
+        let clients = self.lsp_clients.read().await;
+        match clients.get(document.language()) {
+            Some(client) => client.did_close(&path.to_string_lossy()).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Send `didOpen` for a document to its language server.
+    async fn send_open(&self, path: &Path, document: &SyncedDocument) -> Result<()> {
+        if !*self.lsp_enabled.read().await {
+            return Ok(());
+        }
+
+        let clients = self.lsp_clients.read().await;
+        let Some(client) = clients.get(document.language()) else {
+            return Ok(());
+        };
+
+        debug!("Opening {} (version {})", path.display(), document.version());
+        client
+            .did_open(
+                &path.to_string_lossy(),
+                document.language(),
+                document.version(),
+                &document.text(),
+            )
+            .await
+    }
+
+    /// Send a change using the sync mode the server asked for.
+    ///
+    /// Servers without incremental sync get the whole document instead. So
+    /// do edits the mirror could not place, after the mirror is replaced with
+    /// the current text from the source.
+    async fn send_change(
+        &self,
+        path: &Path,
+        document: &mut SyncedDocument,
+        change: Option<TextDocumentContentChange>,
+    ) -> Result<()> {
+        if change.is_none() {
+            warn!("Edit outside of synchronized text for {}, resending document", path.display());
+            let source = self.source.read().await.clone();
+            match source {
+                Some(source) => match source.document_text(path).await {
+                    Some(text) => document.replace(&text),
+                    None => document.version += 1,
+                },
+                None => document.version += 1,
+            }
+        }
+
+        if !*self.lsp_enabled.read().await {
+            return Ok(());
+        }
+
+        let clients = self.lsp_clients.read().await;
+        let Some(client) = clients.get(document.language()) else {
+            return Ok(());
+        };
+
+        let change = match (client.capabilities().text_document_sync, change) {
+            (None | Some(TextDocumentSyncCapability::None), _) => return Ok(()),
+            (Some(TextDocumentSyncCapability::Incremental), Some(change)) => change,
+            (
+                Some(TextDocumentSyncCapability::Incremental | TextDocumentSyncCapability::Full),
+                _,
+            ) => TextDocumentContentChange::full(document.text()),
+        };
+
+        trace!("Sending change for {} (version {})", path.display(), document.version());
+        client.did_change(&path.to_string_lossy(), document.version(), vec![change]).await
+    }
+}
+
+#[async_trait::async_trait]
+impl EventHandler for DocumentSync {
+    async fn handle(&self, event: &EventMessage) -> Result<()> {
+        let Ok(Event::Editor(event)) = serde_json::from_value::<Event>(event.data.clone()) else {
+            return Ok(());
+        };
+
+        let result = match event {
+            EditorEvent::FileOpened { path } => match self.current_text(&path).await {
+                Some(text) => self.open(&path, &text).await,
+                None => Ok(()),
+            },
+            EditorEvent::TextInserted { path, line, column, text } => {
+                self.insert(&path, line, column, &text).await
+            }
+            EditorEvent::TextDeleted { path, start_line, start_column, end_line, end_column } => {
+                self.delete(&path, start_line, start_column, end_line, end_column).await
+            }
+            EditorEvent::FileSaved { path } => self.save(&path).await,
+            EditorEvent::FileClosed { path } => self.close(&path).await,
+            EditorEvent::CursorMoved { .. }
+            | EditorEvent::SelectionChanged { .. }
+            | EditorEvent::FindReplace { .. }
+            | EditorEvent::Undo
+            | EditorEvent::Redo => Ok(()),
+        };
+
+        if let Err(e) = &result {
+            error!("Failed to synchronize document: {}", e);
+        }
+        result
+    }
+
+    fn can_handle(&self, event_type: &str) -> bool {
+        event_type == "editor"
+    }
+
+    fn priority(&self) -> EventPriority {
+        EventPriority::High
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::syntax::lsp_client::ServerCapabilities;
+    use crate::syntax::{CompletionItem, Diagnostic};
+
+    #[test]
+    fn test_insert_and_delete_mirror() {
+        let mut document = SyncedDocument::new("rust".to_string(), "fn main() {\n}");
+
+        let change = document.insert(0, 11, "\n    let é = 1;").unwrap();
+        assert_eq!(document.text(), "fn main() {\n    let é = 1;\n}");
+        assert_eq!(document.version(), 1);
+        let range = change.range.unwrap();
+        assert_eq!((range.start.line, range.start.character), (0, 11));
+
+        // Byte column 11 follows the two-byte `é`, so it is UTF-16 column 10.
+        let change = document.delete(1, 8, 1, 11).unwrap();
+        assert_eq!(document.text(), "fn main() {\n    let = 1;\n}");
+        let range = change.range.unwrap();
+        assert_eq!((range.start.character, range.end.character), (8, 10));
+
+        let change = document.delete(0, 11, 2, 0).unwrap();
+        assert_eq!(document.text(), "fn main() {}");
+        assert_eq!(change.range.unwrap().end.line, 2);
+
+        assert!(document.insert(5, 0, "x").is_none());
+        assert_eq!(document.version(), 3);
+    }
+
+    /// Client that records the notifications it receives.
+    struct RecordingClient {
+        sync: TextDocumentSyncCapability,
+        received: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl LspClient for RecordingClient {
+        async fn set_root_uri(&self, _uri: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn get_diagnostics(&self, _file_path: &str) -> Result<Vec<Diagnostic>> {
+            Ok(Vec::new())
+        }
+
+        async fn get_completion(
+            &self,
+            _file_path: &str,
+            _line: usize,
+            _column: usize,
+        ) -> Result<Vec<CompletionItem>> {
+            Ok(Vec::new())
+        }
+
+        async fn get_hover(
+            &self,
+            _file_path: &str,
+            _line: usize,
+            _column: usize,
+        ) -> Result<Option<String>> {
+            Ok(None)
+        }
+
+        async fn did_open(
+            &self,
+            _file_path: &str,
+            language_id: &str,
+            version: i32,
+            text: &str,
+        ) -> Result<()> {
+            self.received
+                .lock()
+                .unwrap()
+                .push(format!("open {} {} {}", language_id, version, text));
+            Ok(())
+        }
+
+        async fn did_change(
+            &self,
+            _file_path: &str,
+            version: i32,
+            changes: Vec<TextDocumentContentChange>,
+        ) -> Result<()> {
+            let kind = if changes[0].range.is_some() { "range" } else { "full" };
+            self.received
+                .lock()
+                .unwrap()
+                .push(format!("change {} {} {}", version, kind, changes[0].text));
+            Ok(())
+        }
+
+        async fn did_close(&self, _file_path: &str) -> Result<()> {
+            self.received.lock().unwrap().push("close".to_string());
+            Ok(())
+        }
+
+        async fn shutdown(&self) -> Result<()> {
+            Ok(())
+        }
+
+        fn capabilities(&self) -> ServerCapabilities {
+            ServerCapabilities { text_document_sync: Some(self.sync.clone()), ..Default::default() }
+        }
+    }
+
+    async fn sync_with(
+        sync: TextDocumentSyncCapability,
+    ) -> (DocumentSync, Arc<Mutex<Vec<String>>>) {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let client = RecordingClient { sync, received: Arc::clone(&received) };
+        let clients: LspClients = Arc::new(RwLock::new(HashMap::new()));
+        clients.write().await.insert("rust".to_string(), Box::new(client));
+        (
+            DocumentSync::new(clients, Arc::new(RwLock::new(true)), ExtensionLanguages::default()),
+            received,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_incremental_sync() {
+        let (sync, received) = sync_with(TextDocumentSyncCapability::Incremental).await;
+        let path = PathBuf::from("/project/src/main.rs");
+
+        sync.open(&path, "fn main() {}").await.unwrap();
+        sync.insert(&path, 0, 11, " ").await.unwrap();
+        sync.close(&path).await.unwrap();
+
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec!["open rust 0 fn main() {}", "change 1 range  ", "close"]
+        );
+        assert_eq!(sync.version(&path).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_full_sync_fallback() {
+        let (sync, received) = sync_with(TextDocumentSyncCapability::Full).await;
+        let path = PathBuf::from("/project/src/main.rs");
+
+        sync.open(&path, "fn main() {}").await.unwrap();
+        sync.delete(&path, 0, 0, 0, 3).await.unwrap();
+        assert_eq!(sync.text(&path).await.as_deref(), Some("main() {}"));
+
+        // Ignored paths are not tracked.
+        sync.insert(Path::new("/project/other.rs"), 0, 0, "x").await.unwrap();
+
+        assert_eq!(received.lock().unwrap()[1], "change 1 full main() {}");
+        assert_eq!(received.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_resync_from_source() {
+        let (sync, received) = sync_with(TextDocumentSyncCapability::Incremental).await;
+        let path = PathBuf::from("/project/src/main.rs");
+        let buffers = Arc::new(RwLock::new(HashMap::new()));
+        let buffer = Buffer::new("fn main() {\r\n}\r\n", Some(path.clone()));
+        buffers.write().await.insert(path.clone(), buffer);
+        sync.set_source(buffers.clone()).await;
+
+        // The mirror is seeded from the buffer, without carriage returns.
+        let opened = Event::Editor(EditorEvent::FileOpened { path: path.clone() });
+        sync.handle(&EventMessage::from_event(opened)).await.unwrap();
+        assert_eq!(sync.text(&path).await.as_deref(), Some("fn main() {\n}\n"));
+
+        // An edit the mirror cannot place resends the buffer, not the mirror.
+        buffers.write().await.get_mut(&path).unwrap().insert_text(2, 0, "// end").unwrap();
+        sync.insert(&path, 5, 0, "// end").await.unwrap();
+        assert_eq!(sync.text(&path).await.as_deref(), Some("fn main() {\n}\n// end"));
+        assert_eq!(received.lock().unwrap()[1], "change 1 full fn main() {\n}\n// end");
+
+        // Later incremental changes apply to the resynchronized text.
+        sync.insert(&path, 2, 6, "!").await.unwrap();
+        assert_eq!(received.lock().unwrap()[2], "change 2 range !");
+    }
+}
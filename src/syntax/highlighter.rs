@@ -2,8 +2,10 @@
 //!
 //! Trait definitions and interfaces for syntax highlighting functionality.
 
+use std::path::Path;
+
 use crate::core::Result;
-use crate::syntax::{HighlightToken, TokenType};
+use crate::syntax::{FoldingRange, HighlightToken, OutlineItem, TokenType};
 
 /// Trait for syntax highlighters.
 #[async_trait::async_trait]
@@ -11,6 +13,24 @@ pub trait SyntaxHighlighter {
     /// Highlight the given content and return tokens.
     async fn highlight(&self, content: &str) -> Result<Vec<HighlightToken>>;
 
+    /// Highlight the content of a specific file.
+    ///
+    /// Highlighters that keep per-file state, such as syntax trees to reparse
+    /// incrementally, override this; by default the path is ignored.
+    async fn highlight_file(&self, _path: &Path, content: &str) -> Result<Vec<HighlightToken>> {
+        self.highlight(content).await
+    }
+
+    /// Compute foldable regions. Highlighters without a parser have none.
+    async fn folding_ranges(&self, _content: &str) -> Result<Vec<FoldingRange>> {
+        Ok(Vec::new())
+    }
+
+    /// Compute the symbol outline. Highlighters without a parser have none.
+    async fn outline(&self, _content: &str) -> Result<Vec<OutlineItem>> {
+        Ok(Vec::new())
+    }
+
     /// Get the language name this highlighter supports.
     fn language(&self) -> &str {
         "unknown"
@@ -39,6 +59,17 @@ pub trait SyntaxHighlighter {
             TokenType::Type => Some("#4ec9b0"),        // Cyan
             TokenType::Operator => Some("#d4d4d4"),    // Light gray
             TokenType::Punctuation => Some("#d4d4d4"), // Light gray
+            TokenType::Macro => Some("#4fc1ff"),       // Bright blue
+            TokenType::Lifetime => Some("#c586c0"),    // Purple
+            TokenType::Attribute => Some("#d7ba7d"),   // Gold
+            TokenType::DocComment => Some("#608b4e"),  // Dark green
+            TokenType::Property => Some("#9cdcfe"),    // Light blue
+            TokenType::Escape => Some("#d7ba7d"),      // Gold
+            TokenType::Constant => Some("#569cd6"),    // Blue
+            TokenType::Date => Some("#b8d7a3"),        // Pale green
+            TokenType::Heading => Some("#569cd6"),     // Blue
+            TokenType::Emphasis => Some("#c586c0"),    // Purple
+            TokenType::Link => Some("#3794ff"),        // Bright blue
         }
     }
 }
@@ -96,6 +127,17 @@ impl SyntaxTheme {
         token_colors.insert(TokenType::Type, "#4ec9b0".to_string());
         token_colors.insert(TokenType::Operator, "#d4d4d4".to_string());
         token_colors.insert(TokenType::Punctuation, "#d4d4d4".to_string());
+        token_colors.insert(TokenType::Macro, "#4fc1ff".to_string());
+        token_colors.insert(TokenType::Lifetime, "#c586c0".to_string());
+        token_colors.insert(TokenType::Attribute, "#d7ba7d".to_string());
+        token_colors.insert(TokenType::DocComment, "#608b4e".to_string());
+        token_colors.insert(TokenType::Property, "#9cdcfe".to_string());
+        token_colors.insert(TokenType::Escape, "#d7ba7d".to_string());
+        token_colors.insert(TokenType::Constant, "#569cd6".to_string());
+        token_colors.insert(TokenType::Date, "#b8d7a3".to_string());
+        token_colors.insert(TokenType::Heading, "#569cd6".to_string());
+        token_colors.insert(TokenType::Emphasis, "#c586c0".to_string());
+        token_colors.insert(TokenType::Link, "#3794ff".to_string());
 
         Self {
             name: "Dark".to_string(),
@@ -117,6 +159,17 @@ impl SyntaxTheme {
         token_colors.insert(TokenType::Type, "#267f99".to_string());
         token_colors.insert(TokenType::Operator, "#000000".to_string());
         token_colors.insert(TokenType::Punctuation, "#000000".to_string());
+        token_colors.insert(TokenType::Macro, "#0070c1".to_string());
+        token_colors.insert(TokenType::Lifetime, "#af00db".to_string());
+        token_colors.insert(TokenType::Attribute, "#808000".to_string());
+        token_colors.insert(TokenType::DocComment, "#006400".to_string());
+        token_colors.insert(TokenType::Property, "#0451a5".to_string());
+        token_colors.insert(TokenType::Escape, "#ee0000".to_string());
+        token_colors.insert(TokenType::Constant, "#0000ff".to_string());
+        token_colors.insert(TokenType::Date, "#098658".to_string());
+        token_colors.insert(TokenType::Heading, "#800000".to_string());
+        token_colors.insert(TokenType::Emphasis, "#af00db".to_string());
+        token_colors.insert(TokenType::Link, "#0000ee".to_string());
 
         Self {
             name: "Light".to_string(),
@@ -225,6 +278,50 @@ pub mod utils {
         tokens
     }
 
+    /// Split a string literal at `range` into `String` and `Escape` tokens.
+    ///
+    /// Escapes are a backslash followed by one character, or by up to 4 (`\u`)
+    /// or 8 (`\U`) hex digits.
+    pub fn split_escapes(content: &str, range: std::ops::Range<usize>) -> Vec<HighlightToken> {
+        let mut tokens = Vec::new();
+        let literal = &content[range.clone()];
+        let mut segment_start = range.start;
+        let mut chars = literal.char_indices().peekable();
+
+        while let Some((offset, ch)) = chars.next() {
+            if ch != '\\' {
+                continue;
+            }
+            let escape_start = range.start + offset;
+            let Some((_, kind)) = chars.next() else {
+                break;
+            };
+            let mut escape_end = escape_start + 1 + kind.len_utf8();
+            let digits = match kind {
+                'u' => 4,
+                'U' => 8,
+                _ => 0,
+            };
+            for _ in 0..digits {
+                match chars.next_if(|(_, c)| c.is_ascii_hexdigit()) {
+                    Some(_) => escape_end += 1,
+                    None => break,
+                }
+            }
+
+            if segment_start < escape_start {
+                tokens.push(HighlightToken::new(segment_start, escape_start, TokenType::String));
+            }
+            tokens.push(HighlightToken::new(escape_start, escape_end, TokenType::Escape));
+            segment_start = escape_end;
+        }
+
+        if segment_start < range.end {
+            tokens.push(HighlightToken::new(segment_start, range.end, TokenType::String));
+        }
+        tokens
+    }
+
     /// Extract line comments from content.
     pub fn extract_line_comments(content: &str, comment_start: &str) -> Vec<HighlightToken> {
         let mut tokens = Vec::new();
@@ -357,6 +454,28 @@ mod tests {
         assert_eq!(tokens[0].end, 21);
     }
 
+    #[test]
+    fn test_escape_splitting() {
+        let content = r#"x = "a\tb\u00e9c\"""#;
+        let start = content.find('"').unwrap();
+        let tokens = split_escapes(content, start..content.len());
+
+        let parts: Vec<(&str, TokenType)> =
+            tokens.iter().map(|t| (&content[t.start..t.end], t.token_type.clone())).collect();
+        assert_eq!(
+            parts,
+            vec![
+                ("\"a", TokenType::String),
+                ("\\t", TokenType::Escape),
+                ("b", TokenType::String),
+                ("\\u00e9", TokenType::Escape),
+                ("c", TokenType::String),
+                ("\\\"", TokenType::Escape),
+                ("\"", TokenType::String),
+            ]
+        );
+    }
+
     #[test]
     fn test_number_extraction() {
         let content = "let x = 42; let y = 3.14;";
@@ -0,0 +1,143 @@
+//! # JSON Syntax Highlighting
+//!
+//! Tokenizer for JSON documents such as asset manifests. Object keys are told
+//! apart from string values, and `//` and `/* */` comments are accepted since
+//! many tools write JSON with comments.
+
+use async_trait::async_trait;
+
+use crate::core::Result;
+use crate::syntax::highlighter::utils::split_escapes;
+use crate::syntax::{HighlightToken, SyntaxHighlighter, TokenType};
+
+/// JSON syntax highlighter.
+pub struct JsonSyntaxHighlighter;
+
+impl JsonSyntaxHighlighter {
+    /// Create a new JSON syntax highlighter.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for JsonSyntaxHighlighter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl SyntaxHighlighter for JsonSyntaxHighlighter {
+    async fn highlight(&self, content: &str) -> Result<Vec<HighlightToken>> {
+        let bytes = content.as_bytes();
+        let mut tokens = Vec::new();
+        let mut pos = 0;
+
+        while let Some(&byte) = bytes.get(pos) {
+            let start = pos;
+            match byte {
+                b'"' => {
+                    pos = string_end(content, pos);
+                    if next_significant(content, pos) == Some(b':') {
+                        tokens.push(HighlightToken::new(start, pos, TokenType::Property));
+                    } else {
+                        tokens.extend(split_escapes(content, start..pos));
+                    }
+                }
+                b'/' if bytes.get(pos + 1) == Some(&b'/') => {
+                    pos = content[pos..].find('\n').map_or(content.len(), |offset| pos + offset);
+                    tokens.push(HighlightToken::new(start, pos, TokenType::Comment));
+                }
+                b'/' if bytes.get(pos + 1) == Some(&b'*') => {
+                    pos = content[pos + 2..]
+                        .find("*/")
+                        .map_or(content.len(), |offset| pos + offset + 4);
+                    tokens.push(HighlightToken::new(start, pos, TokenType::Comment));
+                }
+                b'{' | b'}' | b'[' | b']' | b',' | b':' => {
+                    pos += 1;
+                    tokens.push(HighlightToken::new(start, pos, TokenType::Punctuation));
+                }
+                b'-' | b'0'..=b'9' => {
+                    pos += bytes[pos..]
+                        .iter()
+                        .take_while(|&&b| {
+                            b.is_ascii_digit() || matches!(b, b'-' | b'+' | b'.' | b'e' | b'E')
+                        })
+                        .count();
+                    tokens.push(HighlightToken::new(start, pos, TokenType::Number));
+                }
+                b if b.is_ascii_alphabetic() => {
+                    pos += bytes[pos..].iter().take_while(|b| b.is_ascii_alphanumeric()).count();
+                    if matches!(&content[start..pos], "true" | "false" | "null") {
+                        tokens.push(HighlightToken::new(start, pos, TokenType::Constant));
+                    }
+                }
+                _ => pos += content[pos..].chars().next().map_or(1, char::len_utf8),
+            }
+        }
+
+        Ok(tokens)
+    }
+
+    fn language(&self) -> &str {
+        "json"
+    }
+
+    fn file_extensions(&self) -> Vec<&str> {
+        vec!["json"]
+    }
+}
+
+/// End of the string starting at `start`. Unterminated strings end at the line.
+fn string_end(content: &str, start: usize) -> usize {
+    let mut escaped = false;
+    for (offset, ch) in content[start + 1..].char_indices() {
+        match ch {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return start + 1 + offset + 1,
+            '\n' => return start + 1 + offset,
+            _ => {}
+        }
+    }
+    content.len()
+}
+
+/// First byte after `pos` that is not whitespace.
+fn next_significant(content: &str, pos: usize) -> Option<u8> {
+    content.as_bytes()[pos..].iter().copied().find(|b| !b.is_ascii_whitespace())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_json_highlighting() {
+        let content = r#"{
+    // Sprite sheet
+    "name": "hero\u0041",
+    "frames": [1, -2.5e3],
+    "loop": true,
+    "next": null
+}"#;
+        let tokens = JsonSyntaxHighlighter::new().highlight(content).await.unwrap();
+        let parts: Vec<(&str, TokenType)> =
+            tokens.iter().map(|t| (&content[t.start..t.end], t.token_type.clone())).collect();
+        let of = |text: &str| {
+            parts.iter().find(|(part, _)| *part == text).map(|(_, token_type)| token_type.clone())
+        };
+
+        assert_eq!(of("// Sprite sheet"), Some(TokenType::Comment));
+        assert_eq!(of("\"name\""), Some(TokenType::Property));
+        assert_eq!(of("\"hero"), Some(TokenType::String));
+        assert_eq!(of("\\u0041"), Some(TokenType::Escape));
+        assert_eq!(of("\"frames\""), Some(TokenType::Property));
+        assert_eq!(of("-2.5e3"), Some(TokenType::Number));
+        assert_eq!(of("true"), Some(TokenType::Constant));
+        assert_eq!(of("null"), Some(TokenType::Constant));
+        assert_eq!(of(":"), Some(TokenType::Punctuation));
+        assert!(tokens.windows(2).all(|pair| pair[0].end <= pair[1].start));
+    }
+}
@@ -4,8 +4,11 @@
 
 use std::collections::HashMap;
 
+use tokio::sync::{broadcast, watch};
+
 use crate::core::Result;
-use crate::syntax::{CompletionItem, Diagnostic};
+use crate::syntax::transport::LspNotification;
+use crate::syntax::{CompletionItem, Diagnostic, DiagnosticSeverity};
 
 /// Trait for Language Server Protocol clients.
 #[async_trait::async_trait]
@@ -79,6 +82,41 @@ pub trait LspClient {
         Ok(None)
     }
 
+    /// Notify the server that a document was opened.
+    async fn did_open(
+        &self,
+        file_path: &str,
+        language_id: &str,
+        version: i32,
+        text: &str,
+    ) -> Result<()> {
+        let _ = (file_path, language_id, version, text);
+        Ok(())
+    }
+
+    /// Notify the server that a document changed.
+    async fn did_change(
+        &self,
+        file_path: &str,
+        version: i32,
+        changes: Vec<TextDocumentContentChange>,
+    ) -> Result<()> {
+        let _ = (file_path, version, &changes);
+        Ok(())
+    }
+
+    /// Notify the server that a document was saved.
+    async fn did_save(&self, file_path: &str) -> Result<()> {
+        let _ = file_path;
+        Ok(())
+    }
+
+    /// Notify the server that a document was closed.
+    async fn did_close(&self, file_path: &str) -> Result<()> {
+        let _ = file_path;
+        Ok(())
+    }
+
     /// Format document.
     async fn format_document(&self, file_path: &str) -> Result<Option<Vec<TextEdit>>> {
         let _ = file_path;
@@ -89,6 +127,7 @@ pub trait LspClient {
     async fn shutdown(&self) -> Result<()>;
 
     /// Get the language this client supports.
+    #[expect(clippy::unnecessary_literal_bound, reason = "clients may return an owned name")]
     fn language(&self) -> &str {
         "unknown"
     }
@@ -102,6 +141,19 @@ pub trait LspClient {
     fn capabilities(&self) -> ServerCapabilities {
         ServerCapabilities::default()
     }
+
+    /// Subscribe to notifications pushed by the server.
+    fn subscribe_notifications(&self) -> Option<broadcast::Receiver<LspNotification>> {
+        None
+    }
+
+    /// Watch for the server process exiting.
+    ///
+    /// The value turns into `Some` with a description of the exit status once
+    /// the process is gone. Clients without a process return `None`.
+    fn exit_watch(&self) -> Option<watch::Receiver<Option<String>>> {
+        None
+    }
 }
 
 /// Represents a code action.
@@ -147,6 +199,15 @@ pub struct TextEdit {
     pub new_text: String,
 }
 
+/// A change to a document's content, sent with `didChange`.
+///
+/// A change without a range replaces the whole document.
+#[derive(Debug, Clone)]
+pub struct TextDocumentContentChange {
+    pub range: Option<Range>,
+    pub text: String,
+}
+
 /// Represents an LSP command.
 #[derive(Debug, Clone)]
 pub struct Command {
@@ -156,7 +217,8 @@ pub struct Command {
 }
 
 /// Server capabilities.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
+#[expect(clippy::struct_excessive_bools, reason = "mirrors the LSP capability flags")]
 pub struct ServerCapabilities {
     pub text_document_sync: Option<TextDocumentSyncCapability>,
     pub hover_provider: bool,
@@ -168,21 +230,55 @@ pub struct ServerCapabilities {
     pub code_action_provider: bool,
     pub document_symbol_provider: bool,
     pub workspace_symbol_provider: bool,
+    pub diagnostic_provider: bool,
 }
 
-impl Default for ServerCapabilities {
-    fn default() -> Self {
+impl ServerCapabilities {
+    /// Parse the `capabilities` object of an `initialize` result.
+    #[must_use]
+    pub fn from_json(capabilities: &serde_json::Value) -> Self {
+        let provider = |key: &str| match capabilities.get(key) {
+            Some(serde_json::Value::Bool(enabled)) => *enabled,
+            Some(serde_json::Value::Object(_)) => true,
+            _ => false,
+        };
+
+        // `textDocumentSync` is either a bare kind or an options object.
+        let sync_kind = capabilities.get("textDocumentSync").and_then(|sync| {
+            sync.as_u64().or_else(|| sync.get("change").and_then(serde_json::Value::as_u64))
+        });
+
+        let completion_provider =
+            capabilities.get("completionProvider").map(|options| CompletionOptions {
+                trigger_characters: options
+                    .get("triggerCharacters")
+                    .and_then(serde_json::Value::as_array)
+                    .map(|chars| {
+                        chars.iter().filter_map(|c| c.as_str().map(String::from)).collect()
+                    })
+                    .unwrap_or_default(),
+                resolve_provider: options
+                    .get("resolveProvider")
+                    .and_then(serde_json::Value::as_bool)
+                    .unwrap_or(false),
+            });
+
         Self {
-            text_document_sync: None,
-            hover_provider: false,
-            completion_provider: None,
-            definition_provider: false,
-            references_provider: false,
-            document_formatting_provider: false,
-            rename_provider: false,
-            code_action_provider: false,
-            document_symbol_provider: false,
-            workspace_symbol_provider: false,
+            text_document_sync: sync_kind.map(|kind| match kind {
+                1 => TextDocumentSyncCapability::Full,
+                2 => TextDocumentSyncCapability::Incremental,
+                _ => TextDocumentSyncCapability::None,
+            }),
+            hover_provider: provider("hoverProvider"),
+            completion_provider,
+            definition_provider: provider("definitionProvider"),
+            references_provider: provider("referencesProvider"),
+            document_formatting_provider: provider("documentFormattingProvider"),
+            rename_provider: provider("renameProvider"),
+            code_action_provider: provider("codeActionProvider"),
+            document_symbol_provider: provider("documentSymbolProvider"),
+            workspace_symbol_provider: provider("workspaceSymbolProvider"),
+            diagnostic_provider: provider("diagnosticProvider"),
         }
     }
 }
@@ -203,48 +299,321 @@ pub struct CompletionOptions {
 }
 
 impl Position {
-    pub fn new(line: usize, character: usize) -> Self {
+    #[must_use]
+    pub const fn new(line: usize, character: usize) -> Self {
         Self { line, character }
     }
+
+    /// Parse an LSP `Position` object.
+    #[must_use]
+    pub fn from_json(value: &serde_json::Value) -> Option<Self> {
+        let line = value.get("line")?.as_u64()?;
+        let character = value.get("character")?.as_u64()?;
+        Some(Self::new(usize::try_from(line).ok()?, usize::try_from(character).ok()?))
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({ "line": self.line, "character": self.character })
+    }
 }
 
 impl Range {
-    pub fn new(start: Position, end: Position) -> Self {
+    #[must_use]
+    pub const fn new(start: Position, end: Position) -> Self {
         Self { start, end }
     }
+
+    /// Parse an LSP `Range` object.
+    #[must_use]
+    pub fn from_json(value: &serde_json::Value) -> Option<Self> {
+        Some(Self::new(
+            Position::from_json(value.get("start")?)?,
+            Position::from_json(value.get("end")?)?,
+        ))
+    }
+
+    /// Convert the range into its JSON representation.
+    #[must_use]
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({ "start": self.start.to_json(), "end": self.end.to_json() })
+    }
 }
 
 impl Location {
-    pub fn new(uri: String, range: Range) -> Self {
+    #[must_use]
+    pub const fn new(uri: String, range: Range) -> Self {
         Self { uri, range }
     }
+
+    /// Parse either a `Location` or a `LocationLink` object.
+    pub fn from_json(value: &serde_json::Value) -> Option<Self> {
+        if let Some(uri) = value.get("uri").and_then(serde_json::Value::as_str) {
+            return Some(Self::new(uri.to_owned(), Range::from_json(value.get("range")?)?));
+        }
+
+        // `LocationLink` points at the whole item; prefer the name it selects.
+        let uri = value.get("targetUri")?.as_str()?;
+        let range = value.get("targetSelectionRange").or_else(|| value.get("targetRange"))?;
+        Some(Self::new(uri.to_owned(), Range::from_json(range)?))
+    }
+
+    /// The file system path of the location.
+    #[must_use]
+    pub fn path(&self) -> String {
+        uri_to_path(&self.uri)
+    }
 }
 
 impl TextEdit {
-    pub fn new(range: Range, new_text: String) -> Self {
+    #[must_use]
+    pub const fn new(range: Range, new_text: String) -> Self {
         Self { range, new_text }
     }
+
+    /// Parse an LSP `TextEdit` object.
+    #[must_use]
+    pub fn from_json(value: &serde_json::Value) -> Option<Self> {
+        let new_text = value.get("newText")?.as_str()?;
+        Some(Self::new(Range::from_json(value.get("range")?)?, new_text.to_owned()))
+    }
+}
+
+impl WorkspaceEdit {
+    /// Parse an LSP `WorkspaceEdit`, accepting both `changes` and `documentChanges`.
+    ///
+    /// Resource operations (create, rename, delete) in `documentChanges` are skipped.
+    pub fn from_json(value: &serde_json::Value) -> Self {
+        let mut changes: HashMap<String, Vec<TextEdit>> = HashMap::new();
+
+        if let Some(map) = value.get("changes").and_then(serde_json::Value::as_object) {
+            for (uri, edits) in map {
+                changes.entry(uri.clone()).or_default().extend(parse_text_edits(edits));
+            }
+        }
+
+        if let Some(document_changes) =
+            value.get("documentChanges").and_then(serde_json::Value::as_array)
+        {
+            for change in document_changes {
+                let (Some(uri), Some(edits)) = (
+                    change.pointer("/textDocument/uri").and_then(serde_json::Value::as_str),
+                    change.get("edits"),
+                ) else {
+                    continue;
+                };
+                changes.entry(uri.to_owned()).or_default().extend(parse_text_edits(edits));
+            }
+        }
+
+        Self { changes }
+    }
+
+    /// Check whether the edit changes nothing.
+    pub fn is_empty(&self) -> bool {
+        self.changes.values().all(Vec::is_empty)
+    }
+}
+
+impl TextDocumentContentChange {
+    /// Create a change replacing a range.
+    #[must_use]
+    pub const fn incremental(range: Range, text: String) -> Self {
+        Self { range: Some(range), text }
+    }
+
+    /// Create a change replacing the whole document.
+    #[must_use]
+    pub const fn full(text: String) -> Self {
+        Self { range: None, text }
+    }
+
+    /// Convert the change into its JSON representation.
+    #[must_use]
+    pub fn to_json(&self) -> serde_json::Value {
+        self.range.as_ref().map_or_else(
+            || serde_json::json!({ "text": self.text }),
+            |range| serde_json::json!({ "range": range.to_json(), "text": self.text }),
+        )
+    }
 }
 
 impl CodeAction {
-    pub fn new(title: String) -> Self {
+    #[must_use]
+    pub const fn new(title: String) -> Self {
         Self { title, kind: None, edit: None, command: None }
     }
 
+    #[must_use]
     pub fn with_edit(mut self, edit: WorkspaceEdit) -> Self {
         self.edit = Some(edit);
         self
     }
 
+    #[must_use]
     pub fn with_command(mut self, command: Command) -> Self {
         self.command = Some(command);
         self
     }
 }
 
+/// Convert LSP diagnostic severity to our enum.
+#[must_use]
+pub const fn convert_diagnostic_severity(lsp_severity: Option<i32>) -> DiagnosticSeverity {
+    match lsp_severity {
+        Some(2) => DiagnosticSeverity::Warning,
+        Some(3) => DiagnosticSeverity::Info,
+        Some(4) => DiagnosticSeverity::Hint,
+        _ => DiagnosticSeverity::Error,
+    }
+}
+
+/// Parse an array of LSP `Diagnostic` objects.
+///
+/// `text` is the document the server diagnosed. Its lines are used to convert
+/// the server's UTF-16 columns into byte columns; without it the columns are
+/// kept as sent, which only matches for ASCII lines.
+pub fn parse_diagnostics(diagnostics: &serde_json::Value, text: Option<&str>) -> Vec<Diagnostic> {
+    let Some(diag_array) = diagnostics.as_array() else {
+        return Vec::new();
+    };
+
+    let lines: Vec<&str> = text.map(|text| text.split('\n').collect()).unwrap_or_default();
+    let position = |position: Option<&serde_json::Value>| {
+        let line = usize::try_from(position?.get("line")?.as_u64()?).ok()?;
+        let character = usize::try_from(position?.get("character")?.as_u64()?).ok()?;
+        let column =
+            lines.get(line).map_or(character, |text| utf16_to_byte_offset(text, character));
+        Some((line, column))
+    };
+
+    let mut parsed = Vec::new();
+    for diag in diag_array {
+        let Some(range) = diag.get("range") else {
+            continue;
+        };
+
+        if let (Some((line, column)), Some((end_line, end_column))) =
+            (position(range.get("start")), position(range.get("end")))
+        {
+            let severity = diag
+                .get("severity")
+                .and_then(serde_json::Value::as_i64)
+                .and_then(|s| i32::try_from(s).ok());
+
+            let message =
+                diag.get("message").and_then(|m| m.as_str()).unwrap_or("Unknown error").to_owned();
+
+            let source = diag.get("source").and_then(|s| s.as_str()).map(String::from);
+
+            parsed.push(Diagnostic {
+                line,
+                column,
+                end_line,
+                end_column,
+                severity: convert_diagnostic_severity(severity),
+                message,
+                source,
+            });
+        }
+    }
+
+    parsed
+}
+
+/// Convert a file system path into a `file://` URI.
+#[must_use]
+pub fn path_to_uri(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-_.~:".contains(&byte) {
+            uri.push(char::from(byte));
+        } else {
+            const HEX: &[u8; 16] = b"0123456789ABCDEF";
+            uri.push('%');
+            uri.push(char::from(HEX[usize::from(byte >> 4)]));
+            uri.push(char::from(HEX[usize::from(byte & 0x0F)]));
+        }
+    }
+
+    uri
+}
+
+/// Convert a `file://` URI back into a file system path.
+#[must_use]
+pub fn uri_to_path(uri: &str) -> String {
+    let encoded = uri.strip_prefix("file://").unwrap_or(uri);
+    // Windows drive paths are encoded as `/C:/...`.
+    let encoded = match encoded.as_bytes() {
+        [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => &encoded[1..],
+        _ => encoded,
+    };
+
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let Some(byte) =
+                encoded.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Parse a definition or references result: `null`, a single location or an array.
+pub fn parse_locations(value: &serde_json::Value) -> Vec<Location> {
+    match value {
+        serde_json::Value::Array(items) => items.iter().filter_map(Location::from_json).collect(),
+        serde_json::Value::Object(_) => Location::from_json(value).into_iter().collect(),
+        serde_json::Value::Null
+        | serde_json::Value::Bool(_)
+        | serde_json::Value::Number(_)
+        | serde_json::Value::String(_) => Vec::new(),
+    }
+}
+
+/// Parse an array of LSP `TextEdit` objects.
+#[must_use]
+pub fn parse_text_edits(value: &serde_json::Value) -> Vec<TextEdit> {
+    value
+        .as_array()
+        .map(|edits| edits.iter().filter_map(TextEdit::from_json).collect())
+        .unwrap_or_default()
+}
+
+/// Convert a UTF-16 `character` offset into a byte offset within `line`.
+///
+/// Offsets past the end of the line, or inside a surrogate pair, are clamped.
+#[must_use]
+pub fn utf16_to_byte_offset(line: &str, character: usize) -> usize {
+    let mut units = 0;
+    for (offset, ch) in line.char_indices() {
+        units += ch.len_utf16();
+        if units > character {
+            return offset;
+        }
+    }
+    line.len()
+}
+
 /// LSP client configuration.
 #[derive(Debug, Clone)]
 pub struct LspClientConfig {
+    /// Language id sent with opened documents.
+    pub language_id: String,
     /// Path to the LSP server binary.
     pub server_path: String,
     /// Arguments to pass to the server.
@@ -259,11 +628,14 @@ pub struct LspClientConfig {
     pub enable_logging: bool,
     /// Log file path.
     pub log_file: Option<String>,
+    /// Server-specific `initializationOptions`.
+    pub initialization_options: Option<serde_json::Value>,
 }
 
 impl Default for LspClientConfig {
     fn default() -> Self {
         Self {
+            language_id: String::new(),
             server_path: String::new(),
             server_args: Vec::new(),
             env_vars: HashMap::new(),
@@ -271,6 +643,7 @@ impl Default for LspClientConfig {
             request_timeout_ms: 5000,
             enable_logging: false,
             log_file: None,
+            initialization_options: None,
         }
     }
 }
@@ -310,6 +683,9 @@ pub mod methods {
     pub const TEXT_DOCUMENT_RENAME: &str = "textDocument/rename";
     pub const TEXT_DOCUMENT_FORMATTING: &str = "textDocument/formatting";
     pub const TEXT_DOCUMENT_CODE_ACTION: &str = "textDocument/codeAction";
+    pub const TEXT_DOCUMENT_DIAGNOSTIC: &str = "textDocument/diagnostic";
+
+    pub const WORKSPACE_DID_CHANGE_WORKSPACE_FOLDERS: &str = "workspace/didChangeWorkspaceFolders";
 
     pub const TEXT_DOCUMENT_PUBLISH_DIAGNOSTICS: &str = "textDocument/publishDiagnostics";
 }
@@ -351,6 +727,137 @@ mod tests {
         assert!(caps.completion_provider.is_none());
     }
 
+    #[test]
+    fn test_diagnostic_severity_conversion() {
+        assert_eq!(convert_diagnostic_severity(Some(1)), DiagnosticSeverity::Error);
+        assert_eq!(convert_diagnostic_severity(Some(2)), DiagnosticSeverity::Warning);
+        assert_eq!(convert_diagnostic_severity(None), DiagnosticSeverity::Error);
+    }
+
+    #[test]
+    fn test_server_capabilities_from_json() {
+        let caps = ServerCapabilities::from_json(&serde_json::json!({
+            "textDocumentSync": { "openClose": true, "change": 2 },
+            "hoverProvider": true,
+            "completionProvider": { "triggerCharacters": [".", ":"] },
+            "definitionProvider": { "workDoneProgress": false },
+            "renameProvider": false
+        }));
+
+        assert!(matches!(caps.text_document_sync, Some(TextDocumentSyncCapability::Incremental)));
+        assert!(caps.hover_provider);
+        assert!(caps.definition_provider);
+        assert!(!caps.rename_provider);
+        assert_eq!(caps.completion_provider.unwrap().trigger_characters, vec![".", ":"]);
+    }
+
+    #[test]
+    fn test_content_change_json() {
+        let change = TextDocumentContentChange::incremental(
+            Range::new(Position::new(1, 2), Position::new(1, 4)),
+            "xy".to_string(),
+        );
+        assert_eq!(change.to_json()["range"]["end"]["character"], 4);
+        assert_eq!(change.to_json()["text"], "xy");
+
+        let full = TextDocumentContentChange::full("fn main() {}".to_string());
+        assert_eq!(full.to_json(), serde_json::json!({ "text": "fn main() {}" }));
+    }
+
+    #[test]
+    fn test_uri_conversion() {
+        assert_eq!(path_to_uri("/home/user/my file.rs"), "file:///home/user/my%20file.rs");
+        assert_eq!(uri_to_path("file:///home/user/my%20file.rs"), "/home/user/my file.rs");
+        assert_eq!(path_to_uri("C:\\src\\main.rs"), "file:///C:/src/main.rs");
+        assert_eq!(uri_to_path("file:///C:/src/main.rs"), "C:/src/main.rs");
+    }
+
+    #[test]
+    fn test_parse_locations() {
+        let single = serde_json::json!({
+            "uri": "file:///src/lib.rs",
+            "range": { "start": { "line": 3, "character": 7 }, "end": { "line": 3, "character": 10 } }
+        });
+        let locations = parse_locations(&single);
+        assert_eq!(locations.len(), 1);
+        assert_eq!(locations[0].path(), "/src/lib.rs");
+        assert_eq!(locations[0].range.start.character, 7);
+
+        let links = serde_json::json!([{
+            "targetUri": "file:///src/main.rs",
+            "targetRange": { "start": { "line": 0, "character": 0 }, "end": { "line": 2, "character": 1 } },
+            "targetSelectionRange": { "start": { "line": 0, "character": 3 }, "end": { "line": 0, "character": 7 } }
+        }]);
+        let locations = parse_locations(&links);
+        assert_eq!(locations[0].uri, "file:///src/main.rs");
+        assert_eq!(locations[0].range.start.character, 3);
+
+        assert!(parse_locations(&serde_json::Value::Null).is_empty());
+    }
+
+    #[test]
+    fn test_workspace_edit_from_json() {
+        let edit = |line: u64| {
+            serde_json::json!({
+                "range": { "start": { "line": line, "character": 0 }, "end": { "line": line, "character": 3 } },
+                "newText": "bar"
+            })
+        };
+        let workspace_edit = WorkspaceEdit::from_json(&serde_json::json!({
+            "changes": { "file:///a.rs": [edit(0)] },
+            "documentChanges": [
+                { "textDocument": { "uri": "file:///a.rs", "version": 1 }, "edits": [edit(4)] },
+                { "textDocument": { "uri": "file:///b.rs", "version": null }, "edits": [edit(1)] },
+                { "kind": "create", "uri": "file:///c.rs" }
+            ]
+        }));
+
+        assert_eq!(workspace_edit.changes.len(), 2);
+        assert_eq!(workspace_edit.changes["file:///a.rs"].len(), 2);
+        assert_eq!(workspace_edit.changes["file:///b.rs"][0].new_text, "bar");
+        assert!(WorkspaceEdit::from_json(&serde_json::json!({})).is_empty());
+    }
+
+    #[test]
+    fn test_utf16_to_byte_offset() {
+        assert_eq!(utf16_to_byte_offset("let x = 1;", 4), 4);
+        // "é" is one UTF-16 unit but two bytes, "🦀" is two units and four bytes.
+        assert_eq!(utf16_to_byte_offset("é🦀x", 1), 2);
+        assert_eq!(utf16_to_byte_offset("é🦀x", 3), 6);
+        assert_eq!(utf16_to_byte_offset("é🦀x", 40), 7);
+    }
+
+    #[test]
+    fn test_parse_diagnostics_ranges() {
+        let diagnostics = serde_json::json!([{
+            "range": {
+                "start": { "line": 0, "character": 8 },
+                "end": { "line": 2, "character": 1 }
+            },
+            "message": "unclosed delimiter"
+        }, {
+            "range": {
+                "start": { "line": 1, "character": 8 },
+                "end": { "line": 1, "character": 11 }
+            },
+            "severity": 2,
+            "message": "unused variable"
+        }]);
+        let text = "fn main() {\n    let 🦀é = 1;\n}";
+
+        let parsed = parse_diagnostics(&diagnostics, Some(text));
+        assert_eq!(
+            (parsed[0].line, parsed[0].column, parsed[0].end_line, parsed[0].end_column),
+            (0, 8, 2, 1)
+        );
+        // The crab is two UTF-16 units but four bytes, `é` one unit but two bytes.
+        assert_eq!((parsed[1].column, parsed[1].end_column), (8, 14));
+        assert_eq!(&text.split('\n').nth(1).unwrap()[8..14], "🦀é");
+
+        let unconverted = parse_diagnostics(&diagnostics, None);
+        assert_eq!((unconverted[1].column, unconverted[1].end_column), (8, 11));
+    }
+
     #[test]
     fn test_lsp_client_config() {
         let config = LspClientConfig::default();
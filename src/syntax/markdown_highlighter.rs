@@ -0,0 +1,475 @@
+//! # Markdown Syntax Highlighting
+//!
+//! Line based tokenizer for Markdown: headings, emphasis, code spans, links,
+//! lists, block quotes and fenced code. Fenced code is highlighted by the
+//! registered highlighter for the fence's language, when there is one.
+
+use std::sync::{Arc, Weak};
+
+use async_trait::async_trait;
+use tokio::sync::RwLock;
+use tracing::debug;
+
+use crate::core::Result;
+use crate::syntax::{HighlightToken, HighlighterMap, Highlighters, SyntaxHighlighter, TokenType};
+
+/// Markdown syntax highlighter.
+pub struct MarkdownSyntaxHighlighter {
+    /// Highlighters to delegate fenced code to. Weak, since this highlighter
+    /// is itself registered there.
+    delegates: Option<Weak<RwLock<HighlighterMap>>>,
+}
+
+impl MarkdownSyntaxHighlighter {
+    /// Create a Markdown highlighter that shows fenced code as plain code.
+    pub fn new() -> Self {
+        Self { delegates: None }
+    }
+
+    /// Create a Markdown highlighter delegating fenced code to `highlighters`.
+    pub fn with_highlighters(highlighters: &Highlighters) -> Self {
+        Self { delegates: Some(Arc::downgrade(highlighters)) }
+    }
+
+    /// Highlight the body of a fenced code block starting at byte `start`.
+    async fn highlight_code(
+        &self,
+        language: &str,
+        body: &str,
+        start: usize,
+    ) -> Vec<HighlightToken> {
+        if body.is_empty() {
+            return Vec::new();
+        }
+
+        let delegate = match self.delegates.as_ref().and_then(Weak::upgrade) {
+            Some(highlighters) => highlighters.read().await.get(fence_extension(language)).cloned(),
+            None => None,
+        };
+        let Some(delegate) = delegate else {
+            return vec![HighlightToken::new(start, start + body.len(), TokenType::String)];
+        };
+
+        match delegate.highlight(body).await {
+            Ok(tokens) => tokens
+                .into_iter()
+                .map(|token| {
+                    HighlightToken::new(token.start + start, token.end + start, token.token_type)
+                })
+                .collect(),
+            Err(e) => {
+                debug!("Failed to highlight {} code block: {}", language, e);
+                vec![HighlightToken::new(start, start + body.len(), TokenType::String)]
+            }
+        }
+    }
+}
+
+impl Default for MarkdownSyntaxHighlighter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An open fenced code block.
+struct Fence {
+    /// Fence character, `` ` `` or `~`.
+    marker: u8,
+    /// Length of the opening fence.
+    len: usize,
+    /// Language from the info string.
+    language: String,
+    /// Byte offset of the first body line.
+    body_start: usize,
+}
+
+/// A paragraph line that a setext underline would turn into a heading.
+struct ParagraphLine {
+    start: usize,
+    end: usize,
+    /// Number of tokens emitted before the line.
+    first_token: usize,
+}
+
+#[async_trait]
+impl SyntaxHighlighter for MarkdownSyntaxHighlighter {
+    async fn highlight(&self, content: &str) -> Result<Vec<HighlightToken>> {
+        let mut tokens = Vec::new();
+        let mut fence: Option<Fence> = None;
+        let mut paragraph: Option<ParagraphLine> = None;
+        let mut line_start = 0;
+
+        for line in content.split_inclusive('\n') {
+            let start = line_start;
+            line_start += line.len();
+            let text = line.trim_end_matches(['\n', '\r']);
+            let indent = text.len() - text.trim_start_matches(' ').len();
+            let trimmed = &text[indent..];
+            let end = start + text.len();
+
+            if let Some(open) = &fence {
+                let run = marker_run(trimmed, open.marker);
+                if indent <= 3 && run >= open.len && trimmed[run..].trim().is_empty() {
+                    let body = &content[open.body_start..start];
+                    tokens.extend(self.highlight_code(&open.language, body, open.body_start).await);
+                    tokens.push(HighlightToken::new(start + indent, end, TokenType::Punctuation));
+                    fence = None;
+                }
+                continue;
+            }
+
+            // Fenced code block
+            let marker = trimmed.bytes().next().filter(|b| matches!(b, b'`' | b'~'));
+            if let Some(marker) = marker.filter(|&m| indent <= 3 && marker_run(trimmed, m) >= 3) {
+                let len = marker_run(trimmed, marker);
+                let info = trimmed[len..].trim();
+                tokens.push(HighlightToken::new(
+                    start + indent,
+                    start + indent + len,
+                    TokenType::Punctuation,
+                ));
+                let language = info.split_whitespace().next().unwrap_or("");
+                if !language.is_empty() {
+                    let info_start = end - trimmed[len..].trim_start().len();
+                    tokens.push(HighlightToken::new(
+                        info_start,
+                        info_start + language.len(),
+                        TokenType::Attribute,
+                    ));
+                }
+                fence = Some(Fence {
+                    marker,
+                    len,
+                    language: language.to_lowercase(),
+                    body_start: line_start,
+                });
+                paragraph = None;
+                continue;
+            }
+
+            if trimmed.trim().is_empty() {
+                paragraph = None;
+                continue;
+            }
+
+            // ATX heading
+            let level = trimmed.bytes().take_while(|&b| b == b'#').count();
+            if indent <= 3
+                && (1..=6).contains(&level)
+                && matches!(trimmed.as_bytes().get(level), None | Some(b' ' | b'\t'))
+            {
+                tokens.push(HighlightToken::new(start + indent, end, TokenType::Heading));
+                paragraph = None;
+                continue;
+            }
+
+            // Setext heading underline
+            let underline = trimmed.trim_end();
+            if let Some(previous) = paragraph.take() {
+                if indent <= 3
+                    && (underline.bytes().all(|b| b == b'=')
+                        || underline.bytes().all(|b| b == b'-'))
+                {
+                    tokens.truncate(previous.first_token);
+                    tokens.push(HighlightToken::new(
+                        previous.start,
+                        previous.end,
+                        TokenType::Heading,
+                    ));
+                    tokens.push(HighlightToken::new(
+                        start + indent,
+                        start + indent + underline.len(),
+                        TokenType::Punctuation,
+                    ));
+                    continue;
+                }
+            }
+
+            // Thematic break
+            if is_thematic_break(trimmed) {
+                tokens.push(HighlightToken::new(start + indent, end, TokenType::Punctuation));
+                continue;
+            }
+
+            // Block quotes and list markers
+            let mut rest = start + indent;
+            let mut block = false;
+            loop {
+                let remaining = &content[rest..end];
+                let spaces = remaining.len() - remaining.trim_start().len();
+                let remaining = &remaining[spaces..];
+                let marker = if remaining.starts_with('>') { 1 } else { list_marker(remaining) };
+                if marker == 0 {
+                    break;
+                }
+                tokens.push(HighlightToken::new(
+                    rest + spaces,
+                    rest + spaces + marker,
+                    TokenType::Punctuation,
+                ));
+                rest += spaces + marker;
+                block = true;
+            }
+
+            let first_token = tokens.len();
+            highlight_inline(content, rest, end, &mut tokens);
+            paragraph =
+                (!block).then_some(ParagraphLine { start: start + indent, end, first_token });
+        }
+
+        // Unclosed fences run to the end of the document.
+        if let Some(open) = fence {
+            let body = &content[open.body_start..];
+            tokens.extend(self.highlight_code(&open.language, body, open.body_start).await);
+        }
+
+        Ok(tokens)
+    }
+
+    fn language(&self) -> &str {
+        "markdown"
+    }
+
+    fn file_extensions(&self) -> Vec<&str> {
+        vec!["md"]
+    }
+}
+
+/// Extension of the highlighter for a fence language, e.g. `rust` -> `rs`.
+fn fence_extension(language: &str) -> &str {
+    match language {
+        "rust" => "rs",
+        "markdown" => "md",
+        other => other,
+    }
+}
+
+/// Number of leading `marker` bytes.
+fn marker_run(text: &str, marker: u8) -> usize {
+    text.bytes().take_while(|&b| b == marker).count()
+}
+
+/// Length of a list marker followed by a space (`-`, `*`, `+`, `1.`, `1)`), or 0.
+fn list_marker(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    let len = match bytes.first() {
+        Some(b'-' | b'*' | b'+') => 1,
+        Some(b'0'..=b'9') => {
+            let digits = bytes.iter().take_while(|b| b.is_ascii_digit()).count();
+            match bytes.get(digits) {
+                Some(b'.' | b')') if digits <= 9 => digits + 1,
+                _ => return 0,
+            }
+        }
+        _ => return 0,
+    };
+    match bytes.get(len) {
+        Some(b' ' | b'\t') => len,
+        _ => 0,
+    }
+}
+
+/// Whether a line is `---`, `***` or `___`, optionally spaced.
+fn is_thematic_break(text: &str) -> bool {
+    let marks: Vec<u8> = text.bytes().filter(|b| !matches!(b, b' ' | b'\t')).collect();
+    marks.len() >= 3
+        && matches!(marks[0], b'-' | b'*' | b'_')
+        && marks.iter().all(|&b| b == marks[0])
+}
+
+/// Highlight code spans, emphasis, links and escapes in `content[start..end]`.
+fn highlight_inline(content: &str, start: usize, end: usize, tokens: &mut Vec<HighlightToken>) {
+    let bytes = content.as_bytes();
+    let mut pos = start;
+
+    while pos < end {
+        match bytes[pos] {
+            b'\\' if pos + 1 < end && bytes[pos + 1].is_ascii_punctuation() => {
+                tokens.push(HighlightToken::new(pos, pos + 2, TokenType::Escape));
+                pos += 2;
+            }
+            b'`' => {
+                let run = marker_run(&content[pos..end], b'`');
+                let fence = &content[pos..pos + run];
+                match content[pos + run..end].find(fence) {
+                    Some(offset) => {
+                        let close = pos + run + offset + run;
+                        tokens.push(HighlightToken::new(pos, close, TokenType::String));
+                        pos = close;
+                    }
+                    None => pos += run,
+                }
+            }
+            b'*' | b'_' => {
+                let marker = bytes[pos];
+                let run = marker_run(&content[pos..end], marker);
+                let intraword =
+                    marker == b'_' && pos > start && bytes[pos - 1].is_ascii_alphanumeric();
+                match (!intraword).then(|| emphasis_end(content, pos, run.min(3), end)).flatten() {
+                    Some(close) => {
+                        tokens.push(HighlightToken::new(pos, close, TokenType::Emphasis));
+                        pos = close;
+                    }
+                    None => pos += run,
+                }
+            }
+            b'[' | b'!' => match link(content, pos, end) {
+                Some((link_tokens, close)) => {
+                    tokens.extend(link_tokens);
+                    pos = close;
+                }
+                None => pos += 1,
+            },
+            b'<' => {
+                let close = content[pos..end].find('>').map(|offset| pos + offset);
+                let target = close.map(|close| &content[pos + 1..close]);
+                match (close, target) {
+                    (Some(close), Some(target))
+                        if !target.contains(char::is_whitespace)
+                            && (target.contains("://") || target.contains('@')) =>
+                    {
+                        tokens.push(HighlightToken::new(pos, close + 1, TokenType::Link));
+                        pos = close + 1;
+                    }
+                    _ => pos += 1,
+                }
+            }
+            _ => pos += 1,
+        }
+    }
+}
+
+/// End of the emphasis opened by `run` markers at `start`, if it is closed.
+fn emphasis_end(content: &str, start: usize, run: usize, end: usize) -> Option<usize> {
+    let delimiter = &content[start..start + run];
+    let inner_start = start + run;
+    if content[inner_start..end].starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let bytes = content.as_bytes();
+    let mut search = inner_start;
+    while let Some(offset) = content[search..end].find(delimiter) {
+        let close = search + offset;
+        let after = close + run;
+        let flanking = close > inner_start && !bytes[close - 1].is_ascii_whitespace();
+        let word_after = bytes[..end].get(after).is_some_and(u8::is_ascii_alphanumeric);
+        if flanking && !(delimiter.starts_with('_') && word_after) {
+            return Some(after);
+        }
+        search = close + 1;
+    }
+    None
+}
+
+/// Tokens of an inline link or image `[text](url)` at `start`, and its end.
+fn link(content: &str, start: usize, end: usize) -> Option<(Vec<HighlightToken>, usize)> {
+    let open = if content[start..end].starts_with("![") {
+        2
+    } else if content[start..end].starts_with('[') {
+        1
+    } else {
+        return None;
+    };
+    let text_start = start + open;
+    let text_end = text_start + content[text_start..end].find(']')?;
+    let rest = &content[text_end..end];
+
+    let mut tokens = vec![HighlightToken::new(start, text_start, TokenType::Punctuation)];
+    if text_start < text_end {
+        tokens.push(HighlightToken::new(text_start, text_end, TokenType::Link));
+    }
+
+    if rest.starts_with("](") {
+        let url_start = text_end + 2;
+        let url_end = url_start + content[url_start..end].find(')')?;
+        tokens.push(HighlightToken::new(text_end, url_start, TokenType::Punctuation));
+        if url_start < url_end {
+            tokens.push(HighlightToken::new(url_start, url_end, TokenType::String));
+        }
+        tokens.push(HighlightToken::new(url_end, url_end + 1, TokenType::Punctuation));
+        return Some((tokens, url_end + 1));
+    }
+    if rest.starts_with("][") {
+        let label_start = text_end + 2;
+        let label_end = label_start + content[label_start..end].find(']')?;
+        tokens.push(HighlightToken::new(text_end, label_start, TokenType::Punctuation));
+        if label_start < label_end {
+            tokens.push(HighlightToken::new(label_start, label_end, TokenType::String));
+        }
+        tokens.push(HighlightToken::new(label_end, label_end + 1, TokenType::Punctuation));
+        return Some((tokens, label_end + 1));
+    }
+    // Reference definitions: `[label]: url`
+    let before = content[..start].trim_end_matches(' ');
+    if rest.starts_with("]:") && (before.is_empty() || before.ends_with('\n')) {
+        tokens.push(HighlightToken::new(text_end, text_end + 2, TokenType::Punctuation));
+        let url = content[text_end + 2..end].trim_start();
+        let url_start = end - url.len();
+        if !url.is_empty() {
+            tokens.push(HighlightToken::new(url_start, end, TokenType::String));
+        }
+        return Some((tokens, end));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::syntax::JsonSyntaxHighlighter;
+
+    fn parts(content: &str, tokens: &[HighlightToken]) -> Vec<(String, TokenType)> {
+        tokens.iter().map(|t| (content[t.start..t.end].to_string(), t.token_type.clone())).collect()
+    }
+
+    #[tokio::test]
+    async fn test_markdown_highlighting() {
+        let content = "# Title\n\nSome *emphasis*, __strong__ and snake_case_name with `code`.\n\
+See [the docs](https://example.com) or <https://xylux.dev>. \\*not emphasis\\*\n\n\
+Setext\n======\n\n- item\n> quote **bold**\n\n---\n";
+        let tokens = MarkdownSyntaxHighlighter::new().highlight(content).await.unwrap();
+        let parts = parts(content, &tokens);
+        let has =
+            |text: &str, token_type: TokenType| parts.contains(&(text.to_string(), token_type));
+
+        assert!(has("# Title", TokenType::Heading));
+        assert!(has("*emphasis*", TokenType::Emphasis));
+        assert!(has("__strong__", TokenType::Emphasis));
+        assert!(!parts.iter().any(|(text, _)| text.contains("case")));
+        assert!(has("`code`", TokenType::String));
+        assert!(has("the docs", TokenType::Link));
+        assert!(has("https://example.com", TokenType::String));
+        assert!(has("<https://xylux.dev>", TokenType::Link));
+        assert!(has("\\*", TokenType::Escape));
+        assert!(has("Setext", TokenType::Heading));
+        assert!(has("======", TokenType::Punctuation));
+        assert!(has("-", TokenType::Punctuation));
+        assert!(has(">", TokenType::Punctuation));
+        assert!(has("**bold**", TokenType::Emphasis));
+        assert!(has("---", TokenType::Punctuation));
+        assert!(tokens.windows(2).all(|pair| pair[0].end <= pair[1].start));
+    }
+
+    #[tokio::test]
+    async fn test_fenced_code_is_delegated() {
+        let highlighters: Highlighters = Arc::new(RwLock::new(HashMap::new()));
+        let json: Arc<dyn SyntaxHighlighter + Send + Sync> = Arc::new(JsonSyntaxHighlighter::new());
+        highlighters.write().await.insert("json".to_string(), json);
+
+        let content = "Intro\n\n```json\n{\"key\": true}\n```\n\n~~~python\nprint(1)\n~~~\n";
+        let markdown = MarkdownSyntaxHighlighter::with_highlighters(&highlighters);
+        let tokens = markdown.highlight(content).await.unwrap();
+        let parts = parts(content, &tokens);
+
+        assert!(parts.contains(&("```".to_string(), TokenType::Punctuation)));
+        assert!(parts.contains(&("json".to_string(), TokenType::Attribute)));
+        assert!(parts.contains(&("\"key\"".to_string(), TokenType::Property)));
+        assert!(parts.contains(&("true".to_string(), TokenType::Constant)));
+        // No highlighter for python: shown as code.
+        assert!(parts.contains(&("print(1)\n".to_string(), TokenType::String)));
+        assert!(tokens.windows(2).all(|pair| pair[0].end <= pair[1].start));
+    }
+}
@@ -2,31 +2,67 @@
 //!
 //! Syntax highlighting and language server management for Xylux IDE.
 
+pub mod alux_parser;
 pub mod alux_syntax;
+pub mod document_sync;
 pub mod highlighter;
+pub mod json_highlighter;
 pub mod lsp_client;
+pub mod markdown_highlighter;
 pub mod rust_analyzer;
+pub mod rust_highlighter;
+pub mod shader_validator;
+pub mod stdio_client;
+pub mod supervisor;
+pub mod toml_highlighter;
+pub mod transport;
+pub mod wgsl_highlighter;
 
 pub use alux_syntax::AluxSyntax;
+pub use document_sync::{DocumentSource, DocumentSync};
 pub use highlighter::SyntaxHighlighter;
+pub use json_highlighter::JsonSyntaxHighlighter;
 pub use lsp_client::LspClient;
+pub use markdown_highlighter::MarkdownSyntaxHighlighter;
 pub use rust_analyzer::RustAnalyzer;
+pub use rust_highlighter::RustSyntaxHighlighter;
+pub use shader_validator::ShaderValidator;
+pub use stdio_client::StdioLspClient;
+pub use supervisor::LspSupervisor;
+pub use toml_highlighter::TomlSyntaxHighlighter;
+pub use wgsl_highlighter::WgslSyntaxHighlighter;
 
 // Type alias for backward compatibility
 pub type HighlightInfo = HighlightToken;
 
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, broadcast};
 use tracing::{debug, error, info, warn};
 
+use crate::core::config::{LanguageServerConfig, LspConfig};
 use crate::core::events::{
     CompletionItemKind as CoreCompletionItemKind, DiagnosticSeverity as CoreDiagnosticSeverity,
     LspCompletionItem, LspDiagnostic,
 };
 use crate::core::{Config, EventBus, Result};
+use crate::syntax::lsp_client::{Location, LspClientConfig, TextEdit, WorkspaceEdit, methods};
+use crate::syntax::supervisor::{ClientFactory, LspClients, StartedHook, client_factory};
+
+/// Client for the Alux language server.
+pub type AluxLspClient = StdioLspClient;
+
+/// Language ids for file extensions claimed by configured language servers.
+pub type ExtensionLanguages = Arc<std::sync::RwLock<HashMap<String, String>>>;
+
+/// Syntax highlighters by file extension.
+pub type HighlighterMap = HashMap<String, Arc<dyn SyntaxHighlighter + Send + Sync>>;
+
+/// Shared registry of syntax highlighters.
+pub type Highlighters = Arc<RwLock<HighlighterMap>>;
 
 /// Main syntax manager that coordinates syntax highlighting and LSP services.
 pub struct SyntaxManager {
@@ -35,9 +71,17 @@ pub struct SyntaxManager {
     /// Event bus for communication.
     event_bus: Arc<EventBus>,
     /// Syntax highlighters by file extension.
-    highlighters: Arc<RwLock<HashMap<String, Box<dyn SyntaxHighlighter + Send + Sync>>>>,
+    highlighters: Highlighters,
     /// LSP clients by language.
-    lsp_clients: Arc<RwLock<HashMap<String, Box<dyn LspClient + Send + Sync>>>>,
+    lsp_clients: LspClients,
+    /// Restarts language servers that crash.
+    supervisor: LspSupervisor,
+    /// Latest published diagnostics by file.
+    diagnostics: Arc<RwLock<HashMap<PathBuf, Vec<Diagnostic>>>>,
+    /// Open documents mirrored to the language servers.
+    document_sync: Arc<DocumentSync>,
+    /// File extensions routed to configured language servers.
+    extension_languages: ExtensionLanguages,
     /// Current project root.
     project_root: Arc<RwLock<Option<PathBuf>>>,
     /// Whether LSP is enabled.
@@ -49,22 +93,62 @@ impl SyntaxManager {
     pub async fn new(config: Arc<RwLock<Config>>, event_bus: Arc<EventBus>) -> Result<Self> {
         debug!("Initializing syntax manager");
 
-        let lsp_enabled = {
+        let (lsp_enabled, extensions, restart) = {
             let config = config.read().await;
-            config.lsp.enabled
+            (config.lsp.enabled, server_extensions(&config.lsp.servers), config.lsp.restart.clone())
         };
 
+        let lsp_clients: LspClients = Arc::new(RwLock::new(HashMap::new()));
+        let diagnostics = Arc::new(RwLock::new(HashMap::new()));
+        let project_root = Arc::new(RwLock::new(None));
+        let lsp_enabled = Arc::new(RwLock::new(lsp_enabled));
+        let extension_languages = Arc::new(std::sync::RwLock::new(extensions));
+        let document_sync = Arc::new(DocumentSync::new(
+            Arc::clone(&lsp_clients),
+            Arc::clone(&lsp_enabled),
+            Arc::clone(&extension_languages),
+        ));
+        let supervisor = LspSupervisor::new(
+            Arc::clone(&lsp_clients),
+            Arc::clone(&event_bus),
+            restart,
+            Self::started_hook(
+                Arc::clone(&lsp_clients),
+                Arc::clone(&diagnostics),
+                Arc::clone(&event_bus),
+                Arc::clone(&document_sync),
+                Arc::clone(&project_root),
+            ),
+        );
+
         let manager = Self {
             config,
             event_bus,
             highlighters: Arc::new(RwLock::new(HashMap::new())),
-            lsp_clients: Arc::new(RwLock::new(HashMap::new())),
-            project_root: Arc::new(RwLock::new(None)),
-            lsp_enabled: Arc::new(RwLock::new(lsp_enabled)),
+            lsp_clients,
+            supervisor,
+            diagnostics,
+            document_sync,
+            extension_languages,
+            project_root,
+            lsp_enabled,
         };
 
         manager.initialize_highlighters().await?;
 
+        // Editor events drive document synchronization.
+        manager.event_bus.register_handler("syntax_document_sync", manager.document_sync()).await?;
+
+        // Saved shaders are validated offline.
+        let shader_validator = Arc::new(ShaderValidator::new(
+            Arc::clone(&manager.config),
+            Arc::clone(&manager.diagnostics),
+            Arc::clone(&manager.event_bus),
+        ));
+        manager.event_bus.register_handler("syntax_shader_validator", shader_validator).await?;
+
+        let lsp_enabled = { *manager.lsp_enabled.read().await };
+
         if lsp_enabled {
             manager.initialize_lsp_clients().await?;
         }
@@ -79,45 +163,209 @@ impl SyntaxManager {
         let mut highlighters = self.highlighters.write().await;
 
         // Register Rust highlighter
-        highlighters.insert("rs".to_string(), Box::new(RustSyntaxHighlighter::new()));
+        highlighters.insert("rs".to_string(), Arc::new(RustSyntaxHighlighter::new()?));
+
+        // Register Alux highlighter
+        for extension in ["alux", "alx", "aux"] {
+            highlighters.insert(extension.to_string(), Arc::new(AluxSyntax::new()));
+        }
+
+        // Register WGSL highlighter
+        highlighters.insert("wgsl".to_string(), Arc::new(WgslSyntaxHighlighter::new()));
 
         // Register TOML highlighter
-        highlighters.insert("toml".to_string(), Box::new(TomlSyntaxHighlighter::new()));
+        highlighters.insert("toml".to_string(), Arc::new(TomlSyntaxHighlighter::new()));
 
         // Register JSON highlighter
-        highlighters.insert("json".to_string(), Box::new(JsonSyntaxHighlighter::new()));
+        highlighters.insert("json".to_string(), Arc::new(JsonSyntaxHighlighter::new()));
 
-        // Register Markdown highlighter
-        highlighters.insert("md".to_string(), Box::new(MarkdownSyntaxHighlighter::new()));
+        // Register Markdown highlighter; fenced code goes to the highlighters above
+        let markdown = MarkdownSyntaxHighlighter::with_highlighters(&self.highlighters);
+        highlighters.insert("md".to_string(), Arc::new(markdown));
 
         info!("Initialized {} syntax highlighters", highlighters.len());
         Ok(())
     }
 
-    /// Initialize LSP clients for supported languages.
+    /// Start LSP clients for supported languages under supervision.
     async fn initialize_lsp_clients(&self) -> Result<()> {
         debug!("Initializing LSP clients");
 
-        let mut clients = self.lsp_clients.write().await;
-        let config = self.config.read().await;
+        let lsp = self.config.read().await.lsp.clone();
+        let mut factories: Vec<(String, ClientFactory)> = Vec::new();
+
+        // rust-analyzer
+        let rust_config = lsp.clone();
+        factories.push((
+            "rust".to_string(),
+            client_factory(move || {
+                let config = rust_config.clone();
+                async move { RustAnalyzer::new(&config).await }
+            }),
+        ));
+
+        // Alux LSP, if available
+        let alux_config = alux_client_config(&lsp);
+        factories.push((
+            "alux".to_string(),
+            client_factory(move || AluxLspClient::start(alux_config.clone())),
+        ));
+
+        // Additional configured servers
+        for server in &lsp.servers {
+            let client_config = server_client_config(server, lsp.request_timeout_ms);
+            factories.push((
+                server.language.clone(),
+                client_factory(move || StdioLspClient::start(client_config.clone())),
+            ));
+        }
 
-        // Initialize rust-analyzer
-        if let Ok(rust_analyzer) = RustAnalyzer::new(&config.lsp.rust_analyzer).await {
-            clients.insert("rust".to_string(), Box::new(rust_analyzer));
-            info!("Initialized rust-analyzer LSP client");
-        } else {
-            warn!("Failed to initialize rust-analyzer");
+        for (language, factory) in factories {
+            if let Err(e) = self.supervisor.supervise(&language, factory).await {
+                warn!("Failed to initialize {} LSP client: {}", language, e);
+            }
         }
 
-        // Initialize Alux LSP if available
-        if let Ok(alux_lsp) = AluxLspClient::new(&config.lsp.alux_lsp).await {
-            clients.insert("alux".to_string(), Box::new(alux_lsp));
-            info!("Initialized Alux LSP client");
-        } else {
-            warn!("Failed to initialize Alux LSP client");
+        info!("Initialized {} LSP clients", self.lsp_clients.read().await.len());
+        Ok(())
+    }
+
+    /// Prepare a freshly started server: listen for its diagnostics, tell it
+    /// the project root and open the documents the editor has open.
+    fn started_hook(
+        lsp_clients: LspClients,
+        diagnostics: Arc<RwLock<HashMap<PathBuf, Vec<Diagnostic>>>>,
+        event_bus: Arc<EventBus>,
+        document_sync: Arc<DocumentSync>,
+        project_root: Arc<RwLock<Option<PathBuf>>>,
+    ) -> StartedHook {
+        Arc::new(move |language: String| {
+            let lsp_clients = Arc::clone(&lsp_clients);
+            let diagnostics = Arc::clone(&diagnostics);
+            let event_bus = Arc::clone(&event_bus);
+            let document_sync = Arc::clone(&document_sync);
+            let project_root = Arc::clone(&project_root);
+
+            Box::pin(async move {
+                {
+                    let clients = lsp_clients.read().await;
+                    let Some(client) = clients.get(&language) else {
+                        return;
+                    };
+                    Self::spawn_diagnostics_listener(
+                        diagnostics,
+                        event_bus,
+                        Arc::clone(&document_sync),
+                        &language,
+                        client.as_ref(),
+                    );
+
+                    let root = project_root.read().await.clone();
+                    if let Some(root) = root {
+                        if let Err(e) = client.set_root_uri(root.to_str().unwrap_or("")).await {
+                            error!("Failed to set root URI for {} LSP: {}", language, e);
+                        }
+                    }
+                }
+
+                // Documents opened while the server was down still need a `didOpen`.
+                if let Err(e) = document_sync.reopen_all(&language).await {
+                    error!("Failed to reopen {} documents: {}", language, e);
+                }
+            })
+        })
+    }
+
+    /// Keep the diagnostics store up to date from a client's
+    /// `publishDiagnostics` notifications.
+    ///
+    /// Columns are converted against the text the server was sent for the file.
+    fn spawn_diagnostics_listener(
+        store: Arc<RwLock<HashMap<PathBuf, Vec<Diagnostic>>>>,
+        event_bus: Arc<EventBus>,
+        document_sync: Arc<DocumentSync>,
+        language: &str,
+        client: &(dyn LspClient + Send + Sync),
+    ) {
+        let Some(mut notifications) = client.subscribe_notifications() else {
+            return;
+        };
+
+        let language = language.to_string();
+        tokio::spawn(async move {
+            loop {
+                match notifications.recv().await {
+                    Ok(notification)
+                        if notification.method == methods::TEXT_DOCUMENT_PUBLISH_DIAGNOSTICS =>
+                    {
+                        let Some(uri) = notification.params.get("uri").and_then(|u| u.as_str())
+                        else {
+                            continue;
+                        };
+                        let path = PathBuf::from(lsp_client::uri_to_path(uri));
+                        let text = document_sync.server_text(&path).await;
+                        let diagnostics = notification
+                            .params
+                            .get("diagnostics")
+                            .map(|diagnostics| {
+                                lsp_client::parse_diagnostics(diagnostics, text.as_deref())
+                            })
+                            .unwrap_or_default();
+
+                        if let Err(e) =
+                            Self::update_diagnostics(&store, &event_bus, path, diagnostics).await
+                        {
+                            error!("Failed to publish {} diagnostics: {}", language, e);
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("{} LSP diagnostics listener skipped {} messages", language, skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+            debug!("{} LSP diagnostics listener stopped", language);
+        });
+    }
+
+    /// Replace the stored diagnostics for a file and publish the update.
+    async fn update_diagnostics(
+        store: &RwLock<HashMap<PathBuf, Vec<Diagnostic>>>,
+        event_bus: &EventBus,
+        path: PathBuf,
+        diagnostics: Vec<Diagnostic>,
+    ) -> Result<()> {
+        let lsp_diagnostics = to_lsp_diagnostics(&diagnostics);
+
+        {
+            let mut store = store.write().await;
+            if diagnostics.is_empty() {
+                store.remove(&path);
+            } else {
+                store.insert(path.clone(), diagnostics);
+            }
+        }
+
+        let event = crate::core::EventMessage::from_event(crate::core::Event::Lsp(
+            crate::core::LspEvent::DiagnosticsReceived { path, diagnostics: lsp_diagnostics },
+        ))
+        .with_priority(crate::core::EventPriority::Normal)
+        .with_source("syntax_manager");
+        event_bus.publish(event).await
+    }
+
+    /// Drop all stored diagnostics, publishing an empty update for each file.
+    async fn clear_diagnostics(&self) -> Result<()> {
+        let paths: Vec<PathBuf> = {
+            let store = self.diagnostics.read().await;
+            store.keys().cloned().collect()
+        };
+
+        for path in paths {
+            Self::update_diagnostics(&self.diagnostics, &self.event_bus, path, Vec::new()).await?;
         }
 
-        info!("Initialized {} LSP clients", clients.len());
         Ok(())
     }
 
@@ -176,17 +424,14 @@ impl SyntaxManager {
             self.event_bus.publish(event).await?;
         }
 
-        // Notify LSP clients if LSP is enabled
+        // Shut down LSP servers if LSP is enabled
         let lsp_enabled = { *self.lsp_enabled.read().await };
         if lsp_enabled {
-            let clients = self.lsp_clients.read().await;
-            for (lang, client) in clients.iter() {
-                if let Err(e) = client.shutdown().await {
-                    error!("Failed to shutdown {} LSP: {}", lang, e);
-                }
-            }
+            self.supervisor.shutdown_all().await;
         }
 
+        self.clear_diagnostics().await?;
+
         Ok(())
     }
 
@@ -198,63 +443,60 @@ impl SyntaxManager {
     ) -> Result<Vec<HighlightToken>> {
         let extension = file_path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
 
-        let highlighters = self.highlighters.read().await;
-        if let Some(highlighter) = highlighters.get(extension) {
-            highlighter.highlight(content).await
+        let highlighter = self.highlighters.read().await.get(extension).cloned();
+        if let Some(highlighter) = highlighter {
+            highlighter.highlight_file(file_path, content).await
         } else {
             // No highlighter available, return plain text
             Ok(vec![HighlightToken::new(0, content.len(), TokenType::Text)])
         }
     }
 
-    /// Get diagnostics for a file.
+    /// Get foldable regions for a file.
+    pub async fn folding_ranges(
+        &self,
+        file_path: &PathBuf,
+        content: &str,
+    ) -> Result<Vec<FoldingRange>> {
+        let extension = file_path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+
+        let highlighter = self.highlighters.read().await.get(extension).cloned();
+        match highlighter {
+            Some(highlighter) => highlighter.folding_ranges(content).await,
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Get the symbol outline for a file.
+    pub async fn outline(&self, file_path: &PathBuf, content: &str) -> Result<Vec<OutlineItem>> {
+        let extension = file_path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+
+        let highlighter = self.highlighters.read().await.get(extension).cloned();
+        match highlighter {
+            Some(highlighter) => highlighter.outline(content).await,
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Get diagnostics for a file from the diagnostics store.
     pub async fn get_diagnostics(&self, file_path: &PathBuf) -> Result<Vec<Diagnostic>> {
         let lsp_enabled = { *self.lsp_enabled.read().await };
         if !lsp_enabled {
             return Ok(Vec::new());
         }
 
-        let language = self.detect_language(file_path);
-
-        let clients = self.lsp_clients.read().await;
-        if let Some(client) = clients.get(&language) {
-            let diagnostics = client.get_diagnostics(file_path.to_str().unwrap_or("")).await?;
-
-            // Publish diagnostics received event
-            if !diagnostics.is_empty() {
-                let lsp_diagnostics: Vec<LspDiagnostic> = diagnostics
-                    .iter()
-                    .map(|d| LspDiagnostic {
-                        line: d.line,
-                        column: d.column,
-                        end_line: d.line,
-                        end_column: d.column + d.length,
-                        severity: match d.severity {
-                            DiagnosticSeverity::Error => CoreDiagnosticSeverity::Error,
-                            DiagnosticSeverity::Warning => CoreDiagnosticSeverity::Warning,
-                            DiagnosticSeverity::Info => CoreDiagnosticSeverity::Info,
-                            DiagnosticSeverity::Hint => CoreDiagnosticSeverity::Hint,
-                        },
-                        message: d.message.clone(),
-                        source: d.source.clone(),
-                    })
-                    .collect();
+        let store = self.diagnostics.read().await;
+        Ok(store.get(file_path).cloned().unwrap_or_default())
+    }
 
-                let event = crate::core::EventMessage::from_event(crate::core::Event::Lsp(
-                    crate::core::LspEvent::DiagnosticsReceived {
-                        path: file_path.clone(),
-                        diagnostics: lsp_diagnostics,
-                    },
-                ))
-                .with_priority(crate::core::EventPriority::Normal)
-                .with_source("syntax_manager");
-                self.event_bus.publish(event).await?;
-            }
+    /// Get the document synchronizer shared with the event bus.
+    pub fn document_sync(&self) -> Arc<DocumentSync> {
+        Arc::clone(&self.document_sync)
+    }
 
-            Ok(diagnostics)
-        } else {
-            Ok(Vec::new())
-        }
+    /// Get every stored diagnostic, keyed by file.
+    pub async fn all_diagnostics(&self) -> HashMap<PathBuf, Vec<Diagnostic>> {
+        self.diagnostics.read().await.clone()
     }
 
     /// Get code completion for a position in a file.
@@ -357,41 +599,120 @@ impl SyntaxManager {
         }
     }
 
-    /// Detect language from file path.
-    fn detect_language(&self, file_path: &PathBuf) -> String {
-        let extension = file_path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+    /// Find the definition of the symbol at a position.
+    pub async fn goto_definition(
+        &self,
+        file_path: &PathBuf,
+        line: usize,
+        column: usize,
+    ) -> Result<Option<Location>> {
+        let lsp_enabled = { *self.lsp_enabled.read().await };
+        if !lsp_enabled {
+            return Ok(None);
+        }
+
+        let language = self.detect_language(file_path);
+
+        let clients = self.lsp_clients.read().await;
+        if let Some(client) = clients.get(&language) {
+            client.goto_definition(file_path.to_str().unwrap_or(""), line, column).await
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Find all references to the symbol at a position.
+    pub async fn find_references(
+        &self,
+        file_path: &PathBuf,
+        line: usize,
+        column: usize,
+    ) -> Result<Vec<Location>> {
+        let lsp_enabled = { *self.lsp_enabled.read().await };
+        if !lsp_enabled {
+            return Ok(Vec::new());
+        }
 
-        match extension {
-            "rs" => "rust".to_string(),
-            "toml" => "toml".to_string(),
-            "json" => "json".to_string(),
-            "md" => "markdown".to_string(),
-            _ => "text".to_string(),
+        let language = self.detect_language(file_path);
+
+        let clients = self.lsp_clients.read().await;
+        if let Some(client) = clients.get(&language) {
+            client.find_references(file_path.to_str().unwrap_or(""), line, column).await
+        } else {
+            Ok(Vec::new())
         }
     }
 
+    /// Compute the edits that rename the symbol at a position.
+    ///
+    /// The edit is not applied; pass it to [`crate::editor::Editor::apply_workspace_edit`].
+    pub async fn rename_symbol(
+        &self,
+        file_path: &PathBuf,
+        line: usize,
+        column: usize,
+        new_name: &str,
+    ) -> Result<Option<WorkspaceEdit>> {
+        let lsp_enabled = { *self.lsp_enabled.read().await };
+        if !lsp_enabled {
+            return Ok(None);
+        }
+
+        let language = self.detect_language(file_path);
+
+        let clients = self.lsp_clients.read().await;
+        if let Some(client) = clients.get(&language) {
+            client.rename_symbol(file_path.to_str().unwrap_or(""), line, column, new_name).await
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Compute the edits that format a document.
+    pub async fn format_document(&self, file_path: &PathBuf) -> Result<Option<Vec<TextEdit>>> {
+        let lsp_enabled = { *self.lsp_enabled.read().await };
+        if !lsp_enabled {
+            return Ok(None);
+        }
+
+        let language = self.detect_language(file_path);
+
+        let clients = self.lsp_clients.read().await;
+        if let Some(client) = clients.get(&language) {
+            client.format_document(file_path.to_str().unwrap_or("")).await
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Detect language from file path.
+    fn detect_language(&self, file_path: &PathBuf) -> String {
+        resolve_language(file_path, &self.extension_languages)
+    }
+
     /// Enable or disable LSP functionality.
     pub async fn set_lsp_enabled(&self, enabled: bool) -> Result<()> {
         let mut lsp_enabled = self.lsp_enabled.write().await;
         let was_enabled = *lsp_enabled;
         *lsp_enabled = enabled;
+        drop(lsp_enabled);
 
-        if enabled && !was_enabled {
+        if enabled == was_enabled {
+            return Ok(());
+        }
+        if enabled {
             // Re-initialize LSP clients
             self.initialize_lsp_clients().await?;
-        } else if !enabled && was_enabled {
+        } else {
             // Shutdown LSP clients
-            let clients = self.lsp_clients.read().await;
-            for (lang, client) in clients.iter() {
-                if let Err(e) = client.shutdown().await {
-                    error!("Failed to shutdown {} LSP: {}", lang, e);
-                }
-            }
+            self.supervisor.shutdown_all().await;
+
+            self.clear_diagnostics().await?;
         }
 
         // Publish LSP status change event
         let event = crate::core::EventMessage::from_event(crate::core::Event::System(
-            crate::core::SystemEvent::ConfigReloaded,
+            crate::core::SystemEvent::ConfigReloaded { changed: vec!["lsp.enabled".to_string()] },
         ))
         .with_priority(crate::core::EventPriority::Normal)
         .with_source("syntax_manager");
@@ -401,6 +722,37 @@ impl SyntaxManager {
         Ok(())
     }
 
+    /// Apply configuration changes, given by dotted key as in
+    /// `SystemEvent::ConfigReloaded`.
+    ///
+    /// Language servers are started or stopped when `lsp.enabled` changes, and
+    /// restarted with the new settings when other server settings change.
+    pub async fn apply_config_changes(&self, changed: &[String]) -> Result<()> {
+        let changed_in = |prefix: &str| changed.iter().any(|key| key.starts_with(prefix));
+        let lsp = self.config.read().await.lsp.clone();
+
+        if changed_in("lsp.servers") {
+            let extensions = server_extensions(&lsp.servers);
+            *self.extension_languages.write().unwrap_or_else(std::sync::PoisonError::into_inner) =
+                extensions;
+        }
+        if changed_in("lsp.restart") {
+            self.supervisor.set_policy(lsp.restart.clone());
+        }
+        if changed_in("lsp.enabled") {
+            return self.set_lsp_enabled(lsp.enabled).await;
+        }
+
+        let restart =
+            ["lsp.rust_analyzer", "lsp.alux_lsp", "lsp.servers", "lsp.request_timeout_ms"];
+        if lsp.enabled && restart.iter().any(|prefix| changed_in(prefix)) {
+            info!("Restarting language servers with the new configuration");
+            self.supervisor.shutdown_all().await;
+            self.initialize_lsp_clients().await?;
+        }
+        Ok(())
+    }
+
     /// Check if LSP is currently enabled.
     pub async fn is_lsp_enabled(&self) -> bool {
         *self.lsp_enabled.read().await
@@ -421,18 +773,102 @@ impl SyntaxManager {
         // Shutdown all LSP clients
         let lsp_enabled = { *self.lsp_enabled.read().await };
         if lsp_enabled {
-            let clients = self.lsp_clients.read().await;
-            for (lang, client) in clients.iter() {
-                if let Err(e) = client.shutdown().await {
-                    error!("Failed to shutdown {} LSP: {}", lang, e);
-                }
-            }
+            self.supervisor.shutdown_all().await;
         }
 
         Ok(())
     }
 }
 
+/// Detect the language of a file from its extension.
+pub fn detect_language(file_path: &Path) -> &'static str {
+    let extension = file_path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+
+    match extension {
+        "rs" => "rust",
+        "alux" | "alx" | "aux" => "alux",
+        "wgsl" => "wgsl",
+        "toml" => "toml",
+        "json" => "json",
+        "md" => "markdown",
+        _ => "text",
+    }
+}
+
+/// Detect the language of a file, preferring extensions claimed by configured servers.
+pub fn resolve_language(file_path: &Path, extension_languages: &ExtensionLanguages) -> String {
+    let extension = file_path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+    let claimed = extension_languages
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .get(extension)
+        .cloned();
+    claimed.unwrap_or_else(|| detect_language(file_path).to_string())
+}
+
+/// Map the file extensions of configured servers to their language ids.
+fn server_extensions(servers: &[LanguageServerConfig]) -> HashMap<String, String> {
+    servers
+        .iter()
+        .flat_map(|server| {
+            server.extensions.iter().map(|extension| {
+                (extension.trim_start_matches('.').to_string(), server.language.clone())
+            })
+        })
+        .collect()
+}
+
+/// Client configuration for `alux-lsp`.
+fn alux_client_config(config: &LspConfig) -> LspClientConfig {
+    LspClientConfig {
+        language_id: "alux".to_string(),
+        server_path: config
+            .alux_lsp
+            .binary_path
+            .as_ref()
+            .map_or_else(|| "alux-lsp".to_string(), |p| p.to_string_lossy().to_string()),
+        request_timeout_ms: config.request_timeout_ms,
+        initialization_options: Some(serde_json::json!({
+            "semanticHighlighting": config.alux_lsp.semantic_highlighting,
+            "inlineHints": config.alux_lsp.inline_hints
+        })),
+        ..LspClientConfig::default()
+    }
+}
+
+/// Client configuration for a server declared in `LspConfig::servers`.
+fn server_client_config(server: &LanguageServerConfig, request_timeout_ms: u64) -> LspClientConfig {
+    LspClientConfig {
+        language_id: server.language.clone(),
+        server_path: server.command.to_string_lossy().to_string(),
+        server_args: server.args.clone(),
+        request_timeout_ms,
+        initialization_options: server.initialization_options.clone(),
+        ..LspClientConfig::default()
+    }
+}
+
+/// Convert diagnostics into the form carried by LSP events.
+fn to_lsp_diagnostics(diagnostics: &[Diagnostic]) -> Vec<LspDiagnostic> {
+    diagnostics
+        .iter()
+        .map(|d| LspDiagnostic {
+            line: d.line,
+            column: d.column,
+            end_line: d.end_line,
+            end_column: d.end_column,
+            severity: match d.severity {
+                DiagnosticSeverity::Error => CoreDiagnosticSeverity::Error,
+                DiagnosticSeverity::Warning => CoreDiagnosticSeverity::Warning,
+                DiagnosticSeverity::Info => CoreDiagnosticSeverity::Info,
+                DiagnosticSeverity::Hint => CoreDiagnosticSeverity::Hint,
+            },
+            message: d.message.clone(),
+            source: d.source.clone(),
+        })
+        .collect()
+}
+
 /// Represents a syntax highlight token.
 #[derive(Debug, Clone)]
 pub struct HighlightToken {
@@ -460,14 +896,75 @@ pub enum TokenType {
     Type,
     Operator,
     Punctuation,
+    Macro,
+    Lifetime,
+    Attribute,
+    DocComment,
+    Property,
+    Escape,
+    Constant,
+    Date,
+    Heading,
+    Emphasis,
+    Link,
+}
+
+/// A foldable region of a document, by zero-based line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoldingRange {
+    pub start_line: usize,
+    pub end_line: usize,
+    pub kind: FoldingRangeKind,
+}
+
+/// Kinds of foldable regions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FoldingRangeKind {
+    Region,
+    Comment,
+    Imports,
+}
+
+/// A symbol in a document outline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutlineItem {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Signature or type, when the symbol has one.
+    pub detail: Option<String>,
+    pub exported: bool,
+    /// Byte range of the whole declaration.
+    pub range: Range<usize>,
+    /// Zero-based line the declaration starts on.
+    pub line: usize,
+    pub children: Vec<OutlineItem>,
+}
+
+/// Kinds of outline symbols.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Import,
+    Export,
+    Component,
+    System,
+    Task,
+    Scene,
+    Shader,
+    Function,
+    Field,
 }
 
 /// Represents a diagnostic message.
+///
+/// Positions are zero-based lines and byte columns, like the editor's buffers.
 #[derive(Debug, Clone)]
 pub struct Diagnostic {
     pub line: usize,
     pub column: usize,
-    pub length: usize,
+    /// Line the diagnostic ends on.
+    pub end_line: usize,
+    /// Byte column the diagnostic ends at, exclusive.
+    pub end_column: usize,
     pub severity: DiagnosticSeverity,
     pub message: String,
     pub source: Option<String>,
@@ -515,234 +1012,222 @@ pub enum CompletionItemKind {
     Reference,
 }
 
-// Basic syntax highlighter implementations
-
-pub struct RustSyntaxHighlighter {
-    keywords: Vec<String>,
-}
-
-impl RustSyntaxHighlighter {
-    pub fn new() -> Self {
-        // Try to load config from assets/syntax_config.json
-        let mut keywords: Vec<String> = vec!["fn".to_string()];
-
-        let config_path = std::path::Path::new("assets").join("syntax_config.json");
-        if let Ok(content) = std::fs::read_to_string(config_path) {
-            #[derive(serde::Deserialize)]
-            struct RustCfg { keywords: Option<Vec<String>> }
-            #[derive(serde::Deserialize)]
-            struct Cfg { rust: Option<RustCfg> }
-            if let Ok(cfg) = serde_json::from_str::<Cfg>(&content) {
-                if let Some(r) = cfg.rust {
-                    if let Some(kw) = r.keywords {
-                        if !kw.is_empty() { keywords = kw; }
-                    }
-                }
-            }
-        }
-
-        Self { keywords }
-    }
-}
-
-#[async_trait::async_trait]
-impl SyntaxHighlighter for RustSyntaxHighlighter {
-    async fn highlight(&self, content: &str) -> Result<Vec<HighlightToken>> {
-        // Keyword + strings + numbers + line comments
-        let mut tokens: Vec<HighlightToken> = Vec::new();
-
-        // Keywords (whole word)
-        let keyword_refs: Vec<&str> = self.keywords.iter().map(|s| s.as_str()).collect();
-        let mut kw = highlighter::utils::extract_keywords(content, &keyword_refs);
-        tokens.append(&mut kw);
-
-        // Strings ("..." and '...')
-        let mut str_tokens = highlighter::utils::extract_strings(content, &['"', '\'']);
-        tokens.append(&mut str_tokens);
-
-        // Numbers
-        let mut nums = highlighter::utils::extract_numbers(content);
-        tokens.append(&mut nums);
-
-        // Line comments
-        let mut comments = highlighter::utils::extract_line_comments(content, "//");
-        tokens.append(&mut comments);
-
-        Ok(highlighter::utils::merge_tokens(tokens))
-    }
-}
-
-pub struct AluxSyntaxHighlighter;
-
-impl AluxSyntaxHighlighter {
-    pub fn new() -> Self {
-        Self
-    }
-}
-
-#[async_trait::async_trait]
-impl SyntaxHighlighter for AluxSyntaxHighlighter {
-    async fn highlight(&self, content: &str) -> Result<Vec<HighlightToken>> {
-        // Simple Alux syntax highlighting
-        let mut tokens = Vec::new();
-        let keywords = ["fn", "let", "if", "else", "while", "for", "return", "task"];
-
-        for keyword in &keywords {
-            for (start, _) in content.match_indices(keyword) {
-                tokens.push(HighlightToken::new(start, start + keyword.len(), TokenType::Keyword));
-            }
-        }
-
-        Ok(tokens)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::LspRestartConfig;
+
+    /// Supervisor for managers built by hand, which start no servers.
+    fn idle_supervisor() -> LspSupervisor {
+        LspSupervisor::new(
+            Arc::default(),
+            Arc::new(EventBus::new()),
+            LspRestartConfig::default(),
+            Arc::new(|_| Box::pin(async {})),
+        )
     }
-}
 
-pub struct TomlSyntaxHighlighter;
+    #[tokio::test]
+    async fn test_syntax_manager_creation() {
+        let config = Arc::new(RwLock::new(Config::default()));
+        let event_bus = Arc::new(EventBus::new());
 
-impl TomlSyntaxHighlighter {
-    pub fn new() -> Self {
-        Self
+        let syntax_manager = SyntaxManager::new(config, event_bus).await;
+        assert!(syntax_manager.is_ok());
     }
-}
 
-#[async_trait::async_trait]
-impl SyntaxHighlighter for TomlSyntaxHighlighter {
-    async fn highlight(&self, _content: &str) -> Result<Vec<HighlightToken>> {
-        // Basic TOML highlighting would go here
-        Ok(Vec::new())
+    #[tokio::test]
+    async fn test_apply_restart_policy_changes() {
+        let mut config = Config::default();
+        config.lsp.enabled = false;
+        let config = Arc::new(RwLock::new(config));
+        let manager =
+            SyntaxManager::new(Arc::clone(&config), Arc::new(EventBus::new())).await.unwrap();
+
+        config.write().await.lsp.restart.max_restarts = 1;
+        config.write().await.lsp.restart.initial_backoff_ms = 50;
+        let changed = ["lsp.restart.initial_backoff_ms", "lsp.restart.max_restarts"];
+        manager.apply_config_changes(&changed.map(String::from)).await.unwrap();
+
+        let policy = manager.supervisor.policy();
+        assert_eq!((policy.max_restarts, policy.initial_backoff_ms), (1, 50));
     }
-}
 
-pub struct JsonSyntaxHighlighter;
+    #[test]
+    fn test_language_detection() {
+        let manager = SyntaxManager {
+            config: Arc::new(RwLock::new(Config::default())),
+            event_bus: Arc::new(EventBus::new()),
+            highlighters: Arc::new(RwLock::new(HashMap::new())),
+            lsp_clients: Arc::new(RwLock::new(HashMap::new())),
+            supervisor: idle_supervisor(),
+            diagnostics: Arc::new(RwLock::new(HashMap::new())),
+            document_sync: Arc::new(DocumentSync::new(
+                Arc::new(RwLock::new(HashMap::new())),
+                Arc::new(RwLock::new(true)),
+                ExtensionLanguages::default(),
+            )),
+            extension_languages: ExtensionLanguages::default(),
+            project_root: Arc::new(RwLock::new(None)),
+            lsp_enabled: Arc::new(RwLock::new(true)),
+        };
 
-impl JsonSyntaxHighlighter {
-    pub fn new() -> Self {
-        Self
+        assert_eq!(manager.detect_language(&PathBuf::from("test.rs")), "rust");
+        assert_eq!(manager.detect_language(&PathBuf::from("script.aux")), "alux");
+        assert_eq!(manager.detect_language(&PathBuf::from("config.toml")), "toml");
+        assert_eq!(manager.detect_language(&PathBuf::from("data.json")), "json");
+        assert_eq!(manager.detect_language(&PathBuf::from("shader.wgsl")), "wgsl");
     }
-}
 
-#[async_trait::async_trait]
-impl SyntaxHighlighter for JsonSyntaxHighlighter {
-    async fn highlight(&self, _content: &str) -> Result<Vec<HighlightToken>> {
-        // Basic JSON highlighting would go here
-        Ok(Vec::new())
+    #[test]
+    fn test_configured_server_languages() {
+        let mut config = LspConfig::default();
+        config.alux_lsp.binary_path = Some(PathBuf::from("/opt/alux/bin/alux-lsp"));
+        config.servers.push(LanguageServerConfig {
+            language: "wgsl".to_string(),
+            command: PathBuf::from("wgsl-analyzer"),
+            args: vec!["--stdio".to_string()],
+            extensions: vec![".wgsl".to_string(), "frag".to_string()],
+            initialization_options: None,
+        });
+
+        let extension_languages: ExtensionLanguages =
+            Arc::new(std::sync::RwLock::new(server_extensions(&config.servers)));
+        assert_eq!(resolve_language(Path::new("blur.frag"), &extension_languages), "wgsl");
+        assert_eq!(resolve_language(Path::new("main.wgsl"), &extension_languages), "wgsl");
+        assert_eq!(resolve_language(Path::new("main.rs"), &extension_languages), "rust");
+
+        let server = server_client_config(&config.servers[0], 1500);
+        assert_eq!(server.language_id, "wgsl");
+        assert_eq!(server.server_path, "wgsl-analyzer");
+        assert_eq!(server.server_args, vec!["--stdio"]);
+        assert_eq!(server.request_timeout_ms, 1500);
+
+        let alux = alux_client_config(&config);
+        assert_eq!(alux.language_id, "alux");
+        assert_eq!(alux.server_path, "/opt/alux/bin/alux-lsp");
+        assert_eq!(alux_client_config(&LspConfig::default()).server_path, "alux-lsp");
     }
-}
-
-pub struct MarkdownSyntaxHighlighter;
 
-impl MarkdownSyntaxHighlighter {
-    pub fn new() -> Self {
-        Self
+    /// Client that forwards notifications from a test-controlled channel.
+    struct NotifyingClient {
+        notifications: broadcast::Sender<transport::LspNotification>,
     }
-}
 
-#[async_trait::async_trait]
-impl SyntaxHighlighter for MarkdownSyntaxHighlighter {
-    async fn highlight(&self, content: &str) -> Result<Vec<HighlightToken>> {
-        // Simple markdown: headings and code fences
-        let mut tokens = Vec::new();
-        for (line_idx, line) in content.lines().enumerate() {
-            let line_start: usize = content
-                .lines()
-                .take(line_idx)
-                .map(|l| l.len() + 1)
-                .sum();
-            if line.trim_start().starts_with('#') {
-                tokens.push(HighlightToken::new(
-                    line_start,
-                    line_start + line.len(),
-                    TokenType::Keyword,
-                ));
-            }
-            if line.trim_start().starts_with("```") {
-                tokens.push(HighlightToken::new(
-                    line_start,
-                    line_start + line.len(),
-                    TokenType::Punctuation,
-                ));
-            }
+    #[async_trait::async_trait]
+    impl LspClient for NotifyingClient {
+        async fn set_root_uri(&self, _uri: &str) -> Result<()> {
+            Ok(())
         }
-        Ok(tokens)
-    }
-}
 
-// Placeholder LSP client
-pub struct AluxLspClient;
-
-impl AluxLspClient {
-    pub async fn new(_config: &crate::core::config::AluxLspConfig) -> Result<Self> {
-        Ok(Self)
-    }
-}
-
-#[async_trait::async_trait]
-impl LspClient for AluxLspClient {
-    async fn set_root_uri(&self, _uri: &str) -> Result<()> {
-        Ok(())
-    }
+        async fn get_diagnostics(&self, _file_path: &str) -> Result<Vec<Diagnostic>> {
+            Ok(Vec::new())
+        }
 
-    async fn get_diagnostics(&self, _file_path: &str) -> Result<Vec<Diagnostic>> {
-        Ok(Vec::new())
-    }
+        async fn get_completion(
+            &self,
+            _file_path: &str,
+            _line: usize,
+            _column: usize,
+        ) -> Result<Vec<CompletionItem>> {
+            Ok(Vec::new())
+        }
 
-    async fn get_completion(
-        &self,
-        _file_path: &str,
-        _line: usize,
-        _column: usize,
-    ) -> Result<Vec<CompletionItem>> {
-        Ok(Vec::new())
-    }
+        async fn get_hover(
+            &self,
+            _file_path: &str,
+            _line: usize,
+            _column: usize,
+        ) -> Result<Option<String>> {
+            Ok(None)
+        }
 
-    async fn get_hover(
-        &self,
-        _file_path: &str,
-        _line: usize,
-        _column: usize,
-    ) -> Result<Option<String>> {
-        Ok(None)
-    }
+        async fn shutdown(&self) -> Result<()> {
+            Ok(())
+        }
 
-    async fn shutdown(&self) -> Result<()> {
-        Ok(())
+        fn subscribe_notifications(
+            &self,
+        ) -> Option<broadcast::Receiver<transport::LspNotification>> {
+            Some(self.notifications.subscribe())
+        }
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
     #[tokio::test]
-    async fn test_syntax_manager_creation() {
+    async fn test_published_diagnostics_are_stored() {
         let config = Arc::new(RwLock::new(Config::default()));
         let event_bus = Arc::new(EventBus::new());
-
-        let syntax_manager = SyntaxManager::new(config, event_bus).await;
-        assert!(syntax_manager.is_ok());
-    }
-
-    #[test]
-    fn test_language_detection() {
         let manager = SyntaxManager {
-            config: Arc::new(RwLock::new(Config::default())),
-            event_bus: Arc::new(EventBus::new()),
+            config,
+            event_bus: Arc::clone(&event_bus),
             highlighters: Arc::new(RwLock::new(HashMap::new())),
             lsp_clients: Arc::new(RwLock::new(HashMap::new())),
+            supervisor: idle_supervisor(),
+            diagnostics: Arc::new(RwLock::new(HashMap::new())),
+            document_sync: Arc::new(DocumentSync::new(
+                Arc::new(RwLock::new(HashMap::new())),
+                Arc::new(RwLock::new(true)),
+                ExtensionLanguages::default(),
+            )),
+            extension_languages: ExtensionLanguages::default(),
             project_root: Arc::new(RwLock::new(None)),
             lsp_enabled: Arc::new(RwLock::new(true)),
         };
 
-        assert_eq!(manager.detect_language(&PathBuf::from("test.rs")), "rust");
-        assert_eq!(manager.detect_language(&PathBuf::from("script.aux")), "alux");
-        assert_eq!(manager.detect_language(&PathBuf::from("config.toml")), "toml");
-        assert_eq!(manager.detect_language(&PathBuf::from("data.json")), "json");
-        assert_eq!(manager.detect_language(&PathBuf::from("shader.wgsl")), "wgsl");
+        let (sender, _) = broadcast::channel(8);
+        let client = NotifyingClient { notifications: sender.clone() };
+        let path = PathBuf::from("/project/src/main.rs");
+        manager.document_sync.open(&path, "fn main() {\n    let é = 1;\n    é\n}").await.unwrap();
+        SyntaxManager::spawn_diagnostics_listener(
+            Arc::clone(&manager.diagnostics),
+            Arc::clone(&event_bus),
+            Arc::clone(&manager.document_sync),
+            "rust",
+            &client,
+        );
+        let mut events = event_bus.subscribe(crate::core::EventSubscription::all());
+
+        let publish = |diagnostics: serde_json::Value| transport::LspNotification {
+            method: methods::TEXT_DOCUMENT_PUBLISH_DIAGNOSTICS.to_string(),
+            params: serde_json::json!({ "uri": "file:///project/src/main.rs", "diagnostics": diagnostics }),
+        };
+        sender
+            .send(publish(serde_json::json!([{
+                "range": { "start": { "line": 1, "character": 8 }, "end": { "line": 2, "character": 5 } },
+                "severity": 1,
+                "message": "mismatched types"
+            }])))
+            .unwrap();
+
+        let event = events.recv().await.unwrap();
+        match serde_json::from_value(event.data).unwrap() {
+            crate::core::Event::Lsp(crate::core::LspEvent::DiagnosticsReceived {
+                path,
+                diagnostics,
+            }) => {
+                assert_eq!(path, PathBuf::from("/project/src/main.rs"));
+                assert_eq!(diagnostics.len(), 1);
+                // `é` is one UTF-16 unit but two bytes.
+                assert_eq!((diagnostics[0].line, diagnostics[0].column), (1, 8));
+                assert_eq!((diagnostics[0].end_line, diagnostics[0].end_column), (2, 6));
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        let stored = manager.get_diagnostics(&path).await.unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].message, "mismatched types");
+
+        // An empty publish clears the file.
+        sender.send(publish(serde_json::json!([]))).unwrap();
+        let _cleared = events.recv().await.unwrap();
+        assert!(manager.get_diagnostics(&path).await.unwrap().is_empty());
+        assert!(manager.all_diagnostics().await.is_empty());
     }
 
     #[tokio::test]
     async fn test_rust_syntax_highlighting() {
-        let highlighter = RustSyntaxHighlighter::new();
+        let highlighter = RustSyntaxHighlighter::new().unwrap();
         let content = "fn main() { let x = 42; }";
 
         let tokens = highlighter.highlight(content).await.unwrap();
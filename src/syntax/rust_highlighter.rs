@@ -0,0 +1,470 @@
+//! # Rust Syntax Highlighting
+//!
+//! Tree-sitter based highlighter for Rust. Syntax trees are kept per file so
+//! that edits only reparse and re-query the part of the file that changed.
+
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+use async_trait::async_trait;
+use streaming_iterator::StreamingIterator;
+use tree_sitter::{InputEdit, Parser, Point, Query, QueryCursor, Tree};
+
+use crate::core::{Result, XyluxError};
+use crate::syntax::{HighlightToken, SyntaxHighlighter, TokenType};
+
+/// Patterns added on top of the grammar's highlight query.
+const EXTRA_HIGHLIGHTS: &str = r"
+(lifetime) @lifetime
+(macro_definition name: (identifier) @function.macro)
+";
+
+/// Number of files whose syntax trees are kept for incremental reparsing.
+const MAX_CACHED_FILES: usize = 32;
+
+/// Rust highlighter driven by the tree-sitter grammar's highlight query.
+pub struct RustSyntaxHighlighter {
+    /// Compiled highlight query.
+    query: Query,
+    /// Token type for each capture name of the query, by capture index.
+    capture_types: Vec<Option<TokenType>>,
+    /// Parser and the syntax trees of recently highlighted files.
+    state: Mutex<ParserState>,
+}
+
+/// Mutable parsing state shared by all files.
+struct ParserState {
+    /// Tree-sitter parser for Rust.
+    parser: Parser,
+    /// Parsed files by path.
+    files: HashMap<PathBuf, ParsedFile>,
+    /// Counter used to find the least recently highlighted file.
+    clock: u64,
+}
+
+/// A highlighted file kept for incremental updates.
+struct ParsedFile {
+    /// Source the tree was parsed from.
+    source: String,
+    /// Syntax tree of `source`.
+    tree: Tree,
+    /// Tokens for `source`, sorted and non-overlapping.
+    tokens: Vec<HighlightToken>,
+    /// Value of the clock when the file was last highlighted.
+    last_used: u64,
+}
+
+impl RustSyntaxHighlighter {
+    /// Create a new Rust highlighter.
+    pub fn new() -> Result<Self> {
+        let language = tree_sitter::Language::new(tree_sitter_rust::LANGUAGE);
+
+        let mut parser = Parser::new();
+        parser
+            .set_language(&language)
+            .map_err(|e| XyluxError::syntax_error(format!("Failed to load Rust grammar: {}", e)))?;
+
+        let source = format!("{}\n{}", tree_sitter_rust::HIGHLIGHTS_QUERY, EXTRA_HIGHLIGHTS);
+        let query = Query::new(&language, &source).map_err(|e| {
+            XyluxError::syntax_error(format!("Invalid Rust highlight query: {}", e))
+        })?;
+        let capture_types = query.capture_names().iter().map(|name| capture_type(name)).collect();
+
+        Ok(Self {
+            query,
+            capture_types,
+            state: Mutex::new(ParserState { parser, files: HashMap::new(), clock: 0 }),
+        })
+    }
+
+    /// Parse `source`, reusing the unchanged parts of `old_tree`.
+    fn parse(parser: &mut Parser, source: &str, old_tree: Option<&Tree>) -> Result<Tree> {
+        parser
+            .parse(source, old_tree)
+            .ok_or_else(|| XyluxError::syntax_error("Failed to parse Rust source"))
+    }
+
+    /// Highlight a range of `source`, widened until it covers every capture it touches.
+    ///
+    /// Returns the tokens and the range they cover.
+    fn highlight_range(
+        &self,
+        tree: &Tree,
+        source: &str,
+        mut range: Range<usize>,
+    ) -> (Vec<HighlightToken>, Range<usize>) {
+        loop {
+            let captures = self.captures(tree, source, range.clone());
+            let extent = captures.iter().fold(range.clone(), |extent, capture| {
+                extent.start.min(capture.range.start)..extent.end.max(capture.range.end)
+            });
+            if extent == range {
+                return (paint(&captures, range.clone()), range);
+            }
+            range = extent;
+        }
+    }
+
+    /// Run the highlight query over a byte range.
+    fn captures(&self, tree: &Tree, source: &str, range: Range<usize>) -> Vec<Capture> {
+        let mut cursor = QueryCursor::new();
+        cursor.set_byte_range(range);
+
+        let mut captures = Vec::new();
+        let mut matches = cursor.captures(&self.query, tree.root_node(), source.as_bytes());
+        while let Some((query_match, index)) = matches.next() {
+            let capture = query_match.captures[*index];
+            let Some(Some(token_type)) = self.capture_types.get(capture.index as usize) else {
+                continue;
+            };
+            captures.push(Capture {
+                range: capture.node.byte_range(),
+                token_type: token_type.clone(),
+                pattern: query_match.pattern_index,
+            });
+        }
+        captures
+    }
+
+    /// Highlight a file, reusing its previous tree and tokens when available.
+    fn highlight_cached(&self, path: &Path, content: &str) -> Result<Vec<HighlightToken>> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let state = &mut *state;
+        state.clock += 1;
+        let clock = state.clock;
+
+        if let Some(file) = state.files.get_mut(path) {
+            file.last_used = clock;
+            let Some(edit) = input_edit(&file.source, content) else {
+                return Ok(file.tokens.clone());
+            };
+
+            file.tree.edit(&edit);
+            let tree = Self::parse(&mut state.parser, content, Some(&file.tree))?;
+
+            // Re-query whatever the edit touched plus any region whose structure changed.
+            let dirty = file.tree.changed_ranges(&tree).fold(
+                edit.start_byte..edit.new_end_byte,
+                |dirty, changed| {
+                    dirty.start.min(changed.start_byte)..dirty.end.max(changed.end_byte)
+                },
+            );
+            let (tokens, covered) =
+                self.highlight_range(&tree, content, line_range(content, dirty));
+
+            file.tokens = splice_tokens(&file.tokens, &edit, tokens, covered);
+            file.tree = tree;
+            file.source = content.to_string();
+            return Ok(file.tokens.clone());
+        }
+
+        let tree = Self::parse(&mut state.parser, content, None)?;
+        let (tokens, _) = self.highlight_range(&tree, content, 0..content.len());
+
+        if state.files.len() >= MAX_CACHED_FILES {
+            let oldest = state
+                .files
+                .iter()
+                .min_by_key(|(_, file)| file.last_used)
+                .map(|(path, _)| path.clone());
+            if let Some(oldest) = oldest {
+                state.files.remove(&oldest);
+            }
+        }
+        state.files.insert(
+            path.to_path_buf(),
+            ParsedFile {
+                source: content.to_string(),
+                tree,
+                tokens: tokens.clone(),
+                last_used: clock,
+            },
+        );
+
+        Ok(tokens)
+    }
+}
+
+#[async_trait]
+impl SyntaxHighlighter for RustSyntaxHighlighter {
+    async fn highlight(&self, content: &str) -> Result<Vec<HighlightToken>> {
+        let tree = {
+            let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+            Self::parse(&mut state.parser, content, None)?
+        };
+        Ok(self.highlight_range(&tree, content, 0..content.len()).0)
+    }
+
+    async fn highlight_file(&self, path: &Path, content: &str) -> Result<Vec<HighlightToken>> {
+        self.highlight_cached(path, content)
+    }
+
+    fn language(&self) -> &str {
+        "rust"
+    }
+
+    fn file_extensions(&self) -> Vec<&str> {
+        vec!["rs"]
+    }
+}
+
+/// A query capture resolved to a token type.
+struct Capture {
+    /// Byte range of the captured node.
+    range: Range<usize>,
+    /// Token type of the capture name.
+    token_type: TokenType,
+    /// Index of the query pattern that matched; later patterns are more specific.
+    pattern: usize,
+}
+
+/// Map a highlight query capture name onto a token type.
+fn capture_type(name: &str) -> Option<TokenType> {
+    let token_type = match name {
+        "keyword" | "variable.builtin" => TokenType::Keyword,
+        "string" | "escape" => TokenType::String,
+        "constant.builtin" => TokenType::Number,
+        "comment" => TokenType::Comment,
+        "comment.documentation" => TokenType::DocComment,
+        "function" | "function.method" => TokenType::Function,
+        "function.macro" => TokenType::Macro,
+        "type" | "type.builtin" | "constructor" => TokenType::Type,
+        "constant" | "property" | "variable.parameter" => TokenType::Variable,
+        "label" | "lifetime" => TokenType::Lifetime,
+        "attribute" => TokenType::Attribute,
+        "operator" => TokenType::Operator,
+        "punctuation.bracket" | "punctuation.delimiter" => TokenType::Punctuation,
+        _ => return None,
+    };
+    Some(token_type)
+}
+
+/// Whether nested captures are hidden inside a token of this type.
+fn is_atomic(token_type: &TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::String
+            | TokenType::Comment
+            | TokenType::DocComment
+            | TokenType::Lifetime
+            | TokenType::Attribute
+    )
+}
+
+/// Flatten possibly nested captures inside `range` into non-overlapping tokens.
+///
+/// Inner captures win over the nodes containing them, except inside atomic
+/// tokens such as comments and attributes. For identical ranges the later
+/// pattern wins.
+fn paint(captures: &[Capture], range: Range<usize>) -> Vec<HighlightToken> {
+    let mut order: Vec<&Capture> = captures.iter().collect();
+    order.sort_by_key(|capture| {
+        (std::cmp::Reverse(capture.range.end - capture.range.start), capture.pattern)
+    });
+
+    let mut painted: Vec<Option<&TokenType>> = vec![None; range.end - range.start];
+    let atomic_pass = order.iter().filter(|capture| is_atomic(&capture.token_type));
+    for capture in order.iter().chain(atomic_pass) {
+        let start = capture.range.start.max(range.start) - range.start;
+        let end = capture.range.end.min(range.end).saturating_sub(range.start);
+        for slot in painted.iter_mut().take(end).skip(start) {
+            *slot = Some(&capture.token_type);
+        }
+    }
+
+    let mut tokens: Vec<HighlightToken> = Vec::new();
+    for (offset, token_type) in painted.into_iter().enumerate() {
+        let Some(token_type) = token_type else {
+            continue;
+        };
+        let position = range.start + offset;
+        match tokens.last_mut() {
+            Some(last) if last.end == position && last.token_type == *token_type => {
+                last.end = position + 1;
+            }
+            _ => tokens.push(HighlightToken::new(position, position + 1, token_type.clone())),
+        }
+    }
+    tokens
+}
+
+/// Replace the tokens inside `covered` with `fresh`, shifting the old tokens
+/// after the edit into the new text's coordinates.
+fn splice_tokens(
+    old: &[HighlightToken],
+    edit: &InputEdit,
+    fresh: Vec<HighlightToken>,
+    covered: Range<usize>,
+) -> Vec<HighlightToken> {
+    let shifted = old.iter().filter_map(|token| {
+        if token.end <= edit.start_byte {
+            Some(token.clone())
+        } else if token.start >= edit.old_end_byte {
+            let start = token.start - edit.old_end_byte + edit.new_end_byte;
+            let end = token.end - edit.old_end_byte + edit.new_end_byte;
+            Some(HighlightToken::new(start, end, token.token_type.clone()))
+        } else {
+            None
+        }
+    });
+
+    let mut tokens: Vec<HighlightToken> = Vec::with_capacity(old.len() + fresh.len());
+    let mut fresh = Some(fresh);
+    for token in shifted {
+        if token.end <= covered.start {
+            tokens.push(token);
+        } else if token.start >= covered.end {
+            if let Some(fresh) = fresh.take() {
+                tokens.extend(fresh);
+            }
+            tokens.push(token);
+        }
+    }
+    if let Some(fresh) = fresh {
+        tokens.extend(fresh);
+    }
+
+    // Rejoin tokens split at the edges of the re-highlighted range.
+    tokens.dedup_by(|next, previous| {
+        let joined = previous.end == next.start && previous.token_type == next.token_type;
+        if joined {
+            previous.end = next.end;
+        }
+        joined
+    });
+    tokens
+}
+
+/// Describe the change from `old` to `new` as a single replaced span, or
+/// `None` if the texts are equal.
+fn input_edit(old: &str, new: &str) -> Option<InputEdit> {
+    if old == new {
+        return None;
+    }
+
+    let mut prefix = old.bytes().zip(new.bytes()).take_while(|(old, new)| old == new).count();
+    while !old.is_char_boundary(prefix) {
+        prefix -= 1;
+    }
+
+    let max_suffix = old.len().min(new.len()) - prefix;
+    let mut suffix = old
+        .bytes()
+        .rev()
+        .zip(new.bytes().rev())
+        .take(max_suffix)
+        .take_while(|(old, new)| old == new)
+        .count();
+    while !old.is_char_boundary(old.len() - suffix) {
+        suffix -= 1;
+    }
+
+    let old_end = old.len() - suffix;
+    let new_end = new.len() - suffix;
+    Some(InputEdit {
+        start_byte: prefix,
+        old_end_byte: old_end,
+        new_end_byte: new_end,
+        start_position: point_at(old, prefix),
+        old_end_position: point_at(old, old_end),
+        new_end_position: point_at(new, new_end),
+    })
+}
+
+/// Row and byte column of a byte offset.
+fn point_at(text: &str, offset: usize) -> Point {
+    let before = &text.as_bytes()[..offset];
+    let row = before.iter().filter(|&&byte| byte == b'\n').count();
+    let line_start =
+        before.iter().rposition(|&byte| byte == b'\n').map_or(0, |newline| newline + 1);
+    Point::new(row, offset - line_start)
+}
+
+/// Widen a byte range to whole lines.
+fn line_range(text: &str, range: Range<usize>) -> Range<usize> {
+    let start = range.start.min(text.len());
+    let end = range.end.clamp(start, text.len());
+    let bytes = text.as_bytes();
+    let line_start = bytes[..start].iter().rposition(|&byte| byte == b'\n').map_or(0, |i| i + 1);
+    let line_end =
+        bytes[end..].iter().position(|&byte| byte == b'\n').map_or(text.len(), |i| end + i);
+    line_start..line_end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token_text<'a>(
+        content: &'a str,
+        tokens: &[HighlightToken],
+        token_type: TokenType,
+    ) -> Vec<&'a str> {
+        tokens
+            .iter()
+            .filter(|token| token.token_type == token_type)
+            .map(|token| &content[token.start..token.end])
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_captures_map_to_token_types() {
+        let highlighter = RustSyntaxHighlighter::new().unwrap();
+        let content = "/// Entry point.\n#[inline]\nfn first<'a>(x: &'a str) -> Option<u32> {\n    println!(\"{}\", x);\n    None\n}\n";
+        let tokens = highlighter.highlight(content).await.unwrap();
+
+        assert_eq!(token_text(content, &tokens, TokenType::DocComment), vec!["/// Entry point.\n"]);
+        assert_eq!(token_text(content, &tokens, TokenType::Attribute), vec!["#[inline]"]);
+        assert_eq!(token_text(content, &tokens, TokenType::Function), vec!["first"]);
+        assert_eq!(token_text(content, &tokens, TokenType::Lifetime), vec!["'a", "'a"]);
+        assert_eq!(token_text(content, &tokens, TokenType::Macro), vec!["println!"]);
+        assert!(token_text(content, &tokens, TokenType::Type).contains(&"Option"));
+        assert!(token_text(content, &tokens, TokenType::Keyword).contains(&"fn"));
+        assert_eq!(token_text(content, &tokens, TokenType::String), vec!["\"{}\""]);
+    }
+
+    #[tokio::test]
+    async fn test_incremental_highlighting_matches_full_parse() {
+        let highlighter = RustSyntaxHighlighter::new().unwrap();
+        let path = PathBuf::from("/project/src/lib.rs");
+        let versions = [
+            "fn main() {\n    let x = 1;\n}\n",
+            "fn main() {\n    let x = 1;\n    run(x);\n}\n",
+            "/* fn main() {\n    let x = 1;\n    run(x);\n}\n",
+            "/* fn main() { */\nstruct Point { x: f32 }\n    run(x);\n}\n",
+            "#[derive(Debug)]\nstruct Point { x: f32 }\n    run(x);\n}\n",
+            "",
+            "// é\nfn ü() {}\n",
+        ];
+
+        // Typing a line character by character inside an attributed item.
+        let typed = "let y = \"s\"; // note";
+        let typing = (0..=typed.len())
+            .map(|end| format!("#[test]\nfn check() {{\n    {}\n}}\n", &typed[..end]));
+
+        for content in versions.iter().map(|v| v.to_string()).chain(typing) {
+            let content = content.as_str();
+            let incremental = highlighter.highlight_file(&path, content).await.unwrap();
+            let full = highlighter.highlight(content).await.unwrap();
+            let spans = |tokens: &[HighlightToken]| {
+                tokens.iter().map(|t| (t.start, t.end, t.token_type.clone())).collect::<Vec<_>>()
+            };
+            assert_eq!(spans(&incremental), spans(&full), "mismatch for {:?}", content);
+        }
+    }
+
+    #[test]
+    fn test_input_edit() {
+        let edit = input_edit("fn main() {}", "fn main() { run(); }").unwrap();
+        assert_eq!((edit.start_byte, edit.old_end_byte, edit.new_end_byte), (11, 11, 19));
+        assert_eq!(edit.start_position, Point::new(0, 11));
+
+        let edit = input_edit("a\nbcd\n", "a\nd\n").unwrap();
+        assert_eq!((edit.start_byte, edit.old_end_byte, edit.new_end_byte), (2, 4, 2));
+        assert_eq!(edit.old_end_position, Point::new(1, 2));
+
+        assert!(input_edit("same", "same").is_none());
+    }
+}
@@ -0,0 +1,238 @@
+//! # Shader Validation
+//!
+//! Offline WGSL validation with naga. When `xylux.shaders.validate_on_save` is
+//! set, saved `.wgsl` files are parsed and validated, the errors are stored as
+//! diagnostics for the file and the outcome is announced as
+//! [`XyluxEvent::ShaderCompiled`].
+
+use std::collections::HashMap;
+use std::error::Error as _;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use naga::valid::{Capabilities, ValidationFlags, Validator};
+use tokio::sync::RwLock;
+use tracing::{debug, error, warn};
+
+use crate::core::{
+    Config, EditorEvent, Event, EventBus, EventHandler, EventMessage, EventPriority, Result,
+    XyluxEvent,
+};
+use crate::syntax::{Diagnostic, DiagnosticSeverity, SyntaxManager};
+
+/// Source name reported on shader diagnostics.
+const DIAGNOSTIC_SOURCE: &str = "naga";
+
+/// Validate WGSL source, returning one diagnostic per error.
+pub fn validate_wgsl(source: &str) -> Vec<Diagnostic> {
+    let module = match naga::front::wgsl::parse_str(source) {
+        Ok(module) => module,
+        Err(e) => {
+            let location = e.location(source);
+            return vec![diagnostic(source, location, e.message().to_owned())];
+        }
+    };
+
+    let mut validator = Validator::new(ValidationFlags::all(), Capabilities::all());
+    match validator.validate(&module) {
+        Ok(_) => Vec::new(),
+        Err(e) => {
+            // The outer error only names the item; the causes say what is wrong.
+            let mut message = e.as_inner().to_string();
+            let mut cause = e.as_inner().source();
+            while let Some(inner) = cause {
+                message.push_str(": ");
+                message.push_str(&inner.to_string());
+                cause = inner.source();
+            }
+            vec![diagnostic(source, e.location(source), message)]
+        }
+    }
+}
+
+/// Build an error diagnostic at a naga source location, or at the start of
+/// the file when the error has none.
+///
+/// naga counts columns in characters; the location's byte span is used
+/// instead so columns are bytes, like those of language server diagnostics.
+fn diagnostic(source: &str, location: Option<naga::SourceLocation>, message: String) -> Diagnostic {
+    let (start, end) = location.map_or((0, 0), |location| {
+        let start = (location.offset as usize).min(source.len());
+        (start, (start + location.length as usize).min(source.len()))
+    });
+    let (line, column) = byte_position(source, start);
+    let (end_line, end_column) = byte_position(source, end);
+
+    Diagnostic {
+        line,
+        column,
+        end_line,
+        end_column,
+        severity: DiagnosticSeverity::Error,
+        message,
+        source: Some(DIAGNOSTIC_SOURCE.to_owned()),
+    }
+}
+
+/// Convert a byte offset into a zero-based line and byte column.
+fn byte_position(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source.as_bytes()[..offset];
+    let line_start = before.iter().rposition(|&b| b == b'\n').map_or(0, |newline| newline + 1);
+    (before.iter().filter(|&&b| b == b'\n').count(), offset - line_start)
+}
+
+/// Validates WGSL shaders when they are saved.
+pub struct ShaderValidator {
+    /// IDE configuration, for `validate_on_save`.
+    config: Arc<RwLock<Config>>,
+    /// Diagnostics store shared with the syntax manager.
+    diagnostics: Arc<RwLock<HashMap<PathBuf, Vec<Diagnostic>>>>,
+    /// Event bus for diagnostics and compilation events.
+    event_bus: Arc<EventBus>,
+}
+
+impl ShaderValidator {
+    /// Create a validator writing into the given diagnostics store.
+    pub fn new(
+        config: Arc<RwLock<Config>>,
+        diagnostics: Arc<RwLock<HashMap<PathBuf, Vec<Diagnostic>>>>,
+        event_bus: Arc<EventBus>,
+    ) -> Self {
+        Self { config, diagnostics, event_bus }
+    }
+
+    /// Validate a shader file and publish its diagnostics.
+    pub async fn validate_file(&self, path: &Path) -> Result<()> {
+        let source = tokio::fs::read_to_string(path).await?;
+        let diagnostics =
+            tokio::task::spawn_blocking(move || validate_wgsl(&source)).await.unwrap_or_else(|e| {
+                error!("Shader validation panicked: {}", e);
+                Vec::new()
+            });
+        let success = diagnostics.is_empty();
+        debug!("Validated {}: {} errors", path.display(), diagnostics.len());
+
+        SyntaxManager::update_diagnostics(
+            &self.diagnostics,
+            &self.event_bus,
+            path.to_path_buf(),
+            diagnostics,
+        )
+        .await?;
+
+        let event = EventMessage::from_event(Event::Xylux(XyluxEvent::ShaderCompiled {
+            path: path.to_path_buf(),
+            success,
+        }))
+        .with_priority(EventPriority::Normal)
+        .with_source("shader_validator");
+        self.event_bus.publish(event).await
+    }
+}
+
+#[async_trait::async_trait]
+impl EventHandler for ShaderValidator {
+    async fn handle(&self, event: &EventMessage) -> Result<()> {
+        let Ok(Event::Editor(EditorEvent::FileSaved { path })) =
+            serde_json::from_value::<Event>(event.data.clone())
+        else {
+            return Ok(());
+        };
+        if path.extension().and_then(|ext| ext.to_str()) != Some("wgsl") {
+            return Ok(());
+        }
+        if !self.config.read().await.xylux.shaders.validate_on_save {
+            return Ok(());
+        }
+
+        if let Err(e) = self.validate_file(&path).await {
+            warn!("Failed to validate shader {}: {}", path.display(), e);
+        }
+        Ok(())
+    }
+
+    fn can_handle(&self, event_type: &str) -> bool {
+        event_type == "editor"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_shader_has_no_diagnostics() {
+        let source = r#"
+@fragment
+fn main(@location(0) uv: vec2<f32>) -> @location(0) vec4<f32> {
+    return vec4<f32>(uv, 0.0, 1.0);
+}
+"#;
+        assert!(validate_wgsl(source).is_empty());
+    }
+
+    #[test]
+    fn test_parse_error_is_located() {
+        let source = "fn main() {\n    let x = 1\n}\n";
+        let diagnostics = validate_wgsl(source);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, 2);
+        assert_eq!(diagnostics[0].severity, DiagnosticSeverity::Error);
+        assert_eq!(diagnostics[0].source.as_deref(), Some("naga"));
+    }
+
+    #[test]
+    fn test_location_uses_byte_columns() {
+        let source = "fn main() {\n    let é = x;\n}\n";
+        let offset = source.find('x').unwrap();
+        // naga's one-based character column, which is not used.
+        let location = naga::SourceLocation {
+            line_number: 2,
+            line_position: 13,
+            offset: u32::try_from(offset).unwrap(),
+            length: 1,
+        };
+
+        let found = diagnostic(source, Some(location), "unknown x".to_string());
+        assert_eq!((found.line, found.column), (1, 13));
+        assert_eq!((found.end_line, found.end_column), (1, 14));
+
+        let location =
+            naga::SourceLocation { line_number: 1, line_position: 11, offset: 10, length: 6 };
+        let found = diagnostic(source, Some(location), "block".to_string());
+        assert_eq!((found.line, found.column), (0, 10));
+        assert_eq!((found.end_line, found.end_column), (1, 4));
+    }
+
+    #[test]
+    fn test_validation_error_is_reported() {
+        let source = "fn main() -> f32 {\n    return 1u;\n}\n";
+        let diagnostics = validate_wgsl(source);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].message.contains("main"), "{}", diagnostics[0].message);
+    }
+
+    #[tokio::test]
+    async fn test_saved_shader_is_validated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("broken.wgsl");
+        std::fs::write(&path, "fn main( {}").unwrap();
+
+        let diagnostics = Arc::new(RwLock::new(HashMap::new()));
+        let validator = ShaderValidator::new(
+            Arc::new(RwLock::new(Config::default())),
+            Arc::clone(&diagnostics),
+            Arc::new(EventBus::new()),
+        );
+        let saved =
+            EventMessage::from_event(Event::Editor(EditorEvent::FileSaved { path: path.clone() }));
+        validator.handle(&saved).await.unwrap();
+        assert_eq!(diagnostics.read().await.get(&path).map(Vec::len), Some(1));
+
+        std::fs::write(&path, "fn main() {}").unwrap();
+        validator.handle(&saved).await.unwrap();
+        assert!(!diagnostics.read().await.contains_key(&path));
+    }
+}
@@ -0,0 +1,773 @@
+//! # Stdio LSP Client
+//!
+//! Generic language server client speaking JSON-RPC over a child process's stdio.
+
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::{broadcast, oneshot, watch};
+use tracing::{debug, error, info, warn};
+
+use crate::core::{Result, XyluxError};
+use crate::syntax::lsp_client::{
+    self, Location, LspClientConfig, ServerCapabilities, TextDocumentContentChange, TextEdit,
+    WorkspaceEdit, methods, path_to_uri,
+};
+use crate::syntax::transport::{LspNotification, LspTransport};
+use crate::syntax::{CompletionItem, CompletionItemKind, Diagnostic, LspClient};
+
+/// How long a server gets to exit on its own after `exit` before it is killed.
+const EXIT_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// Language server client for any server launched as a child process.
+///
+/// The server binary, its arguments, the language id and the
+/// `initializationOptions` all come from [`LspClientConfig`].
+pub struct StdioLspClient {
+    /// Stops the server process when sent to or dropped.
+    kill: Option<oneshot::Sender<()>>,
+    /// Exit status of the server process, once it has exited.
+    exit: Option<watch::Receiver<Option<String>>>,
+    /// Whether `shutdown` has been sent to the server.
+    shutdown_sent: AtomicBool,
+    /// Configuration.
+    config: LspClientConfig,
+    /// JSON-RPC transport to the process.
+    transport: Option<LspTransport>,
+    /// Capabilities reported by the server.
+    capabilities: ServerCapabilities,
+    /// Current workspace root URI.
+    root_uri: Mutex<Option<String>>,
+    /// Notifications buffered since startup, handed to the first subscriber.
+    early_notifications: Mutex<Option<broadcast::Receiver<LspNotification>>>,
+    /// Whether the LSP is initialized.
+    initialized: bool,
+}
+
+impl StdioLspClient {
+    /// Launch and initialize a language server.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the server cannot be spawned or does not initialize.
+    pub async fn start(config: LspClientConfig) -> Result<Self> {
+        let mut client = Self::with_config(config);
+        client.spawn().await?;
+        Ok(client)
+    }
+
+    /// Create a client that has not been started yet.
+    pub(crate) fn with_config(config: LspClientConfig) -> Self {
+        Self {
+            kill: None,
+            exit: None,
+            shutdown_sent: AtomicBool::new(false),
+            config,
+            transport: None,
+            capabilities: ServerCapabilities::default(),
+            root_uri: Mutex::new(None),
+            early_notifications: Mutex::new(None),
+            initialized: false,
+        }
+    }
+
+    /// The configuration the client was created with.
+    pub const fn config(&self) -> &LspClientConfig {
+        &self.config
+    }
+
+    /// Spawn the server process and initialize it.
+    async fn spawn(&mut self) -> Result<()> {
+        let name = self.config.language_id.clone();
+        debug!("Starting {} language server: {}", name, self.config.server_path);
+
+        let mut command = Command::new(&self.config.server_path);
+        command.args(&self.config.server_args);
+        command.envs(&self.config.env_vars);
+        if let Some(directory) = &self.config.working_directory {
+            command.current_dir(directory);
+        }
+        command.stdin(Stdio::piped());
+        command.stdout(Stdio::piped());
+        command.stderr(Stdio::piped());
+        // Backstop for a runtime that shuts down before the process watcher runs.
+        command.kill_on_drop(true);
+
+        let mut child = command.spawn().map_err(|e| {
+            XyluxError::lsp_error(format!(
+                "Failed to start {} language server '{}': {}",
+                name, self.config.server_path, e
+            ))
+        })?;
+
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(XyluxError::lsp_error(format!(
+                "{name} language server stdio is not available"
+            )));
+        };
+
+        // Drain stderr so the server never blocks on a full pipe.
+        if let Some(stderr) = child.stderr.take() {
+            let name = name.clone();
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    debug!("{} language server: {}", name, line);
+                }
+            });
+        }
+
+        let transport =
+            LspTransport::new(stdout, stdin, Duration::from_millis(self.config.request_timeout_ms));
+        // Subscribe before initializing so diagnostics published during startup are kept.
+        self.early_notifications = Mutex::new(Some(transport.subscribe()));
+        self.transport = Some(transport);
+
+        // Own the process in a watcher task so its exit is noticed.
+        let (kill_tx, kill_rx) = oneshot::channel::<()>();
+        let (exit_tx, exit_rx) = watch::channel(None);
+        let watcher_name = name.clone();
+        tokio::spawn(async move {
+            let status = tokio::select! {
+                status = child.wait() => status,
+                _ = kill_rx => {
+                    if let Err(e) = child.start_kill() {
+                        error!("Failed to kill {} language server: {}", watcher_name, e);
+                    }
+                    child.wait().await
+                }
+            };
+            let status = match status {
+                Ok(status) => status.to_string(),
+                Err(e) => e.to_string(),
+            };
+            debug!("{} language server exited: {}", watcher_name, status);
+            exit_tx.send_replace(Some(status));
+        });
+        self.kill = Some(kill_tx);
+        self.exit = Some(exit_rx);
+        info!("{} language server process started", name);
+
+        self.initialize().await
+    }
+
+    /// Initialize the LSP connection.
+    async fn initialize(&mut self) -> Result<()> {
+        debug!("Initializing {} language server", self.config.language_id);
+
+        let mut params = json!({
+            "processId": std::process::id(),
+            "rootUri": null,
+            "capabilities": {
+                "textDocument": {
+                    "synchronization": {
+                        "didSave": true
+                    },
+                    "completion": {
+                        "completionItem": {
+                            "snippetSupport": true,
+                            "resolveSupport": {
+                                "properties": ["documentation", "detail"]
+                            }
+                        }
+                    },
+                    "hover": {
+                        "contentFormat": ["markdown", "plaintext"]
+                    },
+                    "definition": {
+                        "linkSupport": true
+                    },
+                    "references": {},
+                    "rename": {
+                        "prepareSupport": false
+                    },
+                    "formatting": {},
+                    "publishDiagnostics": {
+                        "relatedInformation": true,
+                        "tagSupport": {
+                            "valueSet": [1, 2]
+                        }
+                    }
+                },
+                "workspace": {
+                    "workspaceEdit": {
+                        "documentChanges": true
+                    },
+                    "workspaceFolders": true,
+                    "configuration": true
+                }
+            }
+        });
+        if let Some(options) = &self.config.initialization_options {
+            params["initializationOptions"] = options.clone();
+        }
+
+        let response = self.send_request(methods::INITIALIZE, params).await?;
+        self.capabilities = response
+            .pointer("/result/capabilities")
+            .map(ServerCapabilities::from_json)
+            .unwrap_or_default();
+
+        self.send_notification(methods::INITIALIZED, json!({})).await?;
+        self.initialized = true;
+        info!("{} language server initialized", self.config.language_id);
+
+        Ok(())
+    }
+
+    /// Get the transport, failing if the process was never started.
+    fn transport(&self) -> Result<&LspTransport> {
+        self.transport.as_ref().ok_or_else(|| {
+            XyluxError::lsp_error(format!(
+                "{} language server is not running",
+                self.config.language_id
+            ))
+        })
+    }
+
+    /// Send a request to the server and wait for its response.
+    async fn send_request(&self, method: &str, params: Value) -> Result<Value> {
+        self.transport()?.request(method, params).await
+    }
+
+    /// Send a notification to the server.
+    async fn send_notification(&self, method: &str, params: Value) -> Result<()> {
+        self.transport()?.notify(method, params).await
+    }
+
+    /// Build `TextDocumentPositionParams` for a file position.
+    fn text_document_position(file_path: &str, line: usize, column: usize) -> Value {
+        json!({
+            "textDocument": { "uri": path_to_uri(file_path) },
+            "position": { "line": line, "character": column }
+        })
+    }
+
+    /// Convert LSP completion item kind to our enum.
+    const fn convert_completion_kind(lsp_kind: Option<i32>) -> CompletionItemKind {
+        match lsp_kind {
+            Some(2) => CompletionItemKind::Method,
+            Some(3) => CompletionItemKind::Function,
+            Some(4) => CompletionItemKind::Constructor,
+            Some(5) => CompletionItemKind::Field,
+            Some(6) => CompletionItemKind::Variable,
+            Some(7) => CompletionItemKind::Class,
+            Some(8) => CompletionItemKind::Interface,
+            Some(9) => CompletionItemKind::Module,
+            Some(10) => CompletionItemKind::Property,
+            Some(11) => CompletionItemKind::Unit,
+            Some(12) => CompletionItemKind::Value,
+            Some(13) => CompletionItemKind::Enum,
+            Some(14) => CompletionItemKind::Keyword,
+            Some(15) => CompletionItemKind::Snippet,
+            Some(16) => CompletionItemKind::Color,
+            Some(17) => CompletionItemKind::File,
+            Some(18) => CompletionItemKind::Reference,
+            _ => CompletionItemKind::Text,
+        }
+    }
+
+    /// Parse completion items from LSP response.
+    fn parse_completion_items(response: &Value) -> Vec<CompletionItem> {
+        let mut items = Vec::new();
+
+        if let Some(result) = response.get("result") {
+            let empty_vec = Vec::new();
+            let completion_items = if let Some(list) = result.get("items") {
+                list.as_array().unwrap_or(&empty_vec)
+            } else if let Some(array) = result.as_array() {
+                array
+            } else {
+                return items;
+            };
+
+            for item in completion_items {
+                if let Some(label) = item.get("label").and_then(|l| l.as_str()) {
+                    let kind = item
+                        .get("kind")
+                        .and_then(Value::as_i64)
+                        .and_then(|k| i32::try_from(k).ok());
+
+                    let detail = item.get("detail").and_then(|d| d.as_str()).map(String::from);
+
+                    // Either a plain string or a MarkupContent object
+                    let documentation = item
+                        .get("documentation")
+                        .and_then(|d| d.as_str().or_else(|| d.get("value")?.as_str()))
+                        .map(String::from);
+
+                    let insert_text = item
+                        .get("insertText")
+                        .and_then(|t| t.as_str())
+                        .map(String::from)
+                        .or_else(|| Some(label.to_owned()));
+
+                    items.push(CompletionItem {
+                        label: label.to_owned(),
+                        kind: Self::convert_completion_kind(kind),
+                        detail,
+                        documentation,
+                        insert_text,
+                    });
+                }
+            }
+        }
+
+        items
+    }
+
+    /// Parse diagnostics from a `publishDiagnostics` notification or a pull
+    /// diagnostics response, converting columns against `text`.
+    fn parse_diagnostics(response: &Value, text: Option<&str>) -> Vec<Diagnostic> {
+        response
+            .pointer("/params/diagnostics")
+            .or_else(|| response.pointer("/result/items"))
+            .map(|diagnostics| lsp_client::parse_diagnostics(diagnostics, text))
+            .unwrap_or_default()
+    }
+
+    /// Parse hover contents from LSP response.
+    fn parse_hover(response: &Value) -> Option<String> {
+        fn contents_text(contents: &Value) -> String {
+            match contents {
+                Value::String(text) => text.clone(),
+                Value::Array(parts) => parts
+                    .iter()
+                    .map(contents_text)
+                    .filter(|part| !part.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("\n\n"),
+                // MarkupContent and MarkedString objects both carry `value`.
+                Value::Object(object) => {
+                    object.get("value").and_then(Value::as_str).unwrap_or_default().to_owned()
+                }
+                Value::Null | Value::Bool(_) | Value::Number(_) => String::new(),
+            }
+        }
+
+        let text = contents_text(response.pointer("/result/contents")?);
+        if text.is_empty() { None } else { Some(text) }
+    }
+}
+
+#[async_trait]
+impl LspClient for StdioLspClient {
+    async fn set_root_uri(&self, uri: &str) -> Result<()> {
+        debug!("Setting root URI for {}: {}", self.config.language_id, uri);
+
+        let uri = if uri.starts_with("file://") { uri.to_owned() } else { path_to_uri(uri) };
+        let previous = self
+            .root_uri
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .replace(uri.clone());
+
+        let folder = |uri: &str| {
+            let name = uri.rsplit('/').find(|segment| !segment.is_empty()).unwrap_or(uri);
+            json!({ "uri": uri, "name": name })
+        };
+        let removed: Vec<Value> = previous.as_deref().map(folder).into_iter().collect();
+
+        self.send_notification(
+            methods::WORKSPACE_DID_CHANGE_WORKSPACE_FOLDERS,
+            json!({ "event": { "added": [folder(&uri)], "removed": removed } }),
+        )
+        .await
+    }
+
+    async fn get_diagnostics(&self, file_path: &str) -> Result<Vec<Diagnostic>> {
+        debug!("Getting diagnostics for: {}", file_path);
+
+        if !self.initialized || !self.capabilities.diagnostic_provider {
+            return Ok(Vec::new());
+        }
+
+        let response = self
+            .send_request(
+                methods::TEXT_DOCUMENT_DIAGNOSTIC,
+                json!({ "textDocument": { "uri": path_to_uri(file_path) } }),
+            )
+            .await?;
+
+        let text = tokio::fs::read_to_string(file_path).await.ok();
+        Ok(Self::parse_diagnostics(&response, text.as_deref()))
+    }
+
+    async fn get_completion(
+        &self,
+        file_path: &str,
+        line: usize,
+        column: usize,
+    ) -> Result<Vec<CompletionItem>> {
+        debug!("Getting completion for {}:{}:{}", file_path, line, column);
+
+        if !self.initialized {
+            return Ok(Vec::new());
+        }
+
+        let response = self
+            .send_request(
+                methods::TEXT_DOCUMENT_COMPLETION,
+                Self::text_document_position(file_path, line, column),
+            )
+            .await?;
+
+        Ok(Self::parse_completion_items(&response))
+    }
+
+    async fn get_hover(
+        &self,
+        file_path: &str,
+        line: usize,
+        column: usize,
+    ) -> Result<Option<String>> {
+        debug!("Getting hover for {}:{}:{}", file_path, line, column);
+
+        if !self.initialized {
+            return Ok(None);
+        }
+
+        let response = self
+            .send_request(
+                methods::TEXT_DOCUMENT_HOVER,
+                Self::text_document_position(file_path, line, column),
+            )
+            .await?;
+
+        Ok(Self::parse_hover(&response))
+    }
+
+    async fn goto_definition(
+        &self,
+        file_path: &str,
+        line: usize,
+        column: usize,
+    ) -> Result<Option<Location>> {
+        debug!("Going to definition from {}:{}:{}", file_path, line, column);
+
+        if !self.initialized || !self.capabilities.definition_provider {
+            return Ok(None);
+        }
+
+        let response = self
+            .send_request(
+                methods::TEXT_DOCUMENT_DEFINITION,
+                Self::text_document_position(file_path, line, column),
+            )
+            .await?;
+
+        Ok(response
+            .get("result")
+            .map(lsp_client::parse_locations)
+            .and_then(|locations| locations.into_iter().next()))
+    }
+
+    async fn find_references(
+        &self,
+        file_path: &str,
+        line: usize,
+        column: usize,
+    ) -> Result<Vec<Location>> {
+        debug!("Finding references for {}:{}:{}", file_path, line, column);
+
+        if !self.initialized || !self.capabilities.references_provider {
+            return Ok(Vec::new());
+        }
+
+        let mut params = Self::text_document_position(file_path, line, column);
+        params["context"] = json!({ "includeDeclaration": true });
+
+        let response = self.send_request(methods::TEXT_DOCUMENT_REFERENCES, params).await?;
+        Ok(response.get("result").map(lsp_client::parse_locations).unwrap_or_default())
+    }
+
+    async fn rename_symbol(
+        &self,
+        file_path: &str,
+        line: usize,
+        column: usize,
+        new_name: &str,
+    ) -> Result<Option<WorkspaceEdit>> {
+        debug!("Renaming symbol at {}:{}:{} to {}", file_path, line, column, new_name);
+
+        if !self.initialized || !self.capabilities.rename_provider {
+            return Ok(None);
+        }
+
+        let mut params = Self::text_document_position(file_path, line, column);
+        params["newName"] = json!(new_name);
+
+        let response = self.send_request(methods::TEXT_DOCUMENT_RENAME, params).await?;
+        Ok(response.get("result").filter(|result| !result.is_null()).map(WorkspaceEdit::from_json))
+    }
+
+    async fn format_document(&self, file_path: &str) -> Result<Option<Vec<TextEdit>>> {
+        debug!("Formatting document: {}", file_path);
+
+        if !self.initialized || !self.capabilities.document_formatting_provider {
+            return Ok(None);
+        }
+
+        let response = self
+            .send_request(
+                methods::TEXT_DOCUMENT_FORMATTING,
+                json!({
+                    "textDocument": { "uri": path_to_uri(file_path) },
+                    "options": { "tabSize": 4, "insertSpaces": true }
+                }),
+            )
+            .await?;
+
+        Ok(response
+            .get("result")
+            .filter(|result| !result.is_null())
+            .map(lsp_client::parse_text_edits))
+    }
+
+    async fn did_open(
+        &self,
+        file_path: &str,
+        language_id: &str,
+        version: i32,
+        text: &str,
+    ) -> Result<()> {
+        debug!("Opening document in {}: {}", self.config.language_id, file_path);
+
+        self.send_notification(
+            methods::TEXT_DOCUMENT_DID_OPEN,
+            json!({
+                "textDocument": {
+                    "uri": path_to_uri(file_path),
+                    "languageId": language_id,
+                    "version": version,
+                    "text": text
+                }
+            }),
+        )
+        .await
+    }
+
+    async fn did_change(
+        &self,
+        file_path: &str,
+        version: i32,
+        changes: Vec<TextDocumentContentChange>,
+    ) -> Result<()> {
+        let changes: Vec<Value> = changes.iter().map(TextDocumentContentChange::to_json).collect();
+
+        self.send_notification(
+            methods::TEXT_DOCUMENT_DID_CHANGE,
+            json!({
+                "textDocument": { "uri": path_to_uri(file_path), "version": version },
+                "contentChanges": changes
+            }),
+        )
+        .await
+    }
+
+    async fn did_save(&self, file_path: &str) -> Result<()> {
+        self.send_notification(
+            methods::TEXT_DOCUMENT_DID_SAVE,
+            json!({ "textDocument": { "uri": path_to_uri(file_path) } }),
+        )
+        .await
+    }
+
+    async fn did_close(&self, file_path: &str) -> Result<()> {
+        debug!("Closing document in {}: {}", self.config.language_id, file_path);
+
+        self.send_notification(
+            methods::TEXT_DOCUMENT_DID_CLOSE,
+            json!({ "textDocument": { "uri": path_to_uri(file_path) } }),
+        )
+        .await
+    }
+
+    async fn shutdown(&self) -> Result<()> {
+        debug!("Shutting down {} language server", self.config.language_id);
+
+        let Some(transport) = &self.transport else {
+            return Ok(());
+        };
+        if !transport.is_connected() {
+            return Ok(());
+        }
+
+        self.shutdown_sent.store(true, Ordering::SeqCst);
+        shutdown_gracefully(transport).await
+    }
+
+    fn language(&self) -> &str {
+        &self.config.language_id
+    }
+
+    fn is_running(&self) -> bool {
+        self.initialized
+            && self.transport.as_ref().is_some_and(LspTransport::is_connected)
+            && self.exit.as_ref().is_some_and(|exit| exit.borrow().is_none())
+    }
+
+    fn capabilities(&self) -> ServerCapabilities {
+        self.capabilities.clone()
+    }
+
+    fn subscribe_notifications(&self) -> Option<broadcast::Receiver<LspNotification>> {
+        let early = self
+            .early_notifications
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .take();
+        early.or_else(|| self.transport.as_ref().map(LspTransport::subscribe))
+    }
+
+    fn exit_watch(&self) -> Option<watch::Receiver<Option<String>>> {
+        self.exit.clone()
+    }
+}
+
+/// Send `shutdown` and, once acknowledged, `exit`.
+async fn shutdown_gracefully(transport: &LspTransport) -> Result<()> {
+    transport.request(methods::SHUTDOWN, Value::Null).await?;
+    transport.notify(methods::EXIT, Value::Null).await
+}
+
+impl Drop for StdioLspClient {
+    fn drop(&mut self) {
+        let Some(kill) = self.kill.take() else {
+            return;
+        };
+        // Without a runtime nothing can be awaited; dropping `kill` stops the process.
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+
+        let transport = self.transport.take().filter(|_| !*self.shutdown_sent.get_mut());
+        let exit = self.exit.take();
+        let name = self.config.language_id.clone();
+        runtime.spawn(async move {
+            if let Some(transport) = transport.filter(LspTransport::is_connected) {
+                if let Err(e) = shutdown_gracefully(&transport).await {
+                    debug!("{} language server did not shut down cleanly: {}", name, e);
+                }
+            }
+            if let Some(mut exit) = exit {
+                let exited =
+                    tokio::time::timeout(EXIT_GRACE_PERIOD, exit.wait_for(Option::is_some))
+                        .await
+                        .is_ok();
+                if !exited {
+                    warn!("{} language server did not exit; killing it", name);
+                }
+            }
+            drop(kill);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::DiagnosticSeverity;
+
+    #[tokio::test]
+    async fn test_missing_binary_fails_to_start() {
+        let config = LspClientConfig {
+            server_path: "xylux-missing-language-server".to_string(),
+            language_id: "wgsl".to_string(),
+            ..LspClientConfig::default()
+        };
+        let error = StdioLspClient::start(config).await.err().expect("start should fail");
+        assert!(error.to_string().contains("wgsl"));
+    }
+
+    #[test]
+    fn test_completion_kind_conversion() {
+        assert_eq!(StdioLspClient::convert_completion_kind(Some(3)), CompletionItemKind::Function);
+        assert_eq!(StdioLspClient::convert_completion_kind(Some(7)), CompletionItemKind::Class);
+        assert_eq!(StdioLspClient::convert_completion_kind(None), CompletionItemKind::Text);
+    }
+
+    #[test]
+    fn test_parse_completion_items() {
+        let response = json!({
+            "result": {
+                "items": [
+                    {
+                        "label": "test_function",
+                        "kind": 3,
+                        "detail": "fn test_function()",
+                        "documentation": "A test function"
+                    }
+                ]
+            }
+        });
+
+        let items = StdioLspClient::parse_completion_items(&response);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].label, "test_function");
+        assert_eq!(items[0].kind, CompletionItemKind::Function);
+    }
+
+    #[test]
+    fn test_parse_diagnostics() {
+        let response = json!({
+            "params": {
+                "diagnostics": [
+                    {
+                        "range": {
+                            "start": { "line": 0, "character": 0 },
+                            "end": { "line": 0, "character": 10 }
+                        },
+                        "severity": 1,
+                        "message": "Test error",
+                        "source": "rust-analyzer"
+                    }
+                ]
+            }
+        });
+
+        let diagnostics = StdioLspClient::parse_diagnostics(&response, None);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, 0);
+        assert_eq!(diagnostics[0].severity, DiagnosticSeverity::Error);
+        assert_eq!(diagnostics[0].message, "Test error");
+    }
+
+    #[test]
+    fn test_parse_pull_diagnostics_and_hover() {
+        let response = json!({
+            "result": {
+                "kind": "full",
+                "items": [
+                    {
+                        "range": {
+                            "start": { "line": 3, "character": 4 },
+                            "end": { "line": 4, "character": 1 }
+                        },
+                        "severity": 2,
+                        "message": "unused variable"
+                    }
+                ]
+            }
+        });
+        let diagnostics = StdioLspClient::parse_diagnostics(&response, Some("\n\n\n    x\n}"));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, 3);
+        assert_eq!((diagnostics[0].end_line, diagnostics[0].end_column), (4, 1));
+        assert_eq!(diagnostics[0].severity, DiagnosticSeverity::Warning);
+
+        let hover =
+            json!({ "result": { "contents": { "kind": "markdown", "value": "fn main()" } } });
+        assert_eq!(StdioLspClient::parse_hover(&hover).as_deref(), Some("fn main()"));
+        let hover =
+            json!({ "result": { "contents": ["a", { "language": "rust", "value": "b" }] } });
+        assert_eq!(StdioLspClient::parse_hover(&hover).as_deref(), Some("a\n\nb"));
+        assert_eq!(StdioLspClient::parse_hover(&json!({ "result": null })), None);
+    }
+}
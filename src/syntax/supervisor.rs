@@ -0,0 +1,279 @@
+//! # LSP Supervisor
+//!
+//! Keeps language servers running: notices when a server process exits,
+//! restarts it with exponential backoff and reports its status on the event bus.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use futures::future::BoxFuture;
+use tokio::sync::{RwLock, watch};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+
+use crate::core::config::LspRestartConfig;
+use crate::core::{Event, EventBus, EventMessage, EventPriority, LspEvent, Result};
+use crate::syntax::LspClient;
+
+/// A started language server client.
+pub type BoxedLspClient = Box<dyn LspClient + Send + Sync>;
+
+/// Language server clients by language.
+pub type LspClients = Arc<RwLock<HashMap<String, BoxedLspClient>>>;
+
+/// Starts a fresh client for one language server.
+pub type ClientFactory = Arc<dyn Fn() -> BoxFuture<'static, Result<BoxedLspClient>> + Send + Sync>;
+
+/// Called with the language id each time a server has been (re)started and
+/// registered, e.g. to reopen documents.
+pub type StartedHook = Arc<dyn Fn(String) -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// Wrap a start function returning a concrete client into a [`ClientFactory`].
+pub fn client_factory<C, F>(start: impl Fn() -> F + Send + Sync + 'static) -> ClientFactory
+where
+    C: LspClient + Send + Sync + 'static,
+    F: Future<Output = Result<C>> + Send + 'static,
+{
+    Arc::new(move || {
+        let started = start();
+        Box::pin(async move {
+            let client: BoxedLspClient = Box::new(started.await?);
+            Ok(client)
+        })
+    })
+}
+
+/// Restarts language servers that exit without being asked to.
+pub struct LspSupervisor {
+    /// State shared with the watcher tasks.
+    shared: Arc<Shared>,
+    /// Exit watcher task per supervised language.
+    watchers: Mutex<HashMap<String, JoinHandle<()>>>,
+}
+
+/// State shared between the supervisor and its watcher tasks.
+struct Shared {
+    /// Clients the supervisor registers started servers in.
+    lsp_clients: LspClients,
+    /// Event bus for server status events.
+    event_bus: Arc<EventBus>,
+    /// Restart policy, read again before every restart.
+    policy: Mutex<LspRestartConfig>,
+    /// Run after every successful start.
+    on_started: StartedHook,
+}
+
+impl LspSupervisor {
+    /// Create a supervisor registering clients in `lsp_clients`.
+    pub fn new(
+        lsp_clients: LspClients,
+        event_bus: Arc<EventBus>,
+        policy: LspRestartConfig,
+        on_started: StartedHook,
+    ) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                lsp_clients,
+                event_bus,
+                policy: Mutex::new(policy),
+                on_started,
+            }),
+            watchers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Start a language server and restart it whenever it exits.
+    ///
+    /// Fails if the first start fails; no restarts are attempted in that case.
+    pub async fn supervise(&self, language: &str, factory: ClientFactory) -> Result<()> {
+        self.stop(language);
+
+        let client = match factory().await {
+            Ok(client) => client,
+            Err(e) => {
+                self.shared.publish_error(language, format!("Failed to start: {}", e)).await;
+                return Err(e);
+            }
+        };
+        let exit = client.exit_watch();
+        self.shared.register(language, client).await;
+
+        if let Some(exit) = exit {
+            let watcher =
+                tokio::spawn(Arc::clone(&self.shared).watch(language.to_string(), factory, exit));
+            self.watchers
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(language.to_string(), watcher);
+        }
+
+        Ok(())
+    }
+
+    /// Get the restart policy.
+    pub fn policy(&self) -> LspRestartConfig {
+        self.shared.policy()
+    }
+
+    /// Replace the restart policy.
+    ///
+    /// Servers that are already supervised use it from their next restart.
+    pub fn set_policy(&self, policy: LspRestartConfig) {
+        *self.shared.policy.lock().unwrap_or_else(PoisonError::into_inner) = policy;
+    }
+
+    /// Stop restarting a language server. The server itself keeps running.
+    pub fn stop(&self, language: &str) {
+        let watcher = self.watchers.lock().unwrap_or_else(PoisonError::into_inner).remove(language);
+        if let Some(watcher) = watcher {
+            watcher.abort();
+        }
+    }
+
+    /// Stop supervising and shut down every language server.
+    pub async fn shutdown_all(&self) {
+        let watchers: Vec<JoinHandle<()>> = {
+            let mut watchers = self.watchers.lock().unwrap_or_else(PoisonError::into_inner);
+            watchers.drain().map(|(_, watcher)| watcher).collect()
+        };
+        for watcher in watchers {
+            watcher.abort();
+        }
+
+        let clients = std::mem::take(&mut *self.shared.lsp_clients.write().await);
+        for (language, client) in clients {
+            if let Err(e) = client.shutdown().await {
+                error!("Failed to shutdown {} LSP: {}", language, e);
+            }
+            self.shared.publish(LspEvent::ServerStopped { language }).await;
+        }
+    }
+}
+
+impl Drop for LspSupervisor {
+    fn drop(&mut self) {
+        for (_, watcher) in self.watchers.get_mut().unwrap_or_else(PoisonError::into_inner).drain()
+        {
+            watcher.abort();
+        }
+    }
+}
+
+impl Shared {
+    /// Get a copy of the current restart policy.
+    fn policy(&self) -> LspRestartConfig {
+        self.policy.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// Wait for the server to exit and restart it, until the restart budget runs out.
+    async fn watch(
+        self: Arc<Self>,
+        language: String,
+        factory: ClientFactory,
+        mut exit: watch::Receiver<Option<String>>,
+    ) {
+        let mut restarts = 0;
+        let mut started_at = Instant::now();
+
+        loop {
+            let status = exit
+                .wait_for(Option::is_some)
+                .await
+                .map(|status| status.clone().unwrap_or_default())
+                .unwrap_or_else(|_| "unknown status".to_string());
+            warn!("{} language server exited unexpectedly: {}", language, status);
+
+            self.lsp_clients.write().await.remove(&language);
+            self.publish(LspEvent::ServerStopped { language: language.clone() }).await;
+
+            if started_at.elapsed() >= Duration::from_millis(self.policy().reset_after_ms) {
+                restarts = 0;
+            }
+
+            exit = loop {
+                let policy = self.policy();
+                if restarts >= policy.max_restarts {
+                    self.publish_error(&language, format!("Giving up after {} restarts", restarts))
+                        .await;
+                    return;
+                }
+
+                let delay = restart_delay(&policy, restarts);
+                restarts += 1;
+                debug!("Restarting {} language server in {:?}", language, delay);
+                tokio::time::sleep(delay).await;
+
+                match factory().await {
+                    Ok(client) => {
+                        let exit = client.exit_watch();
+                        self.register(&language, client).await;
+                        match exit {
+                            Some(exit) => break exit,
+                            None => return,
+                        }
+                    }
+                    Err(e) => {
+                        self.publish_error(&language, format!("Failed to restart: {}", e)).await;
+                    }
+                }
+            };
+            started_at = Instant::now();
+        }
+    }
+
+    /// Register a started client and announce it.
+    async fn register(&self, language: &str, client: BoxedLspClient) {
+        self.lsp_clients.write().await.insert(language.to_string(), client);
+        info!("Initialized {} LSP client", language);
+        self.publish(LspEvent::ServerStarted { language: language.to_string() }).await;
+        (self.on_started)(language.to_string()).await;
+    }
+
+    /// Report a server failure.
+    async fn publish_error(&self, language: &str, error: String) {
+        warn!("{} language server: {}", language, error);
+        self.publish(LspEvent::Error { language: language.to_string(), error }).await;
+    }
+
+    /// Publish a server status event.
+    async fn publish(&self, event: LspEvent) {
+        let priority = match event {
+            LspEvent::Error { .. } => EventPriority::High,
+            _ => EventPriority::Normal,
+        };
+        let message = EventMessage::from_event(Event::Lsp(event))
+            .with_priority(priority)
+            .with_source("lsp_supervisor");
+        if let Err(e) = self.event_bus.publish(message).await {
+            error!("Failed to publish LSP status event: {}", e);
+        }
+    }
+}
+
+/// Delay before restart number `attempt` (zero based).
+fn restart_delay(policy: &LspRestartConfig, attempt: u32) -> Duration {
+    let delay = policy.initial_backoff_ms.saturating_mul(1u64 << attempt.min(32));
+    Duration::from_millis(delay.min(policy.max_backoff_ms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restart_delay_backs_off() {
+        let policy = LspRestartConfig {
+            max_restarts: 10,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 1000,
+            reset_after_ms: 60_000,
+        };
+
+        assert_eq!(restart_delay(&policy, 0), Duration::from_millis(100));
+        assert_eq!(restart_delay(&policy, 1), Duration::from_millis(200));
+        assert_eq!(restart_delay(&policy, 3), Duration::from_millis(800));
+        assert_eq!(restart_delay(&policy, 4), Duration::from_millis(1000));
+        assert_eq!(restart_delay(&policy, 200), Duration::from_millis(1000));
+    }
+}
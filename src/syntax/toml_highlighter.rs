@@ -0,0 +1,418 @@
+//! # TOML Syntax Highlighting
+//!
+//! Tokenizer for TOML documents such as `Cargo.toml` and `xylux.toml`:
+//! table headers, keys, strings with escapes, numbers, dates and booleans.
+
+use async_trait::async_trait;
+
+use crate::core::Result;
+use crate::syntax::highlighter::utils::split_escapes;
+use crate::syntax::{HighlightToken, SyntaxHighlighter, TokenType};
+
+/// TOML syntax highlighter.
+pub struct TomlSyntaxHighlighter;
+
+impl TomlSyntaxHighlighter {
+    /// Create a new TOML syntax highlighter.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for TomlSyntaxHighlighter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl SyntaxHighlighter for TomlSyntaxHighlighter {
+    async fn highlight(&self, content: &str) -> Result<Vec<HighlightToken>> {
+        Ok(Scanner::new(content).run())
+    }
+
+    fn language(&self) -> &str {
+        "toml"
+    }
+
+    fn file_extensions(&self) -> Vec<&str> {
+        vec!["toml"]
+    }
+}
+
+/// Single pass scanner over a TOML document.
+struct Scanner<'a> {
+    content: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+    tokens: Vec<HighlightToken>,
+    /// Open arrays (`[`) and inline tables (`{`), innermost last.
+    nesting: Vec<u8>,
+    /// Whether the next bare word is a key rather than a value.
+    expect_key: bool,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(content: &'a str) -> Self {
+        Self {
+            content,
+            bytes: content.as_bytes(),
+            pos: 0,
+            tokens: Vec::new(),
+            nesting: Vec::new(),
+            expect_key: true,
+        }
+    }
+
+    fn run(mut self) -> Vec<HighlightToken> {
+        while let Some(&byte) = self.bytes.get(self.pos) {
+            match byte {
+                b'\n' => {
+                    self.pos += 1;
+                    if self.nesting.is_empty() {
+                        self.expect_key = true;
+                    }
+                }
+                b' ' | b'\t' | b'\r' => self.pos += 1,
+                b'#' => {
+                    let end = self.line_end();
+                    self.push(self.pos, end, TokenType::Comment);
+                    self.pos = end;
+                }
+                b'[' if self.expect_key && self.nesting.is_empty() => self.table_header(),
+                b'"' | b'\'' => {
+                    let start = self.pos;
+                    let end = self.string_end();
+                    if self.expect_key {
+                        self.push(start, end, TokenType::Property);
+                    } else if byte == b'"' {
+                        self.tokens.extend(split_escapes(self.content, start..end));
+                    } else {
+                        // Literal strings have no escapes.
+                        self.push(start, end, TokenType::String);
+                    }
+                    self.pos = end;
+                }
+                b'=' => {
+                    self.push(self.pos, self.pos + 1, TokenType::Operator);
+                    self.pos += 1;
+                    self.expect_key = false;
+                }
+                b'.' if self.expect_key => self.punctuation(),
+                b'[' => {
+                    self.nesting.push(b'[');
+                    self.punctuation();
+                }
+                b'{' => {
+                    self.nesting.push(b'{');
+                    self.expect_key = true;
+                    self.punctuation();
+                }
+                b']' | b'}' => {
+                    self.nesting.pop();
+                    self.punctuation();
+                }
+                b',' => {
+                    self.expect_key = self.nesting.last() == Some(&b'{');
+                    self.punctuation();
+                }
+                _ => self.word(),
+            }
+        }
+        self.tokens
+    }
+
+    /// A bare key, or a number, date or boolean value.
+    fn word(&mut self) {
+        let start = self.pos;
+        let end = start
+            + self.bytes[start..]
+                .iter()
+                .take_while(|&&b| {
+                    b.is_ascii_alphanumeric() || matches!(b, b'_' | b'-' | b'+' | b':' | b'.')
+                })
+                .count();
+        if end == start {
+            // Not TOML; skip the character.
+            self.pos += self.content[start..].chars().next().map_or(1, char::len_utf8);
+            return;
+        }
+
+        if self.expect_key {
+            // Dotted keys: highlight each part.
+            let key_end = start
+                + self.bytes[start..end]
+                    .iter()
+                    .take_while(|&&b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'-'))
+                    .count();
+            let key_end = key_end.max(start + 1);
+            self.push(start, key_end, TokenType::Property);
+            self.pos = key_end;
+            return;
+        }
+
+        // Offset date-times may contain one space between date and time.
+        let mut end = end;
+        if is_date(&self.content[start..end])
+            && self.bytes.get(end) == Some(&b' ')
+            && self.bytes.get(end + 1).is_some_and(u8::is_ascii_digit)
+        {
+            end += 1 + self.bytes[end + 1..]
+                .iter()
+                .take_while(|&&b| {
+                    b.is_ascii_alphanumeric() || matches!(b, b'+' | b'-' | b':' | b'.')
+                })
+                .count();
+        }
+
+        let word = &self.content[start..end];
+        let token_type = if word == "true" || word == "false" {
+            Some(TokenType::Constant)
+        } else if is_date(word) || is_time(word) {
+            Some(TokenType::Date)
+        } else if is_number(word) {
+            Some(TokenType::Number)
+        } else {
+            None
+        };
+        if let Some(token_type) = token_type {
+            self.push(start, end, token_type);
+        }
+        self.pos = end;
+    }
+
+    /// `[table]` or `[[array.of.tables]]` header.
+    fn table_header(&mut self) {
+        let start = self.pos;
+        let line_end = self.line_end();
+        let brackets = if self.bytes.get(start + 1) == Some(&b'[') { 2 } else { 1 };
+        let close =
+            self.content[start..line_end].find(']').map_or(line_end, |offset| start + offset);
+
+        self.push(start, start + brackets, TokenType::Punctuation);
+        let name = &self.content[start + brackets..close];
+        let name_start = start + brackets + (name.len() - name.trim_start().len());
+        let name_end = start + brackets + name.trim_end().len();
+        if name_start < name_end {
+            self.push(name_start, name_end, TokenType::Type);
+        }
+        let close_end = (close + brackets).min(line_end);
+        if close < close_end {
+            self.push(close, close_end, TokenType::Punctuation);
+        }
+        self.pos = close_end;
+    }
+
+    fn punctuation(&mut self) {
+        self.push(self.pos, self.pos + 1, TokenType::Punctuation);
+        self.pos += 1;
+    }
+
+    /// End of the string starting at the current position, including
+    /// multi-line `"""` and `'''` strings. Unterminated strings end at the line.
+    fn string_end(&self) -> usize {
+        let quote = self.bytes[self.pos];
+        let rest = &self.content[self.pos..];
+        let triple = if quote == b'"' { "\"\"\"" } else { "'''" };
+
+        if rest.starts_with(triple) {
+            let body = &rest[3..];
+            let close = find_unescaped(body, triple, quote == b'"');
+            return match close {
+                // Up to two extra quotes may directly precede the delimiter.
+                Some(offset) => {
+                    let extra =
+                        body[offset + 3..].bytes().take(2).take_while(|&b| b == quote).count();
+                    self.pos + 3 + offset + 3 + extra
+                }
+                None => self.content.len(),
+            };
+        }
+
+        let line = &rest[1..rest.find('\n').unwrap_or(rest.len())];
+        let delimiter = if quote == b'"' { "\"" } else { "'" };
+        match find_unescaped(line, delimiter, quote == b'"') {
+            Some(offset) => self.pos + 1 + offset + 1,
+            None => self.pos + 1 + line.len(),
+        }
+    }
+
+    fn line_end(&self) -> usize {
+        self.content[self.pos..].find('\n').map_or(self.content.len(), |offset| self.pos + offset)
+    }
+
+    fn push(&mut self, start: usize, end: usize, token_type: TokenType) {
+        self.tokens.push(HighlightToken::new(start, end, token_type));
+    }
+}
+
+/// Offset of the first `delimiter` in `text` that is not escaped.
+fn find_unescaped(text: &str, delimiter: &str, escapes: bool) -> Option<usize> {
+    let mut escaped = false;
+    for (offset, ch) in text.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if escapes && ch == '\\' {
+            escaped = true;
+        } else if text[offset..].starts_with(delimiter) {
+            return Some(offset);
+        }
+    }
+    None
+}
+
+/// Whether a word is a local date or a date-time: `1979-05-27`,
+/// `1979-05-27T07:32:00Z`, `1979-05-27 07:32:00-08:00`.
+fn is_date(word: &str) -> bool {
+    let bytes = word.as_bytes();
+    let digits = |range: std::ops::Range<usize>| {
+        bytes.get(range).is_some_and(|part| part.iter().all(u8::is_ascii_digit))
+    };
+    if !(digits(0..4)
+        && bytes.get(4) == Some(&b'-')
+        && digits(5..7)
+        && bytes.get(7) == Some(&b'-')
+        && digits(8..10))
+    {
+        return false;
+    }
+    match bytes.get(10) {
+        None => true,
+        Some(b'T' | b't' | b' ') => is_time(&word[11..]),
+        Some(_) => false,
+    }
+}
+
+/// Whether a word is a time, with optional fraction and offset: `07:32:00.999-08:00`.
+fn is_time(word: &str) -> bool {
+    let bytes = word.as_bytes();
+    let digits = |range: std::ops::Range<usize>| {
+        bytes.get(range).is_some_and(|part| part.iter().all(u8::is_ascii_digit))
+    };
+    if !(digits(0..2)
+        && bytes.get(2) == Some(&b':')
+        && digits(3..5)
+        && bytes.get(5) == Some(&b':')
+        && digits(6..8))
+    {
+        return false;
+    }
+
+    let mut rest = &word[8..];
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let len = fraction.bytes().take_while(u8::is_ascii_digit).count();
+        if len == 0 {
+            return false;
+        }
+        rest = &fraction[len..];
+    }
+    match rest.as_bytes() {
+        [] | [b'Z' | b'z'] => true,
+        [b'+' | b'-', h1, h2, b':', m1, m2] => [h1, h2, m1, m2].iter().all(|b| b.is_ascii_digit()),
+        _ => false,
+    }
+}
+
+/// Whether a word is a TOML integer or float, including `inf` and `nan`.
+fn is_number(word: &str) -> bool {
+    let unsigned = word.strip_prefix(['+', '-']).unwrap_or(word);
+    if matches!(unsigned, "inf" | "nan") {
+        return true;
+    }
+
+    let radix = |prefix: &str, valid: fn(&u8) -> bool| {
+        unsigned.strip_prefix(prefix).is_some_and(|digits| {
+            !digits.is_empty() && digits.bytes().all(|b| valid(&b) || b == b'_')
+        })
+    };
+    if radix("0x", u8::is_ascii_hexdigit)
+        || radix("0o", |b| (b'0'..=b'7').contains(b))
+        || radix("0b", |b| matches!(b, b'0' | b'1'))
+    {
+        return true;
+    }
+
+    let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+        Some(offset) => (&unsigned[..offset], Some(&unsigned[offset + 1..])),
+        None => (unsigned, None),
+    };
+    let decimal = |part: &str| {
+        part.starts_with(|c: char| c.is_ascii_digit())
+            && part.bytes().all(|b| b.is_ascii_digit() || b == b'_')
+    };
+    let mantissa_ok = match mantissa.split_once('.') {
+        Some((whole, fraction)) => decimal(whole) && decimal(fraction),
+        None => decimal(mantissa),
+    };
+    let exponent_ok = exponent
+        .is_none_or(|exponent| decimal(exponent.strip_prefix(['+', '-']).unwrap_or(exponent)));
+    mantissa_ok && exponent_ok
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(content: &str, tokens: &[HighlightToken]) -> Vec<(String, TokenType)> {
+        tokens.iter().map(|t| (content[t.start..t.end].to_string(), t.token_type.clone())).collect()
+    }
+
+    #[tokio::test]
+    async fn test_toml_highlighting() {
+        let content = r#"# Manifest
+[package]
+name = "xylux\n"
+version.workspace = true
+edition = 'raw\n'
+
+[[bin]]
+opt-level = 3
+ratio = -1.5e3
+mask = 0xFF_FF
+released = 1979-05-27T07:32:00Z
+also = 1979-05-27 07:32:00-08:00
+when = 07:32:00
+deps = { serde = "1", features = ["derive"] }
+"#;
+        let tokens = TomlSyntaxHighlighter::new().highlight(content).await.unwrap();
+        let parts = parts(content, &tokens);
+        let of = |text: &str| {
+            parts.iter().find(|(part, _)| part == text).map(|(_, token_type)| token_type.clone())
+        };
+
+        assert_eq!(of("# Manifest"), Some(TokenType::Comment));
+        assert_eq!(of("package"), Some(TokenType::Type));
+        assert_eq!(of("bin"), Some(TokenType::Type));
+        assert_eq!(of("[["), Some(TokenType::Punctuation));
+        assert_eq!(of("name"), Some(TokenType::Property));
+        assert_eq!(of("\\n"), Some(TokenType::Escape));
+        assert_eq!(of("version"), Some(TokenType::Property));
+        assert_eq!(of("workspace"), Some(TokenType::Property));
+        assert_eq!(of("true"), Some(TokenType::Constant));
+        assert_eq!(of("'raw\\n'"), Some(TokenType::String));
+        assert_eq!(of("opt-level"), Some(TokenType::Property));
+        assert_eq!(of("3"), Some(TokenType::Number));
+        assert_eq!(of("-1.5e3"), Some(TokenType::Number));
+        assert_eq!(of("0xFF_FF"), Some(TokenType::Number));
+        assert_eq!(of("1979-05-27T07:32:00Z"), Some(TokenType::Date));
+        assert_eq!(of("1979-05-27 07:32:00-08:00"), Some(TokenType::Date));
+        assert_eq!(of("07:32:00"), Some(TokenType::Date));
+        assert_eq!(of("serde"), Some(TokenType::Property));
+        assert_eq!(of("features"), Some(TokenType::Property));
+        assert_eq!(of("\"derive\""), Some(TokenType::String));
+
+        // Tokens are sorted and never overlap.
+        assert!(tokens.windows(2).all(|pair| pair[0].end <= pair[1].start));
+    }
+
+    #[tokio::test]
+    async fn test_multiline_strings() {
+        let content = "text = \"\"\"\nline \"\"quoted\"\" \\\"\"\"\"\"\nkey = 1\n";
+        let tokens = TomlSyntaxHighlighter::new().highlight(content).await.unwrap();
+        let parts = parts(content, &tokens);
+
+        assert_eq!(parts.last(), Some(&("1".to_string(), TokenType::Number)));
+        assert!(parts.contains(&("key".to_string(), TokenType::Property)));
+        assert!(parts.contains(&("\\\"".to_string(), TokenType::Escape)));
+    }
+}
@@ -0,0 +1,487 @@
+//! # LSP Transport
+//!
+//! Content-Length framed JSON-RPC transport for language server processes.
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use serde_json::{Value, json};
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+use tokio::sync::{Mutex as AsyncMutex, broadcast, oneshot};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, trace, warn};
+
+use crate::core::{Result, XyluxError};
+use crate::syntax::lsp_client::LspError;
+
+/// Boxed writer half of a transport.
+type Writer = Box<dyn AsyncWrite + Send + Unpin>;
+
+/// Requests waiting for a response, keyed by request ID.
+type PendingRequests = HashMap<u64, oneshot::Sender<Value>>;
+
+/// Capacity of the notification broadcast channel.
+const NOTIFICATION_CAPACITY: usize = 256;
+
+/// JSON-RPC error code for unknown methods.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// A notification sent by a language server.
+#[derive(Debug, Clone)]
+pub struct LspNotification {
+    /// Notification method.
+    pub method: String,
+    /// Notification parameters.
+    pub params: Value,
+}
+
+/// JSON-RPC transport over the stdio of a language server.
+pub struct LspTransport {
+    /// Writer half, shared with the reader task to answer server requests.
+    writer: Arc<AsyncMutex<Writer>>,
+    /// Requests waiting for a response.
+    pending: Arc<Mutex<PendingRequests>>,
+    /// Request ID counter.
+    next_id: AtomicU64,
+    /// Channel for server notifications.
+    notifications: broadcast::Sender<LspNotification>,
+    /// Whether the server output is still open.
+    connected: Arc<AtomicBool>,
+    /// Timeout applied to every request.
+    request_timeout: Duration,
+    /// Task reading and routing server messages.
+    reader_task: JoinHandle<()>,
+}
+
+impl LspTransport {
+    /// Create a transport over a reader and writer and start routing messages.
+    pub fn new<R, W>(reader: R, writer: W, request_timeout: Duration) -> Self
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+        W: AsyncWrite + Send + Unpin + 'static,
+    {
+        let writer: Arc<AsyncMutex<Writer>> = Arc::new(AsyncMutex::new(Box::new(writer)));
+        let pending = Arc::new(Mutex::new(HashMap::new()));
+        let (notifications, _) = broadcast::channel(NOTIFICATION_CAPACITY);
+        let connected = Arc::new(AtomicBool::new(true));
+
+        let reader_task = tokio::spawn(read_loop(
+            BufReader::new(reader),
+            Arc::clone(&writer),
+            Arc::clone(&pending),
+            notifications.clone(),
+            Arc::clone(&connected),
+        ));
+
+        Self {
+            writer,
+            pending,
+            next_id: AtomicU64::new(0),
+            notifications,
+            connected,
+            request_timeout,
+            reader_task,
+        }
+    }
+
+    /// Send a request and wait for the matching response.
+    ///
+    /// Returns the full response message. Error responses and timeouts are
+    /// reported as [`XyluxError::Lsp`].
+    pub async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let (sender, receiver) = oneshot::channel();
+        lock(&self.pending).insert(id, sender);
+
+        // Checked after registering the request: if the read loop exits in
+        // between, it either sees the entry and drops its sender or has
+        // already cleared the connected flag.
+        if !self.is_connected() {
+            lock(&self.pending).remove(&id);
+            return Err(XyluxError::lsp_error(format!(
+                "Cannot send '{}': language server is not running",
+                method
+            )));
+        }
+
+        let message = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params
+        });
+
+        trace!("Sending request {} ({})", id, method);
+        if let Err(e) = write_message(&self.writer, &message).await {
+            lock(&self.pending).remove(&id);
+            return Err(e);
+        }
+
+        match tokio::time::timeout(self.request_timeout, receiver).await {
+            Ok(Ok(response)) => {
+                if let Some(error) = response.get("error") {
+                    let error = parse_error(error);
+                    return Err(XyluxError::lsp_error(format!(
+                        "Request '{}' failed: {} (code {})",
+                        method, error.message, error.code
+                    )));
+                }
+                Ok(response)
+            }
+            Ok(Err(_)) => Err(XyluxError::lsp_error(format!(
+                "Language server exited before answering '{}'",
+                method
+            ))),
+            Err(_) => {
+                lock(&self.pending).remove(&id);
+                // Best effort: the server may already be gone.
+                let _unused = self.notify("$/cancelRequest", json!({ "id": id })).await;
+                Err(XyluxError::lsp_error(format!(
+                    "Request '{}' timed out after {} ms",
+                    method,
+                    self.request_timeout.as_millis()
+                )))
+            }
+        }
+    }
+
+    /// Send a notification.
+    pub async fn notify(&self, method: &str, params: Value) -> Result<()> {
+        if !self.is_connected() {
+            return Err(XyluxError::lsp_error(format!(
+                "Cannot send '{}': language server is not running",
+                method
+            )));
+        }
+
+        trace!("Sending notification {}", method);
+        let message = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params
+        });
+        write_message(&self.writer, &message).await
+    }
+
+    /// Subscribe to notifications sent by the server.
+    pub fn subscribe(&self) -> broadcast::Receiver<LspNotification> {
+        self.notifications.subscribe()
+    }
+
+    /// Check whether the server output is still open.
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::SeqCst)
+    }
+
+    /// Get the timeout applied to requests.
+    pub const fn request_timeout(&self) -> Duration {
+        self.request_timeout
+    }
+}
+
+impl Drop for LspTransport {
+    fn drop(&mut self) {
+        self.reader_task.abort();
+    }
+}
+
+/// Lock a mutex, recovering the data if a holder panicked.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Parse a JSON-RPC error object.
+fn parse_error(error: &Value) -> LspError {
+    LspError {
+        code: error
+            .get("code")
+            .and_then(Value::as_i64)
+            .and_then(|c| i32::try_from(c).ok())
+            .unwrap_or_default(),
+        message: error
+            .get("message")
+            .and_then(Value::as_str)
+            .unwrap_or("Unknown error")
+            .to_string(),
+        data: error.get("data").cloned(),
+    }
+}
+
+/// Write a framed message.
+async fn write_message(writer: &AsyncMutex<Writer>, message: &Value) -> Result<()> {
+    let body = serde_json::to_vec(message)?;
+    let header = format!("Content-Length: {}\r\n\r\n", body.len());
+
+    let mut writer = writer.lock().await;
+    writer
+        .write_all(header.as_bytes())
+        .await
+        .map_err(|e| XyluxError::io(e, "Failed to write LSP message header"))?;
+    writer.write_all(&body).await.map_err(|e| XyluxError::io(e, "Failed to write LSP message"))?;
+    writer.flush().await.map_err(|e| XyluxError::io(e, "Failed to flush LSP message"))?;
+    Ok(())
+}
+
+/// Read the body of the next framed message, or `None` at end of stream.
+pub async fn read_message<R: AsyncBufRead + Unpin>(reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let mut content_length = None;
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+
+        let header = line.trim_end();
+        if header.is_empty() {
+            if content_length.is_some() {
+                break;
+            }
+            continue;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                let length = value
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                content_length = Some(length);
+            }
+        }
+    }
+
+    let mut body = vec![0; content_length.unwrap_or_default()];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(body))
+}
+
+/// Read server messages until the stream closes, routing each one.
+async fn read_loop<R: AsyncBufRead + Unpin>(
+    mut reader: R,
+    writer: Arc<AsyncMutex<Writer>>,
+    pending: Arc<Mutex<PendingRequests>>,
+    notifications: broadcast::Sender<LspNotification>,
+    connected: Arc<AtomicBool>,
+) {
+    loop {
+        match read_message(&mut reader).await {
+            Ok(Some(body)) => match serde_json::from_slice::<Value>(&body) {
+                Ok(message) => route_message(message, &writer, &pending, &notifications).await,
+                Err(e) => warn!("Discarding malformed LSP message: {}", e),
+            },
+            Ok(None) => {
+                debug!("Language server closed its output");
+                break;
+            }
+            Err(e) => {
+                error!("Failed to read from language server: {}", e);
+                break;
+            }
+        }
+    }
+
+    connected.store(false, Ordering::SeqCst);
+    // Dropping the senders wakes every waiting request.
+    lock(&pending).clear();
+}
+
+/// Route a message to a waiting request, the notification channel, or answer
+/// it if it is a request from the server.
+async fn route_message(
+    message: Value,
+    writer: &AsyncMutex<Writer>,
+    pending: &Mutex<PendingRequests>,
+    notifications: &broadcast::Sender<LspNotification>,
+) {
+    let method = message.get("method").and_then(Value::as_str).map(str::to_string);
+    let id = message.get("id").cloned();
+
+    match (method, id) {
+        (Some(method), Some(id)) => {
+            let reply = server_request_reply(&method, &message, id);
+            if let Err(e) = write_message(writer, &reply).await {
+                error!("Failed to answer server request '{}': {}", method, e);
+            }
+        }
+        (Some(method), None) => {
+            trace!("Received notification {}", method);
+            let params = message.get("params").cloned().unwrap_or(Value::Null);
+            // Sending only fails when nobody is subscribed.
+            let _unused = notifications.send(LspNotification { method, params });
+        }
+        (None, Some(id)) => {
+            let sender = id.as_u64().and_then(|id| lock(pending).remove(&id));
+            match sender {
+                Some(sender) => {
+                    // The receiver is gone if the request already timed out.
+                    let _unused = sender.send(message);
+                }
+                None => debug!("Discarding response to unknown request {}", id),
+            }
+        }
+        (None, None) => warn!("Discarding LSP message without method or id"),
+    }
+}
+
+/// Build the reply to a request initiated by the server.
+fn server_request_reply(method: &str, message: &Value, id: Value) -> Value {
+    match method {
+        "workspace/configuration" => {
+            let count =
+                message.pointer("/params/items").and_then(Value::as_array).map_or(0, Vec::len);
+            json!({ "jsonrpc": "2.0", "id": id, "result": vec![Value::Null; count] })
+        }
+        "client/registerCapability"
+        | "client/unregisterCapability"
+        | "window/workDoneProgress/create"
+        | "workspace/workspaceFolders" => {
+            json!({ "jsonrpc": "2.0", "id": id, "result": null })
+        }
+        _ => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": {
+                "code": METHOD_NOT_FOUND,
+                "message": format!("Unsupported method: {}", method)
+            }
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{DuplexStream, duplex};
+
+    use super::*;
+
+    /// Create a transport connected to an in-memory server end.
+    fn connect(timeout: Duration) -> (LspTransport, BufReader<DuplexStream>, DuplexStream) {
+        let (client_out, server_in) = duplex(4096);
+        let (server_out, client_in) = duplex(4096);
+        let transport = LspTransport::new(client_in, client_out, timeout);
+        (transport, BufReader::new(server_in), server_out)
+    }
+
+    async fn recv(reader: &mut BufReader<DuplexStream>) -> Value {
+        let body = read_message(reader).await.unwrap().unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    async fn send(writer: &mut DuplexStream, message: &Value) {
+        let body = serde_json::to_vec(message).unwrap();
+        let frame = format!("Content-Length: {}\r\n\r\n", body.len());
+        writer.write_all(frame.as_bytes()).await.unwrap();
+        writer.write_all(&body).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_read_message_framing() {
+        let data = b"Content-Length: 2\r\nContent-Type: application/json\r\n\r\n{}";
+        let mut reader = BufReader::new(&data[..]);
+
+        assert_eq!(read_message(&mut reader).await.unwrap(), Some(b"{}".to_vec()));
+        assert_eq!(read_message(&mut reader).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_responses_are_correlated_by_id() {
+        let (transport, mut server_in, mut server_out) = connect(Duration::from_secs(5));
+
+        let server = tokio::spawn(async move {
+            let first = recv(&mut server_in).await;
+            let second = recv(&mut server_in).await;
+            // Answer out of order.
+            for request in [&second, &first] {
+                let reply =
+                    json!({ "jsonrpc": "2.0", "id": request["id"], "result": request["method"] });
+                send(&mut server_out, &reply).await;
+            }
+            (server_in, server_out)
+        });
+
+        let (first, second) = tokio::join!(
+            transport.request("first", json!({})),
+            transport.request("second", json!({}))
+        );
+        assert_eq!(first.unwrap()["result"], "first");
+        assert_eq!(second.unwrap()["result"], "second");
+        let _unused = server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_error_response() {
+        let (transport, mut server_in, mut server_out) = connect(Duration::from_secs(5));
+
+        let server = tokio::spawn(async move {
+            let request = recv(&mut server_in).await;
+            let reply = json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "error": { "code": -32601, "message": "nope" }
+            });
+            send(&mut server_out, &reply).await;
+            (server_in, server_out)
+        });
+
+        let error = transport.request("unknown", json!({})).await.unwrap_err();
+        assert!(error.to_string().contains("nope"), "unexpected error: {}", error);
+        let _unused = server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout_sends_cancel() {
+        let (transport, mut server_in, _server_out) = connect(Duration::from_millis(50));
+
+        let error = transport.request("slow", json!({})).await.unwrap_err();
+        assert!(error.to_string().contains("timed out"), "unexpected error: {}", error);
+
+        let request = recv(&mut server_in).await;
+        let cancel = recv(&mut server_in).await;
+        assert_eq!(cancel["method"], "$/cancelRequest");
+        assert_eq!(cancel["params"]["id"], request["id"]);
+    }
+
+    #[tokio::test]
+    async fn test_notifications_and_server_requests() {
+        let (transport, mut server_in, mut server_out) = connect(Duration::from_secs(5));
+        let mut notifications = transport.subscribe();
+
+        send(&mut server_out, &json!({ "jsonrpc": "2.0", "method": "window/logMessage", "params": { "message": "hi" } })).await;
+        send(
+            &mut server_out,
+            &json!({ "jsonrpc": "2.0", "id": "srv-1", "method": "workspace/configuration", "params": { "items": [{}, {}] } }),
+        )
+        .await;
+
+        let notification = notifications.recv().await.unwrap();
+        assert_eq!(notification.method, "window/logMessage");
+        assert_eq!(notification.params["message"], "hi");
+
+        let reply = recv(&mut server_in).await;
+        assert_eq!(reply["id"], "srv-1");
+        assert_eq!(reply["result"], json!([null, null]));
+    }
+
+    #[tokio::test]
+    async fn test_pending_requests_fail_when_server_exits() {
+        let (transport, server_in, server_out) = connect(Duration::from_secs(5));
+
+        let server = tokio::spawn(async move {
+            let mut server_in = server_in;
+            let _request = recv(&mut server_in).await;
+            drop(server_out);
+        });
+
+        let error = transport.request("never", json!({})).await.unwrap_err();
+        assert!(error.to_string().contains("exited"), "unexpected error: {}", error);
+        server.await.unwrap();
+        assert!(!transport.is_connected());
+    }
+}
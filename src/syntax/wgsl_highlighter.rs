@@ -0,0 +1,331 @@
+//! # WGSL Syntax Highlighting
+//!
+//! Tokenizer for WebGPU shaders: keywords, built-in types, `@attributes`,
+//! typed literals and nested block comments.
+
+use async_trait::async_trait;
+
+use crate::core::Result;
+use crate::syntax::{HighlightToken, SyntaxHighlighter, TokenType};
+
+/// WGSL syntax highlighter.
+pub struct WgslSyntaxHighlighter;
+
+impl WgslSyntaxHighlighter {
+    /// Create a new WGSL syntax highlighter.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for WgslSyntaxHighlighter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl SyntaxHighlighter for WgslSyntaxHighlighter {
+    async fn highlight(&self, content: &str) -> Result<Vec<HighlightToken>> {
+        let bytes = content.as_bytes();
+        let mut tokens: Vec<HighlightToken> = Vec::new();
+        let mut pos = 0;
+        // Previous significant word, to name declarations.
+        let mut previous = "";
+        // Inside the `<...>` of `var` or `ptr`, where address spaces are keywords.
+        let mut in_template = false;
+
+        while let Some(&byte) = bytes.get(pos) {
+            let start = pos;
+            match byte {
+                b if b.is_ascii_whitespace() => {
+                    pos += 1;
+                    continue;
+                }
+                b'/' if bytes.get(pos + 1) == Some(&b'/') => {
+                    pos = content[pos..].find('\n').map_or(content.len(), |offset| pos + offset);
+                    tokens.push(HighlightToken::new(start, pos, TokenType::Comment));
+                    continue;
+                }
+                b'/' if bytes.get(pos + 1) == Some(&b'*') => {
+                    pos = block_comment_end(content, pos);
+                    tokens.push(HighlightToken::new(start, pos, TokenType::Comment));
+                    continue;
+                }
+                b'@' => {
+                    pos += 1 + identifier_length(&content[pos + 1..]);
+                    tokens.push(HighlightToken::new(start, pos, TokenType::Attribute));
+                }
+                b'0'..=b'9' => {
+                    pos += number_length(&content[pos..]);
+                    tokens.push(HighlightToken::new(start, pos, TokenType::Number));
+                }
+                b'.' if bytes.get(pos + 1).is_some_and(u8::is_ascii_digit) => {
+                    pos += number_length(&content[pos..]);
+                    tokens.push(HighlightToken::new(start, pos, TokenType::Number));
+                }
+                b if b.is_ascii_alphabetic() || b == b'_' || !b.is_ascii() => {
+                    let len = identifier_length(&content[pos..]);
+                    if len == 0 {
+                        pos += content[pos..].chars().next().map_or(1, char::len_utf8);
+                        continue;
+                    }
+                    pos += len;
+                    let word = &content[start..pos];
+                    let next = content[pos..].trim_start().as_bytes().first().copied();
+
+                    let token_type = if in_template && is_template_keyword(word) {
+                        TokenType::Keyword
+                    } else if is_keyword(word) {
+                        TokenType::Keyword
+                    } else if word == "true" || word == "false" {
+                        TokenType::Constant
+                    } else if is_builtin_type(word) || previous == "struct" || previous == "alias" {
+                        TokenType::Type
+                    } else if previous == "fn" || next == Some(b'(') {
+                        TokenType::Function
+                    } else if word.starts_with(|c: char| c.is_ascii_uppercase()) {
+                        TokenType::Type
+                    } else {
+                        TokenType::Variable
+                    };
+                    tokens.push(HighlightToken::new(start, pos, token_type));
+                    in_template |= matches!(word, "var" | "ptr") && next == Some(b'<');
+                    previous = word;
+                    continue;
+                }
+                b'(' | b')' | b'{' | b'}' | b'[' | b']' | b';' | b',' | b'.' | b':' => {
+                    pos += 1;
+                    tokens.push(HighlightToken::new(start, pos, TokenType::Punctuation));
+                }
+                _ => {
+                    pos += operator_length(&content[pos..]);
+                    if byte == b'>' {
+                        in_template = false;
+                    }
+                    tokens.push(HighlightToken::new(start, pos, TokenType::Operator));
+                }
+            }
+            previous = "";
+        }
+
+        Ok(tokens)
+    }
+
+    fn language(&self) -> &str {
+        "wgsl"
+    }
+
+    fn file_extensions(&self) -> Vec<&str> {
+        vec!["wgsl"]
+    }
+}
+
+/// Check whether a word is a WGSL keyword.
+fn is_keyword(word: &str) -> bool {
+    matches!(
+        word,
+        "alias"
+            | "break"
+            | "case"
+            | "const"
+            | "const_assert"
+            | "continue"
+            | "continuing"
+            | "default"
+            | "diagnostic"
+            | "discard"
+            | "else"
+            | "enable"
+            | "fn"
+            | "for"
+            | "if"
+            | "let"
+            | "loop"
+            | "override"
+            | "requires"
+            | "return"
+            | "struct"
+            | "switch"
+            | "var"
+            | "while"
+    )
+}
+
+/// Address spaces and access modes, keywords inside `var<...>` and `ptr<...>`.
+fn is_template_keyword(word: &str) -> bool {
+    matches!(
+        word,
+        "function"
+            | "private"
+            | "workgroup"
+            | "uniform"
+            | "storage"
+            | "push_constant"
+            | "read"
+            | "write"
+            | "read_write"
+    )
+}
+
+/// Check whether a word names a built-in WGSL type.
+fn is_builtin_type(word: &str) -> bool {
+    if matches!(
+        word,
+        "bool"
+            | "f16"
+            | "f32"
+            | "i32"
+            | "u32"
+            | "array"
+            | "atomic"
+            | "ptr"
+            | "sampler"
+            | "sampler_comparison"
+            | "vec2"
+            | "vec3"
+            | "vec4"
+    ) || word.starts_with("texture_")
+    {
+        return true;
+    }
+
+    // Sized shorthands such as `vec3f`, `vec4<f32>`, `mat4x4f` and `mat2x3h`.
+    let shape = word
+        .strip_prefix("vec")
+        .filter(|rest| matches!(rest.as_bytes().first(), Some(b'2'..=b'4')))
+        .map(|rest| &rest[1..])
+        .or_else(|| {
+            word.strip_prefix("mat")
+                .filter(|rest| {
+                    let bytes = rest.as_bytes();
+                    bytes.len() >= 3
+                        && matches!(bytes[0], b'2'..=b'4')
+                        && bytes[1] == b'x'
+                        && matches!(bytes[2], b'2'..=b'4')
+                })
+                .map(|rest| &rest[3..])
+        });
+    matches!(shape, Some("" | "f" | "h" | "i" | "u"))
+}
+
+/// Length of the identifier at the start of `text`.
+fn identifier_length(text: &str) -> usize {
+    text.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(text.len())
+}
+
+/// End of the block comment starting at `start`. WGSL block comments nest.
+fn block_comment_end(content: &str, start: usize) -> usize {
+    let bytes = content.as_bytes();
+    let mut depth = 0usize;
+    let mut pos = start;
+    while pos + 1 < bytes.len() {
+        match (bytes[pos], bytes[pos + 1]) {
+            (b'/', b'*') => {
+                depth += 1;
+                pos += 2;
+            }
+            (b'*', b'/') => {
+                depth -= 1;
+                pos += 2;
+                if depth == 0 {
+                    return pos;
+                }
+            }
+            _ => pos += 1,
+        }
+    }
+    content.len()
+}
+
+/// Length of a numeric literal: decimal or hex, with optional fraction,
+/// exponent and `i`, `u`, `f` or `h` suffix.
+fn number_length(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    let mut end = 0;
+    let hex = text.starts_with("0x") || text.starts_with("0X");
+    if hex {
+        end = 2;
+    }
+    let digit = |b: u8| if hex { b.is_ascii_hexdigit() } else { b.is_ascii_digit() };
+
+    end += bytes[end..].iter().take_while(|&&b| digit(b)).count();
+    if bytes.get(end) == Some(&b'.') {
+        end += 1;
+        end += bytes[end..].iter().take_while(|&&b| digit(b)).count();
+    }
+    let exponent = if hex { [b'p', b'P'] } else { [b'e', b'E'] };
+    if bytes.get(end).is_some_and(|b| exponent.contains(b)) {
+        let mut after = end + 1;
+        if matches!(bytes.get(after), Some(b'+' | b'-')) {
+            after += 1;
+        }
+        if bytes.get(after).is_some_and(u8::is_ascii_digit) {
+            end = after + bytes[after..].iter().take_while(|b| b.is_ascii_digit()).count();
+        }
+    }
+    if matches!(bytes.get(end), Some(b'i' | b'u' | b'f' | b'h')) {
+        end += 1;
+    }
+    end
+}
+
+/// Length of the operator at the start of `text`.
+fn operator_length(text: &str) -> usize {
+    const OPERATORS: &[&str] = &[
+        "<<=", ">>=", "->", "&&", "||", "==", "!=", "<=", ">=", "<<", ">>", "++", "--", "+=", "-=",
+        "*=", "/=", "%=", "&=", "|=", "^=",
+    ];
+    OPERATORS
+        .iter()
+        .find(|operator| text.starts_with(*operator))
+        .map_or_else(|| text.chars().next().map_or(1, char::len_utf8), |operator| operator.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_wgsl_highlighting() {
+        let content = r#"/* outer /* nested */ still comment */
+struct Light {
+    color: vec3f,
+}
+
+@group(0) @binding(0) var<uniform> light: Light;
+@group(0) @binding(1) var<storage, read_write> counts: array<atomic<u32>>;
+
+@fragment
+fn fs_main(@location(0) uv: vec2<f32>) -> @location(0) vec4f {
+    let scale = 0x1Fu + 2i;
+    let brightness = max(1.5e-3f, 0.5h);
+    return vec4f(light.color * brightness, 1.0);
+}
+"#;
+        let tokens = WgslSyntaxHighlighter::new().highlight(content).await.unwrap();
+        let parts: Vec<(&str, TokenType)> =
+            tokens.iter().map(|t| (&content[t.start..t.end], t.token_type.clone())).collect();
+        let has = |text: &str, token_type: TokenType| parts.contains(&(text, token_type));
+
+        assert!(has("/* outer /* nested */ still comment */", TokenType::Comment));
+        assert!(has("struct", TokenType::Keyword));
+        assert!(has("Light", TokenType::Type));
+        assert!(has("vec3f", TokenType::Type));
+        assert!(has("@binding", TokenType::Attribute));
+        assert!(has("uniform", TokenType::Keyword));
+        assert!(has("read_write", TokenType::Keyword));
+        assert!(has("atomic", TokenType::Type));
+        assert!(has("fs_main", TokenType::Function));
+        assert!(has("vec2", TokenType::Type));
+        assert!(has("f32", TokenType::Type));
+        assert!(has("0x1Fu", TokenType::Number));
+        assert!(has("2i", TokenType::Number));
+        assert!(has("1.5e-3f", TokenType::Number));
+        assert!(has("0.5h", TokenType::Number));
+        assert!(has("max", TokenType::Function));
+        assert!(has("->", TokenType::Operator));
+        assert!(has("brightness", TokenType::Variable));
+        assert!(tokens.windows(2).all(|pair| pair[0].end <= pair[1].start));
+    }
+}
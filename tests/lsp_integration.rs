@@ -0,0 +1,796 @@
+//! # LSP Integration Tests
+//!
+//! Drives the language server clients against a scripted fake server. This
+//! test binary doubles as the fake server when started with
+//! `--fake-lsp-server`, so the tests need no external tooling.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::FutureExt;
+use futures::future::BoxFuture;
+use serde_json::{Value, json};
+use tokio::sync::RwLock;
+use xylux_ide::core::config::{LanguageServerConfig, LspConfig};
+use xylux_ide::core::{Config, Event, EventBus, EventHandler, EventMessage, LspEvent};
+use xylux_ide::editor::Editor;
+use xylux_ide::syntax::lsp_client::TextDocumentSyncCapability;
+use xylux_ide::syntax::{LspClient, RustAnalyzer, SyntaxManager};
+
+/// Argument that switches this binary into fake server mode.
+const SERVE_FLAG: &str = "--fake-lsp-server";
+
+/// Documents containing this text make the fake server crash on change.
+const CRASH_MARKER: &str = "CRASH";
+
+/// Hover requests on this line are never answered.
+const UNANSWERED_LINE: u64 = 9999;
+
+fn main() -> ExitCode {
+    if std::env::args().any(|arg| arg == SERVE_FLAG) {
+        fake_server::run();
+        return ExitCode::SUCCESS;
+    }
+
+    let tests: &[(&str, fn() -> BoxFuture<'static, ()>)] = &[
+        ("initialize_reads_capabilities", initialize_reads_capabilities),
+        ("completion_roundtrip", completion_roundtrip),
+        ("hover_roundtrip", hover_roundtrip),
+        ("pull_diagnostics_roundtrip", pull_diagnostics_roundtrip),
+        ("request_timeout", request_timeout),
+        ("shutdown_stops_server", shutdown_stops_server),
+        ("published_diagnostics_reach_store", published_diagnostics_reach_store),
+        ("editor_changes_are_synchronized", editor_changes_are_synchronized),
+        ("navigation_and_workspace_edits", navigation_and_workspace_edits),
+        ("configured_servers_handle_their_files", configured_servers_handle_their_files),
+        ("crashed_server_is_restarted", crashed_server_is_restarted),
+    ];
+
+    let runtime = tokio::runtime::Runtime::new().expect("failed to create runtime");
+    let mut failed = 0;
+    println!("\nrunning {} tests", tests.len());
+    for (name, test) in tests {
+        let outcome = std::panic::catch_unwind(|| runtime.block_on(test()));
+        if outcome.is_ok() {
+            println!("test {} ... ok", name);
+        } else {
+            println!("test {} ... FAILED", name);
+            failed += 1;
+        }
+    }
+    println!(
+        "\ntest result: {}. {} passed; {} failed\n",
+        if failed == 0 { "ok" } else { "FAILED" },
+        tests.len() - failed,
+        failed
+    );
+
+    if failed == 0 { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+}
+
+/// LSP configuration that launches this binary as the fake server.
+fn fake_server_config(request_timeout_ms: u64) -> LspConfig {
+    let mut config = LspConfig::default();
+    config.rust_analyzer.binary_path =
+        Some(std::env::current_exe().expect("failed to locate test binary"));
+    config.rust_analyzer.args = vec![SERVE_FLAG.to_string()];
+    config.request_timeout_ms = request_timeout_ms;
+    config
+}
+
+async fn start_client() -> RustAnalyzer {
+    RustAnalyzer::new(&fake_server_config(5000)).await.expect("failed to start fake server")
+}
+
+fn source_file() -> String {
+    PathBuf::from("/tmp/project/src/main.rs").to_string_lossy().into_owned()
+}
+
+fn initialize_reads_capabilities() -> BoxFuture<'static, ()> {
+    async {
+        let client = start_client().await;
+        assert!(client.is_running(), "client should be running after initialize");
+
+        let caps = client.capabilities();
+        assert!(caps.hover_provider, "hover capability missing");
+        assert!(caps.diagnostic_provider, "diagnostic capability missing");
+        assert!(matches!(caps.text_document_sync, Some(TextDocumentSyncCapability::Incremental)));
+        client.shutdown().await.expect("shutdown failed");
+    }
+    .boxed()
+}
+
+fn completion_roundtrip() -> BoxFuture<'static, ()> {
+    async {
+        let client = start_client().await;
+        let items = client.get_completion(&source_file(), 1, 4).await.expect("completion failed");
+        assert_eq!(items.len(), 1, "unexpected completions: {:?}", items);
+        assert_eq!(items[0].label, "fake_fn");
+        assert_eq!(items[0].detail.as_deref(), Some("main.rs:1:4"));
+        client.shutdown().await.expect("shutdown failed");
+    }
+    .boxed()
+}
+
+fn hover_roundtrip() -> BoxFuture<'static, ()> {
+    async {
+        let client = start_client().await;
+        let hover = client.get_hover(&source_file(), 2, 0).await.expect("hover failed");
+        assert_eq!(hover.as_deref(), Some("fake hover at 2:0"));
+        client.shutdown().await.expect("shutdown failed");
+    }
+    .boxed()
+}
+
+fn pull_diagnostics_roundtrip() -> BoxFuture<'static, ()> {
+    async {
+        let client = start_client().await;
+        let diagnostics = client.get_diagnostics(&source_file()).await.expect("diagnostics failed");
+        assert_eq!(diagnostics.len(), 1, "unexpected diagnostics: {:?}", diagnostics);
+        assert_eq!(diagnostics[0].message, "fake warning");
+        assert_eq!((diagnostics[0].column, diagnostics[0].end_column), (4, 7));
+        client.shutdown().await.expect("shutdown failed");
+    }
+    .boxed()
+}
+
+fn request_timeout() -> BoxFuture<'static, ()> {
+    async {
+        let client = RustAnalyzer::new(&fake_server_config(200)).await.expect("failed to start");
+        let started = std::time::Instant::now();
+        let error = client
+            .get_hover(&source_file(), usize::try_from(UNANSWERED_LINE).unwrap_or_default(), 0)
+            .await
+            .expect_err("unanswered request should time out");
+        assert!(error.to_string().contains("timed out"), "unexpected error: {}", error);
+        assert!(started.elapsed() < Duration::from_secs(5), "timeout was not applied");
+
+        // The connection stays usable after a timeout.
+        let hover = client.get_hover(&source_file(), 0, 0).await.expect("hover failed");
+        assert!(hover.is_some(), "hover after timeout should succeed");
+        client.shutdown().await.expect("shutdown failed");
+    }
+    .boxed()
+}
+
+fn shutdown_stops_server() -> BoxFuture<'static, ()> {
+    async {
+        let client = start_client().await;
+        client.shutdown().await.expect("shutdown failed");
+
+        for _ in 0..50 {
+            if !client.is_running() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("fake server did not exit after shutdown");
+    }
+    .boxed()
+}
+
+fn published_diagnostics_reach_store() -> BoxFuture<'static, ()> {
+    async {
+        let mut config = Config::default();
+        config.lsp = fake_server_config(5000);
+        let event_bus = Arc::new(EventBus::new());
+        let manager = SyntaxManager::new(Arc::new(RwLock::new(config)), event_bus)
+            .await
+            .expect("failed to create syntax manager");
+
+        // The server publishes during startup, before anyone asked.
+        let path = PathBuf::from("/tmp/project/src/lib.rs");
+        let mut diagnostics = Vec::new();
+        for _ in 0..100 {
+            diagnostics = manager.get_diagnostics(&path).await.expect("diagnostics failed");
+            if !diagnostics.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(diagnostics.len(), 1, "diagnostics never reached the store");
+        assert_eq!(diagnostics[0].message, "published error");
+        assert_eq!((diagnostics[0].end_line, diagnostics[0].end_column), (1, 6));
+
+        manager.shutdown().await.expect("shutdown failed");
+    }
+    .boxed()
+}
+
+/// Wait until the store holds a diagnostic for `path` matching `expected`.
+async fn wait_for_diagnostic(manager: &SyntaxManager, path: &PathBuf, expected: &str) {
+    let mut last = Vec::new();
+    for _ in 0..100 {
+        last = manager.get_diagnostics(path).await.expect("diagnostics failed");
+        if last.iter().any(|d| d.message == expected) {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("expected diagnostic {:?}, store had {:?}", expected, last);
+}
+
+fn editor_changes_are_synchronized() -> BoxFuture<'static, ()> {
+    async {
+        let mut config = Config::default();
+        config.lsp = fake_server_config(5000);
+        let config = Arc::new(RwLock::new(config));
+        let event_bus = Arc::new(EventBus::new());
+        let manager = SyntaxManager::new(Arc::clone(&config), Arc::clone(&event_bus))
+            .await
+            .expect("failed to create syntax manager");
+        let editor = Editor::new(config, event_bus).await.expect("failed to create editor");
+
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let path = dir.path().join("main.rs");
+        std::fs::write(&path, "fn main() {}").expect("failed to write source");
+
+        // The fake server echoes each document state back as a diagnostic.
+        editor.open_file(&path).await.expect("open failed");
+        wait_for_diagnostic(&manager, &path, "v0:fn main() {}").await;
+
+        editor.move_cursor(0, 11).await.expect("move failed");
+        editor.insert_text("\n    run();\n").await.expect("insert failed");
+        wait_for_diagnostic(&manager, &path, "v1:fn main() {\n    run();\n}").await;
+
+        editor.move_cursor(0, 0).await.expect("move failed");
+        editor.delete_text(3).await.expect("delete failed");
+        wait_for_diagnostic(&manager, &path, "v2:main() {\n    run();\n}").await;
+
+        editor.undo().await.expect("undo failed");
+        wait_for_diagnostic(&manager, &path, "v3:fn main() {\n    run();\n}").await;
+
+        editor.save_current().await.expect("save failed");
+        wait_for_diagnostic(&manager, &path, "saved v3").await;
+
+        editor.close_file(&path).await.expect("close failed");
+        for _ in 0..100 {
+            if manager.get_diagnostics(&path).await.expect("diagnostics failed").is_empty() {
+                manager.shutdown().await.expect("shutdown failed");
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("diagnostics were not cleared after close");
+    }
+    .boxed()
+}
+
+fn navigation_and_workspace_edits() -> BoxFuture<'static, ()> {
+    async {
+        let mut config = Config::default();
+        config.lsp = fake_server_config(5000);
+        let config = Arc::new(RwLock::new(config));
+        let event_bus = Arc::new(EventBus::new());
+        let manager = SyntaxManager::new(Arc::clone(&config), Arc::clone(&event_bus))
+            .await
+            .expect("failed to create syntax manager");
+        let editor = Editor::new(config, event_bus).await.expect("failed to create editor");
+
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let main_path = dir.path().join("main.rs");
+        let lib_path = dir.path().join("lib.rs");
+        let main_source = "fn main() {\n    helper();\n    helper();\n}";
+        let lib_source = "pub fn helper() {}  \n";
+        std::fs::write(&main_path, main_source).expect("failed to write source");
+        std::fs::write(&lib_path, lib_source).expect("failed to write source");
+
+        editor.open_file(&main_path).await.expect("open failed");
+        wait_for_diagnostic(&manager, &main_path, &format!("v0:{}", main_source)).await;
+
+        let definition = manager
+            .goto_definition(&main_path, 1, 6)
+            .await
+            .expect("definition failed")
+            .expect("no definition");
+        assert_eq!(PathBuf::from(definition.path()), lib_path);
+        assert_eq!((definition.range.start.line, definition.range.start.character), (0, 7));
+
+        let references =
+            manager.find_references(&main_path, 1, 6).await.expect("references failed");
+        assert_eq!(references.len(), 3, "unexpected references: {:?}", references);
+
+        // The rename touches the open file and one that is not open yet.
+        let edit = manager
+            .rename_symbol(&main_path, 1, 6, "assist")
+            .await
+            .expect("rename failed")
+            .expect("no rename edit");
+        editor.apply_workspace_edit(&edit, "Rename to 'assist'").await.expect("apply failed");
+        let renamed_main = main_source.replace("helper", "assist");
+        let renamed_lib = lib_source.replace("helper", "assist");
+        assert_eq!(editor.get_content(&main_path).await.as_deref(), Some(renamed_main.as_str()));
+        assert_eq!(editor.get_content(&lib_path).await.as_deref(), Some(renamed_lib.as_str()));
+        assert_eq!(editor.get_active_content().await.as_deref(), Some(renamed_main.as_str()));
+        wait_for_diagnostic(&manager, &lib_path, &format!("v2:{}", renamed_lib)).await;
+
+        // Each file's edits are undone as one step, in that file's history.
+        assert_eq!(editor.can_undo_count().await, 1);
+        editor.undo().await.expect("undo failed");
+        editor.set_active_buffer(&lib_path).await.expect("activate failed");
+        assert_eq!(editor.can_undo_count().await, 1);
+        editor.undo().await.expect("undo failed");
+        assert_eq!(editor.get_content(&main_path).await.as_deref(), Some(main_source));
+        assert_eq!(editor.get_content(&lib_path).await.as_deref(), Some(lib_source));
+        wait_for_diagnostic(&manager, &lib_path, &format!("v4:{}", lib_source)).await;
+
+        let edits = manager
+            .format_document(&lib_path)
+            .await
+            .expect("format failed")
+            .expect("no formatting edits");
+        editor.apply_text_edits(&lib_path, &edits, "Format document").await.expect("apply failed");
+        assert_eq!(editor.get_content(&lib_path).await.as_deref(), Some("pub fn helper() {}\n"));
+
+        manager.shutdown().await.expect("shutdown failed");
+    }
+    .boxed()
+}
+
+fn configured_servers_handle_their_files() -> BoxFuture<'static, ()> {
+    async {
+        let server_binary = std::env::current_exe().expect("failed to locate test binary");
+        let mut config = Config::default();
+        config.lsp = fake_server_config(5000);
+        config.lsp.servers.push(LanguageServerConfig {
+            language: "wgsl".to_string(),
+            command: server_binary,
+            args: vec![SERVE_FLAG.to_string()],
+            extensions: vec!["wgsl".to_string(), "shader".to_string()],
+            initialization_options: None,
+        });
+        let config = Arc::new(RwLock::new(config));
+        let event_bus = Arc::new(EventBus::new());
+        let manager = SyntaxManager::new(Arc::clone(&config), Arc::clone(&event_bus))
+            .await
+            .expect("failed to create syntax manager");
+        let editor = Editor::new(config, event_bus).await.expect("failed to create editor");
+
+        // Both extensions are routed to the configured server.
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        for name in ["main.wgsl", "blur.shader"] {
+            let path = dir.path().join(name);
+            std::fs::write(&path, "@fragment fn main() {}").expect("failed to write source");
+            editor.open_file(&path).await.expect("open failed");
+            wait_for_diagnostic(&manager, &path, "v0:@fragment fn main() {}").await;
+        }
+
+        let path = dir.path().join("main.wgsl");
+        let hover = manager.get_hover(&path, 0, 3).await.expect("hover failed");
+        assert_eq!(hover.as_deref(), Some("fake hover at 0:3"));
+        manager.shutdown().await.expect("shutdown failed");
+    }
+    .boxed()
+}
+
+/// Records the language server status events published on the bus.
+#[derive(Default)]
+struct StatusRecorder {
+    statuses: Mutex<Vec<String>>,
+}
+
+impl StatusRecorder {
+    fn statuses(&self, language: &str) -> Vec<String> {
+        let statuses = self.statuses.lock().expect("recorder poisoned");
+        statuses.iter().filter(|status| status.ends_with(language)).cloned().collect()
+    }
+
+    /// Wait until the statuses recorded for `language` are `expected`.
+    async fn wait_for(&self, language: &str, expected: &[&str]) {
+        for _ in 0..100 {
+            if self.statuses(language) == expected {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("expected statuses {:?}, got {:?}", expected, self.statuses(language));
+    }
+}
+
+#[async_trait::async_trait]
+impl EventHandler for StatusRecorder {
+    async fn handle(&self, event: &EventMessage) -> xylux_ide::core::Result<()> {
+        let status = match serde_json::from_value(event.data.clone()) {
+            Ok(Event::Lsp(LspEvent::ServerStarted { language })) => format!("started {}", language),
+            Ok(Event::Lsp(LspEvent::ServerStopped { language })) => format!("stopped {}", language),
+            Ok(Event::Lsp(LspEvent::Error { language, .. })) => format!("error {}", language),
+            _ => return Ok(()),
+        };
+        self.statuses.lock().expect("recorder poisoned").push(status);
+        Ok(())
+    }
+
+    fn can_handle(&self, event_type: &str) -> bool {
+        event_type == "lsp"
+    }
+}
+
+fn crashed_server_is_restarted() -> BoxFuture<'static, ()> {
+    async {
+        let mut config = Config::default();
+        config.lsp = fake_server_config(5000);
+        config.lsp.restart.max_restarts = 1;
+        config.lsp.restart.initial_backoff_ms = 50;
+        let config = Arc::new(RwLock::new(config));
+        let event_bus = Arc::new(EventBus::new());
+        let recorder = Arc::new(StatusRecorder::default());
+        let handler: Arc<dyn EventHandler> = recorder.clone();
+        event_bus
+            .register_handler("status_recorder", handler)
+            .await
+            .expect("failed to register recorder");
+        let manager = SyntaxManager::new(Arc::clone(&config), Arc::clone(&event_bus))
+            .await
+            .expect("failed to create syntax manager");
+        let editor = Editor::new(config, event_bus).await.expect("failed to create editor");
+        recorder.wait_for("rust", &["started rust"]).await;
+
+        let dir = tempfile::tempdir().expect("failed to create temp dir");
+        let path = dir.path().join("main.rs");
+        std::fs::write(&path, "fn main() {}").expect("failed to write source");
+        editor.open_file(&path).await.expect("open failed");
+        wait_for_diagnostic(&manager, &path, "v0:fn main() {}").await;
+
+        // The restarted server gets the document reopened in its current state.
+        editor.insert_text(CRASH_MARKER).await.expect("insert failed");
+        recorder.wait_for("rust", &["started rust", "stopped rust", "started rust"]).await;
+        wait_for_diagnostic(&manager, &path, "v1:CRASHfn main() {}").await;
+
+        // A second crash exceeds the restart budget.
+        editor.insert_text("!").await.expect("insert failed");
+        recorder
+            .wait_for(
+                "rust",
+                &["started rust", "stopped rust", "started rust", "stopped rust", "error rust"],
+            )
+            .await;
+        assert!(manager.get_hover(&path, 0, 0).await.expect("hover failed").is_none());
+
+        manager.shutdown().await.expect("shutdown failed");
+    }
+    .boxed()
+}
+
+/// Scripted language server speaking LSP over stdio.
+mod fake_server {
+    use std::collections::HashMap;
+
+    use super::{BufRead, BufReader, CRASH_MARKER, UNANSWERED_LINE, Value, Write, io, json};
+
+    pub fn run() {
+        let stdin = io::stdin();
+        let mut input = BufReader::new(stdin.lock());
+        let mut output = io::stdout().lock();
+        // Open documents by URI: version and text.
+        let mut documents: HashMap<String, (i64, String)> = HashMap::new();
+
+        while let Some(message) = read_message(&mut input) {
+            let method = message.get("method").and_then(Value::as_str).unwrap_or_default();
+            let Some(id) = message.get("id").cloned() else {
+                let params = message.get("params").cloned().unwrap_or(Value::Null);
+                let uri = params
+                    .pointer("/textDocument/uri")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string();
+                let version = params.pointer("/textDocument/version").and_then(Value::as_i64);
+
+                match method {
+                    "exit" => return,
+                    "textDocument/didOpen" => {
+                        let text = params.pointer("/textDocument/text").and_then(Value::as_str);
+                        let state =
+                            (version.unwrap_or_default(), text.unwrap_or_default().to_string());
+                        echo(&mut output, &uri, &format!("v{}:{}", state.0, state.1));
+                        documents.insert(uri, state);
+                    }
+                    "textDocument/didChange" => {
+                        let Some(state) = documents.get_mut(&uri) else {
+                            continue;
+                        };
+                        let changes = params.get("contentChanges").and_then(Value::as_array);
+                        for change in changes.into_iter().flatten() {
+                            apply_change(&mut state.1, change);
+                        }
+                        state.0 = version.unwrap_or_default();
+                        if state.1.contains(CRASH_MARKER) {
+                            std::process::exit(1);
+                        }
+                        echo(&mut output, &uri, &format!("v{}:{}", state.0, state.1));
+                    }
+                    "textDocument/didSave" => {
+                        if let Some(state) = documents.get(&uri) {
+                            echo(&mut output, &uri, &format!("saved v{}", state.0));
+                        }
+                    }
+                    "textDocument/didClose" => {
+                        documents.remove(&uri);
+                        send(
+                            &mut output,
+                            &json!({ "jsonrpc": "2.0", "method": "textDocument/publishDiagnostics", "params": { "uri": uri, "diagnostics": [] } }),
+                        );
+                    }
+                    "initialized" => {
+                        // Exercise the client's handling of server requests and notifications.
+                        send(
+                            &mut output,
+                            &json!({ "jsonrpc": "2.0", "id": "srv-1", "method": "client/registerCapability", "params": { "registrations": [] } }),
+                        );
+                        send(
+                            &mut output,
+                            &json!({ "jsonrpc": "2.0", "method": "window/logMessage", "params": { "type": 3, "message": "fake server ready" } }),
+                        );
+                        send(
+                            &mut output,
+                            &json!({
+                                "jsonrpc": "2.0",
+                                "method": "textDocument/publishDiagnostics",
+                                "params": {
+                                    "uri": "file:///tmp/project/src/lib.rs",
+                                    "diagnostics": [{
+                                        "range": { "start": { "line": 1, "character": 0 }, "end": { "line": 1, "character": 6 } },
+                                        "severity": 1,
+                                        "message": "published error"
+                                    }]
+                                }
+                            }),
+                        );
+                    }
+                    _ => {}
+                }
+                continue;
+            };
+
+            // Replies to our own server requests.
+            if method.is_empty() {
+                continue;
+            }
+
+            let params = message.get("params").cloned().unwrap_or(Value::Null);
+            let position = |key: &str| params.pointer(&format!("/position/{}", key)).cloned();
+            let request_uri = || {
+                params
+                    .pointer("/textDocument/uri")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            let file_name = || {
+                params
+                    .pointer("/textDocument/uri")
+                    .and_then(Value::as_str)
+                    .and_then(|uri| uri.rsplit('/').next())
+                    .unwrap_or_default()
+                    .to_string()
+            };
+
+            let result = match method {
+                "initialize" => json!({
+                    "capabilities": {
+                        "textDocumentSync": { "openClose": true, "change": 2, "save": true },
+                        "hoverProvider": true,
+                        "completionProvider": { "triggerCharacters": ["."] },
+                        "definitionProvider": true,
+                        "referencesProvider": true,
+                        "renameProvider": true,
+                        "documentFormattingProvider": true,
+                        "diagnosticProvider": { "interFileDependencies": false, "workspaceDiagnostics": false }
+                    },
+                    "serverInfo": { "name": "fake-lsp-server" }
+                }),
+                "textDocument/completion" => json!({
+                    "isIncomplete": false,
+                    "items": [{
+                        "label": "fake_fn",
+                        "kind": 3,
+                        "detail": format!("{}:{}:{}", file_name(), position("line").unwrap_or_default(), position("character").unwrap_or_default())
+                    }]
+                }),
+                "textDocument/hover" => {
+                    let line = position("line").and_then(|l| l.as_u64()).unwrap_or_default();
+                    if line == UNANSWERED_LINE {
+                        continue;
+                    }
+                    let character = position("character").unwrap_or_default();
+                    json!({ "contents": { "kind": "markdown", "value": format!("fake hover at {}:{}", line, character) } })
+                }
+                "textDocument/diagnostic" => json!({
+                    "kind": "full",
+                    "items": [{
+                        "range": { "start": { "line": 0, "character": 4 }, "end": { "line": 0, "character": 7 } },
+                        "severity": 2,
+                        "message": "fake warning",
+                        "source": "fake-lsp-server"
+                    }]
+                }),
+                "textDocument/definition" => {
+                    let word = word_at(&document_text(&documents, &request_uri()), &params);
+                    let lib_uri = sibling(&request_uri(), "lib.rs");
+                    let definition = occurrences(&document_text(&documents, &lib_uri), &word)
+                        .into_iter()
+                        .next()
+                        .map(|(line, character)| {
+                            let range = range(line, character, character + word.len());
+                            json!([{ "targetUri": lib_uri, "targetRange": range, "targetSelectionRange": range }])
+                        });
+                    definition.unwrap_or(Value::Null)
+                }
+                "textDocument/references" => {
+                    let word = word_at(&document_text(&documents, &request_uri()), &params);
+                    let mut locations = Vec::new();
+                    for uri in [request_uri(), sibling(&request_uri(), "lib.rs")] {
+                        for (line, character) in
+                            occurrences(&document_text(&documents, &uri), &word)
+                        {
+                            locations.push(json!({ "uri": uri, "range": range(line, character, character + word.len()) }));
+                        }
+                    }
+                    Value::Array(locations)
+                }
+                "textDocument/rename" => {
+                    let word = word_at(&document_text(&documents, &request_uri()), &params);
+                    let new_name = params.get("newName").cloned().unwrap_or_default();
+                    let edits = |uri: &str| -> Vec<Value> {
+                        occurrences(&document_text(&documents, uri), &word)
+                            .into_iter()
+                            .map(|(line, character)| json!({ "range": range(line, character, character + word.len()), "newText": new_name }))
+                            .collect()
+                    };
+                    // Answer with both edit shapes so the client merges them.
+                    let lib_uri = sibling(&request_uri(), "lib.rs");
+                    json!({
+                        "changes": { request_uri(): edits(&request_uri()) },
+                        "documentChanges": [{ "textDocument": { "uri": lib_uri, "version": null }, "edits": edits(&lib_uri) }]
+                    })
+                }
+                "textDocument/formatting" => {
+                    // Strip trailing whitespace.
+                    let text = document_text(&documents, &request_uri());
+                    let edits: Vec<Value> = text
+                        .split('\n')
+                        .enumerate()
+                        .filter(|(_, line)| line.len() != line.trim_end().len())
+                        .map(|(index, line)| json!({ "range": range(index, line.trim_end().len(), line.len()), "newText": "" }))
+                        .collect();
+                    Value::Array(edits)
+                }
+                "shutdown" => Value::Null,
+                _ => {
+                    send(
+                        &mut output,
+                        &json!({ "jsonrpc": "2.0", "id": id, "error": { "code": -32601, "message": format!("Unknown method: {}", method) } }),
+                    );
+                    continue;
+                }
+            };
+
+            send(&mut output, &json!({ "jsonrpc": "2.0", "id": id, "result": result }));
+        }
+    }
+
+    /// Publish a single informational diagnostic carrying `message`.
+    fn echo<W: Write>(output: &mut W, uri: &str, message: &str) {
+        send(
+            output,
+            &json!({
+                "jsonrpc": "2.0",
+                "method": "textDocument/publishDiagnostics",
+                "params": {
+                    "uri": uri,
+                    "diagnostics": [{
+                        "range": { "start": { "line": 0, "character": 0 }, "end": { "line": 0, "character": 0 } },
+                        "severity": 3,
+                        "message": message
+                    }]
+                }
+            }),
+        );
+    }
+
+    /// Text of a document: the synchronized copy if open, otherwise read from disk.
+    fn document_text(documents: &HashMap<String, (i64, String)>, uri: &str) -> String {
+        documents.get(uri).map(|(_, text)| text.clone()).unwrap_or_else(|| {
+            std::fs::read_to_string(uri.trim_start_matches("file://")).unwrap_or_default()
+        })
+    }
+
+    /// URI of a file next to `uri`.
+    fn sibling(uri: &str, name: &str) -> String {
+        let directory = uri.rsplit_once('/').map_or(uri, |(directory, _)| directory);
+        format!("{}/{}", directory, name)
+    }
+
+    /// Identifier at the request's `position` in ASCII text.
+    fn word_at(text: &str, params: &Value) -> String {
+        let line = params.pointer("/position/line").and_then(Value::as_u64).unwrap_or_default();
+        let character =
+            params.pointer("/position/character").and_then(Value::as_u64).unwrap_or_default();
+        let line =
+            text.split('\n').nth(usize::try_from(line).unwrap_or_default()).unwrap_or_default();
+        let character = usize::try_from(character).unwrap_or_default().min(line.len());
+
+        let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+        let start = line[..character].rfind(|c| !is_ident(c)).map_or(0, |index| index + 1);
+        let end =
+            line[character..].find(|c| !is_ident(c)).map_or(line.len(), |index| character + index);
+        line[start..end].to_string()
+    }
+
+    /// Positions of whole-word occurrences of `word` in ASCII text.
+    fn occurrences(text: &str, word: &str) -> Vec<(usize, usize)> {
+        if word.is_empty() {
+            return Vec::new();
+        }
+        let is_ident = |c: Option<char>| c.is_some_and(|c| c.is_ascii_alphanumeric() || c == '_');
+        let mut found = Vec::new();
+        for (index, line) in text.split('\n').enumerate() {
+            for (character, _) in line.match_indices(word) {
+                let before = line[..character].chars().next_back();
+                let after = line[character + word.len()..].chars().next();
+                if !is_ident(before) && !is_ident(after) {
+                    found.push((index, character));
+                }
+            }
+        }
+        found
+    }
+
+    /// A single-line LSP range.
+    fn range(line: usize, start: usize, end: usize) -> Value {
+        json!({ "start": { "line": line, "character": start }, "end": { "line": line, "character": end } })
+    }
+
+    /// Apply a `didChange` content change to ASCII text.
+    fn apply_change(text: &mut String, change: &Value) {
+        let new_text = change.get("text").and_then(Value::as_str).unwrap_or_default();
+        let Some(range) = change.get("range") else {
+            *text = new_text.to_string();
+            return;
+        };
+
+        let offset = |key: &str| {
+            let line = range.pointer(&format!("/{}/line", key)).and_then(Value::as_u64);
+            let character = range.pointer(&format!("/{}/character", key)).and_then(Value::as_u64);
+            let (line, character) = (line.unwrap_or_default(), character.unwrap_or_default());
+            let line_start: usize = text
+                .split('\n')
+                .take(usize::try_from(line).unwrap_or_default())
+                .map(|l| l.len() + 1)
+                .sum();
+            line_start + usize::try_from(character).unwrap_or_default()
+        };
+
+        let (start, end) = (offset("start"), offset("end"));
+        text.replace_range(start..end, new_text);
+    }
+
+    fn read_message<R: BufRead>(input: &mut R) -> Option<Value> {
+        let mut content_length = None;
+        loop {
+            let mut line = String::new();
+            if input.read_line(&mut line).ok()? == 0 {
+                return None;
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(value) = line.strip_prefix("Content-Length:") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+
+        let mut body = vec![0; content_length?];
+        input.read_exact(&mut body).ok()?;
+        serde_json::from_slice(&body).ok()
+    }
+
+    fn send<W: Write>(output: &mut W, message: &Value) {
+        let body = message.to_string();
+        let _unused = write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body);
+        let _unused = output.flush();
+    }
+}